@@ -1,11 +1,15 @@
+use crate::commands::workspace_commands::detached_engine;
 use crate::core::ai_analysis_prompts::PromptTemplate;
 use crate::core::ai_analysis_prompts::PromptTemplateManager;
 use crate::core::ai_manager::AIManager;
-use crate::core::git_engine::GitEngine;
+use crate::core::impact_model;
+use crate::core::repo_workspace::RepoWorkspace;
 use crate::core::report_engine::AnalysisEngine;
 use crate::types::git_types::{
-    AIAnalysisConfig, AnalysisConfig, AnalysisDepth, AnalysisProgress, CommitDetailAnalysis, Report,
+    AIAnalysisConfig, AnalysisConfig, AnalysisDepth, AnalysisProgress, CommitDetailAnalysis,
+    ImpactRules, Report,
 };
+use crate::{error_log, info_log};
 use chrono::Utc;
 use std::sync::Arc;
 /// AI分析相关命令
@@ -14,7 +18,7 @@ use std::sync::Arc;
 ///
 /// 此模块提供了AI增强分析的相关命令
 use tauri::{Emitter, Manager};
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
 /// 分析单个提交（使用AI增强）
@@ -27,7 +31,7 @@ pub async fn analyze_commit_with_ai(
     include_code_review: Option<bool>,
     force_refresh: Option<bool>,
 ) -> Result<CommitDetailAnalysis, String> {
-    println!("开始AI分析提交: {} in {}", commit_id, repo_path);
+    info_log!("开始AI分析提交: {} in {}", commit_id, repo_path);
 
     // 获取应用数据目录
     let app_dir = app_handle
@@ -55,8 +59,7 @@ pub async fn analyze_commit_with_ai(
     analysis_engine = analysis_engine.with_ai_config(ai_config);
 
     // 获取Git引擎
-    let git_engine_state = app_handle.state::<Mutex<GitEngine>>();
-    let git_engine = git_engine_state.lock().await;
+    let git_engine = detached_engine(&app_handle.state::<RepoWorkspace>())?;
 
     // 如果强制刷新，清除缓存
     if force_refresh.unwrap_or(false) {
@@ -66,7 +69,7 @@ pub async fn analyze_commit_with_ai(
         if cache_path.exists() {
             std::fs::remove_file(&cache_path)
                 .map_err(|e| format!("Failed to remove cache: {}", e))?;
-            println!("已清除缓存: {:?}", cache_path);
+            info_log!("已清除缓存: {:?}", cache_path);
         }
     }
 
@@ -86,7 +89,7 @@ pub async fn analyze_commit_with_ai(
         .await
         .map_err(|e| format!("Failed to analyze commit: {}", e))?;
 
-    println!("AI分析完成: {}", commit_id);
+    info_log!("AI分析完成: {}", commit_id);
 
     Ok(analysis)
 }
@@ -104,9 +107,10 @@ pub async fn batch_analyze_commits(
     let session_id = session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
     let total_steps = commit_ids.len() as u32;
 
-    println!(
+    info_log!(
         "开始批量分析，会话ID: {}, 总提交数: {}",
-        session_id, total_steps
+        session_id,
+        total_steps
     );
 
     // 创建进度对象
@@ -150,8 +154,7 @@ pub async fn batch_analyze_commits(
     analysis_engine = analysis_engine.with_ai_config(ai_config);
 
     // 获取Git引擎
-    let git_engine_state = app_handle.state::<Mutex<GitEngine>>();
-    let git_engine = git_engine_state.lock().await;
+    let git_engine = detached_engine(&app_handle.state::<RepoWorkspace>())?;
 
     // 分析每个提交
     let mut successful_analyses = 0;
@@ -184,7 +187,7 @@ pub async fn batch_analyze_commits(
             let commit_info = match git_engine.get_commit_info(repo_path, commit_id) {
                 Ok(info) => info,
                 Err(e) => {
-                    eprintln!("Failed to get commit info for {}: {}", commit_id, e);
+                    error_log!("Failed to get commit info for {}: {}", commit_id, e);
                     failed_analyses += 1;
                     continue;
                 }
@@ -194,7 +197,7 @@ pub async fn batch_analyze_commits(
             let diff_info = match git_engine.get_commit_diff(repo_path, commit_id) {
                 Ok(diff) => diff,
                 Err(e) => {
-                    eprintln!("Failed to get diff info for {}: {}", commit_id, e);
+                    error_log!("Failed to get diff info for {}: {}", commit_id, e);
                     failed_analyses += 1;
                     continue;
                 }
@@ -207,15 +210,15 @@ pub async fn batch_analyze_commits(
             {
                 Ok(_) => {
                     successful_analyses += 1;
-                    println!("成功分析提交: {}", commit_id);
+                    info_log!("成功分析提交: {}", commit_id);
                 }
                 Err(e) => {
-                    eprintln!("Failed to analyze commit {}: {}", commit_id, e);
+                    error_log!("Failed to analyze commit {}: {}", commit_id, e);
                     failed_analyses += 1;
                 }
             }
         } else {
-            println!("提交 {} 已存在于缓存中", commit_id);
+            info_log!("提交 {} 已存在于缓存中", commit_id);
             successful_analyses += 1;
         }
     }
@@ -250,8 +253,8 @@ pub async fn generate_ai_enhanced_report(
     use_ai_summary: Option<bool>,
     report_template: Option<String>,
 ) -> Result<Report, String> {
-    println!("开始生成AI增强日报...");
-    println!(
+    info_log!("开始生成AI增强日报...");
+    info_log!(
         "配置: 仓库数={}, 用户数={}, 日期范围={} 到 {}",
         config.repoPaths.len(),
         config.userEmails.len(),
@@ -277,8 +280,7 @@ pub async fn generate_ai_enhanced_report(
     analysis_engine = analysis_engine.with_ai_manager(ai_manager.inner().clone());
 
     // 获取Git引擎
-    let git_engine_state = app_handle.state::<Mutex<GitEngine>>();
-    let git_engine = git_engine_state.lock().await;
+    let git_engine = detached_engine(&app_handle.state::<RepoWorkspace>())?;
 
     // 收集所有提交分析
     let mut all_analyses = Vec::new();
@@ -286,7 +288,7 @@ pub async fn generate_ai_enhanced_report(
 
     // 分析每个仓库的提交
     for repo_path in &config.repoPaths {
-        println!("处理仓库: {}", repo_path);
+        info_log!("处理仓库: {}", repo_path);
 
         // 获取提交列表
         let commits = git_engine
@@ -305,7 +307,7 @@ pub async fn generate_ai_enhanced_report(
                 .collect()
         };
 
-        println!(
+        info_log!(
             "仓库 {} 过滤后提交数: {}",
             repo_path,
             filtered_commits.len()
@@ -331,12 +333,12 @@ pub async fn generate_ai_enhanced_report(
                                 all_analyses.push(analysis);
                             }
                             Err(e) => {
-                                eprintln!("分析提交失败 {}: {}", commit.hash, e);
+                                error_log!("分析提交失败 {}: {}", commit.hash, e);
                             }
                         }
                     }
                     Err(e) => {
-                        eprintln!("获取差异失败 {}: {}", commit.hash, e);
+                        error_log!("获取差异失败 {}: {}", commit.hash, e);
                     }
                 }
             }
@@ -363,7 +365,7 @@ pub async fn generate_ai_enhanced_report(
         {
             Ok(ai_summary) => ai_summary,
             Err(e) => {
-                eprintln!("AI汇总生成失败: {}", e);
+                error_log!("AI汇总生成失败: {}", e);
                 // 回退到基础报告生成
                 generate_basic_report_content(&all_analyses, &config)?
             }
@@ -480,7 +482,7 @@ pub async fn get_ai_analysis_config() -> Result<AIAnalysisConfig, String> {
 #[tauri::command]
 pub async fn set_ai_analysis_config(config: AIAnalysisConfig) -> Result<bool, String> {
     // TODO: 保存配置到本地
-    println!("更新AI分析配置: {:?}", config);
+    info_log!("更新AI分析配置: {:?}", config);
     Ok(true)
 }
 
@@ -689,3 +691,36 @@ pub async fn reset_ai_template(
 
     Ok(true)
 }
+
+/// 获取仓库的提交影响评分规则（本机规则优先，其次 HEAD 中提交的共享规则，都没有时返回默认规则）
+/// Author: Evilek, Date: 2025-10-08
+#[tauri::command]
+pub async fn get_impact_rules(
+    app_handle: tauri::AppHandle,
+    repo_path: String,
+) -> Result<ImpactRules, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    impact_model::load_rules(&app_dir, &repo_path)
+        .map_err(|e| format!("读取影响评分规则失败: {}", e))
+}
+
+/// 保存仓库的提交影响评分规则到应用数据目录（按仓库 id 区分，不写入工作区）
+/// Author: Evilek, Date: 2025-10-08
+#[tauri::command]
+pub async fn save_impact_rules(
+    app_handle: tauri::AppHandle,
+    repo_path: String,
+    rules: ImpactRules,
+) -> Result<(), String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    impact_model::save_rules(&app_dir, &repo_path, &rules)
+        .map_err(|e| format!("保存影响评分规则失败: {}", e))
+}
@@ -1,3 +1,4 @@
+use crate::{debug_log, error_log, warn_log};
 use tauri::{Emitter, State};
 // AI 命令集合：读取配置、拼提示词、调度 AIManager
 // Author: Evilek, Date: 2025-08-11
@@ -7,11 +8,19 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
+use crate::commands::workspace_commands::workspace_engine;
 use crate::core::ai_config::AIConfig;
 use crate::core::ai_manager::AIManager;
 use crate::core::ai_provider::{AIModel, AIRequest, ChatMessage, ConnectionTestResult};
+use crate::core::commit_style::CommitStyleProfile;
 use crate::core::conversation_logger::ConversationRecord;
-use crate::core::prompt_manager::{CommitContext, PromptTemplate};
+use crate::core::prompt_manager::{parse_issue_keys, CommitContext, PromptManager, PromptTemplate};
+use crate::core::repo_registry::RepoRegistry;
+use crate::core::repo_workspace::RepoWorkspace;
+use crate::types::git_types::{
+    CommitLintResult, DiffLineType, FileHistoryEntry, LineHistoryExplanation, LineHistoryRequest,
+};
+use crate::utils::token_counter::TokenCounter;
 
 /**
  * AI相关的Tauri命令
@@ -19,12 +28,29 @@ use crate::core::prompt_manager::{CommitContext, PromptTemplate};
  * 编写日期：2025-07-25
  */
 
+/// 提示模板渲染时附带的最近提交数量
+const RECENT_COMMIT_LIMIT: usize = 10;
+
+/// 提交提示词的目标 token 数，提交风格示例只使用 diff 之外的剩余空间
+const PROMPT_TOKEN_TARGET: u32 = 3200;
+const MIN_STYLE_EXAMPLE_TOKENS: u32 = 150;
+const MAX_STYLE_EXAMPLE_TOKENS: u32 = 600;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateCommitRequest {
     pub selected_files: Vec<String>,
     pub additional_context: Option<String>,
 }
 
+/// 使用提示模板生成提交消息的参数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateCommitRequest {
+    pub template_id: String,
+    pub diff: String,
+    pub staged_files: Vec<String>,
+    pub branch_name: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateCommitResponse {
     pub message: String,
@@ -35,6 +61,18 @@ pub struct GenerateCommitResponse {
     /// 作者：Evilek
     /// 编写日期：2025-01-10
     pub reasoning_content: Option<String>,
+    /// 提交规范校验结果（规范关闭时为空）
+    pub lint: Option<CommitLintResult>,
+}
+
+/// 提交模板预览结果
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitTemplatePreview {
+    pub rendered: String,
+    /// 渲染时可用的全部变量及其当前取值
+    pub variables: serde_json::Value,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -182,8 +220,10 @@ pub async fn refresh_provider_models(
 pub async fn generate_commit_message_ai(
     request: GenerateCommitRequest,
     ai_manager: State<'_, Arc<RwLock<AIManager>>>,
-    git_engine: State<'_, Mutex<crate::core::git_engine::GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GenerateCommitResponse, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     use std::time::Instant;
 
     let start_time = Instant::now();
@@ -208,7 +248,22 @@ pub async fn generate_commit_message_ai(
     let config = manager.get_config().await;
 
     // 构建提示词（参考Dish AI Commit的提示词模板）
-    let system_prompt = create_commit_system_prompt(&config);
+    let mut system_prompt = create_commit_system_prompt(&config);
+
+    if config.features.use_recent_commits {
+        let engine = git_engine.lock().await;
+        let mut context = CommitContext {
+            diff: diff_summary.clone(),
+            branch_name: Some(git_status.branch.clone()),
+            ..Default::default()
+        };
+        if let Ok(facts) = engine.get_commit_template_facts(&request.selected_files, 0) {
+            context.apply_repository_facts(facts);
+        }
+        if let Some(style) = learn_commit_style(&engine, &config, &context) {
+            system_prompt.push_str(&style.to_prompt_section(&context.issue_keys));
+        }
+    }
     let user_prompt = format!(
         "请为以下Git更改生成提交消息：\n\n分支: {}\n文件数量: {}\n修改的文件:\n{}\n\n差异摘要:\n{}\n\n{}",
         git_status.branch,
@@ -238,10 +293,20 @@ pub async fn generate_commit_message_ai(
 
     // 调用AI生成
     let response = manager
-        .generate_commit_message(ai_request)
+        .generate_commit_message(ai_request.clone())
         .await
         .map_err(|e| format!("Failed to generate commit message: {}", e))?;
 
+    // 按提交规范校验，必要时重新提示或自动修正
+    let (response, lint) = manager
+        .enforce_commit_lint(
+            None,
+            &ai_request,
+            response,
+            &parse_issue_keys(&git_status.branch),
+        )
+        .await;
+
     let processing_time = start_time.elapsed().as_millis() as u64;
 
     Ok(GenerateCommitResponse {
@@ -250,6 +315,7 @@ pub async fn generate_commit_message_ai(
         processing_time_ms: processing_time,
         model_used: response.model,
         reasoning_content: response.reasoning_content, // 添加推理内容 - Author: Evilek, Date: 2025-01-10
+        lint,
     })
 }
 
@@ -304,24 +370,110 @@ feat: 新功能, fix: 错误修复, docs: 文档更改, style: 代码格式, ref
 /// 更新日期：2025-08-04
 #[tauri::command]
 pub async fn generate_commit_with_template(
+    request: TemplateCommitRequest,
     ai_manager: State<'_, Arc<RwLock<AIManager>>>,
-    git_engine: State<'_, Mutex<crate::core::git_engine::GitEngine>>,
-    template_id: String,
-    diff: String,
-    staged_files: Vec<String>,
-    branch_name: Option<String>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
 ) -> Result<String, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let manager = ai_manager.read().await;
+    let template_id = request.template_id;
 
-    // 获取当前仓库路径
+    // 从AI配置中获取语言设置
+    let config = manager.get_config().await;
+
+    let mut context = CommitContext {
+        diff: request.diff,
+        staged_files: request.staged_files,
+        branch_name: request.branch_name,
+        language: repo_commit_language(
+            &workspace,
+            &registry,
+            repo_id.as_deref(),
+            &config.base.language,
+        )
+        .await,
+        ..Default::default()
+    };
+
+    let learn_style = manager
+        .get_prompt_manager()
+        .await
+        .uses_recent_commits(&template_id, config.features.use_recent_commits);
+
+    // 获取当前仓库路径，并补充模板可用的仓库上下文（失败时仅使用传入的信息）
     let repository_path = {
         let engine = git_engine.lock().await;
+        if let Ok(facts) =
+            engine.get_commit_template_facts(&context.staged_files, RECENT_COMMIT_LIMIT)
+        {
+            context.apply_repository_facts(facts);
+        }
+        if learn_style {
+            context.style = learn_commit_style(&engine, &config, &context);
+        }
         engine.get_repository_path()
     };
 
-    // 从AI配置中获取语言设置
-    let config = manager.get_config().await;
-    let language = match config.base.language.as_str() {
+    match manager
+        .generate_commit_with_template(&template_id, context, repository_path)
+        .await
+    {
+        Ok(response) => Ok(response.content),
+        Err(e) => Err(format!("Failed to generate commit message: {}", e)),
+    }
+}
+
+/// 从当前分支最近的提交中学习提交风格，示例占用的 token 随 diff 大小收缩
+/// 作者：Evilek
+/// 编写日期：2025-10-02
+fn learn_commit_style(
+    engine: &crate::core::git_engine::GitEngine,
+    config: &AIConfig,
+    context: &CommitContext,
+) -> Option<CommitStyleProfile> {
+    let author_email = if config.features.recent_commits_author_only {
+        context.author.as_ref().map(|a| a.email.as_str())
+    } else {
+        None
+    };
+
+    let messages = engine
+        .get_recent_commit_messages(config.features.recent_commits_count, author_email)
+        .ok()?;
+    if messages.is_empty() {
+        return None;
+    }
+
+    let diff_tokens = TokenCounter::estimate_tokens(&context.diff);
+    let budget = PROMPT_TOKEN_TARGET
+        .saturating_sub(diff_tokens)
+        .clamp(MIN_STYLE_EXAMPLE_TOKENS, MAX_STYLE_EXAMPLE_TOKENS);
+
+    Some(CommitStyleProfile::from_messages(&messages, budget))
+}
+
+/// 提交信息语言：仓库默认设置优先，否则按全局 AI 语言设置
+async fn repo_commit_language(
+    workspace: &RepoWorkspace,
+    registry: &Mutex<RepoRegistry>,
+    repo_id: Option<&str>,
+    configured: &str,
+) -> String {
+    let repo_language = match repo_id
+        .map(str::to_string)
+        .or_else(|| workspace.active_id())
+    {
+        Some(id) => registry.lock().await.defaults(&id).language,
+        None => None,
+    };
+    repo_language.unwrap_or_else(|| commit_language_code(configured).to_string())
+}
+
+/// 将配置中的语言名称转换为提示词使用的语言代码
+fn commit_language_code(language: &str) -> &'static str {
+    match language {
         "Simplified Chinese" => "zh-CN",
         "Traditional Chinese" => "zh-TW",
         "English" => "en",
@@ -342,24 +494,61 @@ pub async fn generate_commit_with_template(
         "Thai" => "th",
         "Indonesian" => "id",
         _ => "en", // 默认英文
-    };
+    }
+}
 
-    let context = CommitContext {
-        diff,
-        staged_files,
-        branch_name,
-        commit_type: None,
-        max_length: None,
-        language: language.to_string(), // 使用配置中的语言设置
+/// 预览提交模板：使用当前暂存区的变更渲染模板，template_content 用于预览编辑中尚未保存的内容
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+#[tauri::command]
+pub async fn preview_commit_template(
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+    template_id: String,
+    template_content: Option<String>,
+) -> Result<CommitTemplatePreview, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let manager = ai_manager.read().await;
+    let prompt_manager = manager.get_prompt_manager().await;
+    let config = manager.get_config().await;
+
+    let mut context = CommitContext {
+        language: repo_commit_language(
+            &workspace,
+            &registry,
+            repo_id.as_deref(),
+            &config.base.language,
+        )
+        .await,
+        ..Default::default()
     };
 
-    match manager
-        .generate_commit_with_template(&template_id, context, repository_path)
-        .await
     {
-        Ok(response) => Ok(response.content),
-        Err(e) => Err(format!("Failed to generate commit message: {}", e)),
+        let engine = git_engine.lock().await;
+        let facts = engine
+            .get_commit_template_facts(&[], RECENT_COMMIT_LIMIT)
+            .map_err(|e| format!("Failed to collect commit context: {}", e))?;
+        context.apply_repository_facts(facts);
+        if !context.staged_files.is_empty() {
+            context.diff = engine
+                .get_diff_summary(&context.staged_files)
+                .map_err(|e| format!("Failed to get staged diff: {}", e))?;
+        }
+        if prompt_manager.uses_recent_commits(&template_id, config.features.use_recent_commits) {
+            context.style = learn_commit_style(&engine, &config, &context);
+        }
     }
+
+    let rendered = prompt_manager
+        .preview_template(&template_id, template_content.as_deref(), &context)
+        .map_err(|e| format!("Failed to render template: {}", e))?;
+
+    Ok(CommitTemplatePreview {
+        rendered,
+        variables: PromptManager::template_variables(&context),
+    })
 }
 
 /// 获取所有可用的提示模板
@@ -536,11 +725,13 @@ pub async fn get_repository_paths(
 #[tauri::command]
 pub async fn should_use_layered_commit(
     ai_manager: State<'_, Arc<RwLock<AIManager>>>,
-    git_engine: State<'_, Mutex<crate::core::git_engine::GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
     template_id: String,
     diff: String,
     staged_files: Vec<String>,
 ) -> Result<bool, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     use crate::core::layered_commit_manager::LayeredCommitManager;
     use std::sync::Arc;
     use tokio::sync::RwLock;
@@ -574,12 +765,14 @@ static LAYERED_COMMIT_MANAGER: Lazy<
 #[tauri::command]
 pub async fn execute_layered_commit(
     ai_manager: State<'_, Arc<RwLock<AIManager>>>,
-    git_engine: State<'_, Mutex<crate::core::git_engine::GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
     app_handle: tauri::AppHandle,
     template_id: String,
     staged_files: Vec<String>,
     branch_name: Option<String>,
 ) -> Result<crate::core::layered_commit_manager::LayeredCommitResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     use crate::core::layered_commit_manager::LayeredCommitManager;
     use std::sync::Arc;
     use tokio::sync::RwLock;
@@ -639,8 +832,8 @@ pub async fn execute_layered_commit(
         Ok(result) => Ok(result),
         Err(e) => {
             // 打印详细错误信息便于调试
-            eprintln!("❌ [分层提交] 执行失败: {:?}", e);
-            eprintln!("❌ [分层提交] 错误类型: {}", std::any::type_name_of_val(&e));
+            error_log!("❌ [分层提交] 执行失败: {:?}", e);
+            error_log!("❌ [分层提交] 错误类型: {}", std::any::type_name_of_val(&e));
             Err(format!("分层提交执行失败: {}", e))
         }
     }
@@ -811,15 +1004,17 @@ pub struct FileTokenCheckResult {
 #[tauri::command]
 pub async fn check_and_process_file_tokens(
     ai_manager: State<'_, Arc<RwLock<AIManager>>>,
-    git_engine: State<'_, Mutex<crate::core::git_engine::GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
     file_paths: Vec<String>,
     template_id: Option<String>,
 ) -> Result<FileTokenCheckResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     use crate::utils::token_counter::TokenCounter;
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
-    println!(
+    debug_log!(
         "🔍 [check_and_process_file_tokens] 开始处理 {} 个文件",
         file_paths.len()
     );
@@ -827,7 +1022,7 @@ pub async fn check_and_process_file_tokens(
     let ai_manager_arc = ai_manager.inner().clone();
     let git_engine_arc = Arc::new(RwLock::new(git_engine.lock().await.clone()));
 
-    println!("🔍 [check_and_process_file_tokens] 获取AI配置...");
+    debug_log!("🔍 [check_and_process_file_tokens] 获取AI配置...");
     // 获取AI配置以确定token限制
     let ai_manager_guard = ai_manager_arc.read().await;
     let config = ai_manager_guard.get_config().await;
@@ -841,7 +1036,7 @@ pub async fn check_and_process_file_tokens(
         _ => Some(4096),                               // 默认限制
     };
     drop(ai_manager_guard);
-    println!(
+    debug_log!(
         "🔍 [check_and_process_file_tokens] 模型token限制: {:?}",
         model_max_tokens
     );
@@ -849,7 +1044,7 @@ pub async fn check_and_process_file_tokens(
     let mut processed_files: Vec<String> = Vec::new();
     let mut needs_split = false;
 
-    println!("🔍 [check_and_process_file_tokens] 开始获取文件diff...");
+    debug_log!("🔍 [check_and_process_file_tokens] 开始获取文件diff...");
 
     // 性能优化：使用批量diff获取，避免单个文件的重复Git操作
     let git_engine_guard = git_engine_arc.read().await;
@@ -861,7 +1056,7 @@ pub async fn check_and_process_file_tokens(
 
     match batch_diff_result {
         Ok(batch_diff) => {
-            println!(
+            debug_log!(
                 "🔍 [check_and_process_file_tokens] 使用批量diff，长度: {}",
                 batch_diff.len()
             );
@@ -877,12 +1072,12 @@ pub async fn check_and_process_file_tokens(
             }
         }
         Err(_) => {
-            println!("⚠️ [check_and_process_file_tokens] 批量diff获取失败，回退到单个文件处理");
+            warn_log!("⚠️ [check_and_process_file_tokens] 批量diff获取失败，回退到单个文件处理");
             // 回退到原来的逻辑，但添加超时保护
             let git_engine_guard = git_engine_arc.read().await;
 
             for (index, file_path) in file_paths.iter().enumerate() {
-                println!(
+                debug_log!(
                     "🔍 [check_and_process_file_tokens] 处理文件 {}/{}: {}",
                     index + 1,
                     file_paths.len(),
@@ -894,7 +1089,7 @@ pub async fn check_and_process_file_tokens(
                 match git_engine_guard.get_simple_file_diff(file_path) {
                     Ok(diff_content) => {
                         let elapsed = start_time.elapsed();
-                        println!(
+                        debug_log!(
                             "🔍 [check_and_process_file_tokens] 文件 {} diff长度: {}, 耗时: {:?}",
                             file_path,
                             diff_content.len(),
@@ -903,9 +1098,10 @@ pub async fn check_and_process_file_tokens(
                         file_diffs.push((file_path.clone(), Some(diff_content)));
                     }
                     Err(e) => {
-                        println!(
+                        warn_log!(
                             "⚠️ [check_and_process_file_tokens] 文件 {} diff获取失败: {}",
-                            file_path, e
+                            file_path,
+                            e
                         );
                         file_diffs.push((file_path.clone(), None));
                     }
@@ -915,7 +1111,7 @@ pub async fn check_and_process_file_tokens(
         }
     }
 
-    println!("🔍 [check_and_process_file_tokens] 完成diff获取，开始token分析...");
+    debug_log!("🔍 [check_and_process_file_tokens] 完成diff获取，开始token分析...");
 
     // 智能分组策略：根据token使用量决定处理方式
     let mut total_tokens = 0u32;
@@ -925,19 +1121,20 @@ pub async fn check_and_process_file_tokens(
     // 计算每个文件的token使用量
     for (file_path, diff_content_opt) in file_diffs {
         if let Some(diff_content) = diff_content_opt {
-            println!(
+            debug_log!(
                 "🔍 [check_and_process_file_tokens] 计算文件 {} 的token...",
                 file_path
             );
             let file_tokens = TokenCounter::estimate_file_diff_tokens(&file_path, &diff_content);
-            println!(
+            debug_log!(
                 "🔍 [check_and_process_file_tokens] 文件 {} token数: {}",
-                file_path, file_tokens
+                file_path,
+                file_tokens
             );
 
             // 单个文件超过限制，需要分割
             if TokenCounter::is_over_limit(file_tokens, model_max_tokens) {
-                println!(
+                warn_log!(
                     "⚠️ [check_and_process_file_tokens] 文件 {} 超过token限制，标记为大文件",
                     file_path
                 );
@@ -949,7 +1146,7 @@ pub async fn check_and_process_file_tokens(
             }
         } else {
             // diff获取失败的文件直接添加
-            println!(
+            warn_log!(
                 "⚠️ [check_and_process_file_tokens] 文件 {} diff获取失败，直接添加",
                 file_path
             );
@@ -957,7 +1154,7 @@ pub async fn check_and_process_file_tokens(
         }
     }
 
-    println!(
+    debug_log!(
         "🔍 [check_and_process_file_tokens] Token分析完成 - 大文件: {}, 普通文件: {}, 总token: {}",
         large_files.len(),
         normal_files.len(),
@@ -1005,9 +1202,10 @@ pub async fn check_and_process_file_tokens(
         }
     }
 
-    println!(
+    debug_log!(
         "🔍 [check_and_process_file_tokens] 处理完成 - 输出文件: {:?}, 需要分割: {}",
-        processed_files, needs_split
+        processed_files,
+        needs_split
     );
 
     Ok(FileTokenCheckResult {
@@ -1015,3 +1213,146 @@ pub async fn check_and_process_file_tokens(
         needs_split,
     })
 }
+
+/// 使用AI解释指定代码行的演变历史
+/// 作者：Evilek
+/// 编写日期：2025-09-28
+#[tauri::command]
+pub async fn explain_line_history(
+    request: LineHistoryRequest,
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<LineHistoryExplanation, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    use std::time::Instant;
+
+    let start_time = Instant::now();
+
+    let end_line = request.end_line.unwrap_or(request.start_line);
+    let max_commits = request.max_commits.unwrap_or(10).clamp(1, 50);
+
+    let commits = {
+        let engine = git_engine.lock().await;
+        engine
+            .get_line_history(
+                &request.file_path,
+                request.start_line,
+                end_line,
+                max_commits,
+            )
+            .map_err(|e| format!("Failed to get line history: {}", e))?
+    };
+
+    if commits.is_empty() {
+        return Err("No history found for the selected lines".to_string());
+    }
+
+    let manager = ai_manager.read().await;
+    let config = manager.get_config().await;
+
+    let system_prompt = format!(
+        "你是资深的代码考古助手。根据给定代码行的提交历史（从新到旧），说明这段代码是何时、由谁、为何引入，以及之后每次修改的动机和影响。\
+         请使用{}回答，按时间顺序组织，引用提交短哈希，不要臆测历史中没有体现的信息。",
+        config.base.language
+    );
+    let user_prompt =
+        build_line_history_prompt(&request.file_path, request.start_line, end_line, &commits);
+
+    let ai_request = AIRequest {
+        messages: vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: system_prompt,
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: user_prompt,
+            },
+        ],
+        model: config.base.model.clone(),
+        temperature: Some(config.advanced.temperature),
+        max_tokens: Some(config.advanced.max_tokens),
+        stream: Some(false),
+    };
+
+    let response = manager
+        .generate_analysis_report(ai_request)
+        .await
+        .map_err(|e| format!("Failed to explain line history: {}", e))?;
+
+    Ok(LineHistoryExplanation {
+        file_path: request.file_path,
+        start_line: request.start_line,
+        end_line,
+        explanation: response.content,
+        commits,
+        model_used: response.model,
+        processing_time_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+/// 构建行历史提示词：每个提交只保留与目标行相关的差异，单个提交的差异行数做上限截断
+/// 作者：Evilek
+/// 编写日期：2025-09-28
+fn build_line_history_prompt(
+    file_path: &str,
+    start_line: u32,
+    end_line: u32,
+    commits: &[FileHistoryEntry],
+) -> String {
+    const MAX_DIFF_LINES_PER_COMMIT: usize = 80;
+
+    let mut prompt = format!(
+        "文件: {}\n行区间: {}-{}\n提交数量: {}\n\n",
+        file_path,
+        start_line,
+        end_line,
+        commits.len()
+    );
+
+    for entry in commits {
+        let date = chrono::DateTime::from_timestamp(entry.commit.timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+
+        prompt.push_str(&format!(
+            "## {} {} <{}> {}\n",
+            entry.commit.short_hash, entry.commit.author, entry.commit.email, date
+        ));
+        prompt.push_str(&format!("提交信息:\n{}\n", entry.commit.message));
+
+        if let Some(old_path) = &entry.old_path {
+            prompt.push_str(&format!("重命名: {} -> {}\n", old_path, entry.path));
+        }
+
+        prompt.push_str("差异:\n```diff\n");
+
+        let mut written = 0;
+        'hunks: for hunk in &entry.hunks {
+            prompt.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+            ));
+            for line in &hunk.lines {
+                if written >= MAX_DIFF_LINES_PER_COMMIT {
+                    prompt.push_str("... (已截断)\n");
+                    break 'hunks;
+                }
+                let prefix = match line.line_type {
+                    DiffLineType::Insert => '+',
+                    DiffLineType::Delete => '-',
+                    DiffLineType::Context => ' ',
+                };
+                prompt.push(prefix);
+                prompt.push_str(&line.content);
+                prompt.push('\n');
+                written += 1;
+            }
+        }
+
+        prompt.push_str("```\n\n");
+    }
+
+    prompt
+}
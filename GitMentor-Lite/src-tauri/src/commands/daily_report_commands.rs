@@ -1,9 +1,12 @@
-use crate::core::git_engine::GitEngine;
-use crate::core::report_engine::{AnalysisEngine, CacheManager};
+use crate::commands::workspace_commands::detached_engine;
+use crate::core::repo_registry::RepoRegistry;
+use crate::core::repo_workspace::RepoWorkspace;
+use crate::core::report_engine::{AnalysisEngine, CacheManager, ReportStore};
 use crate::types::git_types::{
     AnalysisConfig, CommitAnalysis, CommitDetailAnalysis, Contributor, ImpactLevel, Report,
     ReportMeta, Repository,
 };
+use crate::{error_log, info_log};
 use chrono::Utc;
 use std::collections::HashMap;
 use tauri::{Manager, State};
@@ -12,15 +15,28 @@ use uuid::Uuid;
 
 // 日报生成相关命令 - Author: Evilek, Date: 2025-08-21
 
-/// 获取可用仓库列表
+/// 获取可用仓库列表；不传路径时使用已登记的仓库（最近 / 收藏）
 #[tauri::command]
 pub async fn get_available_repositories(
     repoPaths: Vec<String>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
 ) -> Result<Vec<Repository>, String> {
-    let engine = git_engine.lock().await;
+    let repo_paths = if repoPaths.is_empty() {
+        registry
+            .lock()
+            .await
+            .list()
+            .into_iter()
+            .map(|repo| repo.path)
+            .collect()
+    } else {
+        repoPaths
+    };
+
+    let engine = detached_engine(&workspace)?;
     engine
-        .get_available_repositories(repoPaths)
+        .get_available_repositories(repo_paths)
         .map_err(|e| format!("Failed to get repositories: {}", e))
 }
 
@@ -28,9 +44,9 @@ pub async fn get_available_repositories(
 #[tauri::command]
 pub async fn get_repo_contributors(
     repoPaths: Vec<String>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<Vec<Contributor>, String> {
-    let engine = git_engine.lock().await;
+    let engine = detached_engine(&workspace)?;
     engine
         .get_repo_contributors(repoPaths)
         .map_err(|e| format!("Failed to get contributors: {}", e))
@@ -40,9 +56,9 @@ pub async fn get_repo_contributors(
 #[tauri::command]
 pub async fn analyze_commits(
     config: AnalysisConfig,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<CommitAnalysis, String> {
-    let engine = git_engine.lock().await;
+    let engine = detached_engine(&workspace)?;
     engine
         .analyze_commits(config)
         .map_err(|e| format!("Failed to analyze commits: {}", e))
@@ -66,8 +82,7 @@ pub async fn analyze_and_cache_commit(
         .map_err(|e| format!("Failed to create analysis engine: {}", e))?;
 
     // 获取 Git 引擎
-    let git_engine_state = app_handle.state::<Mutex<GitEngine>>();
-    let git_engine = git_engine_state.lock().await;
+    let git_engine = detached_engine(&app_handle.state::<RepoWorkspace>())?;
 
     // 获取提交信息
     let commit_info = git_engine
@@ -94,8 +109,8 @@ pub async fn generate_enhanced_daily_report(
     config: AnalysisConfig,
     app_handle: tauri::AppHandle,
 ) -> Result<Report, String> {
-    println!("开始生成增强版日报...");
-    println!(
+    info_log!("开始生成增强版日报...");
+    info_log!(
         "配置信息: 仓库数量={}, 用户数量={}, 开始日期={}, 结束日期={}",
         config.repoPaths.len(),
         config.userEmails.len(),
@@ -111,26 +126,25 @@ pub async fn generate_enhanced_daily_report(
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-    println!("应用数据目录: {:?}", app_dir);
+    info_log!("应用数据目录: {:?}", app_dir);
 
     // 创建分析引擎
     let analysis_engine = AnalysisEngine::new(&app_dir)
         .map_err(|e| format!("Failed to create analysis engine: {}", e))?;
-    println!("分析引擎创建成功");
+    info_log!("分析引擎创建成功");
 
     // 获取 Git 引擎
-    let git_engine_state = app_handle.state::<Mutex<GitEngine>>();
-    let git_engine = git_engine_state.lock().await;
-    println!("Git 引擎获取成功");
+    let git_engine = detached_engine(&app_handle.state::<RepoWorkspace>())?;
+    info_log!("Git 引擎获取成功");
 
     // 分析所有提交
     let mut all_analyses = Vec::new();
     let mut total_commits_found = 0;
 
-    println!("开始分析 {} 个仓库", config.repoPaths.len());
+    info_log!("开始分析 {} 个仓库", config.repoPaths.len());
 
     for repo_path in &config.repoPaths {
-        println!("正在处理仓库: {}", repo_path);
+        info_log!("正在处理仓库: {}", repo_path);
 
         // 获取仓库在指定时间范围内的提交
         let commits = git_engine
@@ -138,7 +152,7 @@ pub async fn generate_enhanced_daily_report(
             .map_err(|e| format!("Failed to get commits: {}", e))?;
 
         total_commits_found += commits.len();
-        println!(
+        info_log!(
             "仓库 {} 在 {} 至 {} 期间找到 {} 个提交",
             repo_path,
             config.startDate,
@@ -148,24 +162,24 @@ pub async fn generate_enhanced_daily_report(
 
         // 过滤指定用户的提交
         let filtered_commits: Vec<_> = if config.userEmails.is_empty() {
-            println!("未指定用户，包含所有提交");
+            info_log!("未指定用户，包含所有提交");
             commits
         } else {
             let user_list = config.userEmails.join(", ");
-            println!("指定用户: {}", user_list);
+            info_log!("指定用户: {}", user_list);
             commits
                 .into_iter()
                 .filter(|c| {
                     let include = config.userEmails.contains(&c.email);
                     if !include {
-                        println!("跳过提交 {} (用户: {} 不在指定用户列表)", c.hash, c.email);
+                        info_log!("跳过提交 {} (用户: {} 不在指定用户列表)", c.hash, c.email);
                     }
                     include
                 })
                 .collect()
         };
 
-        println!(
+        info_log!(
             "仓库 {} 过滤后有 {} 个提交",
             repo_path,
             filtered_commits.len()
@@ -173,7 +187,7 @@ pub async fn generate_enhanced_daily_report(
 
         // 分析每个提交
         for commit in filtered_commits {
-            println!(
+            info_log!(
                 "正在分析提交: {} - {}",
                 commit.hash,
                 commit.message.lines().next().unwrap_or("")
@@ -184,32 +198,32 @@ pub async fn generate_enhanced_daily_report(
             {
                 Ok(analysis) => {
                     all_analyses.push(analysis);
-                    println!("提交 {} 分析成功", commit.hash);
+                    info_log!("提交 {} 分析成功", commit.hash);
                 }
                 Err(e) => {
-                    eprintln!("Failed to analyze commit {}: {}", commit.hash, e);
-                    println!("提交 {} 分析失败: {}", commit.hash, e);
+                    error_log!("Failed to analyze commit {}: {}", commit.hash, e);
+                    info_log!("提交 {} 分析失败: {}", commit.hash, e);
                 }
             }
         }
     }
 
-    println!(
+    info_log!(
         "总共找到 {} 个提交，成功分析 {} 个",
         total_commits_found,
         all_analyses.len()
     );
 
     if all_analyses.is_empty() {
-        println!("警告: 没有找到任何提交分析结果");
+        info_log!("警告: 没有找到任何提交分析结果");
     }
 
     // 生成报告
-    println!("正在生成报告内容...");
+    info_log!("正在生成报告内容...");
     let title = format!("开发日报 - {} 至 {}", config.startDate, config.endDate);
     let content = generate_enhanced_report_content(&all_analyses, &config)?;
 
-    println!("报告生成完成，内容长度: {} 字符", content.len());
+    info_log!("报告生成完成，内容长度: {} 字符", content.len());
 
     Ok(Report {
         id: report_id,
@@ -551,24 +565,40 @@ fn generate_report_content(
     Ok(content)
 }
 
-// TODO: 实现历史报告管理功能
 /// 保存报告
 #[tauri::command]
-pub async fn save_report(report: Report) -> Result<String, String> {
-    // TODO: 实现报告保存到本地存储
+pub async fn save_report(report: Report, app_handle: tauri::AppHandle) -> Result<String, String> {
+    let store = report_store(&app_handle)?;
+    store
+        .save(&report)
+        .map_err(|e| format!("保存报告失败: {}", e))?;
     Ok(report.id)
 }
 
 /// 获取历史报告列表
 #[tauri::command]
-pub async fn get_history_reports() -> Result<Vec<ReportMeta>, String> {
-    // TODO: 从本地存储加载历史报告
-    Ok(vec![])
+pub async fn get_history_reports(app_handle: tauri::AppHandle) -> Result<Vec<ReportMeta>, String> {
+    let store = report_store(&app_handle)?;
+    store.list().map_err(|e| format!("读取历史报告失败: {}", e))
 }
 
 /// 删除报告
 #[tauri::command]
-pub async fn delete_report(_report_id: String) -> Result<bool, String> {
-    // TODO: 从本地存储删除报告
-    Ok(true)
+pub async fn delete_report(
+    report_id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<bool, String> {
+    let store = report_store(&app_handle)?;
+    store
+        .delete(&report_id)
+        .map_err(|e| format!("删除报告失败: {}", e))
+}
+
+pub(crate) fn report_store(app_handle: &tauri::AppHandle) -> Result<ReportStore, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    ReportStore::new(&app_dir).map_err(|e| format!("初始化报告存储失败: {}", e))
 }
@@ -1,3 +1,4 @@
+use crate::{debug_log, info_log};
 use serde::{Deserialize, Serialize};
 // 调试命令：全局开关 + 前端配置面板读写
 // Author: Evilek, Date: 2025-08-11
@@ -14,9 +15,9 @@ static DEBUG_ENABLED: AtomicBool = AtomicBool::new(true); // 临时启用调试
 pub fn set_debug_enabled(enabled: bool) {
     DEBUG_ENABLED.store(enabled, Ordering::Relaxed);
     if enabled {
-        println!("[DEBUG] 调试日志已启用");
+        debug_log!("调试日志已启用");
     } else {
-        println!("[INFO] 调试日志已禁用");
+        info_log!("调试日志已禁用");
     }
 }
 
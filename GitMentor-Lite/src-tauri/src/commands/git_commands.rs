@@ -1,42 +1,71 @@
+use crate::debug_log;
 use tauri::State;
 // Git 命令集合：仅做参数校验与调度，核心逻辑在 GitEngine
 // Author: Evilek, Date: 2025-08-11
 // 返回值统一走 Result<.., String>，错误别往上抛屎山，格式化清楚点
 
+use crate::commands::workspace_commands::{
+    close_and_remember, open_workspace_repository, workspace_engine,
+};
+use crate::core::ai_manager::AIManager;
 use crate::core::git_engine::GitEngine;
+use crate::core::git_process::GitOperationRegistry;
 use crate::core::llm_client::LLMClient;
+use crate::core::prompt_manager::parse_issue_keys;
+use crate::core::repo_registry::RepoRegistry;
+use crate::core::repo_workspace::{RepoWorkspace, WorkspaceRepository};
 use crate::types::git_types::{
-    BranchInfo, CommitInfo, CommitMessageResult, CommitRequest, FileDiffRequest, FileDiffResult,
-    GitOperationResult, GitStatusResult, RemoteConfiguration, RevertRequest, StageRequest,
+    BlameRequest, BlameResult, BranchInfo, CommitDiffResult, CommitInfo, CommitLintResult,
+    CommitLintSeverity, CommitMessageResult, CommitRequest, FileDiffRequest, FileDiffResult,
+    FileHistoryEntry, GitOperationResult, GitStatusResult, HistoryPage, HistoryQuery,
+    RemoteConfiguration, RevertRequest, StageRequest,
 };
+use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 
+/// 打开仓库并替换活动仓库（兼容单仓库调用：先前的活动仓库随之关闭、停止监控；
+/// 需要同时打开多个仓库用 open_workspace_repository）
 #[tauri::command]
 pub async fn select_repository(
     path: String,
     app_handle: tauri::AppHandle,
-    git_engine: State<'_, Mutex<GitEngine>>,
-) -> Result<String, String> {
-    let mut engine = git_engine.lock().await;
-    engine
-        .open_repository(&path)
-        .map_err(|e| format!("Failed to open repository: {}", e))?;
-    engine
-        .start_repo_watcher(app_handle)
-        .map_err(|e| format!("Failed to start repository watcher: {}", e))?;
-    Ok("Repository opened successfully".to_string())
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<WorkspaceRepository, String> {
+    let previous = workspace.active_id();
+    let repository =
+        open_workspace_repository(path, app_handle, workspace.clone(), registry.clone()).await?;
+
+    if let Some(previous) = previous.filter(|id| *id != repository.id) {
+        close_and_remember(&workspace, &registry, &previous).await?;
+        workspace
+            .set_active(&repository.id)
+            .map_err(|e| format!("切换仓库失败: {}", e))?;
+    }
+
+    Ok(repository)
 }
 
+/// 关闭仓库，缺省关闭活动仓库
 #[tauri::command]
-pub async fn close_repository(git_engine: State<'_, Mutex<GitEngine>>) -> Result<(), String> {
-    let mut engine = git_engine.lock().await;
-    engine.close_repository();
-    Ok(())
+pub async fn close_repository(
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<(), String> {
+    let Some(repo_id) = repo_id.or_else(|| workspace.active_id()) else {
+        return Ok(());
+    };
+    close_and_remember(&workspace, &registry, &repo_id).await
 }
 
 #[tauri::command]
-pub async fn stop_repo_watcher(git_engine: State<'_, Mutex<GitEngine>>) -> Result<(), String> {
+pub async fn stop_repo_watcher(
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<(), String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let mut engine = git_engine.lock().await;
     engine.stop_repo_watcher();
     Ok(())
@@ -44,9 +73,11 @@ pub async fn stop_repo_watcher(git_engine: State<'_, Mutex<GitEngine>>) -> Resul
 
 #[tauri::command]
 pub async fn get_git_status(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitStatusResult, String> {
-    println!("[DEBUG] 前端请求获取Git状态（智能方式）");
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    debug_log!("前端请求获取Git状态（智能方式）");
     let start_time = Instant::now();
 
     let engine = git_engine.lock().await;
@@ -54,14 +85,16 @@ pub async fn get_git_status(
         .get_status()
         .map_err(|e| format!("Failed to get git status: {}", e));
 
-    println!("[DEBUG] Git状态命令完成，耗时: {:?}", start_time.elapsed());
+    debug_log!("Git状态命令完成，耗时: {:?}", start_time.elapsed());
     result
 }
 
 #[tauri::command]
 pub async fn get_remote_configuration(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<RemoteConfiguration, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .get_remote_configuration()
@@ -72,8 +105,10 @@ pub async fn get_remote_configuration(
 pub async fn add_remote(
     name: String,
     url: String,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .add_remote(&name, &url)
@@ -84,8 +119,10 @@ pub async fn add_remote(
 pub async fn update_remote(
     name: String,
     url: String,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .update_remote(&name, &url)
@@ -95,8 +132,10 @@ pub async fn update_remote(
 #[tauri::command]
 pub async fn remove_remote(
     name: String,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .remove_remote(&name)
@@ -108,8 +147,10 @@ pub async fn set_branch_upstream(
     branch: String,
     remote: String,
     remote_branch: String,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .set_branch_upstream(&branch, &remote, &remote_branch)
@@ -119,9 +160,11 @@ pub async fn set_branch_upstream(
 #[tauri::command]
 pub async fn generate_commit_message(
     request: CommitRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
     llm_client: State<'_, LLMClient>,
 ) -> Result<CommitMessageResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let start_time = Instant::now();
 
     // Get Git status and diff info
@@ -168,8 +211,10 @@ pub async fn generate_commit_message(
 #[tauri::command]
 pub async fn stage_files(
     request: StageRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .stage_files(&request)
@@ -178,24 +223,83 @@ pub async fn stage_files(
 
 /// 提交更改
 /// 作者：Evilek
+/// 提交前按提交规范校验消息，存在 error 级别问题时拒绝提交；skip_lint 可跳过
 #[tauri::command]
 pub async fn commit_changes(
     request: CommitRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
+
+    if !request.skip_lint {
+        let manager = ai_manager.read().await;
+        if manager.get_config().await.commit_lint.enabled {
+            let issue_keys = engine
+                .get_status()
+                .map(|status| parse_issue_keys(&status.branch))
+                .unwrap_or_default();
+            let result = manager
+                .lint_commit_message(
+                    &request.message,
+                    request.template_id.as_deref(),
+                    &issue_keys,
+                )
+                .await
+                .map_err(|e| format!("提交规范校验失败: {}", e))?;
+            if !result.valid {
+                let problems: Vec<String> = result
+                    .issues
+                    .iter()
+                    .filter(|issue| issue.severity == CommitLintSeverity::Error)
+                    .map(|issue| format!("{}（{}）", issue.message, issue.rule))
+                    .collect();
+                return Err(format!("提交消息不符合规范: {}", problems.join("；")));
+            }
+        }
+    }
+
     engine
         .commit(&request)
         .map_err(|e| format!("Failed to commit: {}", e))
 }
 
+/// 校验手写的提交消息，返回问题清单与可自动修正的结果
+/// Author: Evilek, Date: 2025-10-07
+#[tauri::command]
+pub async fn lint_commit_message(
+    message: String,
+    template_id: Option<String>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
+) -> Result<CommitLintResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let issue_keys = {
+        let engine = git_engine.lock().await;
+        engine
+            .get_status()
+            .map(|status| parse_issue_keys(&status.branch))
+            .unwrap_or_default()
+    };
+    let manager = ai_manager.read().await;
+    manager
+        .lint_commit_message(&message, template_id.as_deref(), &issue_keys)
+        .await
+        .map_err(|e| format!("提交规范校验失败: {}", e))
+}
+
 /// 回滚文件更改
 /// 作者：Evilek
 #[tauri::command]
 pub async fn revert_files(
     request: RevertRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .revert_files(&request)
@@ -207,20 +311,93 @@ pub async fn revert_files(
 #[tauri::command]
 pub async fn get_commit_history(
     limit: usize,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<Vec<CommitInfo>, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .get_commit_history(limit)
         .map_err(|e| format!("Failed to get commit history: {}", e))
 }
 
+/// 获取提交的全部文件差异；parent_index 用于合并提交选择对比的父提交（从 0 开始）
+/// 作者：Evilek
+/// 编写日期：2025-10-08
+#[tauri::command]
+pub async fn get_commit_diff(
+    commit_id: String,
+    parent_index: Option<usize>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<CommitDiffResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    let repo_path = engine
+        .get_repository_path()
+        .ok_or_else(|| "仓库路径未设置".to_string())?;
+    engine
+        .get_commit_diff_against(&repo_path, &commit_id, parent_index)
+        .map_err(|e| format!("获取提交差异失败: {}", e))
+}
+
+/// 分页查询提交历史（提交图）
+/// 作者：Evilek
+/// 编写日期：2025-09-29
+#[tauri::command]
+pub async fn query_commit_history(
+    query: HistoryQuery,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<HistoryPage, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .query_commit_history(&query)
+        .map_err(|e| format!("Failed to query commit history: {}", e))
+}
+
+/// 获取文件逐行追溯信息（blame）
+/// 作者：Evilek
+/// 编写日期：2025-09-28
+#[tauri::command]
+pub async fn blame_file(
+    request: BlameRequest,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<BlameResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .blame_file(&request)
+        .map_err(|e| format!("Failed to blame file: {}", e))
+}
+
+/// 获取单个文件的提交历史（跟随重命名）
+/// 作者：Evilek
+/// 编写日期：2025-09-28
+#[tauri::command]
+pub async fn get_file_history(
+    file_path: String,
+    limit: Option<usize>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<Vec<FileHistoryEntry>, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .get_file_history(&file_path, limit.unwrap_or(50))
+        .map_err(|e| format!("Failed to get file history: {}", e))
+}
+
 /// 获取分支列表
 /// 作者：Evilek
 #[tauri::command]
 pub async fn get_branches(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<Vec<BranchInfo>, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .get_branches()
@@ -234,8 +411,10 @@ pub async fn get_branches(
 pub async fn checkout_branch(
     branch_name: String,
     is_remote: bool,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .checkout_branch(&branch_name, is_remote)
@@ -247,12 +426,20 @@ pub async fn checkout_branch(
 /// 编写日期：2025-08-12
 #[tauri::command]
 pub async fn pull_current_branch(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    operation_id: Option<String>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    operations: State<'_, GitOperationRegistry>,
 ) -> Result<GitOperationResult, String> {
-    let engine = git_engine.lock().await;
-    engine
-        .pull_current_branch()
-        .map_err(|e| format!("Failed to pull: {}", e))
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let mut engine = git_engine.lock().await;
+    run_cancellable(
+        &mut engine,
+        &operations,
+        operation_id.as_deref(),
+        |engine| engine.pull_current_branch(),
+    )
+    .map_err(|e| format!("Failed to pull: {}", e))
 }
 
 /// 推送当前分支
@@ -261,12 +448,20 @@ pub async fn pull_current_branch(
 #[tauri::command]
 pub async fn push_current_branch(
     force: bool,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    operation_id: Option<String>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    operations: State<'_, GitOperationRegistry>,
 ) -> Result<GitOperationResult, String> {
-    let engine = git_engine.lock().await;
-    engine
-        .push_current_branch(force)
-        .map_err(|e| format!("Failed to push: {}", e))
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let mut engine = git_engine.lock().await;
+    run_cancellable(
+        &mut engine,
+        &operations,
+        operation_id.as_deref(),
+        |engine| engine.push_current_branch(force),
+    )
+    .map_err(|e| format!("Failed to push: {}", e))
 }
 
 /// 获取远程更新
@@ -275,20 +470,60 @@ pub async fn push_current_branch(
 #[tauri::command]
 pub async fn fetch_remote(
     remote_name: Option<String>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    operation_id: Option<String>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    operations: State<'_, GitOperationRegistry>,
 ) -> Result<GitOperationResult, String> {
-    let engine = git_engine.lock().await;
-    engine
-        .fetch_remote(remote_name.as_deref())
-        .map_err(|e| format!("Failed to fetch: {}", e))
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let mut engine = git_engine.lock().await;
+    run_cancellable(
+        &mut engine,
+        &operations,
+        operation_id.as_deref(),
+        |engine| engine.fetch_remote(remote_name.as_deref()),
+    )
+    .map_err(|e| format!("Failed to fetch: {}", e))
+}
+
+/// 取消正在执行的Git操作（按前端传入的 operation_id）
+/// 作者：Evilek
+/// 编写日期：2025-09-30
+#[tauri::command]
+pub async fn cancel_git_operation(
+    operation_id: String,
+    operations: State<'_, GitOperationRegistry>,
+) -> Result<bool, String> {
+    Ok(operations.cancel(&operation_id))
+}
+
+/// 在取消令牌的保护下执行引擎操作，结束后清理令牌与登记
+fn run_cancellable<T>(
+    engine: &mut GitEngine,
+    operations: &GitOperationRegistry,
+    operation_id: Option<&str>,
+    operation: impl FnOnce(&GitEngine) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let token = operation_id.map(|id| operations.register(id));
+    engine.set_cancel_token(token);
+
+    let result = operation(engine);
+
+    engine.set_cancel_token(None);
+    if let Some(id) = operation_id {
+        operations.finish(id);
+    }
+    result
 }
 
 /// 丢弃所有工作区更改
 /// 作者：Evilek
 #[tauri::command]
 pub async fn discard_all_changes(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .discard_all_changes()
@@ -299,8 +534,10 @@ pub async fn discard_all_changes(
 /// 作者：Evilek
 #[tauri::command]
 pub async fn stage_all_changes(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .stage_all_changes()
@@ -311,8 +548,10 @@ pub async fn stage_all_changes(
 /// 作者：Evilek
 #[tauri::command]
 pub async fn unstage_all_changes(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .unstage_all_changes()
@@ -363,8 +602,10 @@ pub async fn open_folder_dialog(
 #[tauri::command]
 pub async fn get_file_diff(
     request: FileDiffRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<FileDiffResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .get_file_diff_detailed(&request)
@@ -377,8 +618,10 @@ pub async fn get_file_diff(
 #[tauri::command]
 pub async fn add_to_gitignore(
     file_paths: Vec<String>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .add_to_gitignore(&file_paths)
@@ -391,8 +634,10 @@ pub async fn add_to_gitignore(
 #[tauri::command]
 pub async fn delete_untracked_files(
     file_paths: Vec<String>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .delete_untracked_files(&file_paths)
@@ -405,8 +650,10 @@ pub async fn delete_untracked_files(
 #[tauri::command]
 pub async fn delete_tracked_files(
     file_paths: Vec<String>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .delete_tracked_files(&file_paths)
@@ -418,8 +665,10 @@ pub async fn delete_tracked_files(
 /// 编写日期：2025-07-27
 #[tauri::command]
 pub async fn get_staged_diff_summary(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<String, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
 
     // 获取Git状态
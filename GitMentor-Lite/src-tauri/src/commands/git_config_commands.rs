@@ -1,5 +1,6 @@
 use crate::core::git_config::{GitConfig, GitConfigManager, GitExecutionMode};
-use crate::core::git_engine::GitEngine;
+use crate::core::git_credentials::{GitCredentialManager, GitCredentialSettings};
+use crate::core::repo_workspace::RepoWorkspace;
 use tauri::State;
 use tokio::sync::Mutex;
 
@@ -19,12 +20,22 @@ pub async fn get_git_config(
 }
 
 /// 更新Git配置
+/// 传 repo_id 时只作为该仓库的专属配置（不落盘），否则保存为全局配置并同步到跟随全局的仓库
 #[tauri::command]
 pub async fn update_git_config(
     config: GitConfig,
+    repo_id: Option<String>,
     git_config_manager: State<'_, Mutex<GitConfigManager>>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<String, String> {
+    if let Some(repo_id) = repo_id {
+        workspace
+            .set_repo_config(&repo_id, Some(config))
+            .await
+            .map_err(|e| format!("Failed to update repository git config: {}", e))?;
+        return Ok("仓库Git配置已更新".to_string());
+    }
+
     // 更新配置管理器
     {
         let mut manager = git_config_manager.lock().await;
@@ -34,14 +45,38 @@ pub async fn update_git_config(
     }
 
     // 更新Git引擎配置
-    {
-        let mut engine = git_engine.lock().await;
-        engine.update_config(config);
-    }
+    workspace
+        .apply_global_config(config)
+        .await
+        .map_err(|e| format!("Failed to apply git config: {}", e))?;
 
     Ok("Git配置已更新".to_string())
 }
 
+/// 获取仓库当前生效的Git配置（缺省为活动仓库）
+#[tauri::command]
+pub async fn get_repository_git_config(
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<GitConfig, String> {
+    workspace
+        .repo_config(repo_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 清除仓库专属Git配置，恢复跟随全局配置
+#[tauri::command]
+pub async fn clear_repository_git_config(
+    repo_id: String,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<(), String> {
+    workspace
+        .set_repo_config(&repo_id, None)
+        .await
+        .map_err(|e| format!("Failed to reset repository git config: {}", e))
+}
+
 /// 获取可用的Git执行方式
 #[tauri::command]
 pub async fn get_available_git_modes() -> Result<Vec<(GitExecutionMode, String)>, String> {
@@ -75,7 +110,7 @@ pub async fn test_git_execution_mode(mode: GitExecutionMode) -> Result<String, S
 #[tauri::command]
 pub async fn reset_git_config(
     git_config_manager: State<'_, Mutex<GitConfigManager>>,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<String, String> {
     let default_config = GitConfig::default();
 
@@ -88,10 +123,47 @@ pub async fn reset_git_config(
     }
 
     // 更新Git引擎配置
-    {
-        let mut engine = git_engine.lock().await;
-        engine.update_config(default_config);
-    }
+    workspace
+        .apply_global_config(default_config)
+        .await
+        .map_err(|e| format!("Failed to apply git config: {}", e))?;
 
     Ok("Git配置已重置为默认值".to_string())
 }
+
+/// 获取Git2Api网络认证凭据设置（令牌与口令已脱敏）
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+#[tauri::command]
+pub async fn get_git_credential_settings(
+    credential_manager: State<'_, Mutex<GitCredentialManager>>,
+) -> Result<GitCredentialSettings, String> {
+    let manager = credential_manager.lock().await;
+    Ok(manager.get_settings().redacted())
+}
+
+/// 更新Git2Api网络认证凭据设置，仍为占位符的令牌/口令保持原值
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+#[tauri::command]
+pub async fn update_git_credential_settings(
+    settings: GitCredentialSettings,
+    credential_manager: State<'_, Mutex<GitCredentialManager>>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<String, String> {
+    let merged = {
+        let mut manager = credential_manager.lock().await;
+        let merged = settings.merge_secrets_from(manager.get_settings());
+        manager
+            .update_settings(merged.clone())
+            .map_err(|e| format!("Failed to save git credentials: {}", e))?;
+        merged
+    };
+
+    workspace
+        .apply_credential_settings(merged)
+        .await
+        .map_err(|e| format!("Failed to apply git credentials: {}", e))?;
+
+    Ok("Git凭据设置已更新".to_string())
+}
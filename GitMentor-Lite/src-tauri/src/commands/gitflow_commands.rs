@@ -1,15 +1,31 @@
-use tauri::State;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
 
+use crate::commands::daily_report_commands::report_store;
+use crate::commands::template_commands::init_template_manager;
+use crate::commands::workspace_commands::workspace_engine;
+use crate::core::ai_manager::AIManager;
+use crate::core::ai_provider::{AIRequest, ChatMessage};
+use crate::core::forge::{ForgeClient, ForgeRemote, PullRequestDraft};
+use crate::core::git_credentials::GitCredentialManager;
 use crate::core::git_engine::GitEngine;
+use crate::core::gitflow_report;
+use crate::core::repo_registry::RepoRegistry;
+use crate::core::repo_workspace::RepoWorkspace;
 use crate::types::git_types::{
-    GitOperationResult, GitflowActionRequest, GitflowCreateRequest, GitflowSummary,
+    GitOperationResult, GitflowActionRequest, GitflowActionResult, GitflowBranchMetadata,
+    GitflowConfig, GitflowCreateRequest, GitflowReport, GitflowReportContext, GitflowReportKind,
+    GitflowStepLog, GitflowStepStatus, GitflowSummary, PullRequestContext, PullRequestOptions,
+    PullRequestResult, ReleaseOptions, ReleasePlan,
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 
 #[tauri::command]
 pub async fn list_gitflow_branches(
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitflowSummary, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .list_gitflow_branches()
@@ -19,21 +35,566 @@ pub async fn list_gitflow_branches(
 #[tauri::command]
 pub async fn create_gitflow_branch(
     request: GitflowCreateRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .create_gitflow_branch(&request)
         .map_err(|e| format!("创建 Gitflow 分支失败: {}", e))
 }
 
+#[tauri::command]
+pub async fn get_gitflow_branch_metadata(
+    branch_name: String,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<GitflowBranchMetadata, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .get_branch_metadata(&branch_name)
+        .map_err(|e| format!("读取分支元数据失败: {}", e))
+}
+
+/// 只更新 metadata 中有值的字段，空字符串表示清除
+#[tauri::command]
+pub async fn update_gitflow_branch_metadata(
+    branch_name: String,
+    metadata: GitflowBranchMetadata,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<GitflowBranchMetadata, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .update_branch_metadata(&branch_name, &metadata)
+        .map_err(|e| format!("更新分支元数据失败: {}", e))
+}
+
+/// 拉取并合并远程的分支元数据，再推送本地记录
+#[tauri::command]
+pub async fn sync_gitflow_branch_metadata(
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .sync_branch_metadata()
+        .map_err(|e| format!("同步分支元数据失败: {}", e))
+}
+
+/// 预览发布流水线：版本推导、版本文件、发布说明与每个步骤，不修改仓库
+#[tauri::command]
+pub async fn plan_gitflow_release(
+    branch_name: String,
+    options: Option<ReleaseOptions>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<ReleasePlan, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .plan_release(&branch_name, &options.unwrap_or_default())
+        .map_err(|e| format!("生成发布计划失败: {}", e))
+}
+
+/// create_pull_request / request_code_review 在配置了托管平台令牌时通过 API 创建 PR/MR，
+/// 否则沿用推送分支并给出 PR 链接建议的方式；
+/// 状态播报/复盘由 AI 按版本化模板生成，AI 不可用时回退为提交摘要
 #[tauri::command]
 pub async fn execute_gitflow_action(
     request: GitflowActionRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
-) -> Result<GitOperationResult, String> {
+    app_handle: AppHandle,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
+    credential_manager: State<'_, Mutex<GitCredentialManager>>,
+) -> Result<GitflowActionResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    if let Some(kind) = GitflowReportKind::from_action(&request.action) {
+        let step = format!("AI 生成{}", kind.label());
+
+        let result = generate_gitflow_report_with(
+            kind,
+            &request.branch_name,
+            &app_handle,
+            &git_engine,
+            &ai_manager,
+        )
+        .await;
+
+        return match result {
+            Ok((report, _)) => Ok(GitflowActionResult {
+                success: true,
+                message: format!("已生成 {} 的{}", request.branch_name, kind.label()),
+                steps: vec![GitflowStepLog {
+                    step,
+                    status: GitflowStepStatus::Succeeded,
+                    output: format!("{} v{}", report.template_id, report.template_version),
+                }],
+                rolled_back: false,
+                report: Some(report.content.clone()),
+                gitflow_report: Some(report),
+            }),
+            Err(error) => {
+                let engine = git_engine.lock().await;
+                let mut fallback = engine
+                    .execute_gitflow_action(&request)
+                    .map_err(|e| format!("执行 Gitflow 操作失败: {}", e))?;
+                fallback.steps.insert(
+                    0,
+                    GitflowStepLog {
+                        step,
+                        status: GitflowStepStatus::Failed,
+                        output: error,
+                    },
+                );
+                fallback.message = format!("{}（AI 生成失败，已回退为提交摘要）", fallback.message);
+                Ok(fallback)
+            }
+        };
+    }
+
+    if matches!(
+        request.action.as_str(),
+        "create_pull_request" | "request_code_review"
+    ) {
+        if let Ok(client) = forge_client(&git_engine, &credential_manager).await {
+            let options = request.pull_request.clone().unwrap_or_default();
+            let step = format!(
+                "推送 {} 并通过 {} API 创建 PR",
+                request.branch_name,
+                client.remote().kind.label()
+            );
+
+            let result = open_pull_request_with(
+                &client,
+                &request.branch_name,
+                options,
+                &git_engine,
+                &ai_manager,
+            )
+            .await;
+
+            return Ok(match result {
+                Ok(result) => GitflowActionResult {
+                    success: true,
+                    message: format!(
+                        "已在 {} 创建 #{}：{}",
+                        result.forge, result.number, result.title
+                    ),
+                    steps: vec![GitflowStepLog {
+                        step,
+                        status: GitflowStepStatus::Succeeded,
+                        output: result.url.clone(),
+                    }],
+                    rolled_back: false,
+                    report: Some(
+                        std::iter::once(result.url)
+                            .chain(result.warnings)
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
+                    gitflow_report: None,
+                },
+                Err(error) => GitflowActionResult {
+                    success: false,
+                    message: error.clone(),
+                    steps: vec![GitflowStepLog {
+                        step,
+                        status: GitflowStepStatus::Failed,
+                        output: error,
+                    }],
+                    rolled_back: false,
+                    report: None,
+                    gitflow_report: None,
+                },
+            });
+        }
+    }
+
     let engine = git_engine.lock().await;
     engine
         .execute_gitflow_action(&request)
         .map_err(|e| format!("执行 Gitflow 操作失败: {}", e))
 }
+
+/// 生成 Gitflow 状态播报/事故复盘/迭代回顾，save 为 true 时同时写入报告存储
+/// 作者：Evilek
+/// 编写日期：2025-10-06
+#[tauri::command]
+pub async fn generate_gitflow_report(
+    branch_name: String,
+    kind: GitflowReportKind,
+    save: Option<bool>,
+    app_handle: AppHandle,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
+) -> Result<GitflowReport, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let (mut report, context) =
+        generate_gitflow_report_with(kind, &branch_name, &app_handle, &git_engine, &ai_manager)
+            .await?;
+
+    if save.unwrap_or(false) {
+        let saved = gitflow_report::to_saved_report(&report, &context);
+        report_store(&app_handle)?
+            .save(&saved)
+            .map_err(|e| format!("保存报告失败: {}", e))?;
+        report.report_id = Some(saved.id);
+    }
+
+    Ok(report)
+}
+
+/// 用当前版本的报告模板渲染分支上下文并请求 AI，输出无法解析时要求模型重新按 JSON 返回一次
+async fn generate_gitflow_report_with(
+    kind: GitflowReportKind,
+    branch_name: &str,
+    app_handle: &AppHandle,
+    git_engine: &Mutex<GitEngine>,
+    ai_manager: &RwLock<AIManager>,
+) -> Result<(GitflowReport, GitflowReportContext), String> {
+    const MAX_ATTEMPTS: usize = 2;
+
+    let context = {
+        let engine = git_engine.lock().await;
+        engine
+            .gitflow_report_context(branch_name)
+            .map_err(|e| format!("收集分支信息失败: {}", e))?
+    };
+
+    let (template, template_version) = report_template(app_handle, kind)?;
+
+    let manager = ai_manager.read().await;
+    let config = manager.get_config().await;
+
+    let prompt = gitflow_report::render_prompt(&template, &context, &config.base.language)
+        .map_err(|e| e.to_string())?;
+
+    let mut messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: gitflow_report::system_prompt(&config.base.language),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+        },
+    ];
+
+    for _ in 0..MAX_ATTEMPTS {
+        let ai_request = AIRequest {
+            messages: messages.clone(),
+            model: config.base.model.clone(),
+            temperature: Some(config.advanced.temperature),
+            max_tokens: Some(config.advanced.max_tokens),
+            stream: Some(false),
+        };
+
+        let response = manager
+            .generate_analysis_report(ai_request)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Some(sections) = gitflow_report::parse_sections(&response.content) {
+            let title = format!("{}：{}", kind.label(), branch_name);
+
+            let report = GitflowReport {
+                kind,
+                branch_name: branch_name.to_string(),
+                repo_path: context.repo_path.clone(),
+                content: gitflow_report::to_markdown(&title, &sections),
+                summary: sections.summary,
+                risks: sections.risks,
+                timeline: sections.timeline,
+                action_items: sections.action_items,
+                template_id: kind.template_id().to_string(),
+                template_version,
+                model: response.model,
+                generated_at: chrono::Utc::now().to_rfc3339(),
+                report_id: None,
+            };
+
+            return Ok((report, context));
+        }
+
+        messages.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: response.content,
+        });
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: gitflow_report::REPAIR_PROMPT.to_string(),
+        });
+    }
+
+    Err("AI 未返回可解析的报告章节".to_string())
+}
+
+/// 从共享模板存储读取报告模板的当前版本，返回内容与版本号
+fn report_template(
+    app_handle: &AppHandle,
+    kind: GitflowReportKind,
+) -> Result<(String, String), String> {
+    let manager =
+        init_template_manager(app_handle).map_err(|e| format!("初始化模板管理器失败: {}", e))?;
+    let manager = manager.lock().map_err(|_| "模板管理器不可用".to_string())?;
+
+    let version = manager
+        .as_ref()
+        .and_then(|manager| manager.get_template(kind.template_id()))
+        .and_then(|template| template.get_current_version())
+        .ok_or_else(|| format!("找不到报告模板 {}", kind.template_id()))?;
+
+    Ok((version.content.clone(), version.version.clone()))
+}
+
+/// 推送分支并通过托管平台 API 创建 PR/MR
+/// 作者：Evilek
+/// 编写日期：2025-10-03
+#[tauri::command]
+pub async fn open_pull_request(
+    branch_name: String,
+    options: Option<PullRequestOptions>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    ai_manager: State<'_, Arc<RwLock<AIManager>>>,
+    credential_manager: State<'_, Mutex<GitCredentialManager>>,
+) -> Result<PullRequestResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let client = forge_client(&git_engine, &credential_manager).await?;
+
+    open_pull_request_with(
+        &client,
+        &branch_name,
+        options.unwrap_or_default(),
+        &git_engine,
+        &ai_manager,
+    )
+    .await
+}
+
+/// 根据默认远程地址与凭据设置创建托管平台客户端，在推送前发现配置问题
+async fn forge_client(
+    git_engine: &Mutex<GitEngine>,
+    credential_manager: &Mutex<GitCredentialManager>,
+) -> Result<ForgeClient, String> {
+    let remote_url = {
+        let engine = git_engine.lock().await;
+        engine
+            .default_remote_url()
+            .map_err(|e| format!("获取远程地址失败: {}", e))?
+    };
+
+    let settings = credential_manager.lock().await.get_settings().clone();
+
+    let remote = ForgeRemote::resolve(&remote_url, &settings).map_err(|e| e.to_string())?;
+    let token = remote.token(&settings).ok_or_else(|| {
+        format!(
+            "未配置 {} 的访问令牌，请在凭据设置中添加托管平台账号",
+            remote.web_url
+        )
+    })?;
+
+    ForgeClient::new(remote, token).map_err(|e| e.to_string())
+}
+
+async fn open_pull_request_with(
+    client: &ForgeClient,
+    branch_name: &str,
+    options: PullRequestOptions,
+    git_engine: &Mutex<GitEngine>,
+    ai_manager: &RwLock<AIManager>,
+) -> Result<PullRequestResult, String> {
+    let context = {
+        let engine = git_engine.lock().await;
+        engine
+            .prepare_pull_request(branch_name, options.base_branch.as_deref())
+            .map_err(|e| format!("准备 PR 失败: {}", e))?
+    };
+
+    let provided_title = options.title.filter(|title| !title.trim().is_empty());
+    let provided_body = options.body.filter(|body| !body.trim().is_empty());
+    let mut warnings = Vec::new();
+
+    let (title, body, ai_generated) = match (provided_title, provided_body) {
+        (Some(title), Some(body)) => (title, body, false),
+        (title, body) => match summarize_pull_request(ai_manager, &context).await {
+            Ok((ai_title, ai_body)) => (title.unwrap_or(ai_title), body.unwrap_or(ai_body), true),
+            Err(e) => {
+                warnings.push(format!("AI 摘要生成失败，已使用分支摘要: {}", e));
+                (
+                    title.unwrap_or_else(|| fallback_title(&context)),
+                    body.unwrap_or_else(|| context.digest.clone()),
+                    false,
+                )
+            }
+        },
+    };
+
+    let draft = PullRequestDraft {
+        source_branch: context.branch_name.clone(),
+        target_branch: context.base_branch.clone(),
+        title,
+        body,
+        reviewers: options.reviewers,
+        labels: options.labels,
+        draft: options.draft,
+    };
+
+    let created = client.create_pull_request(&draft).await.map_err(|e| {
+        format!(
+            "{}（分支已推送，可手动创建：{}）",
+            e,
+            client
+                .remote()
+                .new_pull_request_url(&draft.source_branch, &draft.target_branch)
+        )
+    })?;
+    warnings.extend(created.warnings);
+
+    Ok(PullRequestResult {
+        forge: client.remote().kind.label().to_string(),
+        number: created.number,
+        url: created.url,
+        title: draft.title,
+        source_branch: draft.source_branch,
+        target_branch: draft.target_branch,
+        ai_generated,
+        warnings,
+    })
+}
+
+/// 使用 AI 根据分支摘要生成 PR 标题与描述
+async fn summarize_pull_request(
+    ai_manager: &RwLock<AIManager>,
+    context: &PullRequestContext,
+) -> Result<(String, String), String> {
+    let manager = ai_manager.read().await;
+    let config = manager.get_config().await;
+
+    let system_prompt = format!(
+        "你是资深的代码评审助手。根据分支摘要撰写 Pull Request 的标题和描述。\
+         请使用{}回答：第一行为标题（不超过 72 个字符，不要加前缀），空一行后为 Markdown 格式的描述，\
+         包含变更概述、主要改动和测试建议。不要编造摘要中没有体现的信息。",
+        config.base.language
+    );
+
+    let mut user_prompt = format!(
+        "源分支：{}\n目标分支：{}\n\n{}",
+        context.branch_name, context.base_branch, context.digest
+    );
+    if !context.commits.is_empty() {
+        user_prompt.push_str("\n源分支独有的提交：\n");
+        for commit in &context.commits {
+            user_prompt.push_str(&format!("- {}\n", commit));
+        }
+    }
+
+    let ai_request = AIRequest {
+        messages: vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: system_prompt,
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: user_prompt,
+            },
+        ],
+        model: config.base.model.clone(),
+        temperature: Some(config.advanced.temperature),
+        max_tokens: Some(config.advanced.max_tokens),
+        stream: Some(false),
+    };
+
+    let response = manager
+        .generate_analysis_report(ai_request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    split_title_and_body(&response.content).ok_or_else(|| "AI 未返回有效的标题".to_string())
+}
+
+/// 第一行非空内容为标题（去掉 Markdown 标题符号与“标题：”前缀），其余为描述
+fn split_title_and_body(content: &str) -> Option<(String, String)> {
+    let content = content.trim();
+    let (first, rest) = content.split_once('\n').unwrap_or((content, ""));
+
+    let title = first
+        .trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_start_matches("标题：")
+        .trim_start_matches("Title:")
+        .trim()
+        .trim_matches('*')
+        .trim()
+        .to_string();
+
+    if title.is_empty() {
+        return None;
+    }
+
+    Some((title, rest.trim().to_string()))
+}
+
+/// AI 不可用时的标题：只有一个独有提交时沿用其标题，否则使用分支名
+fn fallback_title(context: &PullRequestContext) -> String {
+    match context.commits.as_slice() {
+        [only] => only.clone(),
+        _ => context.branch_name.clone(),
+    }
+}
+
+#[tauri::command]
+pub async fn get_gitflow_config(
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<GitflowConfig, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .load_gitflow_config()
+        .map_err(|e| format!("读取 Gitflow 配置失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_gitflow_config(
+    config: GitflowConfig,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<GitflowSummary, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let engine = git_engine.lock().await;
+    engine
+        .save_gitflow_config(&config)
+        .map_err(|e| format!("保存 Gitflow 配置失败: {}", e))?;
+    engine
+        .list_gitflow_branches()
+        .map_err(|e| format!("获取 Gitflow 分支失败: {}", e))
+}
+
+/// 初始化 Gitflow，config 为空时使用仓库默认设置中的 Gitflow 配置，再没有就用探测到的分支名与默认前缀
+#[tauri::command]
+pub async fn init_gitflow(
+    config: Option<GitflowConfig>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
+    let config = match (config, repo_id.or_else(|| workspace.active_id())) {
+        (None, Some(id)) => registry.lock().await.defaults(&id).gitflow,
+        (config, _) => config,
+    };
+    let engine = git_engine.lock().await;
+    engine
+        .init_gitflow(config.as_ref())
+        .map_err(|e| format!("初始化 Gitflow 失败: {}", e))
+}
@@ -0,0 +1,100 @@
+use crate::commands::debug_commands::is_debug_enabled;
+use crate::core::app_logger::{self, LogEntry, LogQuery, LogSettings};
+use crate::core::update_manager::PlatformTarget;
+use chrono::Local;
+use std::path::PathBuf;
+use tauri::{command, AppHandle, Manager};
+
+// 日志命令：应用内查看/过滤日志、调整日志级别、导出诊断包
+// Author: Evilek, Date: 2025-10-10
+// 诊断包只放脱敏后的配置，凭据文件一律不打包
+
+/// 获取日志设置
+#[command]
+pub async fn get_log_settings() -> Result<LogSettings, String> {
+    app_logger::settings().map_err(|e| format!("获取日志设置失败: {}", e))
+}
+
+/// 保存日志设置（级别、模块覆盖、轮转参数）
+#[command]
+pub async fn update_log_settings(settings: LogSettings) -> Result<(), String> {
+    app_logger::update_settings(settings).map_err(|e| format!("保存日志设置失败: {}", e))
+}
+
+/// 按级别、模块、关键字和时间查看最新日志
+#[command]
+pub async fn get_logs(query: Option<LogQuery>) -> Result<Vec<LogEntry>, String> {
+    let query = query.unwrap_or_default();
+    tokio::task::spawn_blocking(move || app_logger::query(&query))
+        .await
+        .map_err(|e| format!("读取日志失败: {}", e))?
+        .map_err(|e| format!("读取日志失败: {}", e))
+}
+
+/// 获取日志目录
+#[command]
+pub async fn get_log_dir() -> Result<String, String> {
+    app_logger::log_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .map_err(|e| format!("获取日志目录失败: {}", e))
+}
+
+/// 收集诊断包：日志、脱敏配置和环境信息打成 zip，返回文件路径
+#[command]
+pub async fn collect_diagnostics(app_handle: AppHandle) -> Result<String, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("获取应用数据目录失败: {}", e))?;
+    let log_dir = app_logger::log_dir().map_err(|e| format!("获取日志目录失败: {}", e))?;
+
+    // 配置目录与 run() 中保持一致
+    let mut configs = vec![app_data_dir.join("update_settings.json")];
+    if let Ok(entries) = std::env::current_dir()
+        .map(|dir| dir.join(".config"))
+        .and_then(std::fs::read_dir)
+    {
+        configs.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .filter(|path| !path.to_string_lossy().contains("credential")),
+        );
+    }
+
+    let environment = serde_json::json!({
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "targetTriple": PlatformTarget::current().target_triple(),
+        "gitVersion": git_version(),
+        "debugEnabled": is_debug_enabled(),
+        "logDir": log_dir,
+        "logSettings": app_logger::settings().ok(),
+        "currentDir": std::env::current_dir().ok(),
+        "collectedAt": Local::now().to_rfc3339(),
+    });
+
+    let output = app_data_dir.join("diagnostics").join(format!(
+        "gitmentor-diagnostics-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    tokio::task::spawn_blocking(move || {
+        app_logger::collect_diagnostics(&output, &configs, &environment)
+    })
+    .await
+    .map_err(|e| format!("生成诊断包失败: {}", e))?
+    .map(|path: PathBuf| path.to_string_lossy().to_string())
+    .map_err(|e| format!("生成诊断包失败: {}", e))
+}
+
+fn git_version() -> Option<String> {
+    std::process::Command::new("git")
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -9,8 +9,10 @@ pub mod debug_commands;
 pub mod git_commands;
 pub mod git_config_commands;
 pub mod gitflow_commands;
+pub mod log_commands;
 pub mod repository_commands;
 pub mod system_commands;
 pub mod template_commands;
 pub mod unified_template_commands;
 pub mod update_commands;
+pub mod workspace_commands;
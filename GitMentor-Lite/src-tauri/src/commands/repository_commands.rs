@@ -4,10 +4,11 @@ use std::path::Path;
 use std::time::Instant;
 
 use tauri::State;
-use tokio::sync::Mutex;
 
-use crate::core::git_engine::GitEngine;
+use crate::commands::workspace_commands::{detached_engine, workspace_engine};
+use crate::core::git_process::GitOperationRegistry;
 use crate::core::llm_client::LLMClient;
+use crate::core::repo_workspace::RepoWorkspace;
 use crate::types::git_types::{
     CheckoutRequest, CheckoutResult, CommitMessageResult, GitOperationResult, RemoteConfigRequest,
 };
@@ -16,27 +17,36 @@ use crate::types::git_types::{
 #[tauri::command]
 pub async fn clone_repository(
     request: CheckoutRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    operation_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    operations: State<'_, GitOperationRegistry>,
 ) -> Result<CheckoutResult, String> {
-    let engine_clone = {
-        let engine = git_engine.lock().await;
-        engine.clone()
-    };
+    let mut engine_clone = detached_engine(&workspace)?;
+
+    // 克隆可能持续很久，允许前端通过 cancel_git_operation 中止
+    engine_clone.set_cancel_token(operation_id.as_deref().map(|id| operations.register(id)));
 
     let request_clone = request.clone();
 
-    tokio::task::spawn_blocking(move || engine_clone.clone_repository(&request_clone))
+    let result = tokio::task::spawn_blocking(move || engine_clone.clone_repository(&request_clone))
         .await
-        .map_err(|e| format!("Failed to spawn clone task: {}", e))?
-        .map_err(|e| format!("Failed to clone repository: {}", e))
+        .map_err(|e| format!("Failed to spawn clone task: {}", e));
+
+    if let Some(id) = operation_id.as_deref() {
+        operations.finish(id);
+    }
+
+    result?.map_err(|e| format!("Failed to clone repository: {}", e))
 }
 
 /// 配置远程仓库
 #[tauri::command]
 pub async fn configure_remote(
     request: RemoteConfigRequest,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<GitOperationResult, String> {
+    let git_engine = workspace_engine(&workspace, repo_id.as_deref())?;
     let engine = git_engine.lock().await;
     engine
         .configure_remote(&request)
@@ -47,12 +57,9 @@ pub async fn configure_remote(
 #[tauri::command]
 pub async fn validate_remote_connection(
     url: String,
-    git_engine: State<'_, Mutex<GitEngine>>,
+    workspace: State<'_, RepoWorkspace>,
 ) -> Result<bool, String> {
-    let engine_clone = {
-        let engine = git_engine.lock().await;
-        engine.clone()
-    };
+    let engine_clone = detached_engine(&workspace)?;
 
     tokio::task::spawn_blocking(move || engine_clone.validate_remote_connection(&url))
         .await
@@ -1,3 +1,4 @@
+use crate::{debug_log, error_log};
 use tauri::{command, AppHandle};
 use tauri_plugin_opener::OpenerExt;
 
@@ -10,7 +11,7 @@ use tauri_plugin_opener::OpenerExt;
 /// 打开浏览器链接
 #[command]
 pub async fn open_browser_url(app: AppHandle, url: String) -> Result<(), String> {
-    println!("[DEBUG] 尝试打开浏览器链接: {}", url);
+    debug_log!("尝试打开浏览器链接: {}", url);
 
     // 验证URL格式
     if !url.starts_with("http://") && !url.starts_with("https://") {
@@ -20,11 +21,11 @@ pub async fn open_browser_url(app: AppHandle, url: String) -> Result<(), String>
     // 使用tauri-plugin-opener打开链接
     match app.opener().open_url(url.clone(), None::<&str>) {
         Ok(_) => {
-            println!("[DEBUG] 成功打开浏览器链接: {}", url);
+            debug_log!("成功打开浏览器链接: {}", url);
             Ok(())
         }
         Err(e) => {
-            println!("[ERROR] 打开浏览器链接失败: {}", e);
+            error_log!("打开浏览器链接失败: {}", e);
             Err(format!("打开浏览器失败: {}", e))
         }
     }
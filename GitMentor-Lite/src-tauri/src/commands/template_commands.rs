@@ -11,7 +11,7 @@ static TEMPLATE_MANAGER: Lazy<Mutex<Option<VersionedTemplateManager>>> =
     Lazy::new(|| Mutex::new(None));
 
 /// 初始化模板管理器
-fn init_template_manager(
+pub(crate) fn init_template_manager(
     app_handle: &AppHandle,
 ) -> Result<&'static Mutex<Option<VersionedTemplateManager>>> {
     let mut manager = TEMPLATE_MANAGER.lock().unwrap();
@@ -1,4 +1,8 @@
-use crate::core::update_manager::{UpdateManager, VersionInfo};
+use crate::core::update_manager::{
+    verified_marker, PackageFormat, PackageManagerHandoff, UpdateManager, UpdateSettings,
+    VerificationError, VersionInfo,
+};
+use crate::{debug_log, error_log, info_log, warn_log};
 use anyhow::Result;
 use std::path::PathBuf;
 use tauri::{command, AppHandle, Emitter, Manager};
@@ -17,51 +21,26 @@ struct DownloadProgressEvent {
     percentage: f64,
 }
 
-/// 测试网络连接命令
+/// 测试网络连接命令（使用已配置的 feed 与代理）
 #[command]
-pub async fn test_network_connection() -> Result<bool, String> {
-    println!("[DEBUG] 开始网络连接测试...");
-
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| {
-            println!("[ERROR] 创建HTTP客户端失败: {}", e);
-            format!("创建HTTP客户端失败: {}", e)
-        })?;
-
-    println!("[DEBUG] HTTP客户端创建成功，开始请求 GitHub API...");
-
-    // 测试连接到 GitHub API
-    match client.get("https://api.github.com").send().await {
-        Ok(response) => {
-            let status = response.status();
-            println!("[DEBUG] 网络连接测试收到响应: {}", status);
-            let is_success = status.is_success();
-            println!("[DEBUG] 响应是否成功: {}", is_success);
-
-            if !is_success {
-                println!("[WARN] GitHub API 返回非成功状态码: {}", status);
-                // 即使状态码不是2xx，但能连接到GitHub就算网络正常
-                Ok(true)
-            } else {
-                println!("[DEBUG] 网络连接测试完全成功");
-                Ok(true)
+pub async fn test_network_connection(app_handle: AppHandle) -> Result<bool, String> {
+    debug_log!("开始网络连接测试...");
+
+    let update_manager = create_update_manager(&app_handle).await?;
+
+    debug_log!("开始请求 release feed: {}", update_manager.feed_url());
+
+    match update_manager.test_connection().await {
+        Ok(status) => {
+            debug_log!("网络连接测试收到响应: {}", status);
+            if !status.is_success() {
+                // 即使状态码不是2xx，但能连接到服务器就算网络正常
+                warn_log!("release feed 返回非成功状态码: {}", status);
             }
+            Ok(true)
         }
         Err(e) => {
-            println!("[ERROR] 网络连接测试失败: {}", e);
-            println!("[ERROR] 错误详情: {:?}", e);
-
-            // 检查是否是特定类型的错误
-            if e.is_timeout() {
-                println!("[ERROR] 错误类型: 超时");
-            } else if e.is_connect() {
-                println!("[ERROR] 错误类型: 连接失败");
-            } else if e.is_request() {
-                println!("[ERROR] 错误类型: 请求错误");
-            }
-
+            error_log!("网络连接测试失败: {}", e);
             Err(format!("网络连接失败: {}", e))
         }
     }
@@ -69,70 +48,83 @@ pub async fn test_network_connection() -> Result<bool, String> {
 
 /// 检查更新命令
 #[command]
-pub async fn check_for_updates() -> Result<VersionInfo, String> {
-    println!("[DEBUG] ========== 开始检查更新 ==========");
-
-    // 从 tauri.conf.json 读取当前版本
-    let current_version = env!("CARGO_PKG_VERSION").to_string();
-    println!("[DEBUG] 当前版本: {}", current_version);
+pub async fn check_for_updates(app_handle: AppHandle) -> Result<VersionInfo, String> {
+    debug_log!("========== 开始检查更新 ==========");
 
-    println!("[DEBUG] 创建更新管理器...");
-    let update_manager = UpdateManager::new(current_version);
+    debug_log!("创建更新管理器...");
+    let update_manager = create_update_manager(&app_handle).await?;
 
-    println!("[DEBUG] 调用更新管理器检查更新...");
+    debug_log!("调用更新管理器检查更新...");
     match update_manager.check_for_updates().await {
         Ok(version_info) => {
-            println!("[DEBUG] 检查更新成功: {:?}", version_info);
+            debug_log!("检查更新成功: {:?}", version_info);
             Ok(version_info)
         }
         Err(e) => {
-            println!("[ERROR] 检查更新失败: {}", e);
+            error_log!("检查更新失败: {}", e);
             Err(format!("检查更新失败: {}", e))
         }
     }
 }
 
-/// 下载更新命令
+/// 下载更新命令（下载完成后校验 SHA-256 与签名）
+/// 下载、校验和与签名地址都由后端从当前发布解析，不信任前端传入的地址；
+/// 前端传入的 download_url 只用于确认要下载的仍是对话框里显示的那个版本
 #[command]
 pub async fn download_update(
     app_handle: AppHandle,
-    download_url: String,
+    download_url: Option<String>,
 ) -> Result<String, String> {
-    println!("[DEBUG] ========== 开始下载更新 ==========");
-    println!("[DEBUG] 下载URL: {}", download_url);
+    debug_log!("========== 开始下载更新 ==========");
 
-    let current_version = env!("CARGO_PKG_VERSION").to_string();
-    println!("[DEBUG] 当前版本: {}", current_version);
+    let update_manager = create_update_manager(&app_handle).await?;
+    debug_log!("更新管理器创建成功");
 
-    let update_manager = UpdateManager::new(current_version);
-    println!("[DEBUG] 更新管理器创建成功");
+    let release = update_manager
+        .check_for_updates()
+        .await
+        .map_err(|e| format!("获取发布信息失败: {}", e))?;
+    let Some(resolved_url) = release.download_url else {
+        return Err(release
+            .handoff_message
+            .unwrap_or_else(|| "当前发布没有适用于本平台的更新包".to_string()));
+    };
+    if download_url
+        .as_deref()
+        .is_some_and(|url| url != resolved_url)
+    {
+        return Err("下载链接与当前发布不一致，请重新检查更新".to_string());
+    }
+    let download_url = resolved_url;
+    let (checksum_url, signature_url) = (release.checksum_url, release.signature_url);
+    debug_log!("下载URL: {}", download_url);
 
     // 获取应用数据目录
-    println!("[DEBUG] 获取应用数据目录...");
+    debug_log!("获取应用数据目录...");
     let app_data_dir = match app_handle.path().app_data_dir() {
         Ok(dir) => {
-            println!("[DEBUG] 应用数据目录: {:?}", dir);
+            debug_log!("应用数据目录: {:?}", dir);
             dir
         }
         Err(e) => {
-            println!("[ERROR] 获取应用数据目录失败: {}", e);
+            error_log!("获取应用数据目录失败: {}", e);
             return Err(format!("获取应用数据目录失败: {}", e));
         }
     };
 
     // 创建下载路径
     let download_dir = app_data_dir.join("updates");
-    println!("[DEBUG] 下载目录: {:?}", download_dir);
+    debug_log!("下载目录: {:?}", download_dir);
 
     let filename = extract_filename_from_url(&download_url)
         .unwrap_or_else(|| "GitMentor-update.zip".to_string());
-    println!("[DEBUG] 文件名: {}", filename);
+    debug_log!("文件名: {}", filename);
 
     let download_path = download_dir.join(&filename);
-    println!("[DEBUG] 完整下载路径: {:?}", download_path);
+    debug_log!("完整下载路径: {:?}", download_path);
 
     // 创建进度回调
-    println!("[DEBUG] 创建进度回调...");
+    debug_log!("创建进度回调...");
     let app_handle_clone = app_handle.clone();
     let progress_callback = Box::new(move |downloaded: u64, total: u64| {
         let percentage = if total > 0 {
@@ -141,10 +133,7 @@ pub async fn download_update(
             0.0
         };
 
-        println!(
-            "[DEBUG] 下载进度: {}/{} ({}%)",
-            downloaded, total, percentage
-        );
+        debug_log!("下载进度: {}/{} ({}%)", downloaded, total, percentage);
 
         let event = DownloadProgressEvent {
             downloaded,
@@ -154,21 +143,27 @@ pub async fn download_update(
 
         // 发送进度事件到前端
         if let Err(e) = app_handle_clone.emit("download-progress", &event) {
-            println!("[ERROR] 发送进度事件失败: {}", e);
+            error_log!("发送进度事件失败: {}", e);
         }
     });
 
-    println!("[DEBUG] 开始调用更新管理器下载...");
+    debug_log!("开始调用更新管理器下载...");
     match update_manager
-        .download_update(&download_url, &download_path, Some(progress_callback))
+        .download_verified_update(
+            &download_url,
+            &download_path,
+            checksum_url.as_deref(),
+            signature_url.as_deref(),
+            Some(progress_callback),
+        )
         .await
     {
         Ok(_) => {
-            println!("[DEBUG] 下载完成成功");
+            debug_log!("下载完成成功");
             Ok(download_path.to_string_lossy().to_string())
         }
         Err(e) => {
-            println!("[ERROR] 下载失败: {}", e);
+            error_log!("下载失败: {}", e);
             Err(format!("下载更新失败: {}", e))
         }
     }
@@ -176,29 +171,101 @@ pub async fn download_update(
 
 /// 安装更新命令
 #[command]
-pub async fn install_update(installer_path: String) -> Result<(), String> {
-    let current_version = env!("CARGO_PKG_VERSION").to_string();
-    let update_manager = UpdateManager::new(current_version);
+pub async fn install_update(app_handle: AppHandle, installer_path: String) -> Result<(), String> {
+    let update_manager = create_update_manager(&app_handle).await?;
     let path = PathBuf::from(installer_path);
+    install_with_fallback(&update_manager, &path).await
+}
+
+/// 选择离线更新包，校验文件和签名默认在同目录查找
+#[command]
+pub async fn pick_update_package(app_handle: AppHandle) -> Result<Option<String>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let package = app_handle
+        .dialog()
+        .file()
+        .add_filter(
+            "更新包",
+            &["zip", "msi", "exe", "AppImage", "gz", "deb", "dmg"],
+        )
+        .blocking_pick_file();
+
+    Ok(package.map(|path| path.to_string()))
+}
+
+/// 从本地文件安装更新（离线更新），与在线下载使用相同的校验流程
+#[command]
+pub async fn install_update_from_file(
+    app_handle: AppHandle,
+    package_path: String,
+    checksum_path: Option<String>,
+    signature_path: Option<String>,
+) -> Result<(), String> {
+    debug_log!("从本地文件安装更新: {}", package_path);
+
+    let update_manager = create_update_manager(&app_handle).await?;
+    let download_dir = update_download_dir(&app_handle).await?;
+
+    let checksum_path = checksum_path.map(PathBuf::from);
+    let signature_path = signature_path.map(PathBuf::from);
+    let path = update_manager
+        .import_local_update(
+            &PathBuf::from(&package_path),
+            checksum_path.as_deref(),
+            signature_path.as_deref(),
+            &download_dir,
+        )
+        .await
+        .map_err(|e| format!("本地更新包校验失败: {}", e))?;
+
+    install_with_fallback(&update_manager, &path).await
+}
 
-    match update_manager.install_update(&path).await {
+/// 安装已校验的更新包；便携版 ZIP 直接安装失败时准备延迟更新，其余格式直接返回错误
+async fn install_with_fallback(
+    update_manager: &UpdateManager,
+    path: &PathBuf,
+) -> Result<(), String> {
+    match update_manager.install_update(path).await {
         Ok(_) => {
-            println!("[DEBUG] 更新安装成功");
+            debug_log!("更新安装成功");
             Ok(())
         }
         Err(e) => {
-            println!("[WARN] 直接更新失败，尝试延迟更新: {}", e);
+            // .deb 等需交由系统包管理器处理的更新，直接返回提示
+            if let Some(handoff) = e.downcast_ref::<PackageManagerHandoff>() {
+                return Err(handoff.message.clone());
+            }
+
+            // 校验未通过的更新包拒绝安装，不能留到下次启动
+            if let Some(refused) = e.downcast_ref::<VerificationError>() {
+                error_log!("更新包校验未通过: {}", refused);
+                return Err(refused.message.clone());
+            }
+
+            // 启动时只会应用 pending-update 中的 ZIP，Linux 更新包失败（含已回滚的替换）必须如实报告
+            let format = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .and_then(PackageFormat::from_file_name);
+            if format != Some(PackageFormat::PortableZip) {
+                error_log!("安装更新失败: {}", e);
+                return Err(format!("安装更新失败: {}", e));
+            }
+
+            warn_log!("直接更新失败，尝试延迟更新: {}", e);
 
             // 如果直接更新失败，尝试延迟更新
-            let delayed_result = install_update_delayed(&path, &update_manager).await;
+            let delayed_result = install_update_delayed(path, update_manager).await;
 
             match delayed_result {
                 Ok(_) => {
-                    println!("[DEBUG] 延迟更新已准备，重启后应用");
+                    debug_log!("延迟更新已准备，重启后应用");
                     Ok(())
                 }
                 Err(delayed_e) => {
-                    println!("[ERROR] 延迟更新也失败: {}", delayed_e);
+                    error_log!("延迟更新也失败: {}", delayed_e);
                     Err(format!("安装更新失败: {}", e))
                 }
             }
@@ -207,11 +274,14 @@ pub async fn install_update(installer_path: String) -> Result<(), String> {
 }
 
 /// 延迟更新：在下次启动时应用更新
-async fn install_update_delayed(zip_path: &PathBuf, update_manager: &UpdateManager) -> Result<(), String> {
-    println!("[DEBUG] 准备延迟更新...");
+async fn install_update_delayed(
+    zip_path: &PathBuf,
+    update_manager: &UpdateManager,
+) -> Result<(), String> {
+    debug_log!("准备延迟更新...");
 
-    let current_exe = std::env::current_exe()
-        .map_err(|e| format!("获取当前可执行文件路径失败: {}", e))?;
+    let current_exe =
+        std::env::current_exe().map_err(|e| format!("获取当前可执行文件路径失败: {}", e))?;
 
     let app_dir = current_exe
         .parent()
@@ -236,16 +306,21 @@ async fn install_update_delayed(zip_path: &PathBuf, update_manager: &UpdateManag
         .await
         .map_err(|e| format!("复制待更新文件失败: {}", e))?;
 
+    // 连同校验标记一起复制，启动时安装前会再次核对
+    tokio::fs::copy(verified_marker(zip_path), verified_marker(&pending_zip))
+        .await
+        .map_err(|e| format!("复制校验标记失败: {}", e))?;
+
     // 创建标记文件
     let marker_file = pending_dir.join(".update-pending");
     tokio::fs::write(&marker_file, "pending")
         .await
         .map_err(|e| format!("创建更新标记失败: {}", e))?;
 
-    println!("[DEBUG] 延迟更新已准备: {:?}", pending_zip);
+    debug_log!("延迟更新已准备: {:?}", pending_zip);
 
     // 提示用户重启
-    println!("[INFO] 更新已下载，将在下次重启时应用");
+    info_log!("更新已下载，将在下次重启时应用");
 
     Ok(())
 }
@@ -302,29 +377,14 @@ fn extract_filename_from_url(url: &str) -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
-/// 更新设置结构
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
-pub struct UpdateSettings {
-    pub auto_check: bool,
-    pub check_interval_hours: u32,
-    pub include_prerelease: bool,
-    pub download_path: Option<String>,
-}
-
-impl Default for UpdateSettings {
-    fn default() -> Self {
-        Self {
-            auto_check: true,
-            check_interval_hours: 24,
-            include_prerelease: false,
-            download_path: None,
-        }
-    }
-}
-
 /// 获取更新设置命令
 #[command]
 pub async fn get_update_settings(app_handle: AppHandle) -> Result<UpdateSettings, String> {
+    load_update_settings(&app_handle).await
+}
+
+/// 读取已保存的更新设置，不存在时返回默认值
+async fn load_update_settings(app_handle: &AppHandle) -> Result<UpdateSettings, String> {
     let app_data_dir = app_handle
         .path()
         .app_data_dir()
@@ -337,12 +397,30 @@ pub async fn get_update_settings(app_handle: AppHandle) -> Result<UpdateSettings
             .await
             .map_err(|e| format!("读取更新设置失败: {}", e))?;
 
-        serde_json::from_str(&content).map_err(|e| format!("解析更新设置失败: {}", e))
+        serde_json::from_str::<UpdateSettings>(&content)
+            .map(UpdateSettings::normalize)
+            .map_err(|e| format!("解析更新设置失败: {}", e))
     } else {
         Ok(UpdateSettings::default())
     }
 }
 
+/// 按已保存的设置（渠道、feed、代理）创建更新管理器
+async fn create_update_manager(app_handle: &AppHandle) -> Result<UpdateManager, String> {
+    let settings = load_update_settings(app_handle).await?;
+    UpdateManager::with_settings(env!("CARGO_PKG_VERSION").to_string(), &settings)
+        .map_err(|e| format!("创建更新管理器失败: {}", e))
+}
+
+/// 更新包下载目录
+async fn update_download_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("获取应用数据目录失败: {}", e))?;
+    Ok(app_data_dir.join("updates"))
+}
+
 /// 保存更新设置命令
 #[command]
 pub async fn save_update_settings(
@@ -359,6 +437,11 @@ pub async fn save_update_settings(
         .await
         .map_err(|e| format!("创建应用数据目录失败: {}", e))?;
 
+    // 提前校验代理等配置，避免保存后无法检查更新
+    let settings = settings.normalize();
+    UpdateManager::with_settings(env!("CARGO_PKG_VERSION").to_string(), &settings)
+        .map_err(|e| format!("更新设置无效: {}", e))?;
+
     let settings_path = app_data_dir.join("update_settings.json");
     let content = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("序列化更新设置失败: {}", e))?;
@@ -390,4 +473,53 @@ mod tests {
 
         assert_eq!(extract_filename_from_url("https://example.com/"), None);
     }
+
+    /// 写入更新包及其校验标记，模拟下载校验完成
+    fn verified_package(dir: &std::path::Path, name: &str, content: &[u8]) -> PathBuf {
+        use sha2::{Digest, Sha256};
+
+        let package = dir.join(name);
+        std::fs::write(&package, content).unwrap();
+        std::fs::write(
+            verified_marker(&package),
+            format!("{:x}", Sha256::digest(content)),
+        )
+        .unwrap();
+        package
+    }
+
+    #[tokio::test]
+    async fn linux_install_failure_is_reported_instead_of_deferred() {
+        use crate::core::update_manager::{InstallKind, InstallLocation, PlatformTarget};
+
+        let dir = tempfile::tempdir().unwrap();
+        let package = verified_package(dir.path(), "GitMentor_9.9.9_x86_64.tar.gz", b"tarball");
+        let manager = UpdateManager::new("1.0.0".to_string())
+            .with_platform(PlatformTarget::new("linux", "x86_64"))
+            .with_install_location(InstallLocation {
+                kind: InstallKind::AppImage,
+                path: Some(dir.path().join("GitMentor.AppImage")),
+            });
+
+        let err = install_with_fallback(&manager, &package).await.unwrap_err();
+        assert!(err.contains("不匹配"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn modified_package_is_refused_instead_of_deferred() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = verified_package(dir.path(), "GitMentorLite-9.9.9.zip", b"portable");
+        std::fs::write(&package, b"modified").unwrap();
+        let manager = UpdateManager::new("1.0.0".to_string());
+
+        let err = install_with_fallback(&manager, &package).await.unwrap_err();
+        assert!(err.contains("校验后被修改"), "{}", err);
+        let pending = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("pending-update")
+            .join("GitMentorLite-9.9.9.zip");
+        assert!(!pending.exists());
+    }
 }
@@ -0,0 +1,245 @@
+use crate::core::git_engine::GitEngine;
+use crate::core::repo_registry::{
+    self, DiscoveredRepository, RegisteredRepository, RepoDefaults, RepoRegistry, RepoScanOptions,
+    RepositoryRegistryUpdate,
+};
+use crate::core::repo_workspace::{RepoWorkspace, WorkspaceRepository, WorkspaceStatus};
+use crate::warn_log;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+
+// 工作区命令：多仓库的打开、关闭、切换与状态汇总；最近 / 收藏仓库登记与目录扫描
+// Author: Evilek, Date: 2025-10-12
+// 仓库级命令都带可选 repo_id，取引擎统一走 workspace_engine，别在各命令里自己查表
+
+/// 按 repo_id 取仓库引擎，缺省为活动仓库
+pub(crate) fn workspace_engine(
+    workspace: &RepoWorkspace,
+    repo_id: Option<&str>,
+) -> Result<Arc<Mutex<GitEngine>>, String> {
+    workspace.engine(repo_id).map_err(|e| e.to_string())
+}
+
+/// 不绑定仓库的引擎副本（克隆、远程校验、按路径分析）
+pub(crate) fn detached_engine(workspace: &RepoWorkspace) -> Result<GitEngine, String> {
+    workspace
+        .base_engine()
+        .map_err(|e| format!("获取Git引擎失败: {}", e))
+}
+
+/// 关闭仓库前记下所在分支，下次打开时前端可以提示 / 恢复
+pub(crate) async fn close_and_remember(
+    workspace: &RepoWorkspace,
+    registry: &Mutex<RepoRegistry>,
+    repo_id: &str,
+) -> Result<(), String> {
+    let branch = workspace
+        .list()
+        .into_iter()
+        .find(|repo| repo.id == repo_id)
+        .and_then(|repo| repo_registry::current_branch(&repo.path));
+
+    workspace
+        .close(repo_id)
+        .await
+        .map_err(|e| format!("关闭仓库失败: {}", e))?;
+
+    if let Some(branch) = branch {
+        let mut registry = registry.lock().await;
+        if registry.get(repo_id).is_some() {
+            if let Err(e) = registry.record_branch(repo_id, branch) {
+                warn_log!("记录仓库分支失败: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 打开仓库加入工作区（已打开则切为活动仓库），启动文件监控并记入最近列表
+#[tauri::command]
+pub async fn open_workspace_repository(
+    path: String,
+    app_handle: AppHandle,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<WorkspaceRepository, String> {
+    let repository = workspace
+        .open(&path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+
+    {
+        let engine = workspace_engine(&workspace, Some(&repository.id))?;
+        let mut engine = engine.lock().await;
+        engine
+            .start_repo_watcher(app_handle)
+            .map_err(|e| format!("Failed to start repository watcher: {}", e))?;
+    }
+
+    // 登记失败不影响打开
+    let branch = repo_registry::current_branch(&repository.path);
+    if let Err(e) = registry.lock().await.record_open(&repository.path, branch) {
+        warn_log!("记录最近仓库失败: {}", e);
+    }
+
+    Ok(repository)
+}
+
+/// 从工作区关闭仓库
+#[tauri::command]
+pub async fn close_workspace_repository(
+    repo_id: String,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<(), String> {
+    close_and_remember(&workspace, &registry, &repo_id).await
+}
+
+/// 切换活动仓库（不带 repo_id 的命令作用于活动仓库）
+#[tauri::command]
+pub async fn set_active_repository(
+    repo_id: String,
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<WorkspaceRepository, String> {
+    workspace
+        .set_active(&repo_id)
+        .map_err(|e| format!("切换仓库失败: {}", e))
+}
+
+/// 列出工作区中已打开的仓库
+#[tauri::command]
+pub async fn list_workspace_repositories(
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<Vec<WorkspaceRepository>, String> {
+    Ok(workspace.list())
+}
+
+/// 汇总所有已打开仓库的状态：哪些有未提交 / 未推送的工作
+#[tauri::command]
+pub async fn get_workspace_status(
+    workspace: State<'_, RepoWorkspace>,
+) -> Result<WorkspaceStatus, String> {
+    workspace
+        .status()
+        .await
+        .map_err(|e| format!("获取工作区状态失败: {}", e))
+}
+
+/// 最近 / 收藏仓库列表（收藏在前，按最近打开倒序）
+#[tauri::command]
+pub async fn get_recent_repositories(
+    limit: Option<usize>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<Vec<RegisteredRepository>, String> {
+    let mut repositories = registry.lock().await.list();
+    if let Some(limit) = limit {
+        repositories.truncate(limit);
+    }
+    Ok(repositories)
+}
+
+/// 登记仓库但不打开（扫描结果加入列表）
+#[tauri::command]
+pub async fn register_repositories(
+    paths: Vec<String>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<Vec<RegisteredRepository>, String> {
+    let mut registry = registry.lock().await;
+    paths
+        .iter()
+        .map(|path| {
+            registry
+                .register(path)
+                .map_err(|e| format!("登记仓库失败: {}", e))
+        })
+        .collect()
+}
+
+/// 修改显示名、分组或收藏状态
+#[tauri::command]
+pub async fn update_registered_repository(
+    repo_id: String,
+    update: RepositoryRegistryUpdate,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<RegisteredRepository, String> {
+    registry
+        .lock()
+        .await
+        .update(&repo_id, update)
+        .map_err(|e| format!("更新仓库信息失败: {}", e))
+}
+
+/// 从最近列表移除（不影响磁盘上的仓库）
+#[tauri::command]
+pub async fn remove_registered_repository(
+    repo_id: String,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<(), String> {
+    registry
+        .lock()
+        .await
+        .remove(&repo_id)
+        .map_err(|e| format!("移除仓库失败: {}", e))
+}
+
+/// 所有仓库分组
+#[tauri::command]
+pub async fn get_repository_groups(
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<Vec<String>, String> {
+    Ok(registry.lock().await.groups())
+}
+
+/// 获取仓库默认设置（模板、语言、Gitflow），缺省为活动仓库
+#[tauri::command]
+pub async fn get_repository_defaults(
+    repo_id: Option<String>,
+    workspace: State<'_, RepoWorkspace>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<RepoDefaults, String> {
+    let Some(repo_id) = repo_id.or_else(|| workspace.active_id()) else {
+        return Ok(RepoDefaults::default());
+    };
+    Ok(registry.lock().await.defaults(&repo_id))
+}
+
+/// 保存仓库默认设置
+#[tauri::command]
+pub async fn set_repository_defaults(
+    repo_id: String,
+    defaults: RepoDefaults,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<RegisteredRepository, String> {
+    registry
+        .lock()
+        .await
+        .set_defaults(&repo_id, defaults)
+        .map_err(|e| format!("保存仓库默认设置失败: {}", e))
+}
+
+/// 扫描目录下的 Git 仓库（按深度和忽略规则），结果标记是否已登记
+#[tauri::command]
+pub async fn scan_repositories(
+    root: String,
+    options: Option<RepoScanOptions>,
+    registry: State<'_, Mutex<RepoRegistry>>,
+) -> Result<Vec<DiscoveredRepository>, String> {
+    let options = options.unwrap_or_default();
+    let root = PathBuf::from(root);
+
+    let found =
+        tokio::task::spawn_blocking(move || repo_registry::scan_repositories(&root, &options))
+            .await
+            .map_err(|e| format!("扫描仓库失败: {}", e))?
+            .map_err(|e| format!("扫描仓库失败: {}", e))?;
+
+    let registry = registry.lock().await;
+    Ok(found
+        .into_iter()
+        .map(|mut repo| {
+            repo.registered = registry.get(&repo.id).is_some();
+            repo
+        })
+        .collect())
+}
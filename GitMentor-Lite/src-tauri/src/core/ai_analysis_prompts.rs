@@ -708,6 +708,7 @@ mod tests {
             summary: "Test".to_string(),
             impact_level: ImpactLevel::Low,
             tags: vec!["frontend".to_string()],
+            impact: None,
         };
 
         let tech_stack = manager.analyze_tech_stack(&[analysis]);
@@ -15,6 +15,8 @@ pub struct AIConfig {
     pub providers: ProvidersConfig,
     pub features: FeaturesConfig,
     pub advanced: AdvancedConfig,
+    #[serde(default)]
+    pub commit_lint: CommitLintConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +146,16 @@ pub struct FeaturesConfig {
     pub enable_layered_commit: bool,
     pub use_recent_commits: bool,
     pub enable_streaming: bool,
+    /// 学习提交风格时读取的最近提交数量
+    #[serde(default = "default_recent_commits_count")]
+    pub recent_commits_count: usize,
+    /// 只参考当前作者（user.email）的提交
+    #[serde(default)]
+    pub recent_commits_author_only: bool,
+}
+
+fn default_recent_commits_count() -> usize {
+    20
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,6 +166,59 @@ pub struct AdvancedConfig {
     pub retry_count: u32,
 }
 
+/// 标题描述的大小写要求，只作用于首个 ASCII 字母
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubjectCase {
+    Any,
+    /// 首字母小写（全大写的缩写如 README 不受影响）
+    LowerCase,
+    /// 首字母大写
+    SentenceCase,
+}
+
+/// 提交消息规范，规则对应 commitlint 的同名配置；长度为 0 表示不限制
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommitLintConfig {
+    pub enabled: bool,
+    /// 允许的类型，留空时使用模板的 commit_types
+    pub allowed_types: Vec<String>,
+    pub require_scope: bool,
+    pub header_max_length: usize,
+    pub subject_case: SubjectCase,
+    /// 禁止标题以句号结尾
+    pub subject_full_stop: bool,
+    pub body_max_line_length: usize,
+    /// 必须存在的 trailer，如 Signed-off-by
+    pub required_trailers: Vec<String>,
+    /// 工单号正则，如 `[A-Z]+-\d+`；为空表示不要求
+    pub ticket_pattern: Option<String>,
+    /// AI 输出仍不合规时使用确定性修正
+    pub auto_fix: bool,
+    /// AI 输出不合规时重新提示的次数
+    pub max_reprompts: u32,
+}
+
+impl Default for CommitLintConfig {
+    /// 默认关闭：开启后手写提交也会被校验，由用户在设置中主动启用
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_types: Vec::new(),
+            require_scope: false,
+            header_max_length: 72,
+            subject_case: SubjectCase::LowerCase,
+            subject_full_stop: true,
+            body_max_line_length: 100,
+            required_trailers: Vec::new(),
+            ticket_pattern: None,
+            auto_fix: true,
+            max_reprompts: 1,
+        }
+    }
+}
+
 impl Default for AIConfig {
     fn default() -> Self {
         Self {
@@ -228,6 +293,8 @@ impl Default for AIConfig {
                 enable_layered_commit: true,
                 use_recent_commits: true,
                 enable_streaming: true,
+                recent_commits_count: default_recent_commits_count(),
+                recent_commits_author_only: false,
             },
             advanced: AdvancedConfig {
                 temperature: 0.7,
@@ -235,6 +302,7 @@ impl Default for AIConfig {
                 timeout: 60,
                 retry_count: 3,
             },
+            commit_lint: CommitLintConfig::default(),
         }
     }
 }
@@ -1,15 +1,18 @@
+use crate::{error_log, info_log, warn_log};
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::core::ai_config::{AIConfig, AIConfigManager};
 use crate::core::ai_provider::{
-    AIModel, AIProviderFactory, AIRequest, AIResponse, ConnectionTestResult,
+    AIModel, AIProviderFactory, AIRequest, AIResponse, ChatMessage, ConnectionTestResult,
 };
 use crate::core::ai_response_cache::AIResponseCache;
+use crate::core::commit_lint::CommitLinter;
 use crate::core::conversation_logger::{ConversationLogger, ConversationRecord};
 use crate::core::prompt_manager::{CommitContext, PromptManager, PromptTemplate};
 use crate::core::providers::create_provider_factory;
+use crate::types::git_types::CommitLintResult;
 
 /**
  * AI管理器 - 统一管理所有AI提供商
@@ -120,7 +123,7 @@ impl AIManager {
                         } else {
                             "普通重试"
                         };
-                        eprintln!("🔄 [Retry] {} 第 {} 次重试成功", retry_type, retry);
+                        error_log!("🔄 [Retry] {} 第 {} 次重试成功", retry_type, retry);
                     }
                     return Ok(response);
                 }
@@ -135,7 +138,7 @@ impl AIManager {
                             // 配额超限错误：使用更长的指数退避等待
                             let base_delay = 5000; // 5秒基础延迟
                             let delay = base_delay * (2_u64.pow(retry as u32));
-                            eprintln!(
+                            warn_log!(
                                 "⚠️ [Quota Retry] 检测到配额超限错误，第 {} 次重试，等待 {} 秒后重试...",
                                 retry + 1,
                                 delay / 1000
@@ -143,7 +146,7 @@ impl AIManager {
                             tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
                         } else {
                             // 普通错误：使用标准指数退避等待
-                            eprintln!("⚠️ [Retry] 第 {} 次尝试失败，准备重试...", retry + 1);
+                            warn_log!("⚠️ [Retry] 第 {} 次尝试失败，准备重试...", retry + 1);
                             let wait_time =
                                 tokio::time::Duration::from_millis(1000 * 2_u64.pow(retry as u32));
                             tokio::time::sleep(wait_time).await;
@@ -151,12 +154,12 @@ impl AIManager {
                     } else {
                         // 最后一次重试失败，记录详细错误信息
                         if is_quota_error {
-                            eprintln!("❌ [Quota Error] 所有重试均失败，已达到最大重试次数。错误详情:");
-                            eprintln!("❌ [Quota Error] {}", last_error.as_ref().unwrap());
-                            eprintln!("❌ [Quota Error] 建议：1) 检查API配额设置 2) 升级API套餐 3) 等待配额重置");
+                            error_log!("❌ [Quota Error] 所有重试均失败，已达到最大重试次数。错误详情:");
+                            error_log!("❌ [Quota Error] {}", last_error.as_ref().unwrap());
+                            error_log!("❌ [Quota Error] 建议：1) 检查API配额设置 2) 升级API套餐 3) 等待配额重置");
                         } else {
-                            eprintln!("❌ [Error] 所有重试均失败，已达到最大重试次数");
-                            eprintln!("❌ [Error] 最后错误: {}", last_error.as_ref().unwrap());
+                            error_log!("❌ [Error] 所有重试均失败，已达到最大重试次数");
+                            error_log!("❌ [Error] 最后错误: {}", last_error.as_ref().unwrap());
                         }
                     }
                 }
@@ -219,7 +222,7 @@ impl AIManager {
                         } else {
                             "普通重试"
                         };
-                        eprintln!("🔄 [Retry] {} 第 {} 次重试成功", retry_type, retry);
+                        error_log!("🔄 [Retry] {} 第 {} 次重试成功", retry_type, retry);
                     }
                     return Ok(response);
                 }
@@ -236,7 +239,7 @@ impl AIManager {
                             // 配额超限错误：使用更长的指数退避等待
                             let base_delay = 5000; // 5秒基础延迟
                             let delay = base_delay * (2_u64.pow(retry as u32));
-                            eprintln!(
+                            warn_log!(
                                 "⚠️ [Quota Retry] 检测到配额超限错误，第 {} 次重试，等待 {} 秒后重试...",
                                 retry + 1,
                                 delay / 1000
@@ -244,7 +247,7 @@ impl AIManager {
                             tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
                         } else {
                             // 普通错误：使用标准指数退避等待
-                            eprintln!("⚠️ [Retry] 第 {} 次尝试失败，准备重试...", retry + 1);
+                            warn_log!("⚠️ [Retry] 第 {} 次尝试失败，准备重试...", retry + 1);
                             let wait_time =
                                 tokio::time::Duration::from_millis(1000 * 2_u64.pow(retry as u32));
                             tokio::time::sleep(wait_time).await;
@@ -252,12 +255,12 @@ impl AIManager {
                     } else {
                         // 最后一次重试失败，记录详细错误信息
                         if is_quota_error {
-                            eprintln!("❌ [Quota Error] 所有重试均失败，已达到最大重试次数。错误详情:");
-                            eprintln!("❌ [Quota Error] {}", error_string);
-                            eprintln!("❌ [Quota Error] 建议：1) 检查API配额设置 2) 升级API套餐 3) 等待配额重置");
+                            error_log!("❌ [Quota Error] 所有重试均失败，已达到最大重试次数。错误详情:");
+                            error_log!("❌ [Quota Error] {}", error_string);
+                            error_log!("❌ [Quota Error] 建议：1) 检查API配额设置 2) 升级API套餐 3) 等待配额重置");
                         } else {
-                            eprintln!("❌ [Error] 所有重试均失败，已达到最大重试次数");
-                            eprintln!("❌ [Error] 最后错误: {}", error_string);
+                            error_log!("❌ [Error] 所有重试均失败，已达到最大重试次数");
+                            error_log!("❌ [Error] 最后错误: {}", error_string);
                         }
                     }
                 }
@@ -319,6 +322,7 @@ impl AIManager {
         let (_template_max_tokens, temperature) = prompt_manager
             .get_template_config(template_id)
             .unwrap_or((Some(200), Some(0.3)));
+        drop(prompt_manager);
 
         // 使用系统全局配置的max_tokens，而不是模板中的小数值，避免响应被截断
         // Author: Evilek, Date: 2025-01-09
@@ -351,7 +355,7 @@ impl AIManager {
                 reasoning_content: cached_entry.reasoning_content,
             };
 
-            eprintln!("🎯 [Cache] 返回缓存响应，跳过API调用");
+            error_log!("🎯 [Cache] 返回缓存响应，跳过API调用");
             return Ok(cached_response);
         }
         drop(cache);
@@ -386,6 +390,11 @@ impl AIManager {
                     );
                     drop(logger);
 
+                    // 按提交规范校验，缓存校验后的消息
+                    let (response, _) = self
+                        .enforce_commit_lint(Some(template_id), &request, response, &context.issue_keys)
+                        .await;
+
                     // 缓存成功响应
                     let mut cache = self.response_cache.write().await;
                     let _ = cache.set(
@@ -403,7 +412,7 @@ impl AIManager {
                         } else {
                             "普通重试"
                         };
-                        eprintln!("🔄 [Retry] {} 第 {} 次重试成功", retry_type, retry);
+                        error_log!("🔄 [Retry] {} 第 {} 次重试成功", retry_type, retry);
                     }
 
                     return Ok(response);
@@ -431,7 +440,7 @@ impl AIManager {
                             // 配额超限错误：使用更长的指数退避等待
                             let base_delay = 5000; // 5秒基础延迟
                             let delay = base_delay * (2_u64.pow(retry as u32));
-                            eprintln!(
+                            warn_log!(
                                 "⚠️ [Quota Retry] 检测到配额超限错误，第 {} 次重试，等待 {} 秒后重试...",
                                 retry + 1,
                                 delay / 1000
@@ -439,7 +448,7 @@ impl AIManager {
                             tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
                         } else {
                             // 普通错误：使用标准指数退避等待
-                            eprintln!("⚠️ [Retry] 第 {} 次尝试失败，准备重试...", retry + 1);
+                            warn_log!("⚠️ [Retry] 第 {} 次尝试失败，准备重试...", retry + 1);
                             let wait_time =
                                 tokio::time::Duration::from_millis(1000 * 2_u64.pow(retry as u32));
                             tokio::time::sleep(wait_time).await;
@@ -447,12 +456,12 @@ impl AIManager {
                     } else {
                         // 最后一次重试失败，记录详细错误信息
                         if is_quota_error {
-                            eprintln!("❌ [Quota Error] 所有重试均失败，已达到最大重试次数。错误详情:");
-                            eprintln!("❌ [Quota Error] {}", error_string);
-                            eprintln!("❌ [Quota Error] 建议：1) 检查API配额设置 2) 升级API套餐 3) 等待配额重置");
+                            error_log!("❌ [Quota Error] 所有重试均失败，已达到最大重试次数。错误详情:");
+                            error_log!("❌ [Quota Error] {}", error_string);
+                            error_log!("❌ [Quota Error] 建议：1) 检查API配额设置 2) 升级API套餐 3) 等待配额重置");
                         } else {
-                            eprintln!("❌ [Error] 所有重试均失败，已达到最大重试次数");
-                            eprintln!("❌ [Error] 最后错误: {}", error_string);
+                            error_log!("❌ [Error] 所有重试均失败，已达到最大重试次数");
+                            error_log!("❌ [Error] 最后错误: {}", error_string);
                         }
                     }
                 }
@@ -463,6 +472,105 @@ impl AIManager {
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("未知错误")))
     }
 
+    /// 按配置的提交规范校验提交消息，template_id 用于读取允许的提交类型
+    /// Author: Evilek, Date: 2025-10-07
+    pub async fn lint_commit_message(
+        &self,
+        message: &str,
+        template_id: Option<&str>,
+        issue_keys: &[String],
+    ) -> Result<CommitLintResult> {
+        let linter = self.commit_linter(template_id).await?;
+        Ok(linter.lint(message, issue_keys))
+    }
+
+    /// 校验 AI 生成的提交消息：不合规时带着问题清单重新提示，仍不合规则做确定性修正
+    /// 规范关闭或配置无效时原样返回
+    /// Author: Evilek, Date: 2025-10-07
+    pub async fn enforce_commit_lint(
+        &self,
+        template_id: Option<&str>,
+        request: &AIRequest,
+        mut response: AIResponse,
+        issue_keys: &[String],
+    ) -> (AIResponse, Option<CommitLintResult>) {
+        let config = self.get_config().await.commit_lint;
+        if !config.enabled {
+            return (response, None);
+        }
+        let linter = match self.commit_linter(template_id).await {
+            Ok(linter) => linter,
+            Err(e) => {
+                warn_log!("⚠️ [CommitLint] 提交规范配置无效，跳过校验: {}", e);
+                return (response, None);
+            }
+        };
+
+        let mut result = linter.lint(&response.content, issue_keys);
+        let mut attempt = 0;
+        while !result.valid && attempt < config.max_reprompts {
+            attempt += 1;
+            info_log!(
+                "🔁 [CommitLint] 第 {} 次重新提示，问题: {:?}",
+                attempt,
+                result.issues.iter().map(|issue| &issue.rule).collect::<Vec<_>>()
+            );
+
+            let mut messages = request.messages.clone();
+            messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: response.content.clone(),
+            });
+            messages.push(ChatMessage {
+                role: "user".to_string(),
+                content: linter.reprompt(&result),
+            });
+            let retry_request = AIRequest {
+                messages,
+                ..request.clone()
+            };
+
+            match self.generate_commit_message(retry_request).await {
+                Ok(retried) => {
+                    response = AIResponse {
+                        reasoning_content: retried.reasoning_content.or(response.reasoning_content),
+                        ..retried
+                    };
+                    result = linter.lint(&response.content, issue_keys);
+                }
+                Err(e) => {
+                    warn_log!("⚠️ [CommitLint] 重新提示失败: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if !result.valid && config.auto_fix {
+            if let Some(fixed) = result.fixed_message.clone() {
+                response.content = fixed;
+                result = linter.lint(&response.content, issue_keys);
+            }
+        }
+
+        (response, Some(result))
+    }
+
+    async fn commit_linter(&self, template_id: Option<&str>) -> Result<CommitLinter> {
+        let config = self.get_config().await.commit_lint;
+        let template_types: Vec<String> = match template_id {
+            Some(id) => {
+                let prompt_manager = self.prompt_manager.read().await;
+                prompt_manager
+                    .get_template(id)
+                    .and_then(|template| template.commit_types.as_ref())
+                    .map(|types| types.iter().map(|t| t.name.clone()).collect())
+                    .unwrap_or_default()
+            }
+            None => Vec::new(),
+        };
+        CommitLinter::new(&config, &template_types)
+    }
+
     /// 获取所有可用的提示模板
     pub async fn get_prompt_templates(&self) -> Vec<PromptTemplate> {
         let prompt_manager = self.prompt_manager.read().await;
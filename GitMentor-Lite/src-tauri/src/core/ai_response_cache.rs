@@ -1,3 +1,4 @@
+use crate::{debug_log, error_log, info_log, warn_log};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -39,7 +40,7 @@ impl AIResponseCache {
 
         // 加载现有缓存
         if let Err(e) = cache.load_cache() {
-            eprintln!("⚠️ [Cache] 加载缓存失败: {}", e);
+            warn_log!("⚠️ [Cache] 加载缓存失败: {}", e);
         }
 
         cache
@@ -107,10 +108,10 @@ impl AIResponseCache {
                 .as_secs();
 
             if now - entry.timestamp <= self.max_age_seconds {
-                eprintln!("✅ [Cache] 命中缓存: {}", template_id);
+                debug_log!("✅ [Cache] 命中缓存: {}", template_id);
                 Some(entry.clone())
             } else {
-                eprintln!("⏰ [Cache] 缓存已过期: {}", template_id);
+                warn_log!("⏰ [Cache] 缓存已过期: {}", template_id);
                 None
             }
         } else {
@@ -150,11 +151,11 @@ impl AIResponseCache {
 
         tokio::spawn(async move {
             if let Err(e) = fs::write(&cache_file, cache_data) {
-                eprintln!("❌ [Cache] 保存缓存失败: {}", e);
+                error_log!("❌ [Cache] 保存缓存失败: {}", e);
             }
         });
 
-        eprintln!("💾 [Cache] 缓存响应: {}", template_id);
+        info_log!("💾 [Cache] 缓存响应: {}", template_id);
         Ok(())
     }
 
@@ -180,7 +181,7 @@ impl AIResponseCache {
             .filter(|(_, entry)| now - entry.timestamp <= self.max_age_seconds)
             .collect();
 
-        eprintln!("✅ [Cache] 加载了 {} 个缓存项", self.cache.len());
+        debug_log!("✅ [Cache] 加载了 {} 个缓存项", self.cache.len());
         Ok(())
     }
 
@@ -193,7 +194,7 @@ impl AIResponseCache {
             fs::remove_file(&cache_file)?;
         }
 
-        eprintln!("🧹 [Cache] 已清理所有缓存");
+        info_log!("🧹 [Cache] 已清理所有缓存");
         Ok(())
     }
 
@@ -0,0 +1,498 @@
+use crate::types::git_types::{GitflowBranchMetadata, GitflowQaStatus};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use git2::{Commit, ErrorCode, Oid, Repository, Signature};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// Gitflow 分支元数据存储：QA 状态、工单链接、负责人与风险说明
+// Author: Evilek, Date: 2025-10-06
+// 数据保存在 refs/gitmentor/branch-metadata 指向的提交中，每个分支一个 JSON 文件，
+// 可以像普通引用一样推送/拉取；与远程合并时以共同祖先为基准三方合并，
+// 只有一端改动（含删除）的分支采用改动方，两端都改了的取 updated_at 较新的一份
+
+/// 元数据引用
+pub const METADATA_REF: &str = "refs/gitmentor/branch-metadata";
+
+/// 拉取远程元数据时使用的本地跟踪引用前缀，后接远程名
+pub const REMOTE_METADATA_PREFIX: &str = "refs/gitmentor/remotes";
+
+/// 远程 remote_name 的元数据跟踪引用
+pub fn remote_tracking_ref(remote_name: &str) -> String {
+    format!("{}/{}/branch-metadata", REMOTE_METADATA_PREFIX, remote_name)
+}
+
+/// 读取全部分支的元数据；引用不存在时返回空表
+pub fn load_all(repo: &Repository) -> Result<BTreeMap<String, GitflowBranchMetadata>> {
+    match snapshot_commit(repo, METADATA_REF)? {
+        Some(commit) => read_snapshot(repo, &commit),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+pub fn load(repo: &Repository, branch_name: &str) -> Result<Option<GitflowBranchMetadata>> {
+    Ok(load_all(repo)?.remove(branch_name))
+}
+
+/// 合并更新某个分支的元数据：changes 中有值的字段覆盖原值，空字符串表示清除；
+/// extra 中值为空的键会被删除
+pub fn update(
+    repo: &Repository,
+    branch_name: &str,
+    changes: &GitflowBranchMetadata,
+) -> Result<GitflowBranchMetadata> {
+    let parent = snapshot_commit(repo, METADATA_REF)?;
+    let mut entries = match &parent {
+        Some(commit) => read_snapshot(repo, commit)?,
+        None => BTreeMap::new(),
+    };
+
+    let signature = signature(repo)?;
+    let mut metadata = entries.remove(branch_name).unwrap_or_default();
+    apply_changes(&mut metadata, changes);
+    metadata.updated_at = Some(Utc::now().to_rfc3339());
+    metadata.updated_by = signature.name().map(str::to_string);
+    entries.insert(branch_name.to_string(), metadata.clone());
+
+    let parents: Vec<&Commit> = parent.iter().collect();
+    write_snapshot(
+        repo,
+        &entries,
+        &parents,
+        parent.as_ref().map(Commit::id),
+        &format!("Update branch metadata for {}", branch_name),
+    )?;
+
+    Ok(metadata)
+}
+
+/// 把 remote_ref 指向的远程快照合并进本地，返回从远程采用（含删除）的分支数
+pub fn merge_remote(repo: &Repository, remote_ref: &str) -> Result<usize> {
+    let remote = match snapshot_commit(repo, remote_ref)? {
+        Some(commit) => commit,
+        None => return Ok(0),
+    };
+
+    let local = match snapshot_commit(repo, METADATA_REF)? {
+        Some(commit) => commit,
+        None => {
+            set_metadata_ref(repo, remote.id(), None, "branch metadata: from remote")?;
+            return Ok(read_snapshot(repo, &remote)?.len());
+        }
+    };
+
+    if local.id() == remote.id() || repo.graph_descendant_of(local.id(), remote.id())? {
+        return Ok(0);
+    }
+
+    let local_entries = read_snapshot(repo, &local)?;
+    let remote_entries = read_snapshot(repo, &remote)?;
+
+    if repo.graph_descendant_of(remote.id(), local.id())? {
+        set_metadata_ref(
+            repo,
+            remote.id(),
+            Some(local.id()),
+            "branch metadata: fast-forward",
+        )?;
+        let branches: BTreeSet<&String> =
+            local_entries.keys().chain(remote_entries.keys()).collect();
+        return Ok(branches
+            .into_iter()
+            .filter(|branch| local_entries.get(*branch) != remote_entries.get(*branch))
+            .count());
+    }
+
+    let base_entries = match repo.merge_base(local.id(), remote.id()) {
+        Ok(oid) => read_snapshot(repo, &repo.find_commit(oid)?)?,
+        Err(e) if e.code() == ErrorCode::NotFound => BTreeMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut merged = local_entries.clone();
+    let mut adopted = 0;
+    let branches: BTreeSet<&String> = local_entries.keys().chain(remote_entries.keys()).collect();
+    for branch in branches {
+        let ours = local_entries.get(branch);
+        let theirs = remote_entries.get(branch);
+        let base = base_entries.get(branch);
+
+        let take_theirs = match (ours, theirs) {
+            _ if ours == theirs || theirs == base => false,
+            _ if ours == base => true,
+            // 两端都改了：修改优先于删除，否则取较新的一份
+            (Some(ours), Some(theirs)) => theirs.updated_at > ours.updated_at,
+            (None, Some(_)) => true,
+            _ => false,
+        };
+        if !take_theirs {
+            continue;
+        }
+
+        match theirs {
+            Some(theirs) => merged.insert(branch.clone(), theirs.clone()),
+            None => merged.remove(branch),
+        };
+        adopted += 1;
+    }
+
+    write_snapshot(
+        repo,
+        &merged,
+        &[&local, &remote],
+        Some(local.id()),
+        "Merge remote branch metadata",
+    )?;
+
+    Ok(adopted)
+}
+
+/// 把创建分支时的自定义字段映射为元数据，识别常用键，其余放入 extra
+pub fn from_fields(fields: &HashMap<String, String>) -> GitflowBranchMetadata {
+    let mut metadata = GitflowBranchMetadata::default();
+
+    for (key, value) in fields {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+
+        match key.as_str() {
+            "qaStatus" | "qa_status" => {
+                metadata.qa_status = parse_qa_status(value);
+            }
+            "ticketUrl" | "ticket_url" | "ticket" => metadata.ticket_url = Some(value.to_string()),
+            "owner" => metadata.owner = Some(value.to_string()),
+            "riskNotes" | "risk_notes" | "risk" => metadata.risk_notes = Some(value.to_string()),
+            _ => {
+                metadata.extra.insert(key.clone(), value.to_string());
+            }
+        }
+    }
+
+    metadata
+}
+
+/// 用于状态播报/复盘摘要的元数据行
+pub fn digest_lines(metadata: &GitflowBranchMetadata) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if let Some(status) = metadata.qa_status {
+        lines.push(format!("QA 状态：{}", status.label()));
+    }
+    if let Some(ticket) = &metadata.ticket_url {
+        lines.push(format!("工单：{}", ticket));
+    }
+    if let Some(owner) = &metadata.owner {
+        lines.push(format!("负责人：{}", owner));
+    }
+    if let Some(risk) = &metadata.risk_notes {
+        lines.push(format!("风险说明：{}", risk));
+    }
+    for (key, value) in &metadata.extra {
+        lines.push(format!("{}：{}", key, value));
+    }
+
+    lines
+}
+
+fn parse_qa_status(value: &str) -> Option<GitflowQaStatus> {
+    serde_json::from_value(serde_json::Value::String(value.to_ascii_lowercase())).ok()
+}
+
+fn apply_changes(metadata: &mut GitflowBranchMetadata, changes: &GitflowBranchMetadata) {
+    fn apply(target: &mut Option<String>, change: &Option<String>) {
+        if let Some(value) = change {
+            let value = value.trim();
+            *target = (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+
+    if changes.qa_status.is_some() {
+        metadata.qa_status = changes.qa_status;
+    }
+    apply(&mut metadata.ticket_url, &changes.ticket_url);
+    apply(&mut metadata.owner, &changes.owner);
+    apply(&mut metadata.risk_notes, &changes.risk_notes);
+
+    for (key, value) in &changes.extra {
+        if value.trim().is_empty() {
+            metadata.extra.remove(key);
+        } else {
+            metadata.extra.insert(key.clone(), value.trim().to_string());
+        }
+    }
+}
+
+fn snapshot_commit<'r>(repo: &'r Repository, reference: &str) -> Result<Option<Commit<'r>>> {
+    match repo.find_reference(reference) {
+        Ok(reference) => Ok(Some(reference.peel_to_commit()?)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn read_snapshot(
+    repo: &Repository,
+    commit: &Commit,
+) -> Result<BTreeMap<String, GitflowBranchMetadata>> {
+    let mut entries = BTreeMap::new();
+
+    for entry in commit.tree()?.iter() {
+        let (Some(name), Ok(object)) = (entry.name(), entry.to_object(repo)) else {
+            continue;
+        };
+        let Some(branch) = name.strip_suffix(".json").map(decode_entry_name) else {
+            continue;
+        };
+        let Some(blob) = object.as_blob() else {
+            continue;
+        };
+
+        // 单个文件损坏不影响其他分支
+        if let Ok(metadata) = serde_json::from_slice(blob.content()) {
+            entries.insert(branch, metadata);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// 写入新快照；expected 为读取时的引用值，期间被其他操作改动则失败而不是覆盖
+fn write_snapshot(
+    repo: &Repository,
+    entries: &BTreeMap<String, GitflowBranchMetadata>,
+    parents: &[&Commit],
+    expected: Option<Oid>,
+    message: &str,
+) -> Result<Oid> {
+    let mut builder = repo.treebuilder(None)?;
+    for (branch, metadata) in entries {
+        let content = serde_json::to_string_pretty(metadata)?;
+        let blob = repo.blob(content.as_bytes())?;
+        builder.insert(
+            format!("{}.json", encode_entry_name(branch)),
+            blob,
+            0o100644,
+        )?;
+    }
+
+    let tree = repo.find_tree(builder.write()?)?;
+    let signature = signature(repo)?;
+    let oid = repo.commit(None, &signature, &signature, message, &tree, parents)?;
+    set_metadata_ref(repo, oid, expected, message)?;
+
+    Ok(oid)
+}
+
+/// 比较并交换元数据引用：expected 为 None 时只允许新建
+fn set_metadata_ref(
+    repo: &Repository,
+    oid: Oid,
+    expected: Option<Oid>,
+    message: &str,
+) -> Result<()> {
+    let result = match expected {
+        Some(current) => repo.reference_matching(METADATA_REF, oid, true, current, message),
+        None => repo.reference(METADATA_REF, oid, false, message),
+    };
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) if matches!(e.code(), ErrorCode::Modified | ErrorCode::Exists) => {
+            Err(anyhow!("分支元数据已被其他操作修改，请刷新后重试"))
+        }
+        Err(e) => Err(anyhow!("更新分支元数据引用失败: {}", e)),
+    }
+}
+
+fn signature(repo: &Repository) -> Result<Signature<'static>> {
+    Ok(repo
+        .signature()
+        .or_else(|_| Signature::now("GitMentor", "gitmentor@localhost"))?)
+}
+
+/// 树条目名不能包含 /，分支名中的 % 和 / 需要转义
+fn encode_entry_name(branch: &str) -> String {
+    branch.replace('%', "%25").replace('/', "%2F")
+}
+
+fn decode_entry_name(name: &str) -> String {
+    name.replace("%2F", "/").replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_repo() -> (tempfile::TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn updates_and_reloads_branch_metadata() {
+        let (_dir, repo) = metadata_repo();
+
+        let mut fields = HashMap::new();
+        fields.insert("owner".to_string(), "alice".to_string());
+        fields.insert("ticket".to_string(), "https://tracker/PROJ-1".to_string());
+        fields.insert("component".to_string(), "auth".to_string());
+        update(&repo, "feature/login", &from_fields(&fields)).unwrap();
+
+        let changes = GitflowBranchMetadata {
+            qa_status: Some(GitflowQaStatus::Passed),
+            owner: Some(String::new()),
+            ..Default::default()
+        };
+        let updated = update(&repo, "feature/login", &changes).unwrap();
+
+        assert_eq!(updated.qa_status, Some(GitflowQaStatus::Passed));
+        assert_eq!(updated.owner, None);
+        assert_eq!(
+            updated.ticket_url.as_deref(),
+            Some("https://tracker/PROJ-1")
+        );
+        assert_eq!(
+            updated.extra.get("component").map(String::as_str),
+            Some("auth")
+        );
+        assert_eq!(load(&repo, "feature/login").unwrap(), Some(updated));
+
+        let head = repo
+            .find_reference(METADATA_REF)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(head.parent_count(), 1);
+        assert!(head
+            .tree()
+            .unwrap()
+            .get_name("feature%2Flogin.json")
+            .is_some());
+    }
+
+    #[test]
+    fn merges_remote_snapshot_by_newest_entry() {
+        let (_dir, repo) = metadata_repo();
+
+        update(
+            &repo,
+            "feature/a",
+            &from_fields(&HashMap::from([("owner".to_string(), "old".to_string())])),
+        )
+        .unwrap();
+        let base = repo.refname_to_id(METADATA_REF).unwrap();
+
+        // 模拟远程：在同一基础上修改 feature/a 并新增 feature/b
+        update(
+            &repo,
+            "feature/a",
+            &from_fields(&HashMap::from([("owner".to_string(), "bob".to_string())])),
+        )
+        .unwrap();
+        update(
+            &repo,
+            "feature/b",
+            &from_fields(&HashMap::from([(
+                "qaStatus".to_string(),
+                "blocked".to_string(),
+            )])),
+        )
+        .unwrap();
+        let remote = repo.refname_to_id(METADATA_REF).unwrap();
+        repo.reference(&remote_tracking_ref("origin"), remote, true, "test")
+            .unwrap();
+
+        // 本地从基础分叉，修改另一个字段
+        repo.reference(METADATA_REF, base, true, "test").unwrap();
+        update(
+            &repo,
+            "feature/c",
+            &from_fields(&HashMap::from([(
+                "risk".to_string(),
+                "db migration".to_string(),
+            )])),
+        )
+        .unwrap();
+
+        assert_eq!(
+            merge_remote(&repo, &remote_tracking_ref("origin")).unwrap(),
+            2
+        );
+
+        let merged = load_all(&repo).unwrap();
+        assert_eq!(merged["feature/a"].owner.as_deref(), Some("bob"));
+        assert_eq!(
+            merged["feature/b"].qa_status,
+            Some(GitflowQaStatus::Blocked)
+        );
+        assert_eq!(
+            merged["feature/c"].risk_notes.as_deref(),
+            Some("db migration")
+        );
+
+        let head = repo
+            .find_reference(METADATA_REF)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert_eq!(
+            merge_remote(&repo, &remote_tracking_ref("origin")).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn merge_keeps_deletions_made_on_one_side() {
+        let (_dir, repo) = metadata_repo();
+        let owner =
+            |name: &str| from_fields(&HashMap::from([("owner".to_string(), name.to_string())]));
+
+        update(&repo, "feature/a", &owner("alice")).unwrap();
+        update(&repo, "feature/b", &owner("bob")).unwrap();
+        let base = repo
+            .find_reference(METADATA_REF)
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        let mut entries = read_snapshot(&repo, &base).unwrap();
+
+        // 模拟远程：删除 feature/a
+        entries.remove("feature/a");
+        let remote = write_snapshot(&repo, &entries, &[&base], Some(base.id()), "remote").unwrap();
+        repo.reference(&remote_tracking_ref("origin"), remote, true, "test")
+            .unwrap();
+
+        // 本地从基础分叉：删除 feature/b，新增 feature/c
+        repo.reference(METADATA_REF, base.id(), true, "test")
+            .unwrap();
+        let mut entries = read_snapshot(&repo, &base).unwrap();
+        entries.remove("feature/b");
+        write_snapshot(&repo, &entries, &[&base], Some(base.id()), "local").unwrap();
+        update(&repo, "feature/c", &owner("carol")).unwrap();
+
+        assert_eq!(
+            merge_remote(&repo, &remote_tracking_ref("origin")).unwrap(),
+            1
+        );
+        let merged = load_all(&repo).unwrap();
+        assert_eq!(merged.keys().collect::<Vec<_>>(), vec!["feature/c"]);
+    }
+
+    #[test]
+    fn rejects_writes_based_on_stale_snapshot() {
+        let (_dir, repo) = metadata_repo();
+
+        update(&repo, "feature/a", &GitflowBranchMetadata::default()).unwrap();
+        let stale = repo.refname_to_id(METADATA_REF).unwrap();
+        update(&repo, "feature/b", &GitflowBranchMetadata::default()).unwrap();
+        let current = repo.refname_to_id(METADATA_REF).unwrap();
+
+        let err = write_snapshot(&repo, &BTreeMap::new(), &[], Some(stale), "stale").unwrap_err();
+        assert!(err.to_string().contains("已被其他操作修改"));
+        assert!(write_snapshot(&repo, &BTreeMap::new(), &[], None, "create").is_err());
+        assert_eq!(repo.refname_to_id(METADATA_REF).unwrap(), current);
+    }
+}
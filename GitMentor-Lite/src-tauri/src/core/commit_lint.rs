@@ -0,0 +1,556 @@
+use crate::core::ai_config::{CommitLintConfig, SubjectCase};
+use crate::core::commit_style::strip_gitmoji;
+use crate::types::git_types::{CommitLintIssue, CommitLintResult, CommitLintSeverity};
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// 提交消息规范校验：类似 commitlint 的规则集，用于 AI 输出与手写消息
+// Author: Evilek, Date: 2025-10-07
+// 能确定性修正的问题（类型大小写、句号、超长标题、正文换行、工单号）直接改，其余交给重新提示
+
+/// 模板和配置都没有给出类型时使用的 Conventional Commits 类型
+const DEFAULT_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+/// 模型常写错的类型别名
+const TYPE_ALIASES: &[(&str, &str)] = &[
+    ("feature", "feat"),
+    ("bugfix", "fix"),
+    ("bug", "fix"),
+    ("hotfix", "fix"),
+    ("doc", "docs"),
+    ("tests", "test"),
+    ("refactoring", "refactor"),
+    ("performance", "perf"),
+];
+
+static HEADER_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<type>[A-Za-z]+)(?:\((?P<scope>[^()]*)\))?(?P<breaking>!)?(?P<sep>[:：]\s*)(?P<subject>.*)$",
+    )
+    .unwrap()
+});
+
+static TRAILER_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z][A-Za-z0-9-]*)(?::\s| #)").unwrap());
+
+/// 拆开后的标题：`<emoji> <type>(<scope>)!: <subject>`
+struct Header {
+    emoji: String,
+    kind: String,
+    scope: Option<String>,
+    breaking: bool,
+    separator: String,
+    subject: String,
+}
+
+impl Header {
+    fn parse(header: &str) -> Option<Self> {
+        let (_, rest) = strip_gitmoji(header);
+        let captures = HEADER_PATTERN.captures(rest)?;
+
+        Some(Self {
+            emoji: header[..header.len() - rest.len()].to_string(),
+            kind: captures["type"].to_string(),
+            scope: captures
+                .name("scope")
+                .map(|m| m.as_str().trim().to_string()),
+            breaking: captures.name("breaking").is_some(),
+            separator: captures["sep"].to_string(),
+            subject: captures["subject"].trim().to_string(),
+        })
+    }
+
+    fn prefix(&self) -> String {
+        let mut prefix = format!("{}{}", self.emoji, self.kind);
+        if let Some(scope) = &self.scope {
+            prefix.push_str(&format!("({})", scope));
+        }
+        if self.breaking {
+            prefix.push('!');
+        }
+        prefix.push_str(": ");
+        prefix
+    }
+
+    fn render(&self) -> String {
+        format!("{}{}", self.prefix(), self.subject)
+    }
+}
+
+pub struct CommitLinter {
+    config: CommitLintConfig,
+    allowed_types: Vec<String>,
+    ticket: Option<Regex>,
+}
+
+impl CommitLinter {
+    /// 配置里的 allowed_types 优先，其次是模板的 commit_types，都为空时使用默认类型
+    pub fn new(config: &CommitLintConfig, template_types: &[String]) -> Result<Self> {
+        let allowed_types = [&config.allowed_types[..], template_types]
+            .into_iter()
+            .find(|types| !types.is_empty())
+            .map(|types| types.iter().map(|t| t.to_lowercase()).collect())
+            .unwrap_or_else(|| DEFAULT_TYPES.iter().map(|t| t.to_string()).collect());
+
+        let ticket = match config.ticket_pattern.as_deref().map(str::trim) {
+            Some(pattern) if !pattern.is_empty() => Some(
+                Regex::new(pattern).map_err(|e| anyhow!("工单号正则无效 {}: {}", pattern, e))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            config: config.clone(),
+            allowed_types,
+            ticket,
+        })
+    }
+
+    /// 校验提交消息；issue_keys 为分支名中识别出的工单号，用于修正缺失的工单引用
+    pub fn lint(&self, message: &str, issue_keys: &[String]) -> CommitLintResult {
+        let message = message.trim();
+        let mut issues = Vec::new();
+        let mut push = |rule: &str, severity: CommitLintSeverity, fixable: bool, text: String| {
+            issues.push(CommitLintIssue {
+                rule: rule.to_string(),
+                severity,
+                message: text,
+                fixable,
+            })
+        };
+        let (header_line, body) = split_message(message);
+
+        match Header::parse(header_line) {
+            Some(header) => {
+                if header.separator != ": " {
+                    push(
+                        "header-format",
+                        CommitLintSeverity::Error,
+                        true,
+                        "类型与描述之间应使用英文冒号加一个空格".to_string(),
+                    );
+                }
+                if header.kind != header.kind.to_lowercase() {
+                    push(
+                        "type-case",
+                        CommitLintSeverity::Error,
+                        true,
+                        format!("类型 {} 应为小写", header.kind),
+                    );
+                }
+                if !self.allowed_types.contains(&header.kind.to_lowercase()) {
+                    push(
+                        "type-enum",
+                        CommitLintSeverity::Error,
+                        self.normalize_type(&header.kind).is_some(),
+                        format!(
+                            "类型 {} 不在允许的类型中：{}",
+                            header.kind,
+                            self.allowed_types.join(", ")
+                        ),
+                    );
+                }
+                if self.config.require_scope
+                    && header.scope.as_deref().unwrap_or_default().is_empty()
+                {
+                    push(
+                        "scope-empty",
+                        CommitLintSeverity::Error,
+                        false,
+                        "缺少范围，格式应为 <type>(<scope>): <subject>".to_string(),
+                    );
+                }
+                if header.subject.is_empty() {
+                    push(
+                        "subject-empty",
+                        CommitLintSeverity::Error,
+                        false,
+                        "缺少描述".to_string(),
+                    );
+                }
+                if apply_case(&header.subject, self.config.subject_case) != header.subject {
+                    let expected = match self.config.subject_case {
+                        SubjectCase::SentenceCase => "大写",
+                        _ => "小写",
+                    };
+                    push(
+                        "subject-case",
+                        CommitLintSeverity::Error,
+                        true,
+                        format!("描述首字母应为{}", expected),
+                    );
+                }
+                if self.config.subject_full_stop && has_full_stop(&header.subject) {
+                    push(
+                        "subject-full-stop",
+                        CommitLintSeverity::Error,
+                        true,
+                        "描述不应以句号结尾".to_string(),
+                    );
+                }
+            }
+            None => push(
+                "header-format",
+                CommitLintSeverity::Error,
+                false,
+                "标题不符合 <type>(<scope>): <subject> 格式".to_string(),
+            ),
+        }
+
+        let header_length = header_line.chars().count();
+        if self.config.header_max_length > 0 && header_length > self.config.header_max_length {
+            push(
+                "header-max-length",
+                CommitLintSeverity::Error,
+                false,
+                format!(
+                    "标题长度 {} 超过上限 {} 个字符",
+                    header_length, self.config.header_max_length
+                ),
+            );
+        }
+
+        if body.first().is_some_and(|line| !line.trim().is_empty()) {
+            push(
+                "body-leading-blank",
+                CommitLintSeverity::Warning,
+                true,
+                "标题与正文之间应空一行".to_string(),
+            );
+        }
+        if self.config.body_max_line_length > 0 {
+            let long_lines = body
+                .iter()
+                .filter(|line| line.chars().count() > self.config.body_max_line_length)
+                .count();
+            if long_lines > 0 {
+                push(
+                    "body-max-line-length",
+                    CommitLintSeverity::Warning,
+                    true,
+                    format!(
+                        "正文有 {} 行超过 {} 个字符",
+                        long_lines, self.config.body_max_line_length
+                    ),
+                );
+            }
+        }
+
+        for trailer in &self.config.required_trailers {
+            if !body
+                .iter()
+                .any(|line| trailer_key(line).is_some_and(|key| key.eq_ignore_ascii_case(trailer)))
+            {
+                push(
+                    "trailer-exists",
+                    CommitLintSeverity::Error,
+                    false,
+                    format!("缺少 trailer：{}", trailer),
+                );
+            }
+        }
+
+        if let Some(ticket) = &self.ticket {
+            if !ticket.is_match(message) {
+                push(
+                    "references-empty",
+                    CommitLintSeverity::Error,
+                    self.ticket_for(issue_keys).is_some(),
+                    format!("缺少匹配 {} 的工单号", ticket.as_str()),
+                );
+            }
+        }
+
+        let valid = !issues
+            .iter()
+            .any(|issue| issue.severity == CommitLintSeverity::Error);
+        let fixed_message = if issues.iter().any(|issue| issue.fixable) {
+            Some(self.fix(message, issue_keys)).filter(|fixed| fixed != message)
+        } else {
+            None
+        };
+
+        CommitLintResult {
+            valid,
+            issues,
+            fixed_message,
+        }
+    }
+
+    /// 确定性修正：不改变语义，只处理格式类问题
+    pub fn fix(&self, message: &str, issue_keys: &[String]) -> String {
+        let message = message.trim();
+        let (header_line, body) = split_message(message);
+        let missing_ticket = self
+            .ticket
+            .as_ref()
+            .is_some_and(|ticket| !ticket.is_match(message));
+        let ticket = self.ticket_for(issue_keys).filter(|_| missing_ticket);
+
+        let header = match Header::parse(header_line) {
+            Some(mut header) => {
+                header.kind = self
+                    .normalize_type(&header.kind)
+                    .unwrap_or_else(|| header.kind.clone());
+                if self.config.subject_full_stop {
+                    header.subject = header
+                        .subject
+                        .trim_end_matches(['.', '。'])
+                        .trim_end()
+                        .to_string();
+                }
+                header.subject = apply_case(&header.subject, self.config.subject_case);
+                if let Some(ticket) = ticket {
+                    header.subject = format!("{} {}", ticket, header.subject);
+                }
+                header.render()
+            }
+            None => {
+                let mut header = header_line.to_string();
+                if let Some(ticket) = ticket {
+                    let (_, rest) = strip_gitmoji(&header);
+                    let emoji = header[..header.len() - rest.len()].to_string();
+                    header = format!("{}{} {}", emoji, ticket, rest);
+                }
+                header
+            }
+        };
+
+        let body_lines: Vec<&str> = body
+            .iter()
+            .skip_while(|line| line.trim().is_empty())
+            .copied()
+            .collect();
+        if body_lines.is_empty() {
+            return header;
+        }
+
+        let mut wrapped = Vec::new();
+        for line in body_lines {
+            if self.config.body_max_line_length > 0
+                && line.chars().count() > self.config.body_max_line_length
+                && trailer_key(line).is_none()
+                && !line.contains("://")
+            {
+                wrapped.extend(wrap_line(line, self.config.body_max_line_length));
+            } else {
+                wrapped.push(line.trim_end().to_string());
+            }
+        }
+
+        format!("{}\n\n{}", header, wrapped.join("\n"))
+    }
+
+    /// 重新提示模型时附加的说明，只列出 error 级别的问题
+    pub fn reprompt(&self, result: &CommitLintResult) -> String {
+        let mut prompt = String::from("上面的提交消息不符合仓库的提交规范：\n");
+        for issue in &result.issues {
+            if issue.severity == CommitLintSeverity::Error {
+                prompt.push_str(&format!("- {}（{}）\n", issue.message, issue.rule));
+            }
+        }
+        prompt.push_str(&format!(
+            "\n要求：格式为 <type>(<scope>): <subject>，类型只能是 {}",
+            self.allowed_types.join(", ")
+        ));
+        if self.config.header_max_length > 0 {
+            prompt.push_str(&format!(
+                "，标题不超过 {} 个字符",
+                self.config.header_max_length
+            ));
+        }
+        prompt.push_str("。\n请修正这些问题，只输出新的提交消息，不要任何解释。");
+        prompt
+    }
+
+    /// 大小写或别名能映射到允许的类型时返回规范类型
+    fn normalize_type(&self, kind: &str) -> Option<String> {
+        let lower = kind.to_lowercase();
+        let candidate = TYPE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == lower)
+            .map(|(_, target)| target.to_string())
+            .unwrap_or(lower);
+        self.allowed_types.contains(&candidate).then_some(candidate)
+    }
+
+    fn ticket_for(&self, issue_keys: &[String]) -> Option<String> {
+        let ticket = self.ticket.as_ref()?;
+        issue_keys.iter().find(|key| ticket.is_match(key)).cloned()
+    }
+}
+
+fn split_message(message: &str) -> (&str, Vec<&str>) {
+    let mut lines = message.lines();
+    let header = lines.next().unwrap_or_default().trim();
+    (header, lines.collect())
+}
+
+fn has_full_stop(subject: &str) -> bool {
+    (subject.ends_with('.') && !subject.ends_with("..")) || subject.ends_with('。')
+}
+
+fn apply_case(subject: &str, case: SubjectCase) -> String {
+    let mut chars = subject.chars();
+    let (Some(first), second) = (chars.next(), chars.next()) else {
+        return subject.to_string();
+    };
+    let rest = &subject[first.len_utf8()..];
+    match case {
+        // 第二个字母也是大写时视为缩写（README、API），保持不变
+        SubjectCase::LowerCase
+            if first.is_ascii_uppercase() && !second.is_some_and(|c| c.is_ascii_uppercase()) =>
+        {
+            format!("{}{}", first.to_ascii_lowercase(), rest)
+        }
+        SubjectCase::SentenceCase if first.is_ascii_lowercase() => {
+            format!("{}{}", first.to_ascii_uppercase(), rest)
+        }
+        _ => subject.to_string(),
+    }
+}
+
+/// `Key: value` 或 `Key #value` 形式的 trailer
+fn trailer_key(line: &str) -> Option<&str> {
+    TRAILER_PATTERN
+        .captures(line.trim())
+        .and_then(|captures| captures.get(1))
+        .map(|key| key.as_str())
+}
+
+/// 按词换行；列表项的续行缩进两格，没有空格的长串（如中文）按字符切开
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let indent = if line.trim_start().starts_with("- ") || line.trim_start().starts_with("* ") {
+        " ".repeat(line.len() - line.trim_start().len() + 2)
+    } else {
+        " ".repeat(line.len() - line.trim_start().len())
+    };
+
+    let mut words = Vec::new();
+    for word in line.split_whitespace() {
+        if word.chars().count() > width && !word.is_ascii() {
+            let chars: Vec<char> = word.chars().collect();
+            words.extend(
+                chars
+                    .chunks(width.saturating_sub(indent.len()).max(1))
+                    .map(|chunk| chunk.iter().collect::<String>()),
+            );
+        } else {
+            words.push(word.to_string());
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut current = line[..line.len() - line.trim_start().len()].to_string();
+    let mut has_word = false;
+    for word in words {
+        let length = current.chars().count() + word.chars().count() + usize::from(has_word);
+        if has_word && length > width {
+            lines.push(current);
+            current = format!("{}{}", indent, word);
+        } else {
+            if has_word {
+                current.push(' ');
+            }
+            current.push_str(&word);
+        }
+        has_word = true;
+    }
+    lines.push(current);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(result: &CommitLintResult) -> Vec<&str> {
+        result
+            .issues
+            .iter()
+            .map(|issue| issue.rule.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn fixes_format_problems_deterministically() {
+        let config = CommitLintConfig {
+            header_max_length: 50,
+            body_max_line_length: 40,
+            ticket_pattern: Some(r"PROJ-\d+".to_string()),
+            ..Default::default()
+        };
+        let linter = CommitLinter::new(&config, &["feat".to_string(), "fix".to_string()]).unwrap();
+        let message = "✨ Feature(auth)：Add token refresh when the access token expires soon.\n- refresh the token in the background before the request is sent";
+
+        let result = linter.lint(message, &["PROJ-7".to_string()]);
+        assert!(!result.valid);
+        assert_eq!(
+            rules(&result),
+            vec![
+                "header-format",
+                "type-case",
+                "type-enum",
+                "subject-case",
+                "subject-full-stop",
+                "header-max-length",
+                "body-leading-blank",
+                "body-max-line-length",
+                "references-empty",
+            ]
+        );
+        // 标题过长会改变语义，不自动截断，交给重新生成或用户处理
+        assert!(result
+            .issues
+            .iter()
+            .all(|issue| issue.fixable == (issue.rule != "header-max-length")));
+
+        let fixed = result.fixed_message.unwrap();
+        assert_eq!(
+            fixed,
+            "✨ feat(auth): PROJ-7 add token refresh when the access token expires soon\n\n- refresh the token in the background\n  before the request is sent"
+        );
+        let relinted = linter.lint(&fixed, &[]);
+        assert_eq!(rules(&relinted), vec!["header-max-length"]);
+        assert!(relinted.fixed_message.is_none());
+    }
+
+    #[test]
+    fn reports_unfixable_rules_for_reprompting() {
+        let config = CommitLintConfig {
+            require_scope: true,
+            required_trailers: vec!["Signed-off-by".to_string()],
+            ..Default::default()
+        };
+        let linter = CommitLinter::new(&config, &[]).unwrap();
+
+        let result = linter.lint("wip: 登录页面", &[]);
+        assert_eq!(
+            rules(&result),
+            vec!["type-enum", "scope-empty", "trailer-exists"]
+        );
+        assert!(result.fixed_message.is_none());
+        let prompt = linter.reprompt(&result);
+        assert!(prompt.contains("类型 wip 不在允许的类型中"));
+        assert!(prompt.contains("标题不超过 72 个字符"));
+
+        let ok = linter.lint(
+            "fix(ui): 修复 README 链接\n\nSigned-off-by: Dev <dev@example.com>",
+            &[],
+        );
+        assert!(ok.valid, "{:?}", ok.issues);
+        assert!(ok.issues.is_empty());
+
+        assert!(CommitLinter::new(
+            &CommitLintConfig {
+                ticket_pattern: Some("[".to_string()),
+                ..Default::default()
+            },
+            &[]
+        )
+        .is_err());
+    }
+}
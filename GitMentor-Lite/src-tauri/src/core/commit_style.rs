@@ -0,0 +1,325 @@
+use crate::utils::token_counter::TokenCounter;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 提交风格学习：从最近的提交消息中识别仓库惯例（约定式提交、gitmoji、工单前缀、书写语言），
+// 并挑选少量历史消息作为 few-shot 示例注入提示词
+// Author: Evilek, Date: 2025-10-02
+// 示例按 token 预算裁剪，宁可少给几个示例也别把 diff 挤出上下文
+
+/// 至少需要这么多条历史消息才判断惯例，样本太少时只提供示例
+const MIN_SAMPLES_FOR_CONVENTION: usize = 3;
+/// 单条示例最多保留的行数（标题 + 正文前几行）
+const MAX_EXAMPLE_LINES: usize = 6;
+/// 最多注入的示例条数
+const MAX_EXAMPLES: usize = 8;
+
+static CONVENTIONAL_SUMMARY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([a-z]+)(\([^)]+\))?!?: \S").expect("valid conventional regex"));
+static TICKET_KEY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b([A-Z][A-Z0-9]+)-\d+\b").expect("valid ticket regex"));
+
+/// 仓库的提交风格画像
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CommitStyleProfile {
+    pub sample_size: usize,
+    pub conventional: bool,
+    pub common_types: Vec<String>, // 按出现次数排序的约定式类型，如 feat / fix
+    pub uses_scope: bool,
+    pub uses_gitmoji: bool,
+    pub ticket_prefix: Option<String>, // 工单项目键，如 PROJ
+    pub language: Option<String>,      // zh / ja / ko / en
+    pub uses_body: bool,
+    pub examples: Vec<String>, // 新的在前，已按 token 预算裁剪
+}
+
+impl CommitStyleProfile {
+    /// 分析提交消息（新的在前），examples 总量不超过 example_token_budget
+    pub fn from_messages(messages: &[String], example_token_budget: u32) -> Self {
+        let messages: Vec<&str> = messages
+            .iter()
+            .map(|m| m.trim())
+            .filter(|m| !m.is_empty() && !is_generated_message(m))
+            .collect();
+
+        let mut profile = Self {
+            sample_size: messages.len(),
+            examples: select_examples(&messages, example_token_budget),
+            ..Default::default()
+        };
+
+        if messages.len() < MIN_SAMPLES_FOR_CONVENTION {
+            return profile;
+        }
+
+        let mut conventional = 0;
+        let mut scoped = 0;
+        let mut gitmoji = 0;
+        let mut with_body = 0;
+        let mut types: HashMap<String, usize> = HashMap::new();
+        let mut tickets: HashMap<String, usize> = HashMap::new();
+        let mut languages: HashMap<&'static str, usize> = HashMap::new();
+
+        for message in &messages {
+            let summary = message.lines().next().unwrap_or_default();
+            let (has_gitmoji, rest) = strip_gitmoji(summary);
+            if has_gitmoji {
+                gitmoji += 1;
+            }
+
+            if let Some(captures) = CONVENTIONAL_SUMMARY.captures(rest) {
+                conventional += 1;
+                if captures.get(2).is_some() {
+                    scoped += 1;
+                }
+                *types.entry(captures[1].to_string()).or_default() += 1;
+            }
+
+            if let Some(captures) = TICKET_KEY.captures(summary) {
+                *tickets.entry(captures[1].to_string()).or_default() += 1;
+            }
+
+            *languages.entry(detect_language(summary)).or_default() += 1;
+
+            if message.lines().skip(1).any(|line| !line.trim().is_empty()) {
+                with_body += 1;
+            }
+        }
+
+        let majority = |count: usize| count * 2 >= messages.len();
+
+        profile.conventional = majority(conventional);
+        if profile.conventional {
+            let mut types: Vec<(String, usize)> = types.into_iter().collect();
+            types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            profile.common_types = types.into_iter().take(6).map(|(t, _)| t).collect();
+            profile.uses_scope = scoped * 2 >= conventional; // 约定式提交中过半使用 scope 即可
+        }
+        profile.uses_gitmoji = majority(gitmoji);
+        profile.ticket_prefix = tickets
+            .into_iter()
+            .filter(|(_, count)| majority(*count))
+            .max_by_key(|(_, count)| *count)
+            .map(|(key, _)| key);
+        profile.language = languages
+            .into_iter()
+            .filter(|(_, count)| majority(*count))
+            .max_by_key(|(_, count)| *count)
+            .map(|(language, _)| language.to_string());
+        profile.uses_body = majority(with_body);
+
+        profile
+    }
+
+    /// 生成追加到系统提示词的风格说明；issue_keys 为当前分支解析出的工单号
+    pub fn to_prompt_section(&self, issue_keys: &[String]) -> String {
+        if self.sample_size == 0 {
+            return String::new();
+        }
+
+        let mut rules = Vec::new();
+        if self.conventional {
+            let mut rule = "Follow the Conventional Commits format".to_string();
+            if !self.common_types.is_empty() {
+                rule.push_str(&format!(
+                    " (types used in this repository: {})",
+                    self.common_types.join(", ")
+                ));
+            }
+            if self.uses_scope {
+                rule.push_str(", including a scope");
+            }
+            rules.push(rule);
+        }
+        if self.uses_gitmoji {
+            rules.push("Start the summary line with a gitmoji".to_string());
+        }
+        if let Some(prefix) = &self.ticket_prefix {
+            match issue_keys.iter().find(|key| key.starts_with(prefix.as_str())) {
+                Some(key) => rules.push(format!("Reference the ticket {} in the summary line", key)),
+                None => rules.push(format!(
+                    "Summary lines usually reference {}-<number> tickets; only add one if it is known",
+                    prefix
+                )),
+            }
+        }
+        if let Some(language) = &self.language {
+            rules.push(format!(
+                "Existing commit messages are mostly written in {}",
+                language_name(language)
+            ));
+        }
+        rules.push(if self.uses_body {
+            "Most commits include a body after the summary line".to_string()
+        } else {
+            "Most commits consist of a summary line only".to_string()
+        });
+
+        let mut section = format!(
+            "\n\nRepository commit style (learned from the last {} commits):\n",
+            self.sample_size
+        );
+        for rule in rules {
+            section.push_str(&format!("- {}\n", rule));
+        }
+
+        if !self.examples.is_empty() {
+            section.push_str(
+                "\nRecent commit messages from this repository, use them as style references only:\n",
+            );
+            for example in &self.examples {
+                section.push_str(&format!("---\n{}\n", example));
+            }
+            section.push_str("---");
+        }
+
+        section
+    }
+}
+
+/// 合并、回滚等自动生成的消息不代表团队的书写风格
+fn is_generated_message(message: &str) -> bool {
+    message.starts_with("Merge ") || message.starts_with("Revert \"")
+}
+
+/// 按 token 预算挑选示例，优先保留较新的提交
+fn select_examples(messages: &[&str], token_budget: u32) -> Vec<String> {
+    let mut examples = Vec::new();
+    let mut used = 0u32;
+
+    for message in messages.iter().take(MAX_EXAMPLES) {
+        let example = message
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(MAX_EXAMPLE_LINES)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let tokens = TokenCounter::estimate_tokens(&example);
+        if used + tokens > token_budget {
+            continue; // 较长的示例放不下时，继续尝试更短的
+        }
+        used += tokens;
+        examples.push(example);
+    }
+
+    examples
+}
+
+/// 去掉标题开头的 gitmoji（`:sparkles:` 或 emoji 字符），返回是否存在以及剩余部分
+pub(crate) fn strip_gitmoji(summary: &str) -> (bool, &str) {
+    if let Some(rest) = summary.strip_prefix(':') {
+        if let Some(end) = rest.find(':') {
+            let code = &rest[..end];
+            if !code.is_empty()
+                && code
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c == '_' || c.is_ascii_digit())
+            {
+                return (true, rest[end + 1..].trim_start());
+            }
+        }
+    }
+
+    if let Some(first) = summary.chars().next() {
+        if is_emoji(first) {
+            // 跳过组合字符（变体选择符、零宽连接符及后续 emoji）
+            let rest_start = summary
+                .char_indices()
+                .find(|(_, c)| !is_emoji(*c) && *c != '\u{fe0f}' && *c != '\u{200d}')
+                .map(|(i, _)| i)
+                .unwrap_or(summary.len());
+            return (true, summary[rest_start..].trim_start());
+        }
+    }
+
+    (false, summary)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{1f300}'..='\u{1faff}' | '\u{2600}'..='\u{27bf}' | '\u{2b50}')
+}
+
+fn detect_language(text: &str) -> &'static str {
+    if text.chars().any(|c| matches!(c, '\u{3040}'..='\u{30ff}')) {
+        "ja"
+    } else if text.chars().any(|c| matches!(c, '\u{ac00}'..='\u{d7af}')) {
+        "ko"
+    } else if text.chars().any(|c| matches!(c, '\u{4e00}'..='\u{9fff}')) {
+        "zh"
+    } else {
+        "en"
+    }
+}
+
+fn language_name(code: &str) -> &str {
+    match code {
+        "zh" => "Chinese",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "en" => "English",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn detects_conventional_gitmoji_and_tickets() {
+        let profile = CommitStyleProfile::from_messages(
+            &messages(&[
+                ":sparkles: feat(ui): PROJ-12 add history view",
+                "🐛 fix(core): PROJ-13 handle detached head\n\nDetails here",
+                "Merge branch 'develop'",
+                ":memo: docs: PROJ-14 update readme",
+                ":bug: fix(git): PROJ-15 retry fetch",
+            ]),
+            1000,
+        );
+
+        assert_eq!(profile.sample_size, 4);
+        assert!(profile.conventional);
+        assert_eq!(profile.common_types[0], "fix");
+        assert!(profile.uses_scope);
+        assert!(profile.uses_gitmoji);
+        assert_eq!(profile.ticket_prefix.as_deref(), Some("PROJ"));
+        assert_eq!(profile.language.as_deref(), Some("en"));
+        assert!(!profile.uses_body);
+
+        let section = profile.to_prompt_section(&["PROJ-20".to_string()]);
+        assert!(section.contains("Reference the ticket PROJ-20"));
+        assert!(section.contains("---\n:sparkles: feat(ui): PROJ-12 add history view\n"));
+    }
+
+    #[test]
+    fn detects_freeform_chinese_style() {
+        let profile = CommitStyleProfile::from_messages(
+            &messages(&["修复登录问题", "新增日报导出", "Update deps", "优化性能"]),
+            1000,
+        );
+
+        assert!(!profile.conventional);
+        assert!(!profile.uses_gitmoji);
+        assert_eq!(profile.ticket_prefix, None);
+        assert_eq!(profile.language.as_deref(), Some("zh"));
+    }
+
+    #[test]
+    fn trims_examples_to_token_budget() {
+        let long = format!("feat: {}", "x".repeat(400));
+        let profile = CommitStyleProfile::from_messages(
+            &messages(&[&long, "fix: short one", "fix: short two"]),
+            20,
+        );
+
+        assert_eq!(profile.examples, vec!["fix: short one", "fix: short two"]);
+    }
+}
@@ -0,0 +1,781 @@
+use crate::core::git_credentials::{host_matches, GitCredentialSettings, RemoteEndpoint};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+// 代码托管平台集成：通过 GitHub / GitLab / Gitea API 创建 PR/MR，支持 GitHub Enterprise 与自建实例
+// Author: Evilek, Date: 2025-10-03
+// 令牌来自应用凭据存储（git_credentials.json）：优先使用托管平台账号，其次复用同主机的 HTTPS 令牌
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 托管平台类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+    Gitea,
+}
+
+impl ForgeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ForgeKind::Github => "GitHub",
+            ForgeKind::Gitlab => "GitLab",
+            ForgeKind::Gitea => "Gitea",
+        }
+    }
+
+    /// 公共实例可按主机名识别，自建实例需要在账号中指定类型
+    fn detect(host: &str) -> Option<Self> {
+        match host {
+            "github.com" => Some(ForgeKind::Github),
+            "gitlab.com" => Some(ForgeKind::Gitlab),
+            "gitea.com" | "codeberg.org" => Some(ForgeKind::Gitea),
+            _ if host.starts_with("gitlab.") => Some(ForgeKind::Gitlab),
+            _ if host.starts_with("gitea.") => Some(ForgeKind::Gitea),
+            _ => None,
+        }
+    }
+}
+
+/// 托管平台 API 账号，随 Git 凭据一起保存
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForgeAccount {
+    /// 与远程地址匹配的主机名，如 github.example.com 或 git.example.com:3000
+    pub host: String,
+    pub kind: ForgeKind,
+    /// API 根地址，为空时按平台类型推导（如 https://host/api/v3）
+    #[serde(default)]
+    pub api_url: Option<String>,
+    #[serde(default)]
+    pub token: String,
+}
+
+/// 远程仓库在托管平台上的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRemote {
+    pub kind: ForgeKind,
+    pub web_url: String,
+    pub api_url: String,
+    /// owner/repo，GitLab 可包含子组
+    pub project: String,
+    endpoint: RemoteEndpoint,
+}
+
+impl ForgeRemote {
+    /// 根据远程地址与凭据设置确定平台类型和 API 地址
+    pub fn resolve(remote_url: &str, settings: &GitCredentialSettings) -> Result<Self> {
+        let endpoint = RemoteEndpoint::parse(remote_url)
+            .ok_or_else(|| anyhow!("无法解析远程地址: {}", remote_url))?;
+
+        let project = endpoint
+            .path
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .to_string();
+
+        if !project.contains('/') {
+            return Err(anyhow!("远程地址中缺少 owner/repo 路径: {}", remote_url));
+        }
+
+        let account = Self::find_account(&endpoint, settings);
+
+        let kind = account
+            .map(|account| account.kind)
+            .or_else(|| ForgeKind::detect(&endpoint.host))
+            .ok_or_else(|| {
+                anyhow!(
+                    "无法识别 {} 的托管平台类型，请在凭据设置中添加托管平台账号",
+                    endpoint.host
+                )
+            })?;
+
+        // SSH 地址的端口不是 Web 端口，只有 HTTP(S) 地址保留端口
+        let lower = remote_url.trim().to_lowercase();
+        let scheme = if lower.starts_with("http://") {
+            "http"
+        } else {
+            "https"
+        };
+        let web_url = match endpoint.port {
+            Some(port) if lower.starts_with("http") => {
+                format!("{}://{}:{}", scheme, endpoint.host, port)
+            }
+            _ => format!("{}://{}", scheme, endpoint.host),
+        };
+
+        let api_url = account
+            .and_then(|account| account.api_url.as_deref())
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| match kind {
+                ForgeKind::Github if endpoint.host == "github.com" => {
+                    "https://api.github.com".to_string()
+                }
+                ForgeKind::Github => format!("{}/api/v3", web_url),
+                ForgeKind::Gitlab => format!("{}/api/v4", web_url),
+                ForgeKind::Gitea => format!("{}/api/v1", web_url),
+            });
+
+        Ok(Self {
+            kind,
+            web_url,
+            api_url,
+            project,
+            endpoint,
+        })
+    }
+
+    /// 访问令牌：托管平台账号优先，其次为同主机的 HTTPS 凭据
+    pub fn token(&self, settings: &GitCredentialSettings) -> Option<String> {
+        Self::find_account(&self.endpoint, settings)
+            .map(|account| account.token.as_str())
+            .filter(|token| !token.is_empty())
+            .or_else(|| settings.http_token_for(&self.endpoint))
+            .map(|token| token.to_string())
+    }
+
+    /// 在网页上手动创建 PR/MR 的地址
+    pub fn new_pull_request_url(&self, source: &str, target: &str) -> String {
+        match self.kind {
+            ForgeKind::Github => format!(
+                "{}/{}/compare/{}...{}?expand=1",
+                self.web_url,
+                self.project,
+                encode_component(target),
+                encode_component(source)
+            ),
+            ForgeKind::Gitlab => format!(
+                "{}/{}/-/merge_requests/new?merge_request[source_branch]={}&merge_request[target_branch]={}",
+                self.web_url,
+                self.project,
+                encode_component(source),
+                encode_component(target)
+            ),
+            ForgeKind::Gitea => format!(
+                "{}/{}/compare/{}...{}",
+                self.web_url,
+                self.project,
+                encode_component(target),
+                encode_component(source)
+            ),
+        }
+    }
+
+    fn find_account<'a>(
+        endpoint: &RemoteEndpoint,
+        settings: &'a GitCredentialSettings,
+    ) -> Option<&'a ForgeAccount> {
+        settings
+            .forge_accounts
+            .iter()
+            .find(|account| host_matches(&account.host, endpoint))
+            .or_else(|| {
+                // 账号未写端口时也匹配（SSH 远程的端口与 Web 端口不同）
+                settings
+                    .forge_accounts
+                    .iter()
+                    .find(|account| account.host.trim().eq_ignore_ascii_case(&endpoint.host))
+            })
+    }
+}
+
+/// 待创建的 PR/MR
+#[derive(Debug, Clone, Default)]
+pub struct PullRequestDraft {
+    pub source_branch: String,
+    pub target_branch: String,
+    pub title: String,
+    pub body: String,
+    pub reviewers: Vec<String>,
+    pub labels: Vec<String>,
+    pub draft: bool,
+}
+
+/// 已创建的 PR/MR；评审人或标签设置失败不影响创建结果，记录在 warnings 中
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreatedPullRequest {
+    pub number: u64,
+    pub url: String,
+    pub warnings: Vec<String>,
+}
+
+/// 托管平台 API 客户端
+pub struct ForgeClient {
+    remote: ForgeRemote,
+    token: String,
+    http: reqwest::Client,
+}
+
+impl ForgeClient {
+    pub fn new(remote: ForgeRemote, token: String) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("GitMentorLite/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| anyhow!("创建 HTTP 客户端失败: {}", e))?;
+
+        Ok(Self {
+            remote,
+            token,
+            http,
+        })
+    }
+
+    pub fn remote(&self) -> &ForgeRemote {
+        &self.remote
+    }
+
+    pub async fn create_pull_request(
+        &self,
+        draft: &PullRequestDraft,
+    ) -> Result<CreatedPullRequest> {
+        match self.remote.kind {
+            ForgeKind::Github => self.create_github_pull_request(draft).await,
+            ForgeKind::Gitlab => self.create_gitlab_merge_request(draft).await,
+            ForgeKind::Gitea => self.create_gitea_pull_request(draft).await,
+        }
+    }
+
+    async fn create_github_pull_request(
+        &self,
+        draft: &PullRequestDraft,
+    ) -> Result<CreatedPullRequest> {
+        let repo_url = format!("{}/repos/{}", self.remote.api_url, self.remote.project);
+
+        let created = self
+            .send(
+                reqwest::Method::POST,
+                &format!("{}/pulls", repo_url),
+                Some(json!({
+                    "title": draft.title,
+                    "body": draft.body,
+                    "head": draft.source_branch,
+                    "base": draft.target_branch,
+                    "draft": draft.draft,
+                })),
+            )
+            .await?;
+
+        let number = json_u64(&created, "number")?;
+        let url = json_string(&created, "html_url")?;
+        let mut warnings = Vec::new();
+
+        if !draft.reviewers.is_empty() {
+            if let Err(e) = self
+                .send(
+                    reqwest::Method::POST,
+                    &format!("{}/pulls/{}/requested_reviewers", repo_url, number),
+                    Some(json!({ "reviewers": draft.reviewers })),
+                )
+                .await
+            {
+                warnings.push(format!("设置评审人失败: {}", e));
+            }
+        }
+
+        if !draft.labels.is_empty() {
+            if let Err(e) = self
+                .send(
+                    reqwest::Method::POST,
+                    &format!("{}/issues/{}/labels", repo_url, number),
+                    Some(json!({ "labels": draft.labels })),
+                )
+                .await
+            {
+                warnings.push(format!("设置标签失败: {}", e));
+            }
+        }
+
+        Ok(CreatedPullRequest {
+            number,
+            url,
+            warnings,
+        })
+    }
+
+    async fn create_gitlab_merge_request(
+        &self,
+        draft: &PullRequestDraft,
+    ) -> Result<CreatedPullRequest> {
+        let mut warnings = Vec::new();
+
+        // GitLab 的评审人需要用户 ID
+        let mut reviewer_ids = Vec::new();
+        for username in &draft.reviewers {
+            let users = self
+                .send(
+                    reqwest::Method::GET,
+                    &format!(
+                        "{}/users?username={}",
+                        self.remote.api_url,
+                        encode_component(username)
+                    ),
+                    None,
+                )
+                .await;
+
+            match users
+                .ok()
+                .and_then(|users| users.get(0).and_then(|user| user.get("id")).cloned())
+                .and_then(|id| id.as_u64())
+            {
+                Some(id) => reviewer_ids.push(id),
+                None => warnings.push(format!("未找到评审人 {}", username)),
+            }
+        }
+
+        let title = if draft.draft {
+            format!("Draft: {}", draft.title)
+        } else {
+            draft.title.clone()
+        };
+
+        let created = self
+            .send(
+                reqwest::Method::POST,
+                &format!(
+                    "{}/projects/{}/merge_requests",
+                    self.remote.api_url,
+                    encode_component(&self.remote.project)
+                ),
+                Some(json!({
+                    "source_branch": draft.source_branch,
+                    "target_branch": draft.target_branch,
+                    "title": title,
+                    "description": draft.body,
+                    "labels": draft.labels.join(","),
+                    "reviewer_ids": reviewer_ids,
+                })),
+            )
+            .await?;
+
+        Ok(CreatedPullRequest {
+            number: json_u64(&created, "iid")?,
+            url: json_string(&created, "web_url")?,
+            warnings,
+        })
+    }
+
+    async fn create_gitea_pull_request(
+        &self,
+        draft: &PullRequestDraft,
+    ) -> Result<CreatedPullRequest> {
+        let repo_url = format!("{}/repos/{}", self.remote.api_url, self.remote.project);
+        let mut warnings = Vec::new();
+
+        // Gitea 以 WIP: 前缀表示草稿
+        let title = if draft.draft {
+            format!("WIP: {}", draft.title)
+        } else {
+            draft.title.clone()
+        };
+
+        let created = self
+            .send(
+                reqwest::Method::POST,
+                &format!("{}/pulls", repo_url),
+                Some(json!({
+                    "title": title,
+                    "body": draft.body,
+                    "head": draft.source_branch,
+                    "base": draft.target_branch,
+                })),
+            )
+            .await?;
+
+        let number = json_u64(&created, "number")?;
+        let url = json_string(&created, "html_url")?;
+
+        if !draft.reviewers.is_empty() {
+            if let Err(e) = self
+                .send(
+                    reqwest::Method::POST,
+                    &format!("{}/pulls/{}/requested_reviewers", repo_url, number),
+                    Some(json!({ "reviewers": draft.reviewers })),
+                )
+                .await
+            {
+                warnings.push(format!("设置评审人失败: {}", e));
+            }
+        }
+
+        if !draft.labels.is_empty() {
+            // Gitea 的标签需要按名称换成 ID
+            match self
+                .send(
+                    reqwest::Method::GET,
+                    &format!("{}/labels?limit=100", repo_url),
+                    None,
+                )
+                .await
+            {
+                Ok(Value::Array(existing)) => {
+                    let mut label_ids = Vec::new();
+                    for name in &draft.labels {
+                        let id = existing
+                            .iter()
+                            .find(|label| label.get("name").and_then(Value::as_str) == Some(name))
+                            .and_then(|label| label.get("id"))
+                            .and_then(Value::as_u64);
+                        match id {
+                            Some(id) => label_ids.push(id),
+                            None => warnings.push(format!("仓库中不存在标签 {}", name)),
+                        }
+                    }
+
+                    if !label_ids.is_empty() {
+                        if let Err(e) = self
+                            .send(
+                                reqwest::Method::POST,
+                                &format!("{}/issues/{}/labels", repo_url, number),
+                                Some(json!({ "labels": label_ids })),
+                            )
+                            .await
+                        {
+                            warnings.push(format!("设置标签失败: {}", e));
+                        }
+                    }
+                }
+                Ok(_) => warnings.push("读取仓库标签失败: 响应格式无效".to_string()),
+                Err(e) => warnings.push(format!("读取仓库标签失败: {}", e)),
+            }
+        }
+
+        Ok(CreatedPullRequest {
+            number,
+            url,
+            warnings,
+        })
+    }
+
+    async fn send(&self, method: reqwest::Method, url: &str, body: Option<Value>) -> Result<Value> {
+        let mut request = self.http.request(method, url);
+
+        request = match self.remote.kind {
+            ForgeKind::Github => request
+                .bearer_auth(&self.token)
+                .header("Accept", "application/vnd.github+json"),
+            ForgeKind::Gitlab => request.header("PRIVATE-TOKEN", &self.token),
+            ForgeKind::Gitea => request.header("Authorization", format!("token {}", self.token)),
+        };
+
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("请求 {} API 失败: {}", self.remote.kind.label(), e))?;
+
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+
+        if !status.is_success() {
+            return Err(anyhow!(
+                "{} API 返回 {}: {}",
+                self.remote.kind.label(),
+                status.as_u16(),
+                api_error_message(&text)
+            ));
+        }
+
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+
+        serde_json::from_str(&text).map_err(|e| anyhow!("解析 API 响应失败: {}", e))
+    }
+}
+
+/// 提取平台返回的错误说明（GitHub/Gitea 为 message，GitLab 为 message 或 error）
+fn api_error_message(body: &str) -> String {
+    let parsed: Option<Value> = serde_json::from_str(body).ok();
+
+    let message = parsed.as_ref().and_then(|value| {
+        let message = value.get("message").or_else(|| value.get("error"))?;
+        Some(match message {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        })
+    });
+
+    let mut message = message.unwrap_or_else(|| body.trim().to_string());
+
+    // GitHub 的 422 会把具体原因放在 errors 里，如 "A pull request already exists"
+    if let Some(errors) = parsed
+        .as_ref()
+        .and_then(|value| value.get("errors"))
+        .and_then(Value::as_array)
+    {
+        let details: Vec<String> = errors
+            .iter()
+            .filter_map(|error| error.get("message").and_then(Value::as_str))
+            .map(|text| text.to_string())
+            .collect();
+        if !details.is_empty() {
+            message = format!("{} ({})", message, details.join("; "));
+        }
+    }
+
+    message
+}
+
+fn json_u64(value: &Value, key: &str) -> Result<u64> {
+    value
+        .get(key)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("API 响应缺少字段 {}", key))
+}
+
+fn json_string(value: &Value, key: &str) -> Result<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(|text| text.to_string())
+        .ok_or_else(|| anyhow!("API 响应缺少字段 {}", key))
+}
+
+/// URL 组件编码：保留 RFC 3986 非保留字符，其余按 UTF-8 字节编码
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// 记录到的请求：(方法, 路径, 认证相关请求头, 请求体)
+    type Recorded = Arc<Mutex<Vec<(String, String, String, Value)>>>;
+
+    /// 最小的本地 HTTP 服务：按 (方法, 路径) 返回预设响应，未匹配的返回 404
+    fn mock_server(routes: Vec<(&'static str, &'static str, u16, Value)>) -> (String, Recorded) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let recorded: Recorded = Arc::default();
+        let log = recorded.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_string();
+                let path = parts.next().unwrap_or_default().to_string();
+
+                let mut content_length = 0;
+                let mut auth = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(':').unwrap();
+                    let name = name.to_lowercase();
+                    if name == "content-length" {
+                        content_length = value.trim().parse().unwrap();
+                    } else if name == "authorization" || name == "private-token" {
+                        auth = value.trim().to_string();
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+                let (status, response) = routes
+                    .iter()
+                    .find(|(m, p, _, _)| *m == method && *p == path)
+                    .map(|(_, _, status, response)| (*status, response.to_string()))
+                    .unwrap_or((404, r#"{"message":"Not Found"}"#.to_string()));
+
+                log.lock().unwrap().push((method, path, auth, body));
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        (address, recorded)
+    }
+
+    fn settings_for(host: &str, kind: ForgeKind, api_url: &str) -> GitCredentialSettings {
+        GitCredentialSettings {
+            forge_accounts: vec![ForgeAccount {
+                host: host.to_string(),
+                kind,
+                api_url: Some(api_url.to_string()),
+                token: "secret-token".to_string(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn sample_draft() -> PullRequestDraft {
+        PullRequestDraft {
+            source_branch: "feature/login".to_string(),
+            target_branch: "develop".to_string(),
+            title: "Add login".to_string(),
+            body: "Adds the login page".to_string(),
+            reviewers: vec!["alice".to_string()],
+            labels: vec!["enhancement".to_string()],
+            draft: false,
+        }
+    }
+
+    #[test]
+    fn resolves_public_and_self_hosted_remotes() {
+        let settings = GitCredentialSettings::default();
+
+        let github = ForgeRemote::resolve("git@github.com:acme/app.git", &settings).unwrap();
+        assert_eq!(github.kind, ForgeKind::Github);
+        assert_eq!(github.api_url, "https://api.github.com");
+        assert_eq!(github.project, "acme/app");
+
+        let gitlab =
+            ForgeRemote::resolve("https://gitlab.com/group/sub/app.git", &settings).unwrap();
+        assert_eq!(gitlab.api_url, "https://gitlab.com/api/v4");
+        assert_eq!(gitlab.project, "group/sub/app");
+
+        assert!(ForgeRemote::resolve("https://git.acme.io/acme/app", &settings).is_err());
+
+        let settings = GitCredentialSettings {
+            forge_accounts: vec![ForgeAccount {
+                host: "git.acme.io".to_string(),
+                kind: ForgeKind::Github,
+                api_url: None,
+                token: String::new(),
+            }],
+            ..Default::default()
+        };
+        let enterprise =
+            ForgeRemote::resolve("ssh://git@git.acme.io:2222/acme/app.git", &settings).unwrap();
+        assert_eq!(enterprise.api_url, "https://git.acme.io/api/v3");
+        assert_eq!(enterprise.token(&settings), None);
+        assert_eq!(
+            enterprise.new_pull_request_url("feature/x", "develop"),
+            "https://git.acme.io/acme/app/compare/develop...feature%2Fx?expand=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn github_pull_request_sets_reviewers_and_labels() {
+        let (address, recorded) = mock_server(vec![
+            (
+                "POST",
+                "/repos/acme/app/pulls",
+                201,
+                json!({"number": 7, "html_url": "https://github.com/acme/app/pull/7"}),
+            ),
+            (
+                "POST",
+                "/repos/acme/app/pulls/7/requested_reviewers",
+                201,
+                json!({}),
+            ),
+            ("POST", "/repos/acme/app/issues/7/labels", 200, json!([])),
+        ]);
+        let settings = settings_for("github.com", ForgeKind::Github, &address);
+        let remote = ForgeRemote::resolve("https://github.com/acme/app.git", &settings).unwrap();
+        let token = remote.token(&settings).unwrap();
+
+        let created = ForgeClient::new(remote, token)
+            .unwrap()
+            .create_pull_request(&sample_draft())
+            .await
+            .unwrap();
+
+        assert_eq!(created.number, 7);
+        assert!(created.warnings.is_empty());
+
+        let requests = recorded.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].2, "Bearer secret-token");
+        assert_eq!(requests[0].3["head"], "feature/login");
+        assert_eq!(requests[0].3["base"], "develop");
+        assert_eq!(requests[1].3["reviewers"], json!(["alice"]));
+        assert_eq!(requests[2].3["labels"], json!(["enhancement"]));
+    }
+
+    #[tokio::test]
+    async fn gitlab_merge_request_resolves_reviewer_ids() {
+        let (address, recorded) = mock_server(vec![
+            ("GET", "/users?username=alice", 200, json!([{"id": 42}])),
+            (
+                "POST",
+                "/projects/group%2Fapp/merge_requests",
+                201,
+                json!({"iid": 3, "web_url": "https://gitlab.acme.io/group/app/-/merge_requests/3"}),
+            ),
+        ]);
+        let settings = settings_for("gitlab.acme.io", ForgeKind::Gitlab, &address);
+        let remote = ForgeRemote::resolve("git@gitlab.acme.io:group/app.git", &settings).unwrap();
+
+        let mut draft = sample_draft();
+        draft.reviewers.push("ghost".to_string());
+        let created = ForgeClient::new(remote, "secret-token".to_string())
+            .unwrap()
+            .create_pull_request(&draft)
+            .await
+            .unwrap();
+
+        assert_eq!(created.number, 3);
+        assert_eq!(created.warnings, vec!["未找到评审人 ghost"]);
+
+        let requests = recorded.lock().unwrap();
+        let merge_request = &requests.last().unwrap();
+        assert_eq!(merge_request.2, "secret-token");
+        assert_eq!(merge_request.3["reviewer_ids"], json!([42]));
+        assert_eq!(merge_request.3["labels"], "enhancement");
+    }
+
+    #[tokio::test]
+    async fn gitea_errors_surface_api_message() {
+        let (address, _) = mock_server(vec![(
+            "POST",
+            "/repos/acme/app/pulls",
+            409,
+            json!({"message": "pull request already exists for these targets"}),
+        )]);
+        let settings = settings_for("gitea.acme.io", ForgeKind::Gitea, &address);
+        let remote = ForgeRemote::resolve("https://gitea.acme.io/acme/app", &settings).unwrap();
+
+        let error = ForgeClient::new(remote, "secret-token".to_string())
+            .unwrap()
+            .create_pull_request(&sample_draft())
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Gitea API 返回 409: pull request already exists for these targets"
+        );
+    }
+}
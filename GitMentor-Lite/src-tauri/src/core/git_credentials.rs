@@ -0,0 +1,777 @@
+use crate::core::forge::ForgeAccount;
+use crate::types::git_types::GitError;
+use crate::utils::config_file::load_json_or_backup;
+use crate::warn_log;
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Git 凭据：Git2Api 模式下的网络认证与 SSH 主机密钥校验
+// Author: Evilek, Date: 2025-10-01
+// 尝试顺序：SSH agent → SSH 密钥文件 → 应用内保存的用户名/令牌 → git credential helper
+
+/// 返回给前端时替换敏感字段的占位符，回传时保留原值
+pub const REDACTED_SECRET: &str = "********";
+
+/// HTTPS 用户名/令牌
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpCredential {
+    /// 主机名，如 github.com 或 git.example.com:8443
+    pub host: String,
+    /// 可选的路径前缀（如 my-org/），用于同一主机下区分不同账号
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    pub username: String,
+    pub token: String,
+}
+
+/// SSH 私钥文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SshKeyCredential {
+    /// 为空表示适用于所有主机
+    #[serde(default)]
+    pub host: Option<String>,
+    pub private_key_path: String,
+    #[serde(default)]
+    pub public_key_path: Option<String>,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCredentialSettings {
+    #[serde(default)]
+    pub http_credentials: Vec<HttpCredential>,
+    #[serde(default)]
+    pub ssh_keys: Vec<SshKeyCredential>,
+    /// 自定义 known_hosts 路径，为空时使用 ~/.ssh/known_hosts
+    #[serde(default)]
+    pub known_hosts_path: Option<String>,
+    /// 主机不在 known_hosts 中时是否拒绝连接
+    #[serde(default = "default_strict_host_key_checking")]
+    pub strict_host_key_checking: bool,
+    /// 是否尝试 git credential helper（credential.helper 配置）
+    #[serde(default = "default_use_credential_helper")]
+    pub use_credential_helper: bool,
+    /// 代码托管平台 API 账号（创建 PR/MR 使用）
+    #[serde(default)]
+    pub forge_accounts: Vec<ForgeAccount>,
+}
+
+fn default_strict_host_key_checking() -> bool {
+    true
+}
+
+fn default_use_credential_helper() -> bool {
+    true
+}
+
+impl Default for GitCredentialSettings {
+    fn default() -> Self {
+        Self {
+            http_credentials: Vec::new(),
+            ssh_keys: Vec::new(),
+            known_hosts_path: None,
+            strict_host_key_checking: true,
+            use_credential_helper: true,
+            forge_accounts: Vec::new(),
+        }
+    }
+}
+
+impl GitCredentialSettings {
+    /// 生成可返回前端的副本：令牌与口令替换为占位符
+    pub fn redacted(&self) -> Self {
+        let mut settings = self.clone();
+        for credential in &mut settings.http_credentials {
+            if !credential.token.is_empty() {
+                credential.token = REDACTED_SECRET.to_string();
+            }
+        }
+        for key in &mut settings.ssh_keys {
+            if key.passphrase.as_deref().is_some_and(|p| !p.is_empty()) {
+                key.passphrase = Some(REDACTED_SECRET.to_string());
+            }
+        }
+        for account in &mut settings.forge_accounts {
+            if !account.token.is_empty() {
+                account.token = REDACTED_SECRET.to_string();
+            }
+        }
+        settings
+    }
+
+    /// 合并前端回传的设置：仍为占位符的敏感字段沿用当前保存的值
+    pub fn merge_secrets_from(mut self, current: &GitCredentialSettings) -> Self {
+        for credential in &mut self.http_credentials {
+            if credential.token == REDACTED_SECRET {
+                credential.token = current
+                    .http_credentials
+                    .iter()
+                    .find(|old| old.host == credential.host && old.username == credential.username)
+                    .map(|old| old.token.clone())
+                    .unwrap_or_default();
+            }
+        }
+        for key in &mut self.ssh_keys {
+            if key.passphrase.as_deref() == Some(REDACTED_SECRET) {
+                key.passphrase = current
+                    .ssh_keys
+                    .iter()
+                    .find(|old| old.private_key_path == key.private_key_path)
+                    .and_then(|old| old.passphrase.clone());
+            }
+        }
+        for account in &mut self.forge_accounts {
+            if account.token == REDACTED_SECRET {
+                account.token = current
+                    .forge_accounts
+                    .iter()
+                    .find(|old| old.host == account.host)
+                    .map(|old| old.token.clone())
+                    .unwrap_or_default();
+            }
+        }
+        self
+    }
+
+    /// 查找远程地址对应的 HTTPS 令牌（路径前缀最长者优先），供托管平台 API 复用
+    pub fn http_token_for(&self, endpoint: &RemoteEndpoint) -> Option<&str> {
+        self.http_credentials
+            .iter()
+            .filter(|credential| host_matches(&credential.host, endpoint))
+            .filter(|credential| {
+                credential
+                    .path_prefix
+                    .as_deref()
+                    .is_none_or(|prefix| endpoint.path.starts_with(prefix.trim_start_matches('/')))
+            })
+            .filter(|credential| !credential.token.is_empty())
+            .max_by_key(|credential| credential.path_prefix.as_deref().map_or(0, str::len))
+            .map(|credential| credential.token.as_str())
+    }
+}
+
+/// 凭据配置持久化，保存在配置目录的 git_credentials.json
+pub struct GitCredentialManager {
+    settings: GitCredentialSettings,
+    config_path: PathBuf,
+}
+
+impl GitCredentialManager {
+    pub fn new(config_path: PathBuf) -> Result<Self> {
+        let settings = load_json_or_backup(&config_path)?;
+
+        Ok(Self {
+            settings,
+            config_path,
+        })
+    }
+
+    pub fn get_settings(&self) -> &GitCredentialSettings {
+        &self.settings
+    }
+
+    pub fn update_settings(&mut self, settings: GitCredentialSettings) -> Result<()> {
+        self.settings = settings;
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(&self.settings)?;
+        fs::write(&self.config_path, content)?;
+
+        // 文件含访问令牌，仅允许当前用户读写
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.config_path, fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 远程地址中与认证相关的部分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteEndpoint {
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+    pub username: Option<String>,
+}
+
+impl RemoteEndpoint {
+    /// 解析 https://user@host:port/path、ssh://git@host/path 与 scp 风格的 git@host:path
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+
+        let (authority, path) = if let Some((_, rest)) = url.split_once("://") {
+            match rest.split_once('/') {
+                Some((authority, path)) => (authority, path),
+                None => (rest, ""),
+            }
+        } else {
+            // scp 风格：[user@]host:path，Windows 盘符路径不是远程地址
+            let (authority, path) = url.split_once(':')?;
+            if authority.len() <= 1 || authority.contains('/') {
+                return None;
+            }
+            return Some(Self::from_parts(authority, path, false));
+        };
+
+        if authority.is_empty() {
+            return None;
+        }
+
+        Some(Self::from_parts(authority, path, true))
+    }
+
+    fn from_parts(authority: &str, path: &str, allow_port: bool) -> Self {
+        let (username, host_port) = match authority.rsplit_once('@') {
+            Some((user, host)) => (
+                Some(user.split(':').next().unwrap_or(user).to_string()),
+                host,
+            ),
+            None => (None, authority),
+        };
+
+        let (host, port) = match host_port.rsplit_once(':') {
+            Some((host, port)) if allow_port => (host, port.parse().ok()),
+            _ => (host_port, None),
+        };
+
+        Self {
+            host: host.trim_matches(|c| c == '[' || c == ']').to_lowercase(),
+            port,
+            path: path.trim_start_matches('/').to_string(),
+            username: username.filter(|user| !user.is_empty()),
+        }
+    }
+
+    /// known_hosts 中使用的主机名（非 22 端口写作 [host]:port）
+    fn known_hosts_name(&self) -> String {
+        match self.port {
+            Some(port) if port != 22 => format!("[{}]:{}", self.host, port),
+            _ => self.host.clone(),
+        }
+    }
+
+    fn host_with_port(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}:{}", self.host, port),
+            None => self.host.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProviderState {
+    ssh_attempt: usize,
+    userpass_attempt: usize,
+    auth_exhausted: bool,
+    host_key_error: Option<String>,
+}
+
+/// 单次网络操作的凭据提供者：按顺序尝试各类凭据，并校验 SSH 主机密钥
+pub struct GitCredentialProvider {
+    settings: GitCredentialSettings,
+    config: Option<git2::Config>,
+    endpoint: Option<RemoteEndpoint>,
+    state: RefCell<ProviderState>,
+}
+
+impl GitCredentialProvider {
+    /// config 用于读取 credential.helper，通常为仓库配置（含全局/系统层级）
+    pub fn new(settings: GitCredentialSettings, config: Option<git2::Config>, url: &str) -> Self {
+        Self {
+            settings,
+            config,
+            endpoint: RemoteEndpoint::parse(url),
+            state: RefCell::new(ProviderState::default()),
+        }
+    }
+
+    /// 创建带认证与主机密钥校验的回调
+    pub fn remote_callbacks(&self) -> git2::RemoteCallbacks<'_> {
+        let mut callbacks = git2::RemoteCallbacks::new();
+        callbacks.credentials(|url, username_from_url, allowed_types| {
+            self.credentials(url, username_from_url, allowed_types)
+        });
+        callbacks.certificate_check(|cert, hostname| self.check_certificate(cert, hostname));
+        callbacks
+    }
+
+    /// 将网络操作失败转换为对用户友好的错误；认证或主机密钥问题归为配置错误
+    pub fn classify_error(&self, error: &git2::Error) -> GitError {
+        let state = self.state.borrow();
+        let host = self
+            .endpoint
+            .as_ref()
+            .map(|endpoint| endpoint.host_with_port())
+            .unwrap_or_else(|| "远程仓库".to_string());
+
+        if let Some(message) = &state.host_key_error {
+            return GitError::ConfigurationError(message.clone());
+        }
+
+        if state.auth_exhausted || error.code() == git2::ErrorCode::Auth {
+            return GitError::ConfigurationError(format!(
+                "{} 认证失败：已尝试 SSH agent、SSH 密钥文件、已保存的令牌与 credential helper，请在凭据设置中检查用户名/令牌或 SSH 密钥（{}）",
+                host,
+                error.message()
+            ));
+        }
+
+        match error.class() {
+            git2::ErrorClass::Net => GitError::NetworkError(error.message().to_string()),
+            git2::ErrorClass::Os => GitError::FileSystemError(error.message().to_string()),
+            git2::ErrorClass::Config => GitError::ConfigurationError(error.message().to_string()),
+            _ => GitError::GitOperationError(error.message().to_string()),
+        }
+    }
+
+    fn credentials(
+        &self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: git2::CredentialType,
+    ) -> Result<git2::Cred, git2::Error> {
+        let endpoint = RemoteEndpoint::parse(url).or_else(|| self.endpoint.clone());
+        let username = username_from_url
+            .map(|user| user.to_string())
+            .or_else(|| endpoint.as_ref().and_then(|e| e.username.clone()));
+
+        if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+            if let Some(cred) = self.next_ssh_credential(endpoint.as_ref(), username.as_deref()) {
+                return Ok(cred);
+            }
+        }
+
+        if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(cred) =
+                self.next_userpass_credential(url, endpoint.as_ref(), username.as_deref())
+            {
+                return Ok(cred);
+            }
+        }
+
+        if allowed_types.contains(git2::CredentialType::USERNAME) {
+            return git2::Cred::username(username.as_deref().unwrap_or("git"));
+        }
+
+        if allowed_types.contains(git2::CredentialType::DEFAULT)
+            && !self.state.borrow().auth_exhausted
+        {
+            self.state.borrow_mut().auth_exhausted = true;
+            return git2::Cred::default();
+        }
+
+        self.state.borrow_mut().auth_exhausted = true;
+        Err(git2::Error::from_str("没有可用的凭据"))
+    }
+
+    /// 依次尝试：SSH agent → 配置的密钥文件 → ~/.ssh 下的默认密钥
+    fn next_ssh_credential(
+        &self,
+        endpoint: Option<&RemoteEndpoint>,
+        username: Option<&str>,
+    ) -> Option<git2::Cred> {
+        let username = username.unwrap_or("git");
+
+        let mut candidates: Vec<SshKeyCredential> = self
+            .settings
+            .ssh_keys
+            .iter()
+            .filter(|key| match (&key.host, endpoint) {
+                (Some(host), Some(endpoint)) => host_matches(host, endpoint),
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .cloned()
+            .collect();
+
+        if let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) {
+            for name in ["id_ed25519", "id_ecdsa", "id_rsa"] {
+                let path = ssh_dir.join(name);
+                let path_str = path.to_string_lossy().to_string();
+                if path.exists() && !candidates.iter().any(|k| k.private_key_path == path_str) {
+                    candidates.push(SshKeyCredential {
+                        host: None,
+                        private_key_path: path_str,
+                        public_key_path: None,
+                        passphrase: None,
+                    });
+                }
+            }
+        }
+
+        loop {
+            let attempt = {
+                let mut state = self.state.borrow_mut();
+                state.ssh_attempt += 1;
+                state.ssh_attempt - 1
+            };
+
+            if attempt == 0 {
+                if let Ok(cred) = git2::Cred::ssh_key_from_agent(username) {
+                    return Some(cred);
+                }
+                continue;
+            }
+
+            let key = candidates.get(attempt - 1)?;
+            let passphrase = key.passphrase.as_deref().filter(|p| !p.is_empty());
+            let public_key = key
+                .public_key_path
+                .as_deref()
+                .filter(|p| !p.is_empty())
+                .map(Path::new);
+
+            match git2::Cred::ssh_key(
+                username,
+                public_key,
+                Path::new(&key.private_key_path),
+                passphrase,
+            ) {
+                Ok(cred) => return Some(cred),
+                Err(error) => warn_log!(
+                    "无法加载SSH密钥 {}: {}",
+                    key.private_key_path,
+                    error.message()
+                ),
+            }
+        }
+    }
+
+    /// 依次尝试：应用内保存的令牌（路径前缀最长者优先）→ git credential helper
+    fn next_userpass_credential(
+        &self,
+        url: &str,
+        endpoint: Option<&RemoteEndpoint>,
+        username: Option<&str>,
+    ) -> Option<git2::Cred> {
+        let mut stored: Vec<&HttpCredential> = match endpoint {
+            Some(endpoint) => self
+                .settings
+                .http_credentials
+                .iter()
+                .filter(|credential| host_matches(&credential.host, endpoint))
+                .filter(|credential| {
+                    credential.path_prefix.as_deref().is_none_or(|prefix| {
+                        endpoint.path.starts_with(prefix.trim_start_matches('/'))
+                    })
+                })
+                .filter(|credential| username.is_none_or(|user| credential.username == user))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        stored.sort_by_key(|credential| {
+            std::cmp::Reverse(credential.path_prefix.as_deref().map_or(0, str::len))
+        });
+
+        loop {
+            let attempt = {
+                let mut state = self.state.borrow_mut();
+                state.userpass_attempt += 1;
+                state.userpass_attempt - 1
+            };
+
+            if let Some(credential) = stored.get(attempt) {
+                if let Ok(cred) =
+                    git2::Cred::userpass_plaintext(&credential.username, &credential.token)
+                {
+                    return Some(cred);
+                }
+                continue;
+            }
+
+            // credential helper 只尝试一次
+            if attempt == stored.len() && self.settings.use_credential_helper {
+                if let Some(config) = &self.config {
+                    if let Ok(cred) = git2::Cred::credential_helper(config, url, username) {
+                        return Some(cred);
+                    }
+                }
+            }
+
+            return None;
+        }
+    }
+
+    fn check_certificate(
+        &self,
+        cert: &git2::cert::Cert<'_>,
+        hostname: &str,
+    ) -> Result<git2::CertificateCheckStatus, git2::Error> {
+        // HTTPS 证书交给 libgit2 的系统证书校验
+        let Some(hostkey) = cert.as_hostkey() else {
+            return Ok(git2::CertificateCheckStatus::CertificatePassthrough);
+        };
+
+        let Some(key) = hostkey.hostkey() else {
+            return Ok(git2::CertificateCheckStatus::CertificatePassthrough);
+        };
+
+        let host_name = match &self.endpoint {
+            Some(endpoint) if endpoint.host.eq_ignore_ascii_case(hostname) => {
+                endpoint.known_hosts_name()
+            }
+            _ => hostname.to_lowercase(),
+        };
+
+        let known_hosts_path = self
+            .settings
+            .known_hosts_path
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts")));
+
+        let content = known_hosts_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        let key_type = hostkey
+            .hostkey_type()
+            .map(|kind| kind.name())
+            .unwrap_or("unknown");
+
+        let error = match verify_known_host(&content, &host_name, key) {
+            HostKeyStatus::Match => return Ok(git2::CertificateCheckStatus::CertificateOk),
+            HostKeyStatus::NotFound if !self.settings.strict_host_key_checking => {
+                warn_log!("主机 {} 不在 known_hosts 中，已按设置跳过校验", host_name);
+                return Ok(git2::CertificateCheckStatus::CertificateOk);
+            }
+            HostKeyStatus::NotFound => format!(
+                "SSH 主机 {} 不在 known_hosts（{}）中，请先执行 ssh {} 确认主机指纹（{}），或在凭据设置中关闭严格主机校验",
+                host_name,
+                known_hosts_path
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+                hostname,
+                key_type
+            ),
+            HostKeyStatus::Mismatch => format!(
+                "SSH 主机 {} 的密钥（{}）与 known_hosts 中记录的不一致，可能存在中间人攻击，已拒绝连接",
+                host_name, key_type
+            ),
+            HostKeyStatus::Revoked => format!("SSH 主机 {} 的密钥已被标记为吊销", host_name),
+        };
+
+        self.state.borrow_mut().host_key_error = Some(error.clone());
+        Err(git2::Error::from_str(&error))
+    }
+}
+
+/// 凭据中的主机（可带端口）是否匹配远程地址
+pub(crate) fn host_matches(host: &str, endpoint: &RemoteEndpoint) -> bool {
+    let host = host.trim().to_lowercase();
+    match host.rsplit_once(':') {
+        Some((name, port)) => name == endpoint.host && port.parse::<u16>().ok() == endpoint.port,
+        None => host == endpoint.host,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum HostKeyStatus {
+    Match,
+    Mismatch,
+    NotFound,
+    Revoked,
+}
+
+/// 在 known_hosts 内容中查找主机并比较原始公钥
+fn verify_known_host(content: &str, host_name: &str, key: &[u8]) -> HostKeyStatus {
+    let mut host_found = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let mut hosts = fields.next().unwrap_or("");
+        let mut revoked = false;
+
+        if hosts.starts_with('@') {
+            match hosts {
+                "@revoked" => revoked = true,
+                _ => continue, // @cert-authority 等不做处理
+            }
+            hosts = fields.next().unwrap_or("");
+        }
+
+        let (Some(_key_type), Some(encoded)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        if !known_hosts_entry_matches(hosts, host_name) {
+            continue;
+        }
+
+        let Ok(entry_key) = BASE64.decode(encoded) else {
+            continue;
+        };
+
+        if entry_key == key {
+            if revoked {
+                return HostKeyStatus::Revoked;
+            }
+            return HostKeyStatus::Match;
+        }
+
+        if !revoked {
+            host_found = true;
+        }
+    }
+
+    if host_found {
+        HostKeyStatus::Mismatch
+    } else {
+        HostKeyStatus::NotFound
+    }
+}
+
+/// 主机字段支持逗号分隔、通配符、! 否定与哈希形式 |1|salt|hash
+fn known_hosts_entry_matches(hosts: &str, host_name: &str) -> bool {
+    if let Some(hashed) = hosts.strip_prefix("|1|") {
+        let Some((salt, hash)) = hashed.split_once('|') else {
+            return false;
+        };
+        let (Ok(salt), Ok(hash)) = (BASE64.decode(salt), BASE64.decode(hash)) else {
+            return false;
+        };
+        let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+            return false;
+        };
+        mac.update(host_name.as_bytes());
+        return mac.verify_slice(&hash).is_ok();
+    }
+
+    let mut matched = false;
+    for pattern in hosts.split(',') {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(&negated.to_lowercase(), host_name) {
+                return false;
+            }
+        } else if wildcard_match(&pattern.to_lowercase(), host_name) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            mark = t;
+            p += 1;
+        } else if let Some(star_pos) = star {
+            p = star_pos + 1;
+            mark += 1;
+            t = mark;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remote_urls() {
+        let https =
+            RemoteEndpoint::parse("https://bob@git.example.com:8443/team/repo.git").unwrap();
+        assert_eq!(https.host, "git.example.com");
+        assert_eq!(https.port, Some(8443));
+        assert_eq!(https.path, "team/repo.git");
+        assert_eq!(https.username.as_deref(), Some("bob"));
+
+        let scp = RemoteEndpoint::parse("git@github.com:owner/repo.git").unwrap();
+        assert_eq!(scp.host, "github.com");
+        assert_eq!(scp.port, None);
+        assert_eq!(scp.path, "owner/repo.git");
+
+        assert!(RemoteEndpoint::parse("C:/work/repo").is_none());
+    }
+
+    #[test]
+    fn verifies_plain_and_hashed_known_hosts() {
+        let key = b"fake-host-key-blob".to_vec();
+        let encoded = BASE64.encode(&key);
+
+        let plain = format!("github.com,140.82.112.3 ssh-ed25519 {}\n", encoded);
+        assert_eq!(
+            verify_known_host(&plain, "github.com", &key),
+            HostKeyStatus::Match
+        );
+        assert_eq!(
+            verify_known_host(&plain, "github.com", b"other"),
+            HostKeyStatus::Mismatch
+        );
+        assert_eq!(
+            verify_known_host(&plain, "gitlab.com", &key),
+            HostKeyStatus::NotFound
+        );
+
+        let salt = b"0123456789abcdefghij".to_vec();
+        let mut mac = Hmac::<Sha1>::new_from_slice(&salt).unwrap();
+        mac.update(b"[git.example.com]:2222");
+        let hash = mac.finalize().into_bytes();
+        let hashed = format!(
+            "|1|{}|{} ssh-ed25519 {}\n",
+            BASE64.encode(&salt),
+            BASE64.encode(hash),
+            encoded
+        );
+        assert_eq!(
+            verify_known_host(&hashed, "[git.example.com]:2222", &key),
+            HostKeyStatus::Match
+        );
+    }
+
+    #[test]
+    fn redacted_secrets_are_restored_on_merge() {
+        let current = GitCredentialSettings {
+            http_credentials: vec![HttpCredential {
+                host: "github.com".to_string(),
+                path_prefix: None,
+                username: "bob".to_string(),
+                token: "ghp_secret".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let redacted = current.redacted();
+        assert_eq!(redacted.http_credentials[0].token, REDACTED_SECRET);
+
+        let merged = redacted.merge_secrets_from(&current);
+        assert_eq!(merged.http_credentials[0].token, "ghp_secret");
+    }
+}
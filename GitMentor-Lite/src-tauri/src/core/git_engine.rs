@@ -1,13 +1,31 @@
 use crate::core::git_config::{GitConfig, GitExecutionMode};
+use crate::{error_log, info_log, warn_log};
+
+use crate::core::git_credentials::{GitCredentialProvider, GitCredentialSettings};
+
+use crate::core::git_process::{CancellationToken, GitCommandExt, GitProcessRunner};
+
+use crate::core::branch_metadata;
+
+use crate::core::gitflow_transaction::GitflowTransaction;
+
+use crate::core::release_pipeline;
+
+use crate::core::repo_watcher::{RepoWatchEvent, RepoWatcher, DELTA_EVENT, DIRTY_EVENT};
 
 use crate::debug_log;
 
 use crate::types::git_types::{
-    BranchInfo, CheckoutRequest, CheckoutResult, CommitInfo, CommitRequest, DiffHunk, DiffLine,
-    DiffLineType, DiffType, FileDiffRequest, FileDiffResult, FileStatus, FileStatusType, GitError,
-    GitOperationResult, GitStatusResult, GitflowActionRequest, GitflowBranchInfo,
-    GitflowBranchStatus, GitflowBranchType, GitflowConfig, GitflowCreateRequest, GitflowDivergence,
-    GitflowSummary, RemoteConfigRequest, RemoteOperation, RevertRequest, RevertType, StageRequest,
+    BlameHunk, BlameRequest, BlameResult, BranchInfo, CheckoutRequest, CheckoutResult,
+    CommitAuthor, CommitDiffResult, CommitFileDiff, CommitInfo, CommitRef, CommitRefType,
+    CommitRequest, CommitTemplateFacts, DiffHunk, DiffLine, DiffLineType, DiffType, FileChangeType,
+    FileDiffRequest, FileDiffResult, FileHistoryEntry, FileStatus, FileStatusType, GitError,
+    GitOperationResult, GitStatusResult, GitflowActionRequest, GitflowActionResult,
+    GitflowBranchInfo, GitflowBranchMetadata, GitflowBranchStatus, GitflowBranchType,
+    GitflowConfig, GitflowCreateRequest, GitflowDivergence, GitflowFileStat, GitflowReportCommit,
+    GitflowReportContext, GitflowSummary, GraphCommit, HistoryPage, HistoryQuery, LanguageShare,
+    MergeFilter, PullRequestContext, ReleaseOptions, ReleasePlan, RemoteConfigRequest,
+    RemoteOperation, RevertRequest, RevertType, StageRequest, StagedFileChange, VersionBump,
 };
 
 use anyhow::{anyhow, Result};
@@ -16,17 +34,10 @@ use chrono::{FixedOffset, Utc};
 
 use git2::{BranchType, DiffOptions, Repository, Signature, StatusOptions};
 
-use notify::{
-    Config as NotifyConfig, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
-};
-
-use serde::Serialize;
-
 use std::fmt::Write;
 use std::fs;
 
-use std::ffi::OsStr;
-use std::path::{Component, Path, PathBuf};
+use std::path::Path;
 
 use std::process::Command;
 
@@ -62,6 +73,18 @@ pub struct GitEngine {
     git_path: Option<String>, // 缓存检测到的Git路径
 
     repo_watcher: Option<RepoWatcherHandle>,
+
+    cancel_token: Option<CancellationToken>, // 当前操作的取消令牌，由命令层设置
+
+    credential_settings: GitCredentialSettings, // Git2Api 网络操作使用的凭据
+
+    history_walk: StdMutex<Option<HistoryWalk>>, // 最近一次提交历史遍历的顺序，翻页时复用
+}
+
+/// 提交历史的遍历顺序缓存：起点（仓库与各引用指向的提交）不变时，翻页直接按偏移取，不再重新拓扑排序
+struct HistoryWalk {
+    key: (String, Vec<git2::Oid>),
+    order: Arc<Vec<git2::Oid>>,
 }
 
 impl Clone for GitEngine {
@@ -76,23 +99,34 @@ impl Clone for GitEngine {
             git_path: self.git_path.clone(),
 
             repo_watcher: None,
+
+            cancel_token: self.cancel_token.clone(),
+
+            credential_settings: self.credential_settings.clone(),
+
+            history_walk: StdMutex::new(None),
         }
     }
 }
 
-#[derive(Debug)]
-
-struct RepoWatcherHandle {
-    watcher: RecommendedWatcher,
+/// git2 拉取时的合并策略（pull.rebase / pull.ff）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PullFastForward {
+    Allow,
+    Only,
+    Never,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Clone, Copy)]
+struct PullSettings {
+    rebase: bool,
+    fast_forward: PullFastForward,
+}
 
-struct GitStatusDirtyPayload {
-    repository: String,
+#[derive(Debug)]
 
-    #[serde(rename = "eventKind")]
-    event_kind: String,
+struct RepoWatcherHandle {
+    watcher: RepoWatcher,
 }
 
 impl GitEngine {
@@ -121,6 +155,12 @@ impl GitEngine {
             git_path,
 
             repo_watcher: None,
+
+            cancel_token: None,
+
+            credential_settings: GitCredentialSettings::default(),
+
+            history_walk: StdMutex::new(None),
         }
     }
 
@@ -151,6 +191,12 @@ impl GitEngine {
             git_path,
 
             repo_watcher: None,
+
+            cancel_token: None,
+
+            credential_settings: GitCredentialSettings::default(),
+
+            history_walk: StdMutex::new(None),
         }
     }
 
@@ -170,14 +216,46 @@ impl GitEngine {
         debug_log!("[DEBUG] 新的Git执行方式: {:?}", self.git_method);
     }
 
+    /// 设置当前操作的取消令牌，操作结束后应传入 None 清除
+    /// 作者：Evilek
+    /// 编写日期：2025-09-30
+    pub fn set_cancel_token(&mut self, token: Option<CancellationToken>) {
+        self.cancel_token = token;
+    }
+
+    /// 更新Git2Api网络操作使用的凭据设置
+    /// 作者：Evilek
+    /// 编写日期：2025-10-01
+    pub fn update_credential_settings(&mut self, settings: GitCredentialSettings) {
+        self.credential_settings = settings;
+    }
+
+    /// 为一次网络操作创建凭据提供者；credential helper 配置从仓库（或全局）配置读取
+    fn credential_provider(&self, repo: Option<&Repository>, url: &str) -> GitCredentialProvider {
+        let config = match repo {
+            Some(repo) => repo.config().ok(),
+            None => git2::Config::open_default().ok(),
+        };
+
+        GitCredentialProvider::new(self.credential_settings.clone(), config, url)
+    }
+
+    /// 探测Git可执行文件时使用的执行器，避免异常的 git 包装脚本卡住启动
+    fn probe_runner() -> GitProcessRunner {
+        GitProcessRunner::default().with_timeout(Some(Duration::from_secs(10)))
+    }
+
+    /// 按当前配置（超时、详细日志）与取消令牌创建Git子进程执行器
+    fn process_runner(&self) -> GitProcessRunner {
+        GitProcessRunner::from_config(&self.git_config).with_cancel_token(self.cancel_token.clone())
+    }
+
     /// 获取当前Git配置
 
     /// 作者：Evilek
 
     /// 编写日期：2025-08-07
 
-    #[allow(dead_code)]
-
     pub fn get_config(&self) -> &GitConfig {
         &self.git_config
     }
@@ -190,82 +268,19 @@ impl GitEngine {
 
         self.stop_repo_watcher();
 
-        let repo_path_buf = PathBuf::from(&repo_path);
-
-        let repo_path_for_event = Arc::new(repo_path);
-
-        let debounce_for_cb: Arc<StdMutex<Option<Instant>>> = Arc::new(StdMutex::new(None));
-
-        let app_handle_for_event = app_handle;
-
-        debug_log!("[DEBUG] 启动仓库文件监控: {}", repo_path_for_event.as_ref());
-
-        let mut watcher = RecommendedWatcher::new(
-            move |res: Result<Event, notify::Error>| match res {
-                Ok(event) => {
-                    let path_list = event
-                        .paths
-                        .iter()
-                        .map(|p| p.to_string_lossy())
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    debug_log!(
-                        "[DEBUG] repo watcher event: kind={:?}, paths=[{}]",
-                        event.kind,
-                        path_list
-                    );
-
-                    if !GitEngine::should_emit_event(&event.kind) {
-                        return;
-                    }
-
-                    if GitEngine::is_noise_event(&event) {
-                        debug_log!("[DEBUG] repo watcher filtered as noise");
-                        return;
-                    }
-
-                    let mut should_emit = true;
-
-                    if let Ok(mut guard) = debounce_for_cb.lock() {
-                        let now = Instant::now();
-
-                        if let Some(prev) = *guard {
-                            if now.duration_since(prev) < Duration::from_millis(400) {
-                                should_emit = false;
-                            }
-                        }
-
-                        if should_emit {
-                            *guard = Some(now);
-                        }
-                    }
-
-                    if !should_emit {
-                        return;
-                    }
-
-                    let payload = GitStatusDirtyPayload {
-                        repository: repo_path_for_event.as_ref().clone(),
-
-                        event_kind: format!("{:?}", event.kind),
-                    };
-
-                    if let Err(err) = app_handle_for_event.emit("git-status::dirty", payload) {
-                        debug_log!("[DEBUG] git-status::dirty 事件发送失败: {}", err);
-                    }
-                }
-
-                Err(err) => {
-                    debug_log!("[DEBUG] 仓库文件监控出现错误: {}", err);
+        // 工作区编辑推送状态增量，索引 / HEAD / 引用变化推送整体刷新
+        let watcher = RepoWatcher::start(
+            &repo_path,
+            Box::new(move |event| {
+                let result = match event {
+                    RepoWatchEvent::Dirty(payload) => app_handle.emit(DIRTY_EVENT, payload),
+                    RepoWatchEvent::Delta(payload) => app_handle.emit(DELTA_EVENT, payload),
+                };
+                if let Err(err) = result {
+                    debug_log!("[DEBUG] 仓库状态事件发送失败: {}", err);
                 }
-            },
-            NotifyConfig::default().with_poll_interval(Duration::from_secs(2)),
-        )
-        .map_err(|e| anyhow!("Failed to start repository watcher: {}", e))?;
-
-        watcher
-            .watch(&repo_path_buf, RecursiveMode::Recursive)
-            .map_err(|e| anyhow!("Failed to watch repository directory: {}", e))?;
+            }),
+        )?;
 
         self.repo_watcher = Some(RepoWatcherHandle { watcher });
 
@@ -294,13 +309,15 @@ impl GitEngine {
 
         let repo = Repository::open(repo_path)?;
 
-        let config = self.get_gitflow_config();
+        let (config, initialized) = Self::read_gitflow_config(&repo);
 
         let head_name = repo
             .head()
             .ok()
             .and_then(|head| head.shorthand().map(|s| s.to_string()));
 
+        let mut metadata = branch_metadata::load_all(&repo).unwrap_or_default();
+
         let mut branches = Vec::new();
 
         let mut branch_iter = repo.branches(Some(BranchType::Local))?;
@@ -397,6 +414,8 @@ impl GitEngine {
                     .as_ref()
                     .map(|current| current == &branch_name)
                     .unwrap_or(false),
+
+                metadata: metadata.remove(&branch_name).unwrap_or_default(),
             });
         }
 
@@ -408,6 +427,7 @@ impl GitEngine {
             config,
             branches,
             has_origin_remote,
+            initialized,
         })
     }
 
@@ -417,7 +437,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(["checkout", branch])
-            .output()?;
+            .output_with(&self.process_runner())?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -452,7 +472,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(["merge", "--no-ff", source])
-            .output()?;
+            .output_with(&self.process_runner())?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -474,7 +494,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(["rev-parse", "--abbrev-ref", "HEAD"])
-            .output()?;
+            .output_with(&self.process_runner())?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -485,11 +505,12 @@ impl GitEngine {
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
+    /// 执行 Gitflow 操作：修改仓库的操作在事务中执行，任一步骤失败时回滚本地分支、标签与工作区
     pub fn execute_gitflow_action(
         &self,
 
         request: &GitflowActionRequest,
-    ) -> Result<GitOperationResult> {
+    ) -> Result<GitflowActionResult> {
         let repo_path = self
             .repo_path
             .as_ref()
@@ -499,842 +520,899 @@ impl GitEngine {
 
         let current_branch = self.get_current_branch_name(repo_path)?;
 
-        let branch_type = Self::classify_gitflow_branch(&request.branch_name, &config);
+        // 只读操作不开事务：不储藏，失败时也不回滚工作区
+        let mut transaction = if Self::is_read_only_gitflow_action(request) {
+            GitflowTransaction::log_only(repo_path)
+        } else {
+            GitflowTransaction::begin(repo_path)?
+        };
 
-        let result = (|| -> Result<GitOperationResult> {
-            match request.action.as_str() {
-                "finish_feature" => {
-                    self.merge_branch_into(
-                        repo_path,
-                        &request.branch_name,
-                        &config.develop_branch,
-                    )?;
+        match self.run_gitflow_action(&mut transaction, repo_path, request, &config) {
+            Ok((message, report)) => {
+                let returned =
+                    self.return_to_branch(&mut transaction, repo_path, &current_branch, &config);
 
-                    Ok(GitOperationResult {
-                        success: true,
+                Ok(GitflowActionResult {
+                    success: true,
 
-                        message: format!(
-                            "已将 {} 合并回 {}",
-                            request.branch_name, config.develop_branch
-                        ),
+                    message,
 
-                        details: Some(
-                            "已完成 Feature 分支回流，可继续创建 PR 或删除分支".to_string(),
-                        ),
-                    })
-                }
+                    steps: transaction.commit(returned),
 
-                "finish_bugfix" => {
-                    self.merge_branch_into(
-                        repo_path,
-                        &request.branch_name,
-                        &config.develop_branch,
-                    )?;
+                    rolled_back: false,
 
-                    Ok(GitOperationResult {
-                        success: true,
+                    report,
 
-                        message: format!("已完成缺陷分支 {} 的回流", request.branch_name),
+                    gitflow_report: None,
+                })
+            }
 
-                        details: Some(format!(
-                            "分支已合并到 {}，请确认测试通过",
-                            config.develop_branch
-                        )),
-                    })
-                }
+            Err(error) => Ok(GitflowActionResult {
+                success: false,
 
-                "finish_local" => {
-                    let branch_kind = branch_type
-                        .ok_or_else(|| anyhow!("无法识别分支类型: {}", request.branch_name))?;
+                message: error.to_string(),
 
-                    if !matches!(
-                        branch_kind,
-                        GitflowBranchType::Feature | GitflowBranchType::Bugfix
-                    ) {
-                        return Err(anyhow!("当前分支类型不支持本地完成操作"));
-                    }
+                rolled_back: transaction.can_roll_back(),
 
-                    let base_branch = Self::resolve_base_branch(&branch_kind, &config);
+                steps: transaction.rollback(),
 
-                    return self.finish_branch_locally(
-                        repo_path,
-                        &request.branch_name,
-                        &base_branch,
-                    );
-                }
+                report: None,
 
-                "finish_release" => {
-                    let repo = Repository::open(repo_path)?;
+                gitflow_report: None,
+            }),
+        }
+    }
 
-                    if repo
-                        .find_branch(&request.branch_name, BranchType::Local)
-                        .is_err()
-                    {
-                        return Err(anyhow!(format!(
-                            "当前仓库未找到分支 {}，请刷新后重试。",
-                            request.branch_name
-                        )));
-                    }
+    /// 只读操作（报告、QA 状态、试运行）不修改分支与工作区
+    fn is_read_only_gitflow_action(request: &GitflowActionRequest) -> bool {
+        match request.action.as_str() {
+            "generate_status_report"
+            | "generate_postmortem"
+            | "generate_retrospective"
+            | "update_qa_status" => true,
 
-                    drop(repo);
+            "release_pipeline" => request
+                .release
+                .as_ref()
+                .is_some_and(|options| options.dry_run),
 
-                    let push_details =
-                        self.push_branch_with_upstream(repo_path, &request.branch_name)?;
+            _ => false,
+        }
+    }
 
-                    Ok(GitOperationResult {
-                        success: true,
+    /// 切回操作前的分支，返回是否回到了原分支（决定是否恢复自动储藏）
+    fn return_to_branch(
+        &self,
 
-                        message: format!("已将 {} 推送至远程", request.branch_name),
+        transaction: &mut GitflowTransaction,
 
-                        details: Some(push_details),
-                    })
-                }
+        repo_path: &str,
 
-                "close_release_local" => {
-                    let repo = Repository::open(repo_path)?;
+        original: &str,
 
-                    if repo
-                        .find_branch(&request.branch_name, BranchType::Local)
-                        .is_err()
-                    {
-                        return Err(anyhow!(format!(
-                            "当前仓库未找到分支 {}，请刷新后重试。",
-                            request.branch_name
-                        )));
-                    }
+        config: &GitflowConfig,
+    ) -> bool {
+        if self.get_current_branch_name(repo_path).ok().as_deref() == Some(original) {
+            return true;
+        }
 
-                    drop(repo);
+        let exists = Repository::open(repo_path)
+            .map(|repo| repo.find_branch(original, BranchType::Local).is_ok())
+            .unwrap_or(false);
 
-                    self.merge_branch_into(repo_path, &request.branch_name, &config.main_branch)?;
+        if exists {
+            return transaction
+                .step(format!("切回原分支 {}", original), || {
+                    self.checkout_branch_internal(repo_path, original)
+                })
+                .is_ok();
+        }
 
-                    if config.develop_branch != config.main_branch {
-                        self.merge_branch_into(
-                            repo_path,
-                            &request.branch_name,
-                            &config.develop_branch,
-                        )?;
-                    }
+        transaction.skip(format!("切回原分支 {}", original), "分支已删除");
 
-                    let target_branch = if config.develop_branch != config.main_branch {
-                        &config.develop_branch
-                    } else {
-                        &config.main_branch
-                    };
+        for fallback in [&config.develop_branch, &config.main_branch] {
+            if transaction
+                .step(format!("切换到 {}", fallback), || {
+                    self.checkout_branch_internal(repo_path, fallback)
+                })
+                .is_ok()
+            {
+                break;
+            }
+        }
+
+        false
+    }
 
-                    self.checkout_branch_internal(repo_path, target_branch)?;
+    fn ensure_local_branch(repo_path: &str, branch_name: &str, hint: &str) -> Result<()> {
+        let repo = Repository::open(repo_path)?;
 
-                    let _ = self.delete_local_branch(repo_path, &request.branch_name);
+        if repo.find_branch(branch_name, BranchType::Local).is_err() {
+            return Err(anyhow!("当前仓库未找到分支 {}，{}", branch_name, hint));
+        }
 
-                    Ok(GitOperationResult {
+        Ok(())
+    }
 
-                        success: true,
+    /// 执行具体操作，返回 (结果说明, 生成内容)
+    fn run_gitflow_action(
+        &self,
 
-                        message: format!(
+        tx: &mut GitflowTransaction,
 
-                            "release 分支 {} 已在本地合并并关闭",
+        repo_path: &str,
 
-                            request.branch_name
+        request: &GitflowActionRequest,
 
-                        ),
+        config: &GitflowConfig,
+    ) -> Result<(String, Option<String>)> {
+        let branch = request.branch_name.as_str();
 
-                        details: Some(
+        let branch_type = Self::classify_gitflow_branch(branch, config);
 
-                            "已清理本地 release 分支，未执行远端 push，如需同步请手动推送主干分支。"
+        let develop = config.develop_branch.as_str();
 
-                                .to_string(),
+        let main = config.main_branch.as_str();
 
-                        ),
+        let separate_develop = develop != main;
 
-                    })
-                }
+        match request.action.as_str() {
+            "finish_feature" => {
+                tx.step(format!("合并 {} 到 {}", branch, develop), || {
+                    self.merge_branch_into(repo_path, branch, develop)
+                })?;
 
-                "finalize_release" => {
-                    let repo = Repository::open(repo_path)?;
+                Ok((
+                    format!(
+                        "已将 {} 合并回 {}，可继续创建 PR 或删除分支",
+                        branch, develop
+                    ),
+                    None,
+                ))
+            }
 
-                    if repo
-                        .find_branch(&request.branch_name, BranchType::Local)
-                        .is_err()
-                    {
-                        return Err(anyhow!(format!(
-                            "当前仓库未找到分支 {}，请确认 release 分支仍然存在。",
-                            request.branch_name
-                        )));
-                    }
+            "finish_bugfix" => {
+                tx.step(format!("合并 {} 到 {}", branch, develop), || {
+                    self.merge_branch_into(repo_path, branch, develop)
+                })?;
 
-                    drop(repo);
+                Ok((
+                    format!("已完成缺陷分支 {} 的回流，请确认测试通过", branch),
+                    None,
+                ))
+            }
 
-                    self.merge_branch_into(repo_path, &request.branch_name, &config.main_branch)?;
+            "finish_local" => {
+                let branch_kind =
+                    branch_type.ok_or_else(|| anyhow!("无法识别分支类型: {}", branch))?;
 
-                    if config.develop_branch != config.main_branch {
-                        self.merge_branch_into(
-                            repo_path,
-                            &request.branch_name,
-                            &config.develop_branch,
-                        )?;
-                    }
+                if !matches!(
+                    branch_kind,
+                    GitflowBranchType::Feature | GitflowBranchType::Bugfix
+                ) {
+                    return Err(anyhow!("当前分支类型不支持本地完成操作"));
+                }
 
-                    self.push_branch_to_remote(repo_path, &config.main_branch, false)?;
+                let base_branch = Self::resolve_base_branch(&branch_kind, config);
 
-                    if config.develop_branch != config.main_branch {
-                        self.push_branch_to_remote(repo_path, &config.develop_branch, false)?;
-                    }
+                let result = tx.step(
+                    format!("在本地将 {} 合并到 {} 并清理", branch, base_branch),
+                    || self.finish_branch_locally(repo_path, branch, &base_branch),
+                )?;
 
-                    let target_branch = if config.develop_branch != config.main_branch {
-                        &config.develop_branch
-                    } else {
-                        &config.main_branch
-                    };
+                Ok((result.message, None))
+            }
 
-                    self.checkout_branch_internal(repo_path, target_branch)?;
+            "finish_release" => {
+                Self::ensure_local_branch(repo_path, branch, "请刷新后重试。")?;
 
-                    let _ = self.delete_remote_branch(repo_path, &request.branch_name);
+                tx.remote_step(format!("推送 {} 并设置上游", branch), || {
+                    self.push_branch_with_upstream(repo_path, branch)
+                })?;
 
-                    let _ = self.delete_local_branch(repo_path, &request.branch_name);
+                Ok((format!("已将 {} 推送至远程", branch), None))
+            }
 
-                    Ok(GitOperationResult {
-                        success: true,
+            "close_release_local" => {
+                Self::ensure_local_branch(repo_path, branch, "请刷新后重试。")?;
 
-                        message: format!("发布分支 {} 已合并至主干并完成清理", request.branch_name),
+                tx.step(format!("合并 {} 到 {}", branch, main), || {
+                    self.merge_branch_into(repo_path, branch, main)
+                })?;
 
-                        details: Some(format!(
-                            "已推送更新至远程，并删除 release 分支 {}",
-                            request.branch_name
-                        )),
-                    })
+                if separate_develop {
+                    tx.step(format!("合并 {} 到 {}", branch, develop), || {
+                        self.merge_branch_into(repo_path, branch, develop)
+                    })?;
                 }
 
-                "finish_hotfix" => {
-                    self.merge_branch_into(repo_path, &request.branch_name, &config.main_branch)?;
+                tx.step(format!("切换到 {}", develop), || {
+                    self.checkout_branch_internal(repo_path, develop)
+                })?;
 
-                    let backport_targets = if config.develop_branch != config.main_branch {
-                        self.merge_branch_into(
-                            repo_path,
-                            &request.branch_name,
-                            &config.develop_branch,
-                        )?;
+                // 分支已合并，清理失败只记录不回滚
+                let _ = tx.step(format!("删除本地分支 {}", branch), || {
+                    self.delete_local_branch(repo_path, branch)
+                });
 
-                        format!("{} 与 {}", config.main_branch, config.develop_branch)
-                    } else {
-                        config.main_branch.clone()
-                    };
+                Ok((
+                    format!(
+                        "release 分支 {} 已在本地合并并关闭，未执行远端 push，如需同步请手动推送主干分支",
+                        branch
+                    ),
+                    None,
+                ))
+            }
 
-                    Ok(GitOperationResult {
-                        success: true,
+            "finalize_release" => {
+                Self::ensure_local_branch(repo_path, branch, "请确认 release 分支仍然存在。")?;
 
-                        message: format!(
-                            "热修分支 {} 已回流至 {}",
-                            request.branch_name, backport_targets
-                        ),
+                tx.step(format!("合并 {} 到 {}", branch, main), || {
+                    self.merge_branch_into(repo_path, branch, main)
+                })?;
 
-                        details: Some("已同步热修补丁，请确认线上监控恢复".to_string()),
-                    })
+                if separate_develop {
+                    tx.step(format!("合并 {} 到 {}", branch, develop), || {
+                        self.merge_branch_into(repo_path, branch, develop)
+                    })?;
                 }
 
-                "sync_with_base" => {
-                    let branch_kind = branch_type
-                        .ok_or_else(|| anyhow!("无法识别分支类型: {}", request.branch_name))?;
+                tx.remote_step(format!("推送 {}", main), || {
+                    self.push_branch_to_remote(repo_path, main, false)
+                })?;
 
-                    let base_branch = Self::resolve_base_branch(&branch_kind, &config);
+                if separate_develop {
+                    tx.remote_step(format!("推送 {}", develop), || {
+                        self.push_branch_to_remote(repo_path, develop, false)
+                    })?;
+                }
 
-                    let repo = Repository::open(repo_path)?;
+                tx.step(format!("切换到 {}", develop), || {
+                    self.checkout_branch_internal(repo_path, develop)
+                })?;
 
-                    if repo.find_branch(&base_branch, BranchType::Local).is_err() {
-                        return Err(anyhow!(format!(
-                            "基线分支 {} 不存在，请先创建或在 Gitflow 设置中调整基线名称。",
-                            base_branch
-                        )));
-                    }
+                // 分支已合并，清理失败只记录不回滚
+                let _ = tx.remote_step(format!("删除远程分支 {}", branch), || {
+                    self.delete_remote_branch(repo_path, branch)
+                });
 
-                    if repo
-                        .find_branch(&request.branch_name, BranchType::Local)
-                        .is_err()
-                    {
-                        return Err(anyhow!(format!(
-                            "当前仓库未找到分支 {}，请刷新 Gitflow 仪表盘后重试。",
-                            request.branch_name
-                        )));
-                    }
+                let _ = tx.step(format!("删除本地分支 {}", branch), || {
+                    self.delete_local_branch(repo_path, branch)
+                });
 
-                    drop(repo);
+                Ok((format!("发布分支 {} 已合并至主干并完成清理", branch), None))
+            }
 
-                    // 更新基线分支到最新
+            "finish_hotfix" => {
+                tx.step(format!("合并 {} 到 {}", branch, main), || {
+                    self.merge_branch_into(repo_path, branch, main)
+                })?;
 
-                    self.checkout_branch_internal(repo_path, &base_branch)?;
+                if separate_develop {
+                    tx.step(format!("合并 {} 到 {}", branch, develop), || {
+                        self.merge_branch_into(repo_path, branch, develop)
+                    })?;
+                } else {
+                    tx.skip(format!("合并 {} 到开发分支", branch), "开发分支与主干相同");
+                }
 
-                    let remote_name = self.require_remote_name(repo_path)?;
-                    let _ = self.fetch_remote(Some(remote_name.as_str()))?;
-                    let _ = self.pull_current_branch()?;
+                Ok((
+                    format!("热修分支 {} 已回流，请确认线上监控恢复", branch),
+                    None,
+                ))
+            }
 
-                    // 合并最新基线到目标分支
+            "sync_with_base" => {
+                let branch_kind =
+                    branch_type.ok_or_else(|| anyhow!("无法识别分支类型: {}", branch))?;
 
-                    self.merge_branch_into(repo_path, &base_branch, &request.branch_name)?;
+                let base_branch = Self::resolve_base_branch(&branch_kind, config);
 
-                    Ok(GitOperationResult {
-                        success: true,
+                Self::ensure_local_branch(
+                    repo_path,
+                    &base_branch,
+                    "请先创建或在 Gitflow 设置中调整基线名称。",
+                )?;
 
-                        message: format!(
-                            "已同步 {} 的最新提交到 {}",
-                            base_branch, request.branch_name
-                        ),
+                Self::ensure_local_branch(repo_path, branch, "请刷新 Gitflow 仪表盘后重试。")?;
 
-                        details: Some("建议在本地验证后再继续开发或创建 PR".to_string()),
-                    })
-                }
+                // 更新基线分支到最新，再合并到目标分支
 
-                "generate_status_report" => {
-                    let digest =
-                        self.build_branch_digest(repo_path, &request.branch_name, "状态播报")?;
+                tx.step(format!("切换到 {}", base_branch), || {
+                    self.checkout_branch_internal(repo_path, &base_branch)
+                })?;
 
-                    Ok(GitOperationResult {
-                        success: true,
+                let remote_name = self.require_remote_name(repo_path)?;
 
-                        message: format!("已生成 {} 的状态播报草稿", request.branch_name),
+                tx.step(format!("获取 {} 的最新提交", remote_name), || {
+                    self.fetch_remote(Some(remote_name.as_str()))
+                        .map(|result| result.message)
+                })?;
 
-                        details: Some(digest),
-                    })
-                }
+                tx.step(format!("拉取 {}", base_branch), || {
+                    self.pull_current_branch().map(|result| result.message)
+                })?;
 
-                "create_pull_request" => {
-                    let push_details =
-                        self.push_branch_with_upstream(repo_path, &request.branch_name)?;
+                tx.step(format!("合并 {} 到 {}", base_branch, branch), || {
+                    self.merge_branch_into(repo_path, &base_branch, branch)
+                })?;
 
-                    let remote_name = self.require_remote_name(repo_path)?;
+                Ok((
+                    format!(
+                        "已同步 {} 的最新提交到 {}，建议在本地验证后再继续开发或创建 PR",
+                        base_branch, branch
+                    ),
+                    None,
+                ))
+            }
 
-                    let remote_url = self.get_remote_url(repo_path, &remote_name)?;
+            "generate_status_report" => {
+                let digest = tx.step("生成状态播报", || {
+                    self.build_branch_digest(repo_path, branch, "状态播报")
+                })?;
 
-                    let pr_hint = remote_url
-                        .as_deref()
-                        .and_then(|url| Self::build_pr_url_hint(url, &request.branch_name));
+                Ok((format!("已生成 {} 的状态播报草稿", branch), Some(digest)))
+            }
 
-                    let mut details = String::new();
+            "create_pull_request" => {
+                tx.remote_step(format!("推送 {} 并设置上游", branch), || {
+                    self.push_branch_with_upstream(repo_path, branch)
+                })?;
 
-                    if let Some(pr_url) = pr_hint {
-                        write!(&mut details, "PR 链接建议：{}\n", pr_url).ok();
-                    }
+                let remote_name = self.require_remote_name(repo_path)?;
 
-                    if !push_details.is_empty() {
-                        write!(&mut details, "Git 推送输出：{}", push_details).ok();
-                    }
+                let pr_hint = self
+                    .get_remote_url(repo_path, &remote_name)?
+                    .as_deref()
+                    .and_then(|url| Self::build_pr_url_hint(url, branch))
+                    .map(|pr_url| format!("PR 链接建议：{}", pr_url));
 
-                    Ok(GitOperationResult {
-                        success: true,
+                Ok((
+                    format!("分支 {} 已推送远程，可继续创建 PR", branch),
+                    pr_hint,
+                ))
+            }
 
-                        message: format!("分支 {} 已推送远程，可继续创建 PR", request.branch_name),
+            "update_qa_status" => {
+                let changes = request
+                    .metadata
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("缺少要更新的 QA 状态或分支元数据"))?;
 
-                        details: if details.is_empty() {
-                            None
-                        } else {
-                            Some(details)
-                        },
-                    })
-                }
+                let metadata = tx.step(format!("更新 {} 的分支元数据", branch), || {
+                    let repo = Repository::open(repo_path)?;
 
-                "update_qa_status" => Ok(GitOperationResult {
-                    success: true,
+                    let metadata = branch_metadata::update(&repo, branch, changes)?;
 
-                    message: format!("已记录 {} 的 QA 状态更新", request.branch_name),
+                    Ok(branch_metadata::digest_lines(&metadata).join("\n"))
+                })?;
 
-                    details: Some("请在详情栏同步最新回归结果与风险说明".to_string()),
-                }),
+                Ok((
+                    format!(
+                        "已记录 {} 的 QA 状态与分支元数据，同步远程后团队成员可见",
+                        branch
+                    ),
+                    Some(metadata),
+                ))
+            }
 
-                "backport_to_develop" => {
-                    self.merge_branch_into(
-                        repo_path,
-                        &request.branch_name,
-                        &config.develop_branch,
-                    )?;
+            "backport_to_develop" => {
+                tx.step(format!("合并 {} 到 {}", branch, develop), || {
+                    self.merge_branch_into(repo_path, branch, develop)
+                })?;
 
-                    Ok(GitOperationResult {
-                        success: true,
+                Ok((
+                    format!(
+                        "已将热修 {} 回流到 {}，请确认 develop CI 通过后再清理热修分支",
+                        branch, develop
+                    ),
+                    None,
+                ))
+            }
 
-                        message: format!(
-                            "已将热修 {} 回流到 {}",
-                            request.branch_name, config.develop_branch
-                        ),
+            "generate_postmortem" => {
+                let digest = tx.step("生成复盘草稿", || {
+                    self.build_branch_digest(repo_path, branch, "事故复盘")
+                })?;
 
-                        details: Some("请确认 develop CI 通过后再清理热修分支".to_string()),
-                    })
-                }
+                Ok((format!("已生成 {} 的复盘草稿", branch), Some(digest)))
+            }
 
-                "generate_postmortem" => {
-                    let digest =
-                        self.build_branch_digest(repo_path, &request.branch_name, "事故复盘")?;
+            "request_code_review" => {
+                tx.remote_step(format!("推送 {} 并设置上游", branch), || {
+                    self.push_branch_with_upstream(repo_path, branch)
+                })?;
 
-                    Ok(GitOperationResult {
-                        success: true,
+                Ok((
+                    format!(
+                        "已准备好 {} 的代码评审，建议在评审描述中引用最新推送",
+                        branch
+                    ),
+                    None,
+                ))
+            }
 
-                        message: format!("已生成 {} 的复盘草稿", request.branch_name),
+            "generate_retrospective" => {
+                let digest = tx.step("生成复盘提纲", || {
+                    self.build_branch_digest(repo_path, branch, "复盘提纲")
+                })?;
 
-                        details: Some(digest),
-                    })
-                }
+                Ok((format!("已生成 {} 的复盘提纲", branch), Some(digest)))
+            }
 
-                "request_code_review" => {
-                    let push_details =
-                        self.push_branch_with_upstream(repo_path, &request.branch_name)?;
+            "release_pipeline" => {
+                let options = request.release.clone().unwrap_or_default();
 
-                    Ok(GitOperationResult {
-                        success: true,
+                self.run_release_pipeline(tx, repo_path, branch, &options)
+            }
 
-                        message: format!("已准备好 {} 的代码评审", request.branch_name),
+            other => Err(anyhow!("未支持的 Gitflow 操作: {}", other)),
+        }
+    }
 
-                        details: Some(format!("建议在评审描述中引用最新推送：{}", push_details)),
-                    })
-                }
+    /// 计算发布计划：上次版本标签以来的提交 → 下一个语义化版本、版本文件与发布说明
+    pub fn plan_release(&self, branch_name: &str, options: &ReleaseOptions) -> Result<ReleasePlan> {
+        let repo = self.get_repository()?;
 
-                "generate_retrospective" => {
-                    let digest =
-                        self.build_branch_digest(repo_path, &request.branch_name, "复盘提纲")?;
+        let (config, _) = Self::read_gitflow_config(&repo);
 
-                    Ok(GitOperationResult {
-                        success: true,
+        if Self::classify_gitflow_branch(branch_name, &config) != Some(GitflowBranchType::Release) {
+            return Err(anyhow!(
+                "发布流水线只适用于 {} 开头的 release 分支",
+                config.release_prefix
+            ));
+        }
 
-                        message: format!("已生成 {} 的复盘提纲", request.branch_name),
+        let release_tip = repo
+            .find_branch(branch_name, BranchType::Local)
+            .map_err(|_| anyhow!("当前仓库未找到分支 {}", branch_name))?
+            .into_reference()
+            .peel_to_commit()?;
 
-                        details: Some(digest),
-                    })
-                }
+        // 上次发布：release 分支可达的、带版本前缀的最大版本标签
+        let mut previous: Option<(release_pipeline::SemVer, String, git2::Oid)> = None;
 
-                other => Err(anyhow!("未支持的 Gitflow 操作: {}", other)),
-            }
-        })();
+        for tag_name in repo.tag_names(None)?.iter().flatten() {
+            let Some(version) = tag_name
+                .strip_prefix(config.version_tag_prefix.as_str())
+                .and_then(release_pipeline::SemVer::parse)
+            else {
+                continue;
+            };
 
-        // 尝试切回原分支
+            let Ok(tag_commit) = repo
+                .revparse_single(&format!("refs/tags/{}", tag_name))
+                .and_then(|object| object.peel_to_commit())
+            else {
+                continue;
+            };
 
-        if self
-            .checkout_branch_internal(repo_path, &current_branch)
-            .is_err()
-        {
-            let _ = self.checkout_branch_internal(repo_path, &config.develop_branch);
+            let reachable = tag_commit.id() == release_tip.id()
+                || repo
+                    .graph_descendant_of(release_tip.id(), tag_commit.id())
+                    .unwrap_or(false);
 
-            if config.develop_branch != config.main_branch {
-                let _ = self.checkout_branch_internal(repo_path, &config.main_branch);
+            if reachable && previous.as_ref().is_none_or(|(best, _, _)| version > *best) {
+                previous = Some((version, tag_name.to_string(), tag_commit.id()));
             }
         }
 
-        result
-    }
-
-    fn build_branch_digest(
-        &self,
+        let mut revwalk = repo.revwalk()?;
 
-        repo_path: &str,
+        revwalk.push(release_tip.id())?;
 
-        branch_name: &str,
+        if let Some((_, _, tag_oid)) = &previous {
+            revwalk.hide(*tag_oid)?;
+        }
 
-        template_label: &str,
-    ) -> Result<String> {
-        let commits = self.collect_recent_commits(repo_path, branch_name, 5)?;
+        let mut messages = Vec::new();
 
-        let divergence = self.describe_branch_divergence(branch_name);
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
 
-        let mut buffer = String::new();
+            if commit.parent_count() > 1 {
+                continue;
+            }
 
-        write!(&mut buffer, "{}：{}\n", template_label, branch_name).ok();
+            if let Some(message) = commit.message() {
+                messages.push(message.trim().to_string());
+            }
+        }
 
-        if let Some(div_text) = divergence {
-            write!(&mut buffer, "{}\n", div_text).ok();
+        if repo.is_bare() {
+            return Err(anyhow!("裸仓库不支持发布流水线"));
         }
 
-        write!(&mut buffer, "最近提交：\n").ok();
+        // 版本文件取自 release 分支提交的树，而不是当前检出分支的索引与工作区；
+        // 只改写已跟踪的版本文件，避免碰到 node_modules 等目录
+        let mut version_files = Vec::new();
 
-        for line in commits {
-            write!(&mut buffer, "{}\n", line).ok();
-        }
+        let mut file_version = None;
 
-        buffer.push_str("建议：检查待办和差异，必要时更新 QA 清单或准备 PR。\n");
+        release_tip
+            .tree()?
+            .walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+                let Some(file_name) = entry.name() else {
+                    return git2::TreeWalkResult::Ok;
+                };
 
-        Ok(buffer)
-    }
+                if entry.kind() != Some(git2::ObjectType::Blob)
+                    || !release_pipeline::VERSION_FILE_NAMES.contains(&file_name)
+                {
+                    return git2::TreeWalkResult::Ok;
+                }
 
-    fn collect_recent_commits(
-        &self,
+                let Ok(blob) = repo.find_blob(entry.id()) else {
+                    return git2::TreeWalkResult::Ok;
+                };
 
-        repo_path: &str,
+                let Ok(content) = std::str::from_utf8(blob.content()) else {
+                    return git2::TreeWalkResult::Ok;
+                };
 
-        branch_name: &str,
+                if let Some(version) = release_pipeline::read_file_version(file_name, content) {
+                    file_version.get_or_insert(version);
 
-        limit: usize,
-    ) -> Result<Vec<String>> {
-        let git_command = self.get_git_command();
+                    version_files.push(format!("{}{}", dir, file_name));
+                }
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args([
-                "log",
-                branch_name,
-                "--max-count",
-                &limit.to_string(),
-                "--pretty=format:%h|%s|%an|%ar",
-            ])
-            .output()?;
+                git2::TreeWalkResult::Ok
+            })?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        let current = previous
+            .as_ref()
+            .map(|(version, _, _)| version.clone())
+            .or(file_version)
+            .unwrap_or(release_pipeline::SemVer {
+                major: 0,
+                minor: 0,
+                patch: 0,
+                pre: None,
+            });
 
-            return Err(anyhow!("获取提交摘要失败: {}", stderr.trim()));
-        }
+        // 版本来源：参数 > release 分支名（git-flow 惯例 release/1.2.0）> 约定式提交推导
+        let explicit = match options
+            .version
+            .as_deref()
+            .filter(|version| !version.trim().is_empty())
+        {
+            Some(version) => Some(
+                release_pipeline::SemVer::parse(version)
+                    .ok_or_else(|| anyhow!("无效的版本号: {}", version))?,
+            ),
+
+            None => branch_name
+                .strip_prefix(config.release_prefix.as_str())
+                .map(|name| {
+                    name.strip_prefix(config.version_tag_prefix.as_str())
+                        .unwrap_or(name)
+                })
+                .and_then(release_pipeline::SemVer::parse),
+        };
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (next, bump) = match explicit {
+            Some(version) => (version, None),
 
-        let mut lines = Vec::new();
+            None => {
+                let bump = release_pipeline::bump_from_commits(&messages)
+                    .ok_or_else(|| anyhow!("自上次发布以来 {} 没有新提交", branch_name))?;
 
-        for raw in stdout.lines() {
-            if raw.trim().is_empty() {
-                continue;
+                (current.bump(bump), Some(bump))
             }
+        };
 
-            let parts: Vec<&str> = raw.split('|').collect();
-
-            if parts.len() >= 4 {
-                lines.push(format!(
-                    "- {} \"{}\" · {} · {}",
-                    parts[0],
-                    parts[1].trim(),
-                    parts[2].trim(),
-                    parts[3].trim()
-                ));
-            } else {
-                lines.push(format!("- {}", raw.trim()));
-            }
+        if previous.is_some() && next <= current {
+            return Err(anyhow!("新版本 {} 必须高于上次发布的 {}", next, current));
         }
 
-        if lines.is_empty() {
-            lines.push("- 最近没有新的提交".to_string());
+        let tag_name = format!("{}{}", config.version_tag_prefix, next);
+
+        if repo
+            .find_reference(&format!("refs/tags/{}", tag_name))
+            .is_ok()
+        {
+            return Err(anyhow!("标签 {} 已存在", tag_name));
         }
 
-        Ok(lines)
-    }
+        let release_notes = release_pipeline::release_notes(
+            &tag_name,
+            &chrono::Local::now().format("%Y-%m-%d").to_string(),
+            &messages,
+        );
 
-    fn describe_branch_divergence(&self, branch_name: &str) -> Option<String> {
-        if let Ok(summary) = self.list_gitflow_branches() {
-            if let Some(info) = summary.branches.into_iter().find(|b| b.name == branch_name) {
-                let ahead = info.divergence.ahead;
+        let mut steps = vec![
+            if version_files.is_empty() {
+                format!("在 {} 上未找到版本文件，跳过版本号更新", branch_name)
+            } else {
+                format!(
+                    "在 {} 上将版本号 {} 更新为 {}：{}",
+                    branch_name,
+                    current,
+                    next,
+                    version_files.join("、")
+                )
+            },
+            format!(
+                "将发布说明写入 {} 并提交 \"chore(release): {}\"",
+                release_pipeline::CHANGELOG_FILE,
+                tag_name
+            ),
+            format!("将 {} 合并到 {}", branch_name, config.main_branch),
+            format!("在 {} 上创建附注标签 {}", config.main_branch, tag_name),
+        ];
 
-                let behind = info.divergence.behind;
+        if config.develop_branch != config.main_branch {
+            steps.push(format!(
+                "将 {}（含标签 {}）回流合并到 {}",
+                config.main_branch, tag_name, config.develop_branch
+            ));
+        }
 
-                if ahead > 0 || behind > 0 {
-                    return Some(format!(
-                        "差异：领先 {} 提交，落后 {} 提交（基线：{}）",
-                        ahead, behind, info.base
-                    ));
-                }
-            }
+        steps.push(format!("删除本地分支 {}", branch_name));
+
+        if options.push {
+            steps.push(format!(
+                "推送 {}、{} 与标签 {} 到远程",
+                config.main_branch, config.develop_branch, tag_name
+            ));
         }
 
-        None
-    }
+        Ok(ReleasePlan {
+            branch_name: branch_name.to_string(),
 
-    fn push_branch_with_upstream(&self, repo_path: &str, branch_name: &str) -> Result<String> {
-        let git_command = self.get_git_command();
+            previous_tag: previous.map(|(_, name, _)| name),
 
-        let remote_name = self.require_remote_name(repo_path)?;
+            current_version: current.to_string(),
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(["push", "-u", remote_name.as_str(), branch_name])
-            .output()?;
+            next_version: next.to_string(),
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+            bump,
 
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            tag_name,
 
-            let details = if !stderr.trim().is_empty() {
-                stderr.trim().to_string()
-            } else {
-                stdout.trim().to_string()
-            };
+            commit_count: messages.len(),
 
-            Ok(details)
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            version_files,
 
-            Err(anyhow!("推送分支失败: {}", stderr.trim()))
-        }
+            release_notes,
+
+            steps,
+        })
     }
 
-    fn push_branch_to_remote(
+    fn run_release_pipeline(
         &self,
 
+        tx: &mut GitflowTransaction,
+
         repo_path: &str,
 
         branch_name: &str,
 
-        force: bool,
-    ) -> Result<String> {
-        let git_command = self.get_git_command();
+        options: &ReleaseOptions,
+    ) -> Result<(String, Option<String>)> {
+        let plan = self.plan_release(branch_name, options)?;
 
-        let remote_name = self.require_remote_name(repo_path)?;
+        let summary = Self::format_release_summary(&plan);
 
-        let mut args: Vec<String> = vec!["push".to_string(), remote_name.clone()];
+        if options.dry_run {
+            for step in &plan.steps {
+                tx.skip(step.clone(), "预演，未执行");
+            }
 
-        if force {
-            args.push("--force-with-lease".to_string());
+            return Ok((
+                format!("发布预演：{} 将发布为 {}", branch_name, plan.tag_name),
+                Some(summary),
+            ));
         }
 
-        args.push(branch_name.to_string());
+        let config = self.get_gitflow_config();
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(args.iter().map(|arg| arg.as_str()))
-            .output()?;
+        let workdir = Path::new(repo_path);
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+        let next = release_pipeline::SemVer::parse(&plan.next_version)
+            .ok_or_else(|| anyhow!("无效的版本号: {}", plan.next_version))?;
 
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut step_names = plan.steps.iter().cloned();
 
-            let details = if !stderr.trim().is_empty() {
-                stderr.trim().to_string()
-            } else {
-                stdout.trim().to_string()
-            };
+        let mut next_step = || step_names.next().unwrap_or_default();
 
-            Ok(details)
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        tx.step(next_step(), || {
+            self.checkout_branch_internal(repo_path, branch_name)?;
 
-            Err(anyhow!(format!(
-                "推送分支 {} 失败: {}",
-                branch_name,
-                stderr.trim()
-            )))
-        }
-    }
+            for path in &plan.version_files {
+                let file_path = workdir.join(path);
 
-    fn finish_branch_locally(
-        &self,
-        repo_path: &str,
-        feature_branch: &str,
-        base_branch: &str,
-    ) -> Result<GitOperationResult> {
-        let repo = Repository::open(repo_path)?;
+                let file_name = path.rsplit('/').next().unwrap_or(path);
 
-        if repo.find_branch(feature_branch, BranchType::Local).is_err() {
-            return Err(anyhow!(
-                "当前仓库未找到分支 {}，请刷新 Gitflow 面板后重试",
-                feature_branch
-            ));
-        }
+                let content = fs::read_to_string(&file_path)?;
 
-        if repo.find_branch(base_branch, BranchType::Local).is_err() {
-            return Err(anyhow!(
-                "未找到基线分支 {}，请先创建或检出后再执行合并",
-                base_branch
-            ));
-        }
+                if let Some(updated) =
+                    release_pipeline::bump_file_version(file_name, &content, &next)
+                {
+                    fs::write(&file_path, updated)?;
+                }
+            }
 
-        let default_remote = Self::resolve_default_remote(&repo);
+            Ok(())
+        })?;
 
-        let (feature_ahead, feature_behind) = {
-            let feature = repo.find_branch(feature_branch, BranchType::Local)?;
-            let base = repo.find_branch(base_branch, BranchType::Local)?;
-            let feature_target = feature.get().target();
-            let base_target = base.get().target();
-            if let (Some(feature_oid), Some(base_oid)) = (feature_target, base_target) {
-                repo.graph_ahead_behind(feature_oid, base_oid)
-                    .unwrap_or((0, 0))
-            } else {
-                (0, 0)
-            }
-        };
+        tx.step(next_step(), || {
+            let changelog_path = workdir.join(release_pipeline::CHANGELOG_FILE);
 
-        drop(repo);
+            let existing_changelog = fs::read_to_string(&changelog_path).ok();
 
-        self.checkout_branch_internal(repo_path, base_branch)?;
+            fs::write(
+                &changelog_path,
+                release_pipeline::prepend_changelog(
+                    existing_changelog.as_deref(),
+                    &plan.release_notes,
+                ),
+            )?;
 
-        let mut detail_parts: Vec<String> = Vec::new();
+            let mut add_args = vec!["add", "--", release_pipeline::CHANGELOG_FILE];
 
-        if let Some(remote_name) = default_remote.as_ref() {
-            let fetch_result = self.fetch_remote(Some(remote_name.as_str()))?;
-            if let Some(info) = fetch_result.details {
-                let trimmed = info.trim();
-                if !trimmed.is_empty() {
-                    detail_parts.push(trimmed.to_string());
-                }
-            }
+            add_args.extend(plan.version_files.iter().map(String::as_str));
 
-            match self.pull_current_branch() {
-                Ok(result) => {
-                    if let Some(info) = result.details {
-                        let trimmed = info.trim();
-                        if !trimmed.is_empty() {
-                            detail_parts.push(trimmed.to_string());
-                        }
-                    }
-                }
-                Err(err) => {
-                    return Err(anyhow!("更新 {} 失败: {}", base_branch, err));
-                }
-            }
-        }
+            self.run_git(repo_path, &add_args)?;
 
-        self.merge_branch_into(repo_path, feature_branch, base_branch)?;
+            self.run_git(
+                repo_path,
+                &[
+                    "commit",
+                    "-m",
+                    &format!("chore(release): {}", plan.tag_name),
+                ],
+            )
+        })?;
 
-        if let Err(err) = self.delete_local_branch(repo_path, feature_branch) {
-            println!("[WARN] 删除本地分支 {} 失败: {}", feature_branch, err);
-        }
+        tx.step(next_step(), || {
+            self.merge_branch_into(repo_path, branch_name, &config.main_branch)
+        })?;
 
-        if feature_behind > 0 {
-            detail_parts.push(format!(
-                "合并前分支相对 {base_branch} 落后 {feature_behind} 个提交，已完成同步。"
-            ));
-        }
+        let tag_message = format!("Release {}\n\n{}", plan.tag_name, plan.release_notes);
 
-        if feature_ahead > 0 {
-            detail_parts.push(format!("包含 {feature_ahead} 个待合并提交。"));
+        tx.step(next_step(), || {
+            self.run_git(
+                repo_path,
+                &[
+                    "tag",
+                    "-a",
+                    &plan.tag_name,
+                    "-m",
+                    &tag_message,
+                    &config.main_branch,
+                ],
+            )
+        })?;
+
+        if config.develop_branch != config.main_branch {
+            // 与 git-flow 一致，回流的是带标签的主干，develop 因此包含发布提交
+            tx.step(next_step(), || {
+                self.merge_branch_into(repo_path, &config.main_branch, &config.develop_branch)
+            })?;
         }
 
-        Ok(GitOperationResult {
-            success: true,
-            message: format!("已将 {feature_branch} 合并至 {base_branch} 并关闭本地分支"),
-            details: if detail_parts.is_empty() {
-                None
-            } else {
-                Some(detail_parts.join(" "))
-            },
-        })
-    }
+        tx.step(next_step(), || {
+            self.checkout_branch_internal(repo_path, &config.develop_branch)?;
 
-    fn delete_remote_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
-        let git_command = self.get_git_command();
+            self.delete_local_branch(repo_path, branch_name)
+        })?;
 
-        let remote_name = self.require_remote_name(repo_path)?;
+        if options.push {
+            let tag_ref = format!("refs/tags/{}", plan.tag_name);
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(["push", remote_name.as_str(), "--delete", branch_name])
-            .output()?;
+            tx.remote_step(next_step(), || {
+                for refspec in [
+                    config.main_branch.as_str(),
+                    config.develop_branch.as_str(),
+                    tag_ref.as_str(),
+                ] {
+                    self.push_branch_to_remote(repo_path, refspec, false)?;
+                }
 
-        if output.status.success() {
-            return Ok(());
+                Ok(())
+            })?;
         }
 
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if stderr.contains("remote ref does not exist") || stderr.contains("unknown revision") {
-            return Ok(());
-        }
+        Ok((
+            format!(
+                "已发布 {}（{} → {}）",
+                plan.tag_name, plan.current_version, plan.next_version
+            ),
+            Some(summary),
+        ))
+    }
 
-        Err(anyhow!(format!(
-            "删除远程分支 {} 失败: {}",
-            branch_name,
-            stderr.trim()
-        )))
+    fn format_release_summary(plan: &ReleasePlan) -> String {
+        format!(
+            "版本：{} → {}（{}），自 {} 以来 {} 个提交\n\n{}",
+            plan.current_version,
+            plan.next_version,
+            match plan.bump {
+                Some(VersionBump::Major) => "major",
+                Some(VersionBump::Minor) => "minor",
+                Some(VersionBump::Patch) => "patch",
+                None => "指定版本",
+            },
+            plan.previous_tag.as_deref().unwrap_or("仓库起点"),
+            plan.commit_count,
+            plan.release_notes
+        )
     }
 
-    fn delete_local_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+    /// 运行 git 命令，失败时返回 stderr
+    fn run_git(&self, repo_path: &str, args: &[&str]) -> Result<String> {
         let git_command = self.get_git_command();
 
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
-            .args(["branch", "-D", branch_name])
-            .output()?;
-
-        if output.status.success() {
-            return Ok(());
-        }
+            .args(args)
+            .output_with(&self.process_runner())?;
 
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-        if stderr.contains("not found") || stderr.contains("Unknown branch") {
-            return Ok(());
+            return Err(anyhow!(
+                "git {} 失败: {}",
+                args.first().copied().unwrap_or_default(),
+                stderr.trim()
+            ));
         }
 
-        Err(anyhow!(format!(
-            "删除本地分支 {} 失败: {}",
-            branch_name,
-            stderr.trim()
-        )))
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
-    fn get_remote_url(&self, repo_path: &str, remote: &str) -> Result<Option<String>> {
-        let git_command = self.get_git_command();
+    fn build_branch_digest(
+        &self,
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(["remote", "get-url", remote])
-            .output()?;
+        repo_path: &str,
 
-        if !output.status.success() {
-            return Ok(None);
-        }
-
-        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-        if url.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(url))
-        }
-    }
+        branch_name: &str,
 
-    fn build_pr_url_hint(remote_url: &str, branch_name: &str) -> Option<String> {
-        let cleaned = remote_url.trim().trim_end_matches(".git");
+        template_label: &str,
+    ) -> Result<String> {
+        let commits = self.collect_recent_commits(repo_path, branch_name, 5)?;
 
-        let encoded_branch = Self::encode_branch_for_url(branch_name);
+        let divergence = self.describe_branch_divergence(branch_name);
 
-        if cleaned.contains("github.com") {
-            let repo_path = if cleaned.starts_with("git@github.com:") {
-                cleaned.trim_start_matches("git@github.com:").to_string()
-            } else if let Some(idx) = cleaned.find("github.com/") {
-                cleaned[idx + "github.com/".len()..].to_string()
-            } else {
-                return None;
-            };
+        let mut buffer = String::new();
 
-            Some(format!(
-                "https://github.com/{}/compare/{}?expand=1",
-                repo_path, encoded_branch
-            ))
-        } else if cleaned.contains("gitlab.com") {
-            let repo_path = if cleaned.starts_with("git@gitlab.com:") {
-                cleaned.trim_start_matches("git@gitlab.com:").to_string()
-            } else if let Some(idx) = cleaned.find("gitlab.com/") {
-                cleaned[idx + "gitlab.com/".len()..].to_string()
-            } else {
-                return None;
-            };
+        write!(&mut buffer, "{}：{}\n", template_label, branch_name).ok();
 
-            Some(format!(
-                "https://gitlab.com/{}/-/merge_requests/new?merge_request[source_branch]={}",
-                repo_path, encoded_branch
-            ))
-        } else {
-            None
+        if let Some(div_text) = divergence {
+            write!(&mut buffer, "{}\n", div_text).ok();
         }
-    }
-
-    fn encode_branch_for_url(branch: &str) -> String {
-        let mut encoded = String::new();
-
-        for ch in branch.chars() {
-            match ch {
-                '/' => encoded.push_str("%2F"),
-
-                ' ' => encoded.push_str("%20"),
 
-                '#' => encoded.push_str("%23"),
+        let metadata = Repository::open(repo_path)
+            .ok()
+            .and_then(|repo| branch_metadata::load(&repo, branch_name).ok().flatten());
 
-                '?' => encoded.push_str("%3F"),
+        if let Some(metadata) = metadata {
+            for line in branch_metadata::digest_lines(&metadata) {
+                buffer.push_str(&line);
 
-                '%' => encoded.push_str("%25"),
+                buffer.push('\n');
+            }
+        }
 
-                '+' => encoded.push_str("%2B"),
+        write!(&mut buffer, "最近提交：\n").ok();
 
-                _ => encoded.push(ch),
-            }
+        for line in commits {
+            write!(&mut buffer, "{}\n", line).ok();
         }
 
-        encoded
+        buffer.push_str("建议：检查待办和差异，必要时更新 QA 清单或准备 PR。\n");
+
+        Ok(buffer)
     }
 
-    pub fn create_gitflow_branch(
-        &self,
+    /// 收集生成 Gitflow AI 报告所需的上下文：分支独有提交、相对基线的 diffstat、差异与分支元数据
+    pub fn gitflow_report_context(&self, branch_name: &str) -> Result<GitflowReportContext> {
+        const MAX_COMMITS: usize = 50;
+
+        const MAX_FILES: usize = 100;
 
-        request: &GitflowCreateRequest,
-    ) -> Result<GitOperationResult> {
         let repo_path = self
             .repo_path
             .as_ref()
@@ -1342,2511 +1420,4705 @@ impl GitEngine {
 
         let repo = Repository::open(repo_path)?;
 
-        let branch_name = request.branch_name.trim();
+        let config = Self::read_gitflow_config(&repo).0;
 
-        if branch_name.is_empty() {
-            return Err(anyhow!("分支名称不能为空"));
-        }
+        let branch_type = Self::classify_gitflow_branch(branch_name, &config);
 
-        if repo.find_branch(branch_name, BranchType::Local).is_ok() {
-            return Err(anyhow!("分支 {} 已存在", branch_name));
-        }
+        let base_branch = branch_type
+            .as_ref()
+            .map(|branch_type| Self::resolve_base_branch(branch_type, &config))
+            .unwrap_or_else(|| config.develop_branch.clone());
 
-        let config = self.get_gitflow_config();
+        let branch_tip = repo
+            .revparse_single(branch_name)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| anyhow!("无法读取分支 {}: {}", branch_name, e))?;
 
-        let default_base = Self::resolve_base_branch(&request.branch_type, &config);
+        let base_tip = repo
+            .revparse_single(&base_branch)
+            .and_then(|object| object.peel_to_commit())
+            .ok()
+            .filter(|base| base.id() != branch_tip.id());
 
-        let base_branch_name = request
-            .base_branch
-            .as_ref()
-            .filter(|name| !name.trim().is_empty())
-            .cloned()
-            .unwrap_or(default_base.clone());
+        let mut divergence = GitflowDivergence::default();
 
-        let base_branch = repo
-            .find_branch(&base_branch_name, BranchType::Local)
-            .map_err(|_| anyhow!("找不到基线分支 {}", base_branch_name))?;
+        let mut revwalk = repo.revwalk()?;
 
-        let base_commit = base_branch
-            .into_reference()
-            .peel_to_commit()
-            .map_err(|e| anyhow!("无法获取基线分支提交: {}", e))?;
+        revwalk.push(branch_tip.id())?;
 
-        repo.branch(branch_name, &base_commit, false)
-            .map_err(|e| anyhow!("创建分支失败: {}", e))?;
+        let mut base_tree = None;
 
-        if request.auto_push {
-            let git_command = self.get_git_command();
+        if let Some(base_tip) = &base_tip {
+            revwalk.hide(base_tip.id())?;
 
-            let remote_name = self.require_remote_name(repo_path)?;
+            let (ahead, behind) = repo.graph_ahead_behind(branch_tip.id(), base_tip.id())?;
 
-            let output = Self::create_hidden_command(&git_command)
-                .current_dir(repo_path)
-                .args(["push", "-u", remote_name.as_str(), branch_name])
-                .output()
-                .map_err(|e| anyhow!("推送分支失败: {}", e))?;
+            divergence = GitflowDivergence {
+                ahead: ahead as u32,
 
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
+                behind: behind as u32,
+            };
 
-                return Err(anyhow!("推送分支失败: {}", stderr.trim()));
+            // diffstat 相对合并基点计算，不把基线上的新提交算进来
+            if let Ok(merge_base) = repo.merge_base(branch_tip.id(), base_tip.id()) {
+                base_tree = Some(repo.find_commit(merge_base)?.tree()?);
             }
         }
 
-        Ok(GitOperationResult {
-            success: true,
+        let mut commits = Vec::new();
 
-            message: format!("已创建分支 {}", branch_name),
+        for oid in revwalk.take(MAX_COMMITS) {
+            let commit = repo.find_commit(oid?)?;
 
-            details: Some(format!(
-                "基线：{}；类型：{:?}",
-                base_branch_name, request.branch_type
-            )),
-        })
-    }
+            commits.push(GitflowReportCommit {
+                id: commit.id().to_string()[..7].to_string(),
 
-    fn get_gitflow_config(&self) -> GitflowConfig {
-        let mut config = GitflowConfig::default();
+                summary: commit.summary().unwrap_or_default().to_string(),
 
-        if let Ok(repo) = self.get_repository() {
-            config.main_branch =
-                Self::detect_branch_name(&repo, &config.main_branch, &["main", "master", "trunk"]);
+                author: commit.author().name().unwrap_or_default().to_string(),
 
-            config.develop_branch = Self::detect_branch_name(
-                &repo,
-                &config.develop_branch,
-                &[
-                    "develop",
-                    "development",
-                    "dev",
-                    config.main_branch.as_str(),
-                    "master",
-                ],
-            );
+                date: Self::format_git_time(commit.time()),
+            });
         }
 
-        config
-    }
+        let mut diffstat = Vec::new();
 
-    fn detect_branch_name(repo: &Repository, preferred: &str, fallbacks: &[&str]) -> String {
-        if repo.find_branch(preferred, BranchType::Local).is_ok() {
-            return preferred.to_string();
-        }
+        let (mut insertions, mut deletions) = (0, 0);
 
-        for candidate in fallbacks {
-            if repo.find_branch(candidate, BranchType::Local).is_ok() {
-                return candidate.to_string();
-            }
-        }
+        let mut files_changed = 0;
 
-        if let Ok(head) = repo.head() {
-            if let Some(name) = head.shorthand() {
-                if repo.find_branch(name, BranchType::Local).is_ok() {
-                    return name.to_string();
-                }
-            }
-        }
+        if base_tree.is_some() {
+            let diff =
+                repo.diff_tree_to_tree(base_tree.as_ref(), Some(&branch_tip.tree()?), None)?;
 
-        preferred.to_string()
-    }
+            files_changed = diff.deltas().len() as u32;
 
-    fn classify_gitflow_branch(
-        branch_name: &str,
+            for index in 0..diff.deltas().len() {
+                let Some(patch) = git2::Patch::from_diff(&diff, index)? else {
+                    continue;
+                };
 
-        config: &GitflowConfig,
-    ) -> Option<GitflowBranchType> {
-        if branch_name.starts_with(&config.feature_prefix) {
-            Some(GitflowBranchType::Feature)
-        } else if branch_name.starts_with(&config.release_prefix) {
-            Some(GitflowBranchType::Release)
-        } else if branch_name.starts_with(&config.bugfix_prefix) {
-            Some(GitflowBranchType::Bugfix)
-        } else if branch_name.starts_with(&config.hotfix_prefix) {
-            Some(GitflowBranchType::Hotfix)
-        } else {
-            None
-        }
-    }
+                let (_, added, removed) = patch.line_stats()?;
 
-    fn resolve_base_branch(branch_type: &GitflowBranchType, config: &GitflowConfig) -> String {
-        match branch_type {
-            GitflowBranchType::Hotfix => config.main_branch.clone(),
+                insertions += added as u32;
 
-            _ => config.develop_branch.clone(),
-        }
-    }
+                deletions += removed as u32;
 
-    fn infer_gitflow_status(
-        branch_type: &GitflowBranchType,
+                if diffstat.len() < MAX_FILES {
+                    let delta = patch.delta();
 
-        divergence: &GitflowDivergence,
-    ) -> GitflowBranchStatus {
-        if divergence.ahead == 0 && divergence.behind == 0 {
-            match branch_type {
-                GitflowBranchType::Feature | GitflowBranchType::Bugfix => GitflowBranchStatus::Idle,
+                    let path = delta
+                        .new_file()
+                        .path()
+                        .or_else(|| delta.old_file().path())
+                        .map(|path| path.to_string_lossy().to_string())
+                        .unwrap_or_default();
 
-                GitflowBranchType::Release | GitflowBranchType::Hotfix => {
-                    GitflowBranchStatus::AwaitingMerge
+                    diffstat.push(GitflowFileStat {
+                        path,
+
+                        insertions: added as u32,
+
+                        deletions: removed as u32,
+                    });
                 }
             }
-        } else if divergence.behind > 0 {
-            GitflowBranchStatus::AwaitingMerge
-        } else {
-            GitflowBranchStatus::InProgress
+
+            diffstat.sort_by_key(|stat| std::cmp::Reverse(stat.insertions + stat.deletions));
         }
-    }
 
-    fn format_git_time(time: git2::Time) -> String {
-        let seconds = time.seconds();
+        let metadata = branch_metadata::load(&repo, branch_name)?.unwrap_or_default();
 
-        let offset_minutes = time.offset_minutes();
+        Ok(GitflowReportContext {
+            repo_path: repo_path.clone(),
 
-        let offset = FixedOffset::east_opt(offset_minutes * 60)
-            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+            branch_name: branch_name.to_string(),
 
-        if let Some(datetime_utc) = chrono::DateTime::<Utc>::from_timestamp(seconds, 0) {
-            datetime_utc.with_timezone(&offset).to_rfc3339()
-        } else {
-            Utc::now().to_rfc3339()
-        }
-    }
+            branch_type,
 
-    fn should_emit_event(kind: &EventKind) -> bool {
-        matches!(
-            kind,
-            EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) | EventKind::Any
-        )
-    }
+            base_branch,
 
-    fn is_noise_event(event: &Event) -> bool {
-        if event.paths.is_empty() {
-            return false;
-        }
+            divergence,
 
-        event.paths.iter().all(|path| Self::path_in_git_dir(path))
-    }
+            metadata,
 
-    fn path_in_git_dir(path: &Path) -> bool {
-        path.components().any(|component| match component {
-            Component::Normal(name) => name == OsStr::new(".git"),
-            _ => false,
+            commits,
+
+            diffstat,
+
+            files_changed,
+
+            insertions,
+
+            deletions,
         })
     }
 
-    /// 根据配置确定Git执行方式
+    /// 默认远程（origin 或当前分支的上游远程）的地址
+    pub fn default_remote_url(&self) -> Result<String> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No repository opened"))?;
 
-    /// 作者：Evilek
+        let remote_name = self.require_remote_name(repo_path)?;
 
-    /// 编写日期：2025-08-07
+        self.get_remote_url(repo_path, &remote_name)?
+            .ok_or_else(|| anyhow!("远程 {} 未配置地址", remote_name))
+    }
 
-    fn determine_git_method(config: &GitConfig) -> GitMethod {
-        match config.execution_mode {
-            GitExecutionMode::Auto => {
-                debug_log!("[DEBUG] 配置为自动检测，开始检测流程");
+    /// 推送分支并收集创建 PR/MR 所需的信息；base_branch 为空时按 Gitflow 分支类型推导
+    pub fn prepare_pull_request(
+        &self,
 
-                Self::detect_git_method()
-            }
+        branch_name: &str,
 
-            GitExecutionMode::SystemGit => {
-                debug_log!("[DEBUG] 配置强制使用系统Git");
+        base_branch: Option<&str>,
+    ) -> Result<PullRequestContext> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No repository opened"))?;
 
-                GitMethod::SystemGit
-            }
+        let config = self.get_gitflow_config();
 
-            GitExecutionMode::BundledGit => {
-                debug_log!("[DEBUG] 配置强制使用内置Git");
+        let base_branch = base_branch
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                Self::classify_gitflow_branch(branch_name, &config)
+                    .map(|branch_type| Self::resolve_base_branch(&branch_type, &config))
+                    .unwrap_or_else(|| config.develop_branch.clone())
+            });
 
-                GitMethod::BundledGit
-            }
+        if base_branch == branch_name {
+            return Err(anyhow!("源分支与目标分支相同: {}", branch_name));
+        }
 
-            GitExecutionMode::Git2Api => {
-                debug_log!("[DEBUG] 配置强制使用Git2库API");
+        let push_output = self.push_branch_with_upstream(repo_path, branch_name)?;
 
-                GitMethod::Git2Api
-            }
-        }
-    }
-
-    /// 检测Git路径
-
-    /// Author: Evilek, Date: 2025-01-08
-
-    /// 复用系统启动时的Git检测逻辑
+        let digest = self.build_branch_digest(repo_path, branch_name, "PR 摘要")?;
 
-    fn detect_git_path() -> Option<String> {
-        // 尝试不同的git命令名称（Windows兼容性）
+        let commits = self.collect_branch_only_commits(repo_path, branch_name, &base_branch)?;
 
-        let git_commands = if cfg!(windows) {
-            vec!["git.exe", "git"]
-        } else {
-            vec!["git"]
-        };
+        Ok(PullRequestContext {
+            branch_name: branch_name.to_string(),
 
-        // 首先尝试直接执行git命令
+            base_branch,
 
-        for git_cmd in &git_commands {
-            if let Ok(output) = Self::create_hidden_command(git_cmd)
-                .arg("--version")
-                .output()
-            {
-                if output.status.success() {
-                    let version = String::from_utf8_lossy(&output.stdout);
+            push_output,
 
-                    if !version.trim().is_empty() {
-                        debug_log!("[DEBUG] 找到系统Git: {}", git_cmd);
+            digest,
 
-                        return Some(git_cmd.to_string());
-                    }
-                }
-            }
-        }
+            commits,
+        })
+    }
 
-        // 如果直接执行失败，尝试常见的Git安装路径
+    /// 源分支相对目标分支独有的提交标题；目标分支只在远程存在时与远程分支比较
+    fn collect_branch_only_commits(
+        &self,
 
-        let common_paths = if cfg!(windows) {
-            vec![
-                "C:\\Program Files\\Git\\bin\\git.exe",
-                "C:\\Program Files (x86)\\Git\\bin\\git.exe",
-                "D:\\Soft\\Git\\bin\\git.exe", // 用户的Git路径
-                "C:\\Git\\bin\\git.exe",
-            ]
-        } else {
-            vec![
-                "/usr/bin/git",
-                "/usr/local/bin/git",
-                "/opt/homebrew/bin/git",
-            ]
-        };
+        repo_path: &str,
 
-        for path in common_paths {
-            if let Ok(output) = Self::create_hidden_command(path).arg("--version").output() {
-                if output.status.success() {
-                    let version = String::from_utf8_lossy(&output.stdout);
+        branch_name: &str,
 
-                    if !version.trim().is_empty() {
-                        debug_log!("[DEBUG] 找到Git路径: {}", path);
+        base_branch: &str,
+    ) -> Result<Vec<String>> {
+        const MAX_COMMITS: usize = 50;
 
-                        return Some(path.to_string());
-                    }
-                }
-            }
-        }
+        let repo = Repository::open(repo_path)?;
 
-        debug_log!("[WARN] 未找到可用的Git路径");
+        let branch_tip = repo
+            .revparse_single(branch_name)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| anyhow!("无法读取分支 {}: {}", branch_name, e))?;
 
-        None
-    }
+        let mut revwalk = repo.revwalk()?;
 
-    /// 检测最佳的Git执行方式（自动模式）
+        revwalk.push(branch_tip.id())?;
 
-    /// 作者：Evilek
+        let remote_base =
+            Self::resolve_default_remote(&repo).map(|remote| format!("{}/{}", remote, base_branch));
 
-    /// 编写日期：2025-08-07
+        let base_tip = std::iter::once(base_branch.to_string())
+            .chain(remote_base)
+            .find_map(|name| {
+                repo.revparse_single(&name)
+                    .and_then(|object| object.peel_to_commit())
+                    .ok()
+            });
 
-    fn detect_git_method() -> GitMethod {
-        debug_log!("[DEBUG] ========================================");
+        if let Some(base_tip) = base_tip {
+            revwalk.hide(base_tip.id())?;
+        }
 
-        debug_log!("[DEBUG] 开始Git执行方式自动检测流程");
+        let mut commits = Vec::new();
 
-        debug_log!("[DEBUG] ========================================");
+        for oid in revwalk.take(MAX_COMMITS) {
+            let commit = repo.find_commit(oid?)?;
 
-        // 1. 检测系统Git
+            if commit.parent_count() > 1 {
+                continue;
+            }
 
-        debug_log!("[DEBUG] 步骤1: 检测系统Git");
+            if let Some(summary) = commit.summary() {
+                commits.push(summary.to_string());
+            }
+        }
 
-        if Self::is_system_git_available() {
-            debug_log!("[SUCCESS] ✅ 检测到系统Git命令，优先使用");
+        Ok(commits)
+    }
 
-            debug_log!("[DEBUG] ========================================");
+    fn collect_recent_commits(
+        &self,
 
-            return GitMethod::SystemGit;
-        } else {
-            debug_log!("[WARN] ❌ 系统Git不可用，尝试内置Git");
-        }
+        repo_path: &str,
 
-        // 2. 检测内置Git
+        branch_name: &str,
 
-        debug_log!("[DEBUG] 步骤2: 检测内置Git");
+        limit: usize,
+    ) -> Result<Vec<String>> {
+        let git_command = self.get_git_command();
 
-        if Self::is_bundled_git_available() {
-            debug_log!("[SUCCESS] ✅ 检测到内置Git，使用备选方案");
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args([
+                "log",
+                branch_name,
+                "--max-count",
+                &limit.to_string(),
+                "--pretty=format:%h|%s|%an|%ar",
+            ])
+            .output_with(&self.process_runner())?;
 
-            debug_log!("[DEBUG] ========================================");
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-            return GitMethod::BundledGit;
-        } else {
-            debug_log!("[ERROR] ❌ 内置Git不可用");
+            return Err(anyhow!("获取提交摘要失败: {}", stderr.trim()));
         }
 
-        // 3. 不再自动降级到Git2库API，强制要求Git命令
+        let stdout = String::from_utf8_lossy(&output.stdout);
 
-        debug_log!("[DEBUG] 步骤3: 强制返回SystemGit，要求用户安装Git");
+        let mut lines = Vec::new();
 
-        debug_log!("[ERROR] ⚠️  系统Git和内置Git都不可用，GitMentor需要Git命令行工具才能正常工作");
+        for raw in stdout.lines() {
+            if raw.trim().is_empty() {
+                continue;
+            }
 
-        debug_log!("[ERROR] 请安装Git并确保在PATH中可用，或者在设置中手动配置为Git2Api模式");
+            let parts: Vec<&str> = raw.split('|').collect();
 
-        debug_log!("[DEBUG] ========================================");
+            if parts.len() >= 4 {
+                lines.push(format!(
+                    "- {} \"{}\" · {} · {}",
+                    parts[0],
+                    parts[1].trim(),
+                    parts[2].trim(),
+                    parts[3].trim()
+                ));
+            } else {
+                lines.push(format!("- {}", raw.trim()));
+            }
+        }
 
-        GitMethod::SystemGit // 强制返回SystemGit，让错误在实际使用时暴露
+        if lines.is_empty() {
+            lines.push("- 最近没有新的提交".to_string());
+        }
+
+        Ok(lines)
     }
 
-    /// 检测系统是否安装了Git命令
+    fn describe_branch_divergence(&self, branch_name: &str) -> Option<String> {
+        if let Ok(summary) = self.list_gitflow_branches() {
+            if let Some(info) = summary.branches.into_iter().find(|b| b.name == branch_name) {
+                let ahead = info.divergence.ahead;
 
-    /// 作者：Evilek
+                let behind = info.divergence.behind;
 
-    /// 编写日期：2025-08-07
+                if ahead > 0 || behind > 0 {
+                    return Some(format!(
+                        "差异：领先 {} 提交，落后 {} 提交（基线：{}）",
+                        ahead, behind, info.base
+                    ));
+                }
+            }
+        }
 
-    fn is_system_git_available() -> bool {
-        debug_log!("[DEBUG] ==================== 开始检测系统Git ====================");
+        None
+    }
 
-        debug_log!("[DEBUG] 当前工作目录: {:?}", std::env::current_dir());
+    fn push_branch_with_upstream(&self, repo_path: &str, branch_name: &str) -> Result<String> {
+        let git_command = self.get_git_command();
 
-        debug_log!("[DEBUG] PATH环境变量: {:?}", std::env::var("PATH"));
+        let remote_name = self.require_remote_name(repo_path)?;
 
-        // 尝试执行git --version命令
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(["push", "-u", remote_name.as_str(), branch_name])
+            .output_with(&self.process_runner())?;
 
-        debug_log!("[DEBUG] 执行命令: git --version");
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
 
-        // 先尝试直接执行git命令
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-        debug_log!("[DEBUG] 尝试方式1: 直接执行 'git'");
+            let details = if !stderr.trim().is_empty() {
+                stderr.trim().to_string()
+            } else {
+                stdout.trim().to_string()
+            };
 
-        match Self::create_hidden_command("git").arg("--version").output() {
-            Ok(output) => {
-                let version = String::from_utf8_lossy(&output.stdout);
+            Ok(details)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-                let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(anyhow!("推送分支失败: {}", stderr.trim()))
+        }
+    }
 
-                let status_code = output.status.code();
+    fn push_branch_to_remote(
+        &self,
 
-                debug_log!("[DEBUG] 方式1执行成功");
+        repo_path: &str,
 
-                debug_log!("[DEBUG] 退出状态码: {:?}", status_code);
+        branch_name: &str,
 
-                debug_log!("[DEBUG] 标准输出: '{}'", version.trim());
+        force: bool,
+    ) -> Result<String> {
+        let git_command = self.get_git_command();
 
-                debug_log!("[DEBUG] 标准错误: '{}'", stderr.trim());
+        let remote_name = self.require_remote_name(repo_path)?;
 
-                debug_log!("[DEBUG] 状态成功: {}", output.status.success());
+        let mut args: Vec<String> = vec!["push".to_string(), remote_name.clone()];
 
-                debug_log!("[DEBUG] 版本非空: {}", !version.trim().is_empty());
+        if force {
+            args.push("--force-with-lease".to_string());
+        }
 
-                let success = output.status.success() && !version.trim().is_empty();
+        args.push(branch_name.to_string());
 
-                if success {
-                    debug_log!("[SUCCESS] 方式1成功，系统Git可用");
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(args.iter().map(|arg| arg.as_str()))
+            .output_with(&self.process_runner())?;
 
-                    debug_log!("[DEBUG] ==================== 系统Git检测完成 ====================");
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
 
-                    return true;
-                } else {
-                    debug_log!("[WARN] 方式1失败，尝试方式2");
-                }
-            }
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-            Err(e) => {
-                debug_log!("[ERROR] 方式1执行失败: {}", e);
+            let details = if !stderr.trim().is_empty() {
+                stderr.trim().to_string()
+            } else {
+                stdout.trim().to_string()
+            };
 
-                debug_log!("[ERROR] 错误类型: {:?}", e.kind());
+            Ok(details)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-                debug_log!("[WARN] 尝试方式2: 使用完整路径");
-            }
+            Err(anyhow!(format!(
+                "推送分支 {} 失败: {}",
+                branch_name,
+                stderr.trim()
+            )))
         }
+    }
 
-        // 方式2: 尝试常见的Git安装路径
-
-        let git_paths = vec![
-            "C:\\Program Files\\Git\\bin\\git.exe",
-            "C:\\Program Files (x86)\\Git\\bin\\git.exe",
-            "D:\\Soft\\Git\\bin\\git.exe", // 用户的Git路径
-            "git.exe",
-        ];
-
-        for git_path in git_paths {
-            debug_log!("[DEBUG] 尝试路径: {}", git_path);
+    fn finish_branch_locally(
+        &self,
+        repo_path: &str,
+        feature_branch: &str,
+        base_branch: &str,
+    ) -> Result<GitOperationResult> {
+        let repo = Repository::open(repo_path)?;
 
-            match Self::create_hidden_command(git_path)
-                .arg("--version")
-                .output()
-            {
-                Ok(output) => {
-                    let version = String::from_utf8_lossy(&output.stdout);
+        if repo.find_branch(feature_branch, BranchType::Local).is_err() {
+            return Err(anyhow!(
+                "当前仓库未找到分支 {}，请刷新 Gitflow 面板后重试",
+                feature_branch
+            ));
+        }
 
-                    let stderr = String::from_utf8_lossy(&output.stderr);
+        if repo.find_branch(base_branch, BranchType::Local).is_err() {
+            return Err(anyhow!(
+                "未找到基线分支 {}，请先创建或检出后再执行合并",
+                base_branch
+            ));
+        }
 
-                    debug_log!("[DEBUG] 路径 {} 执行成功", git_path);
+        let default_remote = Self::resolve_default_remote(&repo);
 
-                    debug_log!("[DEBUG] 退出状态码: {:?}", output.status.code());
+        let (feature_ahead, feature_behind) = {
+            let feature = repo.find_branch(feature_branch, BranchType::Local)?;
+            let base = repo.find_branch(base_branch, BranchType::Local)?;
+            let feature_target = feature.get().target();
+            let base_target = base.get().target();
+            if let (Some(feature_oid), Some(base_oid)) = (feature_target, base_target) {
+                repo.graph_ahead_behind(feature_oid, base_oid)
+                    .unwrap_or((0, 0))
+            } else {
+                (0, 0)
+            }
+        };
 
-                    debug_log!("[DEBUG] 标准输出: '{}'", version.trim());
+        drop(repo);
 
-                    debug_log!("[DEBUG] 标准错误: '{}'", stderr.trim());
+        self.checkout_branch_internal(repo_path, base_branch)?;
 
-                    if output.status.success() && !version.trim().is_empty() {
-                        debug_log!("[SUCCESS] ✅ 找到可用的Git: {}", git_path);
+        let mut detail_parts: Vec<String> = Vec::new();
 
-                        debug_log!(
-                            "[DEBUG] ==================== 系统Git检测完成 ===================="
-                        );
+        if let Some(remote_name) = default_remote.as_ref() {
+            let fetch_result = self.fetch_remote(Some(remote_name.as_str()))?;
+            if let Some(info) = fetch_result.details {
+                let trimmed = info.trim();
+                if !trimmed.is_empty() {
+                    detail_parts.push(trimmed.to_string());
+                }
+            }
 
-                        return true;
+            match self.pull_current_branch() {
+                Ok(result) => {
+                    if let Some(info) = result.details {
+                        let trimmed = info.trim();
+                        if !trimmed.is_empty() {
+                            detail_parts.push(trimmed.to_string());
+                        }
                     }
                 }
-
-                Err(e) => {
-                    debug_log!("[DEBUG] 路径 {} 失败: {}", git_path, e);
+                Err(err) => {
+                    return Err(anyhow!("更新 {} 失败: {}", base_branch, err));
                 }
             }
         }
 
-        debug_log!("[ERROR] ❌ 所有Git路径都失败了");
-
-        debug_log!("[DEBUG] ==================== 系统Git检测失败 ====================");
-
-        false
-    }
+        self.merge_branch_into(repo_path, feature_branch, base_branch)?;
 
-    /// 检测内置Git是否可用
+        if let Err(err) = self.delete_local_branch(repo_path, feature_branch) {
+            warn_log!("删除本地分支 {} 失败: {}", feature_branch, err);
+        }
 
-    /// 作者：Evilek
+        if feature_behind > 0 {
+            detail_parts.push(format!(
+                "合并前分支相对 {base_branch} 落后 {feature_behind} 个提交，已完成同步。"
+            ));
+        }
 
-    /// 编写日期：2025-08-07
+        if feature_ahead > 0 {
+            detail_parts.push(format!("包含 {feature_ahead} 个待合并提交。"));
+        }
 
-    fn is_bundled_git_available() -> bool {
-        debug_log!("[DEBUG] ==================== 开始检测内置Git ====================");
+        Ok(GitOperationResult {
+            success: true,
+            message: format!("已将 {feature_branch} 合并至 {base_branch} 并关闭本地分支"),
+            details: if detail_parts.is_empty() {
+                None
+            } else {
+                Some(detail_parts.join(" "))
+            },
+        })
+    }
 
-        // 获取当前目标平台
+    fn delete_remote_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        let git_command = self.get_git_command();
 
-        let target_triple = Self::get_target_triple();
+        let remote_name = self.require_remote_name(repo_path)?;
 
-        debug_log!("[DEBUG] 当前目标平台: {}", target_triple);
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(["push", remote_name.as_str(), "--delete", branch_name])
+            .output_with(&self.process_runner())?;
 
-        // 构建Git二进制文件名
+        if output.status.success() {
+            return Ok(());
+        }
 
-        let git_binary_name = if cfg!(windows) {
-            format!("git-{}.exe", target_triple)
-        } else {
-            format!("git-{}", target_triple)
-        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
 
-        debug_log!("[DEBUG] 期望的Git二进制文件名: {}", git_binary_name);
+        if stderr.contains("remote ref does not exist") || stderr.contains("unknown revision") {
+            return Ok(());
+        }
 
-        // 检查二进制文件是否存在
+        Err(anyhow!(format!(
+            "删除远程分支 {} 失败: {}",
+            branch_name,
+            stderr.trim()
+        )))
+    }
 
-        // 在开发环境中，检查 src-tauri/binaries/ 目录
+    fn delete_local_branch(&self, repo_path: &str, branch_name: &str) -> Result<()> {
+        let git_command = self.get_git_command();
 
-        // 在生产环境中，Tauri会自动处理sidecar的路径
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(["branch", "-D", branch_name])
+            .output_with(&self.process_runner())?;
 
-        let binary_path = if cfg!(debug_assertions) {
-            // 开发环境：检查binaries/目录（当前工作目录已经是src-tauri）
+        if output.status.success() {
+            return Ok(());
+        }
 
-            let current_dir =
-                std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+        let stderr = String::from_utf8_lossy(&output.stderr);
 
-            debug_log!("[DEBUG] 当前工作目录: {}", current_dir.display());
+        if stderr.contains("not found") || stderr.contains("Unknown branch") {
+            return Ok(());
+        }
 
-            let path = current_dir.join("binaries").join(&git_binary_name);
+        Err(anyhow!(format!(
+            "删除本地分支 {} 失败: {}",
+            branch_name,
+            stderr.trim()
+        )))
+    }
 
-            debug_log!("[DEBUG] 开发环境检查路径: {}", path.display());
+    fn get_remote_url(&self, repo_path: &str, remote: &str) -> Result<Option<String>> {
+        let git_command = self.get_git_command();
 
-            path
-        } else {
-            // 生产环境：Tauri会处理sidecar路径，这里只是检查逻辑
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(["remote", "get-url", remote])
+            .output_with(&self.process_runner())?;
 
-            // 实际使用时应该通过tauri::api::process::Command::sidecar来调用
+        if !output.status.success() {
+            return Ok(None);
+        }
 
-            let path = std::path::PathBuf::from("binaries").join(&git_binary_name);
+        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
-            debug_log!("[DEBUG] 生产环境检查路径: {}", path.display());
+        if url.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(url))
+        }
+    }
 
-            path
-        };
+    fn build_pr_url_hint(remote_url: &str, branch_name: &str) -> Option<String> {
+        let cleaned = remote_url.trim().trim_end_matches(".git");
 
-        debug_log!("[DEBUG] 检查文件是否存在: {}", binary_path.display());
+        let encoded_branch = Self::encode_branch_for_url(branch_name);
 
-        let exists = binary_path.exists();
+        if cleaned.contains("github.com") {
+            let repo_path = if cleaned.starts_with("git@github.com:") {
+                cleaned.trim_start_matches("git@github.com:").to_string()
+            } else if let Some(idx) = cleaned.find("github.com/") {
+                cleaned[idx + "github.com/".len()..].to_string()
+            } else {
+                return None;
+            };
 
-        debug_log!("[DEBUG] 文件存在性检查结果: {}", exists);
+            Some(format!(
+                "https://github.com/{}/compare/{}?expand=1",
+                repo_path, encoded_branch
+            ))
+        } else if cleaned.contains("gitlab.com") {
+            let repo_path = if cleaned.starts_with("git@gitlab.com:") {
+                cleaned.trim_start_matches("git@gitlab.com:").to_string()
+            } else if let Some(idx) = cleaned.find("gitlab.com/") {
+                cleaned[idx + "gitlab.com/".len()..].to_string()
+            } else {
+                return None;
+            };
 
-        if exists {
-            debug_log!("[DEBUG] 找到内置Git文件: {}", binary_path.display());
+            Some(format!(
+                "https://gitlab.com/{}/-/merge_requests/new?merge_request[source_branch]={}",
+                repo_path, encoded_branch
+            ))
+        } else {
+            None
+        }
+    }
 
-            // 获取文件元数据
+    fn encode_branch_for_url(branch: &str) -> String {
+        let mut encoded = String::new();
 
-            match std::fs::metadata(&binary_path) {
-                Ok(metadata) => {
-                    debug_log!("[DEBUG] 文件大小: {} bytes", metadata.len());
+        for ch in branch.chars() {
+            match ch {
+                '/' => encoded.push_str("%2F"),
 
-                    debug_log!("[DEBUG] 文件类型: {:?}", metadata.file_type());
+                ' ' => encoded.push_str("%20"),
 
-                    // 检查文件是否可执行（Unix系统）
+                '#' => encoded.push_str("%23"),
 
-                    #[cfg(unix)]
-                    {
-                        use std::os::unix::fs::PermissionsExt;
+                '?' => encoded.push_str("%3F"),
 
-                        let permissions = metadata.permissions();
+                '%' => encoded.push_str("%25"),
 
-                        let mode = permissions.mode();
+                '+' => encoded.push_str("%2B"),
 
-                        let is_executable = mode & 0o111 != 0;
+                _ => encoded.push(ch),
+            }
+        }
 
-                        debug_log!("[DEBUG] 文件权限模式: {:o}", mode);
+        encoded
+    }
 
-                        debug_log!("[DEBUG] 文件可执行性: {}", is_executable);
+    pub fn create_gitflow_branch(
+        &self,
 
-                        if !is_executable {
-                            debug_log!(
-                                "[WARN] 内置Git文件存在但不可执行: {}",
-                                binary_path.display()
-                            );
-
-                            debug_log!(
-                                "[DEBUG] ==================== 内置Git检测失败 ===================="
-                            );
+        request: &GitflowCreateRequest,
+    ) -> Result<GitOperationResult> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No repository opened"))?;
 
-                            return false;
-                        }
-                    }
+        let repo = Repository::open(repo_path)?;
 
-                    #[cfg(windows)]
-                    {
-                        debug_log!("[DEBUG] Windows系统，跳过可执行性检查");
-                    }
-                }
+        let branch_name = request.branch_name.trim();
 
-                Err(e) => {
-                    debug_log!("[ERROR] 无法获取文件元数据: {}", e);
+        if branch_name.is_empty() {
+            return Err(anyhow!("分支名称不能为空"));
+        }
 
-                    debug_log!("[DEBUG] ==================== 内置Git检测失败 ====================");
+        if repo.find_branch(branch_name, BranchType::Local).is_ok() {
+            return Err(anyhow!("分支 {} 已存在", branch_name));
+        }
 
-                    return false;
-                }
-            }
+        let config = self.get_gitflow_config();
 
-            debug_log!("[DEBUG] 内置Git检测成功");
+        let default_base = Self::resolve_base_branch(&request.branch_type, &config);
 
-            debug_log!("[DEBUG] ==================== 内置Git检测完成 ====================");
+        let base_branch_name = request
+            .base_branch
+            .as_ref()
+            .filter(|name| !name.trim().is_empty())
+            .cloned()
+            .unwrap_or(default_base.clone());
 
-            true
-        } else {
-            debug_log!("[DEBUG] 未找到内置Git文件: {}", binary_path.display());
+        let base_branch = repo
+            .find_branch(&base_branch_name, BranchType::Local)
+            .map_err(|_| anyhow!("找不到基线分支 {}", base_branch_name))?;
 
-            // 列出binaries目录的内容，帮助调试
+        let base_commit = base_branch
+            .into_reference()
+            .peel_to_commit()
+            .map_err(|e| anyhow!("无法获取基线分支提交: {}", e))?;
 
-            let binaries_dir = if cfg!(debug_assertions) {
-                std::env::current_dir()
-                    .unwrap_or_else(|_| std::path::PathBuf::from("."))
-                    .join("binaries")
-            } else {
-                std::path::PathBuf::from("binaries")
-            };
+        repo.branch(branch_name, &base_commit, false)
+            .map_err(|e| anyhow!("创建分支失败: {}", e))?;
 
-            debug_log!("[DEBUG] 检查binaries目录: {}", binaries_dir.display());
+        if !request.metadata.is_empty() {
+            branch_metadata::update(
+                &repo,
+                branch_name,
+                &branch_metadata::from_fields(&request.metadata),
+            )
+            .map_err(|e| anyhow!("保存分支元数据失败: {}", e))?;
+        }
 
-            if binaries_dir.exists() {
-                match std::fs::read_dir(&binaries_dir) {
-                    Ok(entries) => {
-                        debug_log!("[DEBUG] binaries目录内容:");
+        if request.auto_push {
+            let git_command = self.get_git_command();
 
-                        for entry in entries {
-                            if let Ok(entry) = entry {
-                                debug_log!("[DEBUG]   - {}", entry.file_name().to_string_lossy());
-                            }
-                        }
-                    }
+            let remote_name = self.require_remote_name(repo_path)?;
 
-                    Err(e) => {
-                        debug_log!("[DEBUG] 无法读取binaries目录: {}", e);
-                    }
-                }
-            } else {
-                debug_log!("[DEBUG] binaries目录不存在");
-            }
+            let output = Self::create_hidden_command(&git_command)
+                .current_dir(repo_path)
+                .args(["push", "-u", remote_name.as_str(), branch_name])
+                .output_with(&self.process_runner())
+                .map_err(|e| anyhow!("推送分支失败: {}", e))?;
 
-            debug_log!("[DEBUG] ==================== 内置Git检测失败 ====================");
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
 
-            false
+                return Err(anyhow!("推送分支失败: {}", stderr.trim()));
+            }
         }
-    }
-
-    /// 获取当前目标平台的target triple
 
-    /// 作者：Evilek
+        Ok(GitOperationResult {
+            success: true,
 
-    /// 编写日期：2025-08-07
+            message: format!("已创建分支 {}", branch_name),
 
-    fn get_target_triple() -> String {
-        // 在编译时确定的目标平台，使用条件编译来确定
+            details: Some(format!(
+                "基线：{}；类型：{:?}",
+                base_branch_name, request.branch_type
+            )),
+        })
+    }
 
-        #[cfg(all(target_arch = "x86_64", target_os = "windows"))]
-        return "x86_64-pc-windows-msvc".to_string();
+    /// 读取分支元数据，未记录时返回空元数据
+    pub fn get_branch_metadata(&self, branch_name: &str) -> Result<GitflowBranchMetadata> {
+        let repo = self.get_repository()?;
 
-        #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
-        return "x86_64-unknown-linux-gnu".to_string();
+        Ok(branch_metadata::load(&repo, branch_name)?.unwrap_or_default())
+    }
 
-        #[cfg(all(target_arch = "x86_64", target_os = "macos"))]
-        return "x86_64-apple-darwin".to_string();
+    /// 合并更新分支元数据（只在本地记录，需调用 sync_branch_metadata 推送到远程）
+    pub fn update_branch_metadata(
+        &self,
 
-        #[cfg(all(target_arch = "aarch64", target_os = "macos"))]
-        return "aarch64-apple-darwin".to_string();
+        branch_name: &str,
 
-        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
-        return "aarch64-unknown-linux-gnu".to_string();
+        changes: &GitflowBranchMetadata,
+    ) -> Result<GitflowBranchMetadata> {
+        let repo = self.get_repository()?;
 
-        // 默认返回通用标识符（只有在不匹配任何已知平台时才会执行）
+        if repo.find_branch(branch_name, BranchType::Local).is_err() {
+            return Err(anyhow!("找不到分支 {}", branch_name));
+        }
 
-        #[cfg(not(any(
-            all(target_arch = "x86_64", target_os = "windows"),
-            all(target_arch = "x86_64", target_os = "linux"),
-            all(target_arch = "x86_64", target_os = "macos"),
-            all(target_arch = "aarch64", target_os = "macos"),
-            all(target_arch = "aarch64", target_os = "linux")
-        )))]
-        "unknown-target".to_string()
+        branch_metadata::update(&repo, branch_name, changes)
     }
 
-    /// 创建隐藏窗口的命令（Windows 下避免黑色 CMD 闪窗）
+    /// 与默认远程同步分支元数据：先拉取远程快照并合并，再推送合并结果；
+    /// Git2Api 模式下走 git2 并使用凭据设置，其余模式调用 Git 命令
+    pub fn sync_branch_metadata(&self) -> Result<GitOperationResult> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No repository opened"))?;
 
-    /// 作者：Evilek
+        let remote_name = self.require_remote_name(repo_path)?;
 
-    /// 编写日期：2025-08-11
+        let tracking_ref = branch_metadata::remote_tracking_ref(&remote_name);
 
-    /// 说明：统一封装外部 git 命令创建，Windows 使用 CREATE_NO_WINDOW 隐藏控制台窗口；其他平台保持默认
+        let fetched = match self.git_method {
+            GitMethod::Git2Api => {
+                self.fetch_metadata_with_git2(repo_path, &remote_name, &tracking_ref)?
+            }
 
-    /// Confirmed via 寸止(ID:WIN-CMD-HIDE-20250811)
+            GitMethod::SystemGit | GitMethod::BundledGit => {
+                self.fetch_metadata_with_command(repo_path, &remote_name, &tracking_ref)?
+            }
+        };
 
-    fn create_hidden_command(program: &str) -> Command {
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
+        let repo = Repository::open(repo_path)?;
 
-            // CREATE_NO_WINDOW 常量，避免创建控制台窗口
+        let adopted = if fetched {
+            branch_metadata::merge_remote(&repo, &tracking_ref)?
+        } else {
+            0
+        };
 
-            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let pushed = repo.find_reference(branch_metadata::METADATA_REF).is_ok();
 
-            let mut cmd = Command::new(program);
+        if pushed {
+            // 不强制推送：合并后的本地快照是远程的后代，期间远程被其他人更新时推送会被拒绝
+            let refspec = format!("{0}:{0}", branch_metadata::METADATA_REF);
 
-            cmd.creation_flags(CREATE_NO_WINDOW);
+            match self.git_method {
+                GitMethod::Git2Api => self.push_refspec_with_git2(&repo, &remote_name, &refspec)?,
 
-            return cmd;
+                GitMethod::SystemGit | GitMethod::BundledGit => {
+                    self.run_git(repo_path, &["push", remote_name.as_str(), refspec.as_str()])?;
+                }
+            }
         }
 
-        #[cfg(not(windows))]
-        {
-            Command::new(program)
-        }
-    }
+        Ok(GitOperationResult {
+            success: true,
 
-    /// 使用Git命令获取状态（超快速）
+            message: format!("已与 {} 同步分支元数据", remote_name),
 
-    /// 作者：Evilek
+            details: Some(if pushed {
+                format!("从远程更新了 {} 个分支的元数据，并已推送本地记录", adopted)
+            } else {
+                "本地与远程都还没有分支元数据".to_string()
+            }),
+        })
+    }
 
-    /// 编写日期：2025-08-06
+    /// 用 Git 命令把远程元数据拉取到跟踪引用，远程没有元数据时返回 false
+    fn fetch_metadata_with_command(
+        &self,
+        repo_path: &str,
+        remote_name: &str,
+        tracking_ref: &str,
+    ) -> Result<bool> {
+        let remote_state = self.run_git(
+            repo_path,
+            &["ls-remote", remote_name, branch_metadata::METADATA_REF],
+        )?;
 
-    pub fn get_status_with_git_command(&self) -> Result<GitStatusResult> {
-        debug_log!("[DEBUG] 使用Git命令获取状态...");
+        if remote_state.trim().is_empty() {
+            return Ok(false);
+        }
 
-        let start_time = Instant::now();
+        let refspec = format!("+{}:{}", branch_metadata::METADATA_REF, tracking_ref);
 
-        let repo_path = self
-            .repo_path
-            .as_ref()
-            .ok_or_else(|| anyhow!("No repository opened"))?;
+        self.run_git(repo_path, &["fetch", remote_name, refspec.as_str()])?;
 
-        debug_log!("[DEBUG] 当前仓库路径: {}", repo_path);
+        Ok(true)
+    }
 
-        debug_log!("[DEBUG] 当前工作目录: {:?}", std::env::current_dir());
+    /// 用 git2 把远程元数据拉取到跟踪引用，远程没有元数据时返回 false
+    fn fetch_metadata_with_git2(
+        &self,
+        repo_path: &str,
+        remote_name: &str,
+        tracking_ref: &str,
+    ) -> Result<bool> {
+        let repo = Repository::open(repo_path)?;
 
-        // 获取当前分支
+        let mut remote = repo.find_remote(remote_name)?;
 
-        println!("[DEBUG] 获取当前分支...");
+        let remote_url = remote.url().unwrap_or("").to_string();
 
-        let branch_start = Instant::now();
+        let provider = self.credential_provider(Some(&repo), &remote_url);
 
-        let branch = self.get_current_branch_with_command(repo_path)?;
+        let mut fetch_options = git2::FetchOptions::new();
 
-        println!(
-            "[DEBUG] 分支获取完成: {}, 耗时: {:?}",
-            branch,
-            branch_start.elapsed()
-        );
+        fetch_options.remote_callbacks(provider.remote_callbacks());
 
-        // 获取暂存区文件
+        // 远程没有元数据时 fetch 不会报错，先清掉旧的跟踪引用，拉取后据其是否存在判断
+        if let Ok(mut reference) = repo.find_reference(tracking_ref) {
+            reference.delete()?;
+        }
 
-        println!("[DEBUG] 获取暂存区文件...");
+        let refspec = format!("+{}:{}", branch_metadata::METADATA_REF, tracking_ref);
 
-        let staged_start = Instant::now();
+        remote
+            .fetch(&[refspec.as_str()], Some(&mut fetch_options), None)
+            .map_err(|e| anyhow::Error::new(provider.classify_error(&e)))?;
 
-        let staged_files = self.get_staged_files_with_command(repo_path)?;
+        let fetched = repo.find_reference(tracking_ref).is_ok();
 
-        println!(
-            "[DEBUG] 暂存区文件获取完成，找到 {} 个文件，耗时: {:?}",
-            staged_files.len(),
-            staged_start.elapsed()
-        );
+        Ok(fetched)
+    }
 
-        // 获取工作区修改文件
+    /// 用 git2 推送单个引用规范，远程拒绝更新时返回错误
+    fn push_refspec_with_git2(
+        &self,
+        repo: &Repository,
+        remote_name: &str,
+        refspec: &str,
+    ) -> Result<()> {
+        let mut remote = repo.find_remote(remote_name)?;
 
-        println!("[DEBUG] 获取工作区修改文件...");
+        let remote_url = remote.url().unwrap_or("").to_string();
 
-        let unstaged_start = Instant::now();
+        let provider = self.credential_provider(Some(repo), &remote_url);
 
-        let unstaged_files = self.get_unstaged_files_with_command(repo_path)?;
+        let mut rejected = None;
 
-        println!(
-            "[DEBUG] 工作区文件获取完成，找到 {} 个文件，耗时: {:?}",
-            unstaged_files.len(),
-            unstaged_start.elapsed()
-        );
+        let mut callbacks = provider.remote_callbacks();
 
-        // 获取未跟踪文件（可选，可能较慢）
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(msg) = status {
+                rejected = Some(format!("{}: {}", refname, msg));
+            }
 
-        println!("[DEBUG] 获取未跟踪文件...");
+            Ok(())
+        });
 
-        let untracked_start = Instant::now();
+        let mut push_options = git2::PushOptions::new();
 
-        let untracked_files = self.get_untracked_files_with_command(repo_path)?;
+        push_options.remote_callbacks(callbacks);
 
-        println!(
-            "[DEBUG] 未跟踪文件获取完成，找到 {} 个文件，耗时: {:?}",
-            untracked_files.len(),
-            untracked_start.elapsed()
-        );
+        remote
+            .push(&[refspec], Some(&mut push_options))
+            .map_err(|e| anyhow::Error::new(provider.classify_error(&e)))?;
 
-        // 获取远程分支信息（简化）
+        drop(push_options);
 
-        let (ahead, behind) = (0, 0); // TODO: 实现远程分支比较
+        match rejected {
+            Some(reason) => Err(anyhow!("远程拒绝更新 {}", reason)),
+            None => Ok(()),
+        }
+    }
 
-        println!(
-            "[DEBUG] Git命令状态获取完成，总耗时: {:?}",
-            start_time.elapsed()
-        );
+    fn get_gitflow_config(&self) -> GitflowConfig {
+        match self.get_repository() {
+            Ok(repo) => Self::read_gitflow_config(&repo).0,
 
-        Ok(GitStatusResult {
-            branch,
+            Err(_) => GitflowConfig::default(),
+        }
+    }
 
-            upstream: None, // TODO: 实现远程分支跟踪检测
+    /// 读取仓库的 Gitflow 配置，未初始化时返回按分支名探测的结果
+    pub fn load_gitflow_config(&self) -> Result<GitflowConfig> {
+        let repo = self.get_repository()?;
 
-            has_changes: !staged_files.is_empty()
-                || !unstaged_files.is_empty()
-                || !untracked_files.is_empty(),
+        Ok(Self::read_gitflow_config(&repo).0)
+    }
 
-            staged_files,
+    /// 从 .git/config 读取 git-flow 命令行使用的 gitflow.branch.* / gitflow.prefix.* 键
+    /// 返回 (配置, 是否已初始化)；缺失的基线分支按常见命名探测，缺失的前缀沿用默认值
+    fn read_gitflow_config(repo: &Repository) -> (GitflowConfig, bool) {
+        let mut config = GitflowConfig::default();
 
-            unstaged_files,
+        let git_config = repo.config().and_then(|mut c| c.snapshot()).ok();
 
-            untracked_files,
+        let read = |key: &str| {
+            git_config
+                .as_ref()
+                .and_then(|c| c.get_string(&format!("gitflow.{}", key)).ok())
+        };
 
-            conflicted_files: Vec::new(), // TODO: 实现冲突文件检测
-
-            ahead,
-
-            behind,
-        })
-    }
+        for (key, field) in [
+            ("prefix.feature", &mut config.feature_prefix),
+            ("prefix.release", &mut config.release_prefix),
+            ("prefix.bugfix", &mut config.bugfix_prefix),
+            ("prefix.hotfix", &mut config.hotfix_prefix),
+            ("prefix.support", &mut config.support_prefix),
+            ("prefix.versiontag", &mut config.version_tag_prefix),
+        ] {
+            if let Some(value) = read(key) {
+                *field = value;
+            }
+        }
 
-    /// 使用Git命令获取当前分支
+        let stored_main = read("branch.master").filter(|name| !name.trim().is_empty());
 
-    fn get_current_branch_with_command(&self, repo_path: &str) -> Result<String> {
-        let git_command = self.get_git_command();
+        let stored_develop = read("branch.develop").filter(|name| !name.trim().is_empty());
 
-        debug_log!(
-            "[DEBUG] 执行Git命令: {} symbolic-ref --short HEAD",
-            git_command
-        );
+        let initialized = stored_main.is_some() && stored_develop.is_some();
 
-        debug_log!("[DEBUG] 在目录: {}", repo_path);
+        config.main_branch = stored_main.unwrap_or_else(|| {
+            Self::detect_branch_name(repo, &config.main_branch, &["main", "master", "trunk"])
+        });
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(&["symbolic-ref", "--short", "HEAD"])
-            .output()?;
+        config.develop_branch = stored_develop.unwrap_or_else(|| {
+            Self::detect_branch_name(
+                repo,
+                &config.develop_branch,
+                &[
+                    "develop",
+                    "development",
+                    "dev",
+                    config.main_branch.as_str(),
+                    "master",
+                ],
+            )
+        });
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        (config, initialized)
+    }
 
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    fn validate_gitflow_config(config: &GitflowConfig) -> Result<()> {
+        for (label, name) in [
+            ("主干分支", &config.main_branch),
+            ("开发分支", &config.develop_branch),
+        ] {
+            let name = name.trim();
 
-        debug_log!("[DEBUG] symbolic-ref 退出状态: {:?}", output.status.code());
+            if name.is_empty() || !git2::Reference::is_valid_name(&format!("refs/heads/{}", name)) {
+                return Err(anyhow!("{}名称无效: {:?}", label, name));
+            }
+        }
 
-        debug_log!("[DEBUG] symbolic-ref 标准输出: '{}'", stdout.trim());
+        if config.main_branch.trim() == config.develop_branch.trim() {
+            return Err(anyhow!("主干分支与开发分支不能相同"));
+        }
 
-        debug_log!("[DEBUG] symbolic-ref 标准错误: '{}'", stderr.trim());
+        let prefixes = [
+            ("feature", &config.feature_prefix),
+            ("release", &config.release_prefix),
+            ("bugfix", &config.bugfix_prefix),
+            ("hotfix", &config.hotfix_prefix),
+            ("support", &config.support_prefix),
+        ];
 
-        if output.status.success() {
-            let branch = stdout.trim().to_string();
+        for (index, (label, prefix)) in prefixes.iter().enumerate() {
+            if prefix.trim().is_empty() {
+                return Err(anyhow!("{} 分支前缀不能为空", label));
+            }
 
-            debug_log!("[DEBUG] 成功获取分支: {}", branch);
+            if prefix.chars().any(char::is_whitespace) {
+                return Err(anyhow!("{} 分支前缀不能包含空白字符", label));
+            }
 
-            Ok(branch)
-        } else {
-            debug_log!("[DEBUG] symbolic-ref失败，尝试rev-parse");
+            // 前缀互为前缀时分支类型无法区分
+            for (other_label, other) in prefixes.iter().skip(index + 1) {
+                if prefix.starts_with(other.as_str()) || other.starts_with(prefix.as_str()) {
+                    return Err(anyhow!(
+                        "{} 与 {} 分支前缀冲突: {} / {}",
+                        label,
+                        other_label,
+                        prefix,
+                        other
+                    ));
+                }
+            }
+        }
 
-            // 可能是detached HEAD，尝试获取commit hash
+        if config.version_tag_prefix.chars().any(char::is_whitespace) {
+            return Err(anyhow!("版本标签前缀不能包含空白字符"));
+        }
 
-            let output = Self::create_hidden_command(&git_command)
-                .current_dir(repo_path)
-                .args(&["rev-parse", "--short", "HEAD"])
-                .output()?;
+        Ok(())
+    }
 
-            let stdout = String::from_utf8_lossy(&output.stdout);
+    fn write_gitflow_config(repo: &Repository, config: &GitflowConfig) -> Result<()> {
+        let mut git_config = repo
+            .config()
+            .and_then(|c| c.open_level(git2::ConfigLevel::Local))
+            .map_err(|e| anyhow!("无法打开仓库配置: {}", e))?;
+
+        for (key, value) in [
+            ("branch.master", config.main_branch.trim()),
+            ("branch.develop", config.develop_branch.trim()),
+            ("prefix.feature", config.feature_prefix.as_str()),
+            ("prefix.release", config.release_prefix.as_str()),
+            ("prefix.bugfix", config.bugfix_prefix.as_str()),
+            ("prefix.hotfix", config.hotfix_prefix.as_str()),
+            ("prefix.support", config.support_prefix.as_str()),
+            ("prefix.versiontag", config.version_tag_prefix.as_str()),
+        ] {
+            git_config
+                .set_str(&format!("gitflow.{}", key), value)
+                .map_err(|e| anyhow!("写入 gitflow.{} 失败: {}", key, e))?;
+        }
 
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(())
+    }
 
-            debug_log!("[DEBUG] rev-parse 退出状态: {:?}", output.status.code());
+    /// 保存 Gitflow 配置到 .git/config，不创建分支
+    pub fn save_gitflow_config(&self, config: &GitflowConfig) -> Result<()> {
+        Self::validate_gitflow_config(config)?;
 
-            debug_log!("[DEBUG] rev-parse 标准输出: '{}'", stdout.trim());
+        let repo = self.get_repository()?;
 
-            debug_log!("[DEBUG] rev-parse 标准错误: '{}'", stderr.trim());
+        Self::write_gitflow_config(&repo, config)
+    }
 
-            if output.status.success() {
-                let commit = stdout.trim().to_string();
+    /// 等价于 git flow init：补齐缺失的主干/开发分支并写入配置
+    /// 未传入配置时沿用当前读取或探测到的配置
+    pub fn init_gitflow(&self, config: Option<&GitflowConfig>) -> Result<GitOperationResult> {
+        let repo = self.get_repository()?;
 
-                let result = format!("HEAD@{}", commit);
+        let config = match config {
+            Some(config) => config.clone(),
 
-                debug_log!("[DEBUG] 获取到commit hash: {}", result);
+            None => {
+                let (mut detected, _) = Self::read_gitflow_config(&repo);
 
-                Ok(result)
-            } else {
-                debug_log!("[ERROR] 所有分支获取方式都失败了，返回unknown");
+                // 探测不到开发分支时会回退为主干分支，此时使用默认的 develop
+                if detected.develop_branch == detected.main_branch {
+                    detected.develop_branch = GitflowConfig::default().develop_branch;
+                }
 
-                Ok("unknown".to_string())
+                detected
             }
-        }
-    }
-
-    /// 使用Git命令获取当前分支的上游
-    fn get_current_upstream_with_command(&self, repo_path: &str) -> Result<Option<String>> {
-        let git_command = self.get_git_command();
-
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
-            .output()
-            .map_err(|e| anyhow!("Failed to get upstream branch: {}", e))?;
-
-        if !output.status.success() {
-            // 如果没有上游分支，返回None
-            return Ok(None);
-        }
-
-        let output = String::from_utf8_lossy(&output.stdout);
-        let upstream = output.trim().to_string();
+        };
 
-        if upstream.is_empty() || upstream == "@{u}" {
-            Ok(None)
-        } else {
-            Ok(Some(upstream))
-        }
-    }
+        Self::validate_gitflow_config(&config)?;
 
-    /// 获取Git命令路径（根据当前使用的Git方式）
+        let main_branch = config.main_branch.trim();
 
-    /// 作者：Evilek
+        let develop_branch = config.develop_branch.trim();
 
-    /// 编写日期：2025-08-07
+        let remote = Self::resolve_default_remote(&repo);
 
-    fn get_git_command(&self) -> String {
-        match self.git_method {
-            GitMethod::SystemGit => {
-                // 尝试使用完整路径，解决"error launching git"问题
+        let mut created = Vec::new();
 
-                if cfg!(windows) {
-                    // 尝试常见的Git安装路径
+        if repo.find_branch(main_branch, BranchType::Local).is_err() {
+            if Self::create_branch_from_remote(&repo, remote.as_deref(), main_branch)? {
+                created.push(format!("{}（跟踪远程）", main_branch));
+            } else if let Ok(head_commit) = repo.head().and_then(|head| head.peel_to_commit()) {
+                repo.branch(main_branch, &head_commit, false)
+                    .map_err(|e| anyhow!("创建主干分支失败: {}", e))?;
 
-                    let git_paths = vec![
-                        "D:\\Soft\\Git\\bin\\git.exe", // 用户的Git路径
-                        "C:\\Program Files\\Git\\bin\\git.exe",
-                        "C:\\Program Files (x86)\\Git\\bin\\git.exe",
-                        "git.exe",
-                        "git",
-                    ];
+                created.push(main_branch.to_string());
+            } else {
+                // 空仓库：与 git flow init 一样先创建初始提交
+                let signature = repo
+                    .signature()
+                    .map_err(|e| anyhow!("请先配置 user.name 与 user.email: {}", e))?;
 
-                    for path in git_paths {
-                        // 简单检查文件是否存在（对于完整路径）
+                let tree_id = repo.treebuilder(None)?.write()?;
 
-                        if path.contains(":\\") {
-                            if std::path::Path::new(path).exists() {
-                                debug_log!("[DEBUG] 使用Git路径: {}", path);
+                let tree = repo.find_tree(tree_id)?;
 
-                                return path.to_string();
-                            }
-                        } else {
-                            // 对于相对路径，直接返回
+                let reference = format!("refs/heads/{}", main_branch);
 
-                            debug_log!("[DEBUG] 使用Git命令: {}", path);
+                repo.commit(
+                    Some(&reference),
+                    &signature,
+                    &signature,
+                    "Initial commit",
+                    &tree,
+                    &[],
+                )
+                .map_err(|e| anyhow!("创建初始提交失败: {}", e))?;
 
-                            return path.to_string();
-                        }
-                    }
+                repo.set_head(&reference)?;
 
-                    "git".to_string() // 降级
-                } else {
-                    "git".to_string()
-                }
+                created.push(main_branch.to_string());
             }
+        }
 
-            GitMethod::BundledGit => {
-                // 对于内置Git，返回完整的二进制文件名
-
-                // 注意：在实际使用中，应该通过Tauri的sidecar API来调用
+        if repo.find_branch(develop_branch, BranchType::Local).is_err() {
+            if Self::create_branch_from_remote(&repo, remote.as_deref(), develop_branch)? {
+                created.push(format!("{}（跟踪远程）", develop_branch));
+            } else {
+                let main_commit = repo
+                    .find_branch(main_branch, BranchType::Local)?
+                    .into_reference()
+                    .peel_to_commit()
+                    .map_err(|e| anyhow!("无法获取主干分支提交: {}", e))?;
 
-                let target_triple = Self::get_target_triple();
+                repo.branch(develop_branch, &main_commit, false)
+                    .map_err(|e| anyhow!("创建开发分支失败: {}", e))?;
 
-                if cfg!(windows) {
-                    format!("git-{}.exe", target_triple)
-                } else {
-                    format!("git-{}", target_triple)
-                }
+                created.push(develop_branch.to_string());
             }
-
-            GitMethod::Git2Api => "git".to_string(), // 降级到系统Git
         }
-    }
 
-    /// 使用Git命令获取暂存区文件
+        Self::write_gitflow_config(&repo, &config)?;
 
-    fn get_staged_files_with_command(&self, repo_path: &str) -> Result<Vec<FileStatus>> {
-        let git_command = self.get_git_command();
+        Ok(GitOperationResult {
+            success: true,
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(&["diff", "--cached", "--name-status"])
-            .output()?;
+            message: format!("已初始化 Gitflow：{} / {}", main_branch, develop_branch),
 
-        let mut staged_files = Vec::new();
+            details: Some(if created.is_empty() {
+                "基线分支均已存在，仅写入配置".to_string()
+            } else {
+                format!("已创建分支：{}", created.join("、"))
+            }),
+        })
+    }
 
-        if output.status.success() {
-            let content = String::from_utf8_lossy(&output.stdout);
+    /// 远程存在同名分支时创建跟踪它的本地分支，返回是否创建
+    fn create_branch_from_remote(
+        repo: &Repository,
 
-            for line in content.lines() {
-                if let Some((status_char, file_path)) = line.split_once('\t') {
-                    let status_type = match status_char {
-                        "A" => FileStatusType::Added,
+        remote: Option<&str>,
 
-                        "M" => FileStatusType::Modified,
+        branch_name: &str,
+    ) -> Result<bool> {
+        let Some(remote) = remote else {
+            return Ok(false);
+        };
 
-                        "D" => FileStatusType::Deleted,
+        let remote_branch_name = format!("{}/{}", remote, branch_name);
 
-                        "R" => FileStatusType::Renamed,
+        let remote_commit = match repo.find_branch(&remote_branch_name, BranchType::Remote) {
+            Ok(branch) => branch.into_reference().peel_to_commit()?,
 
-                        "C" => FileStatusType::Copied,
+            Err(_) => return Ok(false),
+        };
 
-                        _ => FileStatusType::Modified,
-                    };
+        let mut local = repo
+            .branch(branch_name, &remote_commit, false)
+            .map_err(|e| anyhow!("创建分支 {} 失败: {}", branch_name, e))?;
 
-                    staged_files.push(FileStatus {
-                        path: file_path.to_string(),
+        local.set_upstream(Some(&remote_branch_name))?;
 
-                        working_tree_status: None,
+        Ok(true)
+    }
 
-                        index_status: Some(status_type),
+    fn detect_branch_name(repo: &Repository, preferred: &str, fallbacks: &[&str]) -> String {
+        if repo.find_branch(preferred, BranchType::Local).is_ok() {
+            return preferred.to_string();
+        }
 
-                        selected: false,
+        for candidate in fallbacks {
+            if repo.find_branch(candidate, BranchType::Local).is_ok() {
+                return candidate.to_string();
+            }
+        }
 
-                        is_staged: true,
-                    });
+        if let Ok(head) = repo.head() {
+            if let Some(name) = head.shorthand() {
+                if repo.find_branch(name, BranchType::Local).is_ok() {
+                    return name.to_string();
                 }
             }
         }
 
-        Ok(staged_files)
+        preferred.to_string()
     }
 
-    /// 使用Git命令获取工作区修改文件
-
-    fn get_unstaged_files_with_command(&self, repo_path: &str) -> Result<Vec<FileStatus>> {
-        let git_command = self.get_git_command();
+    fn classify_gitflow_branch(
+        branch_name: &str,
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(&["diff", "--name-status"])
-            .output()?;
+        config: &GitflowConfig,
+    ) -> Option<GitflowBranchType> {
+        if branch_name.starts_with(&config.feature_prefix) {
+            Some(GitflowBranchType::Feature)
+        } else if branch_name.starts_with(&config.release_prefix) {
+            Some(GitflowBranchType::Release)
+        } else if branch_name.starts_with(&config.bugfix_prefix) {
+            Some(GitflowBranchType::Bugfix)
+        } else if branch_name.starts_with(&config.hotfix_prefix) {
+            Some(GitflowBranchType::Hotfix)
+        } else if branch_name.starts_with(&config.support_prefix) {
+            Some(GitflowBranchType::Support)
+        } else {
+            None
+        }
+    }
 
-        let mut unstaged_files = Vec::new();
+    fn resolve_base_branch(branch_type: &GitflowBranchType, config: &GitflowConfig) -> String {
+        match branch_type {
+            GitflowBranchType::Hotfix | GitflowBranchType::Support => config.main_branch.clone(),
 
-        if output.status.success() {
-            let content = String::from_utf8_lossy(&output.stdout);
+            _ => config.develop_branch.clone(),
+        }
+    }
 
-            for line in content.lines() {
-                if let Some((status_char, file_path)) = line.split_once('\t') {
-                    let status_type = match status_char {
-                        "M" => FileStatusType::Modified,
+    fn infer_gitflow_status(
+        branch_type: &GitflowBranchType,
 
-                        "D" => FileStatusType::Deleted,
+        divergence: &GitflowDivergence,
+    ) -> GitflowBranchStatus {
+        if divergence.ahead == 0 && divergence.behind == 0 {
+            match branch_type {
+                GitflowBranchType::Feature
+                | GitflowBranchType::Bugfix
+                | GitflowBranchType::Support => GitflowBranchStatus::Idle,
 
-                        _ => FileStatusType::Modified,
-                    };
-
-                    unstaged_files.push(FileStatus {
-                        path: file_path.to_string(),
+                GitflowBranchType::Release | GitflowBranchType::Hotfix => {
+                    GitflowBranchStatus::AwaitingMerge
+                }
+            }
+        } else if divergence.behind > 0 {
+            GitflowBranchStatus::AwaitingMerge
+        } else {
+            GitflowBranchStatus::InProgress
+        }
+    }
 
-                        working_tree_status: Some(status_type),
+    fn format_git_time(time: git2::Time) -> String {
+        let seconds = time.seconds();
 
-                        index_status: None,
+        let offset_minutes = time.offset_minutes();
 
-                        selected: false,
+        let offset = FixedOffset::east_opt(offset_minutes * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
 
-                        is_staged: false,
-                    });
-                }
-            }
+        if let Some(datetime_utc) = chrono::DateTime::<Utc>::from_timestamp(seconds, 0) {
+            datetime_utc.with_timezone(&offset).to_rfc3339()
+        } else {
+            Utc::now().to_rfc3339()
         }
-
-        Ok(unstaged_files)
     }
 
-    /// 使用Git命令获取未跟踪文件
+    /// 根据配置确定Git执行方式
 
-    fn get_untracked_files_with_command(&self, repo_path: &str) -> Result<Vec<FileStatus>> {
-        let git_command = self.get_git_command();
+    /// 作者：Evilek
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(&["ls-files", "--others", "--exclude-standard"])
-            .output()?;
+    /// 编写日期：2025-08-07
 
-        let mut untracked_files = Vec::new();
+    fn determine_git_method(config: &GitConfig) -> GitMethod {
+        match config.execution_mode {
+            GitExecutionMode::Auto => {
+                debug_log!("[DEBUG] 配置为自动检测，开始检测流程");
 
-        if output.status.success() {
-            let content = String::from_utf8_lossy(&output.stdout);
+                Self::detect_git_method()
+            }
 
-            for line in content.lines() {
-                if !line.trim().is_empty() {
-                    untracked_files.push(FileStatus {
-                        path: line.trim().to_string(),
+            GitExecutionMode::SystemGit => {
+                debug_log!("[DEBUG] 配置强制使用系统Git");
 
-                        working_tree_status: Some(FileStatusType::Untracked),
+                GitMethod::SystemGit
+            }
 
-                        index_status: None,
+            GitExecutionMode::BundledGit => {
+                debug_log!("[DEBUG] 配置强制使用内置Git");
 
-                        selected: false,
+                GitMethod::BundledGit
+            }
 
-                        is_staged: false,
-                    });
-                }
+            GitExecutionMode::Git2Api => {
+                debug_log!("[DEBUG] 配置强制使用Git2库API");
+
+                GitMethod::Git2Api
             }
         }
-
-        Ok(untracked_files)
     }
 
-    pub fn clone_repository(&self, request: &CheckoutRequest) -> Result<CheckoutResult> {
-        let start_time = Instant::now();
+    /// 检测Git路径
 
-        if request.repository_url.trim().is_empty() {
-            return Err(anyhow!("Repository URL cannot be empty"));
-        }
+    /// Author: Evilek, Date: 2025-01-08
 
-        let target_path = Path::new(&request.target_path);
+    /// 复用系统启动时的Git检测逻辑
 
-        if let Err(error) = Self::ensure_target_directory(target_path) {
-            let elapsed = start_time.elapsed().as_millis() as u64;
-            let info = error.into_info(None);
-            return Ok(CheckoutResult {
-                success: false,
-                repository_path: request.target_path.clone(),
-                duration_ms: elapsed,
-                commit_info: None,
-                error_message: Some(info.user_message.clone()),
-                suggestion: info.suggestion.clone(),
-                error: Some(info),
-            });
-        }
+    fn detect_git_path() -> Option<String> {
+        // 尝试不同的git命令名称（Windows兼容性）
 
-        let clone_result = match self.git_method {
-            GitMethod::SystemGit | GitMethod::BundledGit => self.clone_with_cli(request),
-            GitMethod::Git2Api => self.clone_with_git2(request),
+        let git_commands = if cfg!(windows) {
+            vec!["git.exe", "git"]
+        } else {
+            vec!["git"]
         };
 
-        match clone_result {
-            Ok(_) => {
-                let elapsed = start_time.elapsed().as_millis() as u64;
-                let commit_info = self.get_commit_info(&request.target_path, "HEAD").ok();
-                Ok(CheckoutResult {
-                    success: true,
-                    repository_path: request.target_path.clone(),
-                    duration_ms: elapsed,
-                    commit_info,
-                    error_message: None,
-                    suggestion: None,
-                    error: None,
-                })
-            }
-            Err((git_error, raw_message)) => {
-                let elapsed = start_time.elapsed().as_millis() as u64;
-                let info = git_error.into_info(Some(raw_message.clone()));
-                Ok(CheckoutResult {
-                    success: false,
-                    repository_path: request.target_path.clone(),
-                    duration_ms: elapsed,
-                    commit_info: None,
-                    error_message: Some(info.user_message.clone()),
-                    suggestion: info.suggestion.clone(),
-                    error: Some(info),
-                })
+        // 首先尝试直接执行git命令
+
+        for git_cmd in &git_commands {
+            if let Ok(output) = Self::create_hidden_command(git_cmd)
+                .arg("--version")
+                .output_with(&Self::probe_runner())
+            {
+                if output.status.success() {
+                    let version = String::from_utf8_lossy(&output.stdout);
+
+                    if !version.trim().is_empty() {
+                        debug_log!("[DEBUG] 找到系统Git: {}", git_cmd);
+
+                        return Some(git_cmd.to_string());
+                    }
+                }
             }
         }
-    }
 
-    fn ensure_target_directory(target_path: &Path) -> Result<(), GitError> {
-        if target_path.exists() {
-            if !target_path.is_dir() {
-                return Err(GitError::ValidationError(
-                    "目标路径已存在且不是目录".to_string(),
-                ));
-            }
-            return Err(GitError::ValidationError(
-                "目标目录已存在，请选择新的路径或使用空父目录".to_string(),
-            ));
-        } else if let Some(parent) = target_path.parent() {
-            if !parent.as_os_str().is_empty() && !parent.exists() {
-                fs::create_dir_all(parent)
-                    .map_err(|error| GitError::FileSystemError(error.to_string()))?;
+        // 如果直接执行失败，尝试常见的Git安装路径
+
+        let common_paths = if cfg!(windows) {
+            vec![
+                "C:\\Program Files\\Git\\bin\\git.exe",
+                "C:\\Program Files (x86)\\Git\\bin\\git.exe",
+                "D:\\Soft\\Git\\bin\\git.exe", // 用户的Git路径
+                "C:\\Git\\bin\\git.exe",
+            ]
+        } else {
+            vec![
+                "/usr/bin/git",
+                "/usr/local/bin/git",
+                "/opt/homebrew/bin/git",
+            ]
+        };
+
+        for path in common_paths {
+            if let Ok(output) = Self::create_hidden_command(path)
+                .arg("--version")
+                .output_with(&Self::probe_runner())
+            {
+                if output.status.success() {
+                    let version = String::from_utf8_lossy(&output.stdout);
+
+                    if !version.trim().is_empty() {
+                        debug_log!("[DEBUG] 找到Git路径: {}", path);
+
+                        return Some(path.to_string());
+                    }
+                }
             }
         }
-        Ok(())
+
+        debug_log!("[WARN] 未找到可用的Git路径");
+
+        None
     }
 
-    fn clone_with_cli(&self, request: &CheckoutRequest) -> Result<(), (GitError, String)> {
-        let git_command = self.get_git_command();
+    /// 检测最佳的Git执行方式（自动模式）
 
-        let mut args: Vec<String> = vec!["clone".to_string()];
+    /// 作者：Evilek
 
-        if let Some(depth) = request.depth {
-            if depth > 0 {
-                args.push("--depth".to_string());
-                args.push(depth.to_string());
-            }
-        }
+    /// 编写日期：2025-08-07
 
-        if request.recursive {
-            args.push("--recurse-submodules".to_string());
-        }
+    fn detect_git_method() -> GitMethod {
+        debug_log!("[DEBUG] ========================================");
 
-        if let Some(branch) = &request.branch {
-            let trimmed = branch.trim();
-            if !trimmed.is_empty() {
-                args.push("--branch".to_string());
-                args.push(trimmed.to_string());
-            }
-        }
+        debug_log!("[DEBUG] 开始Git执行方式自动检测流程");
 
-        args.push(request.repository_url.clone());
-        args.push(request.target_path.clone());
+        debug_log!("[DEBUG] ========================================");
 
-        let output = Self::create_hidden_command(&git_command)
-            .args(&args)
-            .output()
-            .map_err(|error| {
-                (
-                    GitError::GitOperationError(format!("无法执行 git 命令: {}", error)),
-                    error.to_string(),
-                )
-            })?;
+        // 1. 检测系统Git
 
-        if output.status.success() {
-            Ok(())
+        debug_log!("[DEBUG] 步骤1: 检测系统Git");
+
+        if Self::is_system_git_available() {
+            debug_log!("[SUCCESS] ✅ 检测到系统Git命令，优先使用");
+
+            debug_log!("[DEBUG] ========================================");
+
+            return GitMethod::SystemGit;
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            let classified = Self::classify_clone_error(&stderr);
-            Err((classified, stderr))
+            debug_log!("[WARN] ❌ 系统Git不可用，尝试内置Git");
         }
-    }
 
-    fn clone_with_git2(&self, request: &CheckoutRequest) -> Result<(), (GitError, String)> {
-        let mut fetch_options = git2::FetchOptions::new();
-        if let Some(depth) = request.depth {
-            if depth > 0 {
-                let limited_depth = std::cmp::min(depth, i32::MAX as u32) as i32;
-                fetch_options.depth(limited_depth);
-            }
-        }
+        // 2. 检测内置Git
+
+        debug_log!("[DEBUG] 步骤2: 检测内置Git");
+
+        if Self::is_bundled_git_available() {
+            debug_log!("[SUCCESS] ✅ 检测到内置Git，使用备选方案");
+
+            debug_log!("[DEBUG] ========================================");
+
+            return GitMethod::BundledGit;
+        } else {
+            debug_log!("[ERROR] ❌ 内置Git不可用");
+        }
+
+        // 3. 不再自动降级到Git2库API，强制要求Git命令
+
+        debug_log!("[DEBUG] 步骤3: 强制返回SystemGit，要求用户安装Git");
+
+        debug_log!("[ERROR] ⚠️  系统Git和内置Git都不可用，GitMentor需要Git命令行工具才能正常工作");
+
+        debug_log!("[ERROR] 请安装Git并确保在PATH中可用，或者在设置中手动配置为Git2Api模式");
+
+        debug_log!("[DEBUG] ========================================");
+
+        GitMethod::SystemGit // 强制返回SystemGit，让错误在实际使用时暴露
+    }
+
+    /// 检测系统是否安装了Git命令
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-07
+
+    fn is_system_git_available() -> bool {
+        debug_log!("[DEBUG] ==================== 开始检测系统Git ====================");
+
+        debug_log!("[DEBUG] 当前工作目录: {:?}", std::env::current_dir());
+
+        debug_log!("[DEBUG] PATH环境变量: {:?}", std::env::var("PATH"));
+
+        // 尝试执行git --version命令
+
+        debug_log!("[DEBUG] 执行命令: git --version");
+
+        // 先尝试直接执行git命令
+
+        debug_log!("[DEBUG] 尝试方式1: 直接执行 'git'");
+
+        match Self::create_hidden_command("git")
+            .arg("--version")
+            .output_with(&Self::probe_runner())
+        {
+            Ok(output) => {
+                let version = String::from_utf8_lossy(&output.stdout);
+
+                let stderr = String::from_utf8_lossy(&output.stderr);
+
+                let status_code = output.status.code();
+
+                debug_log!("[DEBUG] 方式1执行成功");
+
+                debug_log!("[DEBUG] 退出状态码: {:?}", status_code);
+
+                debug_log!("[DEBUG] 标准输出: '{}'", version.trim());
+
+                debug_log!("[DEBUG] 标准错误: '{}'", stderr.trim());
+
+                debug_log!("[DEBUG] 状态成功: {}", output.status.success());
+
+                debug_log!("[DEBUG] 版本非空: {}", !version.trim().is_empty());
+
+                let success = output.status.success() && !version.trim().is_empty();
+
+                if success {
+                    debug_log!("[SUCCESS] 方式1成功，系统Git可用");
+
+                    debug_log!("[DEBUG] ==================== 系统Git检测完成 ====================");
+
+                    return true;
+                } else {
+                    debug_log!("[WARN] 方式1失败，尝试方式2");
+                }
+            }
+
+            Err(e) => {
+                debug_log!("[ERROR] 方式1执行失败: {}", e);
+
+                debug_log!("[ERROR] 错误类型: {:?}", e.kind());
+
+                debug_log!("[WARN] 尝试方式2: 使用完整路径");
+            }
+        }
+
+        // 方式2: 尝试常见的Git安装路径
+
+        let git_paths = vec![
+            "C:\\Program Files\\Git\\bin\\git.exe",
+            "C:\\Program Files (x86)\\Git\\bin\\git.exe",
+            "D:\\Soft\\Git\\bin\\git.exe", // 用户的Git路径
+            "git.exe",
+        ];
+
+        for git_path in git_paths {
+            debug_log!("[DEBUG] 尝试路径: {}", git_path);
+
+            match Self::create_hidden_command(git_path)
+                .arg("--version")
+                .output_with(&Self::probe_runner())
+            {
+                Ok(output) => {
+                    let version = String::from_utf8_lossy(&output.stdout);
+
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+
+                    debug_log!("[DEBUG] 路径 {} 执行成功", git_path);
+
+                    debug_log!("[DEBUG] 退出状态码: {:?}", output.status.code());
+
+                    debug_log!("[DEBUG] 标准输出: '{}'", version.trim());
+
+                    debug_log!("[DEBUG] 标准错误: '{}'", stderr.trim());
+
+                    if output.status.success() && !version.trim().is_empty() {
+                        debug_log!("[SUCCESS] ✅ 找到可用的Git: {}", git_path);
+
+                        debug_log!(
+                            "[DEBUG] ==================== 系统Git检测完成 ===================="
+                        );
+
+                        return true;
+                    }
+                }
+
+                Err(e) => {
+                    debug_log!("[DEBUG] 路径 {} 失败: {}", git_path, e);
+                }
+            }
+        }
+
+        debug_log!("[ERROR] ❌ 所有Git路径都失败了");
+
+        debug_log!("[DEBUG] ==================== 系统Git检测失败 ====================");
+
+        false
+    }
+
+    /// 检测内置Git是否可用
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-07
+
+    fn is_bundled_git_available() -> bool {
+        debug_log!("[DEBUG] ==================== 开始检测内置Git ====================");
+
+        // 获取当前目标平台
+
+        let target_triple = Self::get_target_triple();
+
+        debug_log!("[DEBUG] 当前目标平台: {}", target_triple);
+
+        // 构建Git二进制文件名
+
+        let git_binary_name = if cfg!(windows) {
+            format!("git-{}.exe", target_triple)
+        } else {
+            format!("git-{}", target_triple)
+        };
+
+        debug_log!("[DEBUG] 期望的Git二进制文件名: {}", git_binary_name);
+
+        // 检查二进制文件是否存在
+
+        // 在开发环境中，检查 src-tauri/binaries/ 目录
+
+        // 在生产环境中，Tauri会自动处理sidecar的路径
+
+        let binary_path = if cfg!(debug_assertions) {
+            // 开发环境：检查binaries/目录（当前工作目录已经是src-tauri）
+
+            let current_dir =
+                std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+
+            debug_log!("[DEBUG] 当前工作目录: {}", current_dir.display());
+
+            let path = current_dir.join("binaries").join(&git_binary_name);
+
+            debug_log!("[DEBUG] 开发环境检查路径: {}", path.display());
+
+            path
+        } else {
+            // 生产环境：Tauri会处理sidecar路径，这里只是检查逻辑
+
+            // 实际使用时应该通过tauri::api::process::Command::sidecar来调用
+
+            let path = std::path::PathBuf::from("binaries").join(&git_binary_name);
+
+            debug_log!("[DEBUG] 生产环境检查路径: {}", path.display());
+
+            path
+        };
+
+        debug_log!("[DEBUG] 检查文件是否存在: {}", binary_path.display());
+
+        let exists = binary_path.exists();
+
+        debug_log!("[DEBUG] 文件存在性检查结果: {}", exists);
+
+        if exists {
+            debug_log!("[DEBUG] 找到内置Git文件: {}", binary_path.display());
+
+            // 获取文件元数据
+
+            match std::fs::metadata(&binary_path) {
+                Ok(metadata) => {
+                    debug_log!("[DEBUG] 文件大小: {} bytes", metadata.len());
+
+                    debug_log!("[DEBUG] 文件类型: {:?}", metadata.file_type());
+
+                    // 检查文件是否可执行（Unix系统）
+
+                    #[cfg(unix)]
+                    {
+                        use std::os::unix::fs::PermissionsExt;
+
+                        let permissions = metadata.permissions();
+
+                        let mode = permissions.mode();
+
+                        let is_executable = mode & 0o111 != 0;
+
+                        debug_log!("[DEBUG] 文件权限模式: {:o}", mode);
+
+                        debug_log!("[DEBUG] 文件可执行性: {}", is_executable);
+
+                        if !is_executable {
+                            debug_log!(
+                                "[WARN] 内置Git文件存在但不可执行: {}",
+                                binary_path.display()
+                            );
+
+                            debug_log!(
+                                "[DEBUG] ==================== 内置Git检测失败 ===================="
+                            );
+
+                            return false;
+                        }
+                    }
+
+                    #[cfg(windows)]
+                    {
+                        debug_log!("[DEBUG] Windows系统，跳过可执行性检查");
+                    }
+                }
+
+                Err(e) => {
+                    debug_log!("[ERROR] 无法获取文件元数据: {}", e);
+
+                    debug_log!("[DEBUG] ==================== 内置Git检测失败 ====================");
+
+                    return false;
+                }
+            }
+
+            debug_log!("[DEBUG] 内置Git检测成功");
+
+            debug_log!("[DEBUG] ==================== 内置Git检测完成 ====================");
+
+            true
+        } else {
+            debug_log!("[DEBUG] 未找到内置Git文件: {}", binary_path.display());
+
+            // 列出binaries目录的内容，帮助调试
+
+            let binaries_dir = if cfg!(debug_assertions) {
+                std::env::current_dir()
+                    .unwrap_or_else(|_| std::path::PathBuf::from("."))
+                    .join("binaries")
+            } else {
+                std::path::PathBuf::from("binaries")
+            };
+
+            debug_log!("[DEBUG] 检查binaries目录: {}", binaries_dir.display());
+
+            if binaries_dir.exists() {
+                match std::fs::read_dir(&binaries_dir) {
+                    Ok(entries) => {
+                        debug_log!("[DEBUG] binaries目录内容:");
+
+                        for entry in entries {
+                            if let Ok(entry) = entry {
+                                debug_log!("[DEBUG]   - {}", entry.file_name().to_string_lossy());
+                            }
+                        }
+                    }
+
+                    Err(e) => {
+                        debug_log!("[DEBUG] 无法读取binaries目录: {}", e);
+                    }
+                }
+            } else {
+                debug_log!("[DEBUG] binaries目录不存在");
+            }
+
+            debug_log!("[DEBUG] ==================== 内置Git检测失败 ====================");
+
+            false
+        }
+    }
+
+    /// 获取当前目标平台的target triple
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-07
+
+    fn get_target_triple() -> String {
+        // 在编译时确定的目标平台，使用条件编译来确定
+
+        #[cfg(all(target_arch = "x86_64", target_os = "windows"))]
+        return "x86_64-pc-windows-msvc".to_string();
+
+        #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+        return "x86_64-unknown-linux-gnu".to_string();
+
+        #[cfg(all(target_arch = "x86_64", target_os = "macos"))]
+        return "x86_64-apple-darwin".to_string();
+
+        #[cfg(all(target_arch = "aarch64", target_os = "macos"))]
+        return "aarch64-apple-darwin".to_string();
+
+        #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+        return "aarch64-unknown-linux-gnu".to_string();
+
+        // 默认返回通用标识符（只有在不匹配任何已知平台时才会执行）
+
+        #[cfg(not(any(
+            all(target_arch = "x86_64", target_os = "windows"),
+            all(target_arch = "x86_64", target_os = "linux"),
+            all(target_arch = "x86_64", target_os = "macos"),
+            all(target_arch = "aarch64", target_os = "macos"),
+            all(target_arch = "aarch64", target_os = "linux")
+        )))]
+        "unknown-target".to_string()
+    }
+
+    /// 创建隐藏窗口的命令（Windows 下避免黑色 CMD 闪窗）
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-11
+
+    /// 说明：统一封装外部 git 命令创建，Windows 使用 CREATE_NO_WINDOW 隐藏控制台窗口；其他平台保持默认
+
+    /// Confirmed via 寸止(ID:WIN-CMD-HIDE-20250811)
+
+    fn create_hidden_command(program: &str) -> Command {
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+
+            // CREATE_NO_WINDOW 常量，避免创建控制台窗口
+
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+            let mut cmd = Command::new(program);
+
+            cmd.creation_flags(CREATE_NO_WINDOW);
+
+            return cmd;
+        }
+
+        #[cfg(not(windows))]
+        {
+            Command::new(program)
+        }
+    }
+
+    /// 使用Git命令获取状态（超快速）
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-06
+
+    pub fn get_status_with_git_command(&self) -> Result<GitStatusResult> {
+        debug_log!("[DEBUG] 使用Git命令获取状态...");
+
+        let start_time = Instant::now();
+
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No repository opened"))?;
+
+        debug_log!("[DEBUG] 当前仓库路径: {}", repo_path);
+
+        debug_log!("[DEBUG] 当前工作目录: {:?}", std::env::current_dir());
+
+        // 获取当前分支
+
+        debug_log!("获取当前分支...");
+
+        let branch_start = Instant::now();
+
+        let branch = self.get_current_branch_with_command(repo_path)?;
+
+        debug_log!(
+            "分支获取完成: {}, 耗时: {:?}",
+            branch,
+            branch_start.elapsed()
+        );
+
+        // 获取暂存区文件
+
+        debug_log!("获取暂存区文件...");
+
+        let staged_start = Instant::now();
+
+        let staged_files = self.get_staged_files_with_command(repo_path)?;
+
+        debug_log!(
+            "暂存区文件获取完成，找到 {} 个文件，耗时: {:?}",
+            staged_files.len(),
+            staged_start.elapsed()
+        );
+
+        // 获取工作区修改文件
+
+        debug_log!("获取工作区修改文件...");
+
+        let unstaged_start = Instant::now();
+
+        let unstaged_files = self.get_unstaged_files_with_command(repo_path)?;
+
+        debug_log!(
+            "工作区文件获取完成，找到 {} 个文件，耗时: {:?}",
+            unstaged_files.len(),
+            unstaged_start.elapsed()
+        );
+
+        // 获取未跟踪文件（可选，可能较慢）
+
+        debug_log!("获取未跟踪文件...");
+
+        let untracked_start = Instant::now();
+
+        let untracked_files = self.get_untracked_files_with_command(repo_path)?;
+
+        debug_log!(
+            "未跟踪文件获取完成，找到 {} 个文件，耗时: {:?}",
+            untracked_files.len(),
+            untracked_start.elapsed()
+        );
+
+        // 上游分支与领先/落后提交数（git2 读取，开销很小）
+
+        let (upstream, ahead, behind) = Repository::open(repo_path)
+            .ok()
+            .and_then(|repo| Self::upstream_tracking(&repo).ok())
+            .unwrap_or((None, 0, 0));
+
+        debug_log!("Git命令状态获取完成，总耗时: {:?}", start_time.elapsed());
+
+        Ok(GitStatusResult {
+            branch,
+
+            upstream,
+
+            has_changes: !staged_files.is_empty()
+                || !unstaged_files.is_empty()
+                || !untracked_files.is_empty(),
+
+            staged_files,
+
+            unstaged_files,
+
+            untracked_files,
+
+            conflicted_files: Vec::new(), // TODO: 实现冲突文件检测
+
+            ahead,
+
+            behind,
+        })
+    }
+
+    /// 使用Git命令获取当前分支
+
+    fn get_current_branch_with_command(&self, repo_path: &str) -> Result<String> {
+        let git_command = self.get_git_command();
+
+        debug_log!(
+            "[DEBUG] 执行Git命令: {} symbolic-ref --short HEAD",
+            git_command
+        );
+
+        debug_log!("[DEBUG] 在目录: {}", repo_path);
+
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(&["symbolic-ref", "--short", "HEAD"])
+            .output_with(&self.process_runner())?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        debug_log!("[DEBUG] symbolic-ref 退出状态: {:?}", output.status.code());
+
+        debug_log!("[DEBUG] symbolic-ref 标准输出: '{}'", stdout.trim());
+
+        debug_log!("[DEBUG] symbolic-ref 标准错误: '{}'", stderr.trim());
+
+        if output.status.success() {
+            let branch = stdout.trim().to_string();
+
+            debug_log!("[DEBUG] 成功获取分支: {}", branch);
+
+            Ok(branch)
+        } else {
+            debug_log!("[DEBUG] symbolic-ref失败，尝试rev-parse");
+
+            // 可能是detached HEAD，尝试获取commit hash
+
+            let output = Self::create_hidden_command(&git_command)
+                .current_dir(repo_path)
+                .args(&["rev-parse", "--short", "HEAD"])
+                .output_with(&self.process_runner())?;
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            debug_log!("[DEBUG] rev-parse 退出状态: {:?}", output.status.code());
+
+            debug_log!("[DEBUG] rev-parse 标准输出: '{}'", stdout.trim());
+
+            debug_log!("[DEBUG] rev-parse 标准错误: '{}'", stderr.trim());
+
+            if output.status.success() {
+                let commit = stdout.trim().to_string();
+
+                let result = format!("HEAD@{}", commit);
+
+                debug_log!("[DEBUG] 获取到commit hash: {}", result);
+
+                Ok(result)
+            } else {
+                debug_log!("[ERROR] 所有分支获取方式都失败了，返回unknown");
+
+                Ok("unknown".to_string())
+            }
+        }
+    }
+
+    /// 使用Git命令获取当前分支的上游
+    fn get_current_upstream_with_command(&self, repo_path: &str) -> Result<Option<String>> {
+        let git_command = self.get_git_command();
+
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow!("Failed to get upstream branch: {}", e))?;
+
+        if !output.status.success() {
+            // 如果没有上游分支，返回None
+            return Ok(None);
+        }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let upstream = output.trim().to_string();
+
+        if upstream.is_empty() || upstream == "@{u}" {
+            Ok(None)
+        } else {
+            Ok(Some(upstream))
+        }
+    }
+
+    /// 获取Git命令路径（根据当前使用的Git方式）
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-07
+
+    fn get_git_command(&self) -> String {
+        match self.git_method {
+            GitMethod::SystemGit => {
+                // 尝试使用完整路径，解决"error launching git"问题
+
+                if cfg!(windows) {
+                    // 尝试常见的Git安装路径
+
+                    let git_paths = vec![
+                        "D:\\Soft\\Git\\bin\\git.exe", // 用户的Git路径
+                        "C:\\Program Files\\Git\\bin\\git.exe",
+                        "C:\\Program Files (x86)\\Git\\bin\\git.exe",
+                        "git.exe",
+                        "git",
+                    ];
+
+                    for path in git_paths {
+                        // 简单检查文件是否存在（对于完整路径）
+
+                        if path.contains(":\\") {
+                            if std::path::Path::new(path).exists() {
+                                debug_log!("[DEBUG] 使用Git路径: {}", path);
+
+                                return path.to_string();
+                            }
+                        } else {
+                            // 对于相对路径，直接返回
+
+                            debug_log!("[DEBUG] 使用Git命令: {}", path);
+
+                            return path.to_string();
+                        }
+                    }
+
+                    "git".to_string() // 降级
+                } else {
+                    "git".to_string()
+                }
+            }
+
+            GitMethod::BundledGit => {
+                // 对于内置Git，返回完整的二进制文件名
+
+                // 注意：在实际使用中，应该通过Tauri的sidecar API来调用
+
+                let target_triple = Self::get_target_triple();
+
+                if cfg!(windows) {
+                    format!("git-{}.exe", target_triple)
+                } else {
+                    format!("git-{}", target_triple)
+                }
+            }
+
+            GitMethod::Git2Api => "git".to_string(), // 降级到系统Git
+        }
+    }
+
+    /// 使用Git命令获取暂存区文件
+
+    fn get_staged_files_with_command(&self, repo_path: &str) -> Result<Vec<FileStatus>> {
+        let git_command = self.get_git_command();
+
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(&["diff", "--cached", "--name-status"])
+            .output_with(&self.process_runner())?;
+
+        let mut staged_files = Vec::new();
+
+        if output.status.success() {
+            let content = String::from_utf8_lossy(&output.stdout);
+
+            for line in content.lines() {
+                if let Some((status_char, file_path)) = line.split_once('\t') {
+                    let status_type = match status_char {
+                        "A" => FileStatusType::Added,
+
+                        "M" => FileStatusType::Modified,
+
+                        "D" => FileStatusType::Deleted,
+
+                        "R" => FileStatusType::Renamed,
+
+                        "C" => FileStatusType::Copied,
+
+                        _ => FileStatusType::Modified,
+                    };
+
+                    staged_files.push(FileStatus {
+                        path: file_path.to_string(),
+
+                        working_tree_status: None,
+
+                        index_status: Some(status_type),
+
+                        selected: false,
+
+                        is_staged: true,
+                    });
+                }
+            }
+        }
+
+        Ok(staged_files)
+    }
+
+    /// 使用Git命令获取工作区修改文件
+
+    fn get_unstaged_files_with_command(&self, repo_path: &str) -> Result<Vec<FileStatus>> {
+        let git_command = self.get_git_command();
+
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(&["diff", "--name-status"])
+            .output_with(&self.process_runner())?;
+
+        let mut unstaged_files = Vec::new();
+
+        if output.status.success() {
+            let content = String::from_utf8_lossy(&output.stdout);
+
+            for line in content.lines() {
+                if let Some((status_char, file_path)) = line.split_once('\t') {
+                    let status_type = match status_char {
+                        "M" => FileStatusType::Modified,
+
+                        "D" => FileStatusType::Deleted,
+
+                        _ => FileStatusType::Modified,
+                    };
+
+                    unstaged_files.push(FileStatus {
+                        path: file_path.to_string(),
+
+                        working_tree_status: Some(status_type),
+
+                        index_status: None,
+
+                        selected: false,
+
+                        is_staged: false,
+                    });
+                }
+            }
+        }
+
+        Ok(unstaged_files)
+    }
+
+    /// 使用Git命令获取未跟踪文件
+
+    fn get_untracked_files_with_command(&self, repo_path: &str) -> Result<Vec<FileStatus>> {
+        let git_command = self.get_git_command();
+
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(&["ls-files", "--others", "--exclude-standard"])
+            .output_with(&self.process_runner())?;
+
+        let mut untracked_files = Vec::new();
+
+        if output.status.success() {
+            let content = String::from_utf8_lossy(&output.stdout);
+
+            for line in content.lines() {
+                if !line.trim().is_empty() {
+                    untracked_files.push(FileStatus {
+                        path: line.trim().to_string(),
+
+                        working_tree_status: Some(FileStatusType::Untracked),
+
+                        index_status: None,
+
+                        selected: false,
+
+                        is_staged: false,
+                    });
+                }
+            }
+        }
+
+        Ok(untracked_files)
+    }
+
+    pub fn clone_repository(&self, request: &CheckoutRequest) -> Result<CheckoutResult> {
+        let start_time = Instant::now();
+
+        if request.repository_url.trim().is_empty() {
+            return Err(anyhow!("Repository URL cannot be empty"));
+        }
+
+        let target_path = Path::new(&request.target_path);
+
+        if let Err(error) = Self::ensure_target_directory(target_path) {
+            let elapsed = start_time.elapsed().as_millis() as u64;
+            let info = error.into_info(None);
+            return Ok(CheckoutResult {
+                success: false,
+                repository_path: request.target_path.clone(),
+                duration_ms: elapsed,
+                commit_info: None,
+                error_message: Some(info.user_message.clone()),
+                suggestion: info.suggestion.clone(),
+                error: Some(info),
+            });
+        }
+
+        let clone_result = match self.git_method {
+            GitMethod::SystemGit | GitMethod::BundledGit => self.clone_with_cli(request),
+            GitMethod::Git2Api => self.clone_with_git2(request),
+        };
+
+        match clone_result {
+            Ok(_) => {
+                let elapsed = start_time.elapsed().as_millis() as u64;
+                let commit_info = self.get_commit_info(&request.target_path, "HEAD").ok();
+                Ok(CheckoutResult {
+                    success: true,
+                    repository_path: request.target_path.clone(),
+                    duration_ms: elapsed,
+                    commit_info,
+                    error_message: None,
+                    suggestion: None,
+                    error: None,
+                })
+            }
+            Err((git_error, raw_message)) => {
+                let elapsed = start_time.elapsed().as_millis() as u64;
+                let info = git_error.into_info(Some(raw_message.clone()));
+                Ok(CheckoutResult {
+                    success: false,
+                    repository_path: request.target_path.clone(),
+                    duration_ms: elapsed,
+                    commit_info: None,
+                    error_message: Some(info.user_message.clone()),
+                    suggestion: info.suggestion.clone(),
+                    error: Some(info),
+                })
+            }
+        }
+    }
+
+    fn ensure_target_directory(target_path: &Path) -> Result<(), GitError> {
+        if target_path.exists() {
+            if !target_path.is_dir() {
+                return Err(GitError::ValidationError(
+                    "目标路径已存在且不是目录".to_string(),
+                ));
+            }
+            return Err(GitError::ValidationError(
+                "目标目录已存在，请选择新的路径或使用空父目录".to_string(),
+            ));
+        } else if let Some(parent) = target_path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent)
+                    .map_err(|error| GitError::FileSystemError(error.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn clone_with_cli(&self, request: &CheckoutRequest) -> Result<(), (GitError, String)> {
+        let git_command = self.get_git_command();
+
+        let mut args: Vec<String> = vec!["clone".to_string()];
+
+        if let Some(depth) = request.depth {
+            if depth > 0 {
+                args.push("--depth".to_string());
+                args.push(depth.to_string());
+            }
+        }
+
+        if request.recursive {
+            args.push("--recurse-submodules".to_string());
+        }
+
+        if let Some(branch) = &request.branch {
+            let trimmed = branch.trim();
+            if !trimmed.is_empty() {
+                args.push("--branch".to_string());
+                args.push(trimmed.to_string());
+            }
+        }
+
+        args.push(request.repository_url.clone());
+        args.push(request.target_path.clone());
+
+        let output = Self::create_hidden_command(&git_command)
+            .args(&args)
+            .output_with(&self.process_runner())
+            .map_err(|error| {
+                (
+                    GitError::GitOperationError(format!("无法执行 git 命令: {}", error)),
+                    error.to_string(),
+                )
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let classified = Self::classify_clone_error(&stderr);
+            Err((classified, stderr))
+        }
+    }
+
+    fn clone_with_git2(&self, request: &CheckoutRequest) -> Result<(), (GitError, String)> {
+        let provider = self.credential_provider(None, &request.repository_url);
+
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(provider.remote_callbacks());
+        if let Some(depth) = request.depth {
+            if depth > 0 {
+                let limited_depth = std::cmp::min(depth, i32::MAX as u32) as i32;
+                fetch_options.depth(limited_depth);
+            }
+        }
+
+        let mut builder = git2::build::RepoBuilder::new();
+        builder.fetch_options(fetch_options);
+
+        if let Some(branch) = &request.branch {
+            let trimmed = branch.trim();
+            if !trimmed.is_empty() {
+                builder.branch(trimmed);
+            }
+        }
+
+        match builder.clone(
+            request.repository_url.as_str(),
+            Path::new(&request.target_path),
+        ) {
+            Ok(repo) => {
+                if request.recursive {
+                    if let Err(error) = Self::update_submodules(&repo) {
+                        return Err((
+                            GitError::GitOperationError(error.message().to_string()),
+                            error.to_string(),
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            Err(error) => {
+                let classified = provider.classify_error(&error);
+                Err((classified, error.to_string()))
+            }
+        }
+    }
+
+    fn update_submodules(repo: &Repository) -> Result<(), git2::Error> {
+        let mut submodules = repo.submodules()?;
+        for submodule in submodules.iter_mut() {
+            submodule.init(true)?;
+            submodule.update(true, None)?;
+        }
+        Ok(())
+    }
+
+    fn classify_clone_error(stderr: &str) -> GitError {
+        let lower = stderr.to_lowercase();
+
+        if lower.contains("could not resolve host")
+            || lower.contains("failed to connect")
+            || lower.contains("timed out")
+        {
+            GitError::NetworkError(stderr.trim().to_string())
+        } else if lower.contains("authentication failed")
+            || lower.contains("repository not found")
+            || lower.contains("remote branch")
+        {
+            GitError::GitOperationError(stderr.trim().to_string())
+        } else if lower.contains("permission denied")
+            || lower.contains("access is denied")
+            || lower.contains("no such file or directory")
+        {
+            GitError::FileSystemError(stderr.trim().to_string())
+        } else if lower.contains("already exists and is not an empty directory")
+            || lower.contains("is not empty")
+        {
+            GitError::ValidationError("目标目录已存在且非空".to_string())
+        } else {
+            GitError::GitOperationError(stderr.trim().to_string())
+        }
+    }
+
+    pub fn open_repository(&mut self, path: &str) -> Result<()> {
+        let _repo = Repository::open(path)?;
+
+        self.stop_repo_watcher();
+
+        self.repo_path = Some(path.to_string());
+
+        Ok(())
+    }
+
+    /// 获取当前仓库路径
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-04
+
+    pub fn get_repository_path(&self) -> Option<String> {
+        self.repo_path.clone()
+    }
+
+    /// 获取单个文件的diff内容（简单版本）
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-05
+
+    pub fn get_simple_file_diff(&self, file_path: &str) -> Result<String> {
+        debug_log!("🔍 [get_simple_file_diff] 开始处理文件: {}", file_path);
+
+        let start_time = std::time::Instant::now();
+
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No repository opened"))?;
+
+        debug_log!("🔍 [get_simple_file_diff] 打开Git仓库: {}", repo_path);
+
+        let repo = Repository::open(repo_path)
+            .map_err(|e| anyhow::anyhow!("无法打开Git仓库 {}: {}", repo_path, e))?;
+
+        debug_log!(
+            "🔍 [get_simple_file_diff] 仓库打开耗时: {:?}",
+            start_time.elapsed()
+        );
+
+        // 性能优化：使用git命令行工具，比libgit2更快
+
+        let git_diff_start = std::time::Instant::now();
+
+        let result = self.get_file_diff_via_command(repo_path, file_path);
+
+        debug_log!(
+            "🔍 [get_simple_file_diff] Git命令耗时: {:?}",
+            git_diff_start.elapsed()
+        );
+
+        if result.is_ok() {
+            debug_log!(
+                "🔍 [get_simple_file_diff] 文件 {} 处理完成，总耗时: {:?}",
+                file_path,
+                start_time.elapsed()
+            );
+
+            return result;
+        }
+
+        warn_log!("⚠️ [get_simple_file_diff] Git命令失败，回退到libgit2方法");
+
+        let libgit2_start = std::time::Instant::now();
+
+        let head = repo
+            .head()
+            .map_err(|e| anyhow::anyhow!("无法获取HEAD引用: {}", e))?;
+
+        let head_commit = head
+            .peel_to_commit()
+            .map_err(|e| anyhow::anyhow!("无法获取HEAD提交: {}", e))?;
+
+        let head_tree = head_commit
+            .tree()
+            .map_err(|e| anyhow::anyhow!("无法获取HEAD树: {}", e))?;
+
+        // 获取工作目录状态
+
+        let mut opts = DiffOptions::new();
+
+        opts.include_untracked(true);
+
+        let diff = repo
+            .diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut opts))
+            .map_err(|e| anyhow::anyhow!("创建diff失败: {}", e))?;
+
+        // 首先列出所有diff中的文件
+
+        let mut all_files = Vec::new();
+
+        // 使用更安全的回调处理方式
+
+        let foreach_result = diff.foreach(
+            &mut |delta, _progress| {
+                match delta.new_file().path() {
+                    Some(path) => {
+                        let delta_path = path.to_string_lossy().to_string();
+
+                        all_files.push(delta_path.clone());
+
+                        true // 继续处理
+                    }
+
+                    None => {
+                        true // 继续处理，即使这个delta没有路径
+                    }
+                }
+            },
+            None,
+            None,
+            None,
+        );
+
+        if let Err(e) = foreach_result {
+            return Err(anyhow::anyhow!("遍历diff文件列表失败: {}", e));
+        }
+
+        // 查找指定文件的diff
+
+        let mut file_diff = String::new();
+
+        let mut found_file = false;
+
+        // 使用更安全的回调处理方式，分离文件查找和内容处理
+
+        let diff_result = diff.foreach(
+            &mut |delta, _progress| {
+                match delta.new_file().path() {
+                    Some(path) => {
+                        let delta_path = path.to_string_lossy();
+
+                        // 尝试多种路径匹配方式
+
+                        let delta_path_str = delta_path.as_ref();
+
+                        let is_match = delta_path_str == file_path
+                            || delta_path_str.ends_with(file_path)
+                            || file_path.ends_with(delta_path_str)
+                            || delta_path_str.replace('\\', "/") == file_path.replace('\\', "/");
+
+                        if is_match {
+                            found_file = true;
+                        }
+
+                        true // 总是返回true，避免用户中断错误
+                    }
+
+                    None => {
+                        true // 总是返回true，避免用户中断错误
+                    }
+                }
+            },
+            None,
+            None,
+            Some(&mut |delta, _hunk, line| {
+                // 只处理匹配文件的diff行
+
+                if let Some(path) = delta.new_file().path() {
+                    let delta_path = path.to_string_lossy();
+
+                    let delta_path_str = delta_path.as_ref();
+
+                    let is_match = delta_path_str == file_path
+                        || delta_path_str.ends_with(file_path)
+                        || file_path.ends_with(delta_path_str)
+                        || delta_path_str.replace('\\', "/") == file_path.replace('\\', "/");
+
+                    if !is_match {
+                        return true; // 不是目标文件，跳过这行
+                    }
+                }
+
+                match line.origin() {
+                    '+' | '-' | ' ' => {
+                        file_diff.push(line.origin());
+
+                        if let Ok(content) = std::str::from_utf8(line.content()) {
+                            file_diff.push_str(content);
+                        }
+                    }
+
+                    _ => {
+
+                        // 跳过非内容行
+                    }
+                }
+
+                true // 总是返回true继续处理
+            }),
+        );
+
+        if let Err(e) = diff_result {
+            return Err(anyhow::anyhow!("获取文件diff内容失败: {}", e));
+        }
+
+        if !found_file {
+            // 尝试备用方法：使用简化的路径匹配
+
+            return self.get_simple_file_diff_fallback(file_path);
+        }
+
+        if file_diff.is_empty() {
+            return self.get_simple_file_diff_fallback(file_path);
+        }
+
+        debug_log!(
+            "🔍 [get_simple_file_diff] 文件 {} libgit2处理完成，libgit2耗时: {:?}, 总耗时: {:?}",
+            file_path,
+            libgit2_start.elapsed(),
+            start_time.elapsed()
+        );
+
+        Ok(file_diff)
+    }
+
+    /// 使用Git命令行工具获取文件diff（性能优化）
+
+    /// Author: Evilek, Date: 2025-01-08
+
+    fn get_file_diff_via_command(&self, repo_path: &str, file_path: &str) -> Result<String> {
+        // use std::process::Command;
+
+        // 使用缓存的Git路径，如果没有则回退到检测
+
+        let git_cmd = if let Some(ref git_path) = self.git_path {
+            git_path.clone()
+        } else {
+            // 回退到简单检测
+
+            if cfg!(windows) {
+                "git.exe".to_string()
+            } else {
+                "git".to_string()
+            }
+        };
+
+        debug_log!(
+            "🔍 [get_file_diff_via_command] 使用缓存的Git路径: {} diff HEAD -- {}",
+            git_cmd,
+            file_path
+        );
+
+        // 首先尝试获取工作目录相对于HEAD的diff
+
+        let output = Self::create_hidden_command(&git_cmd)
+            .arg("diff")
+            .arg("HEAD")
+            .arg("--")
+            .arg(file_path)
+            .current_dir(repo_path)
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow::anyhow!("执行git命令失败: {}", e))?;
+
+        debug_log!(
+            "🔍 [get_file_diff_via_command] git diff HEAD 状态: {}, stdout长度: {}, stderr: {}",
+            output.status.success(),
+            output.stdout.len(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        if output.status.success() {
+            let diff_content = String::from_utf8_lossy(&output.stdout);
+
+            if !diff_content.trim().is_empty() {
+                debug_log!(
+                    "✅ [get_file_diff_via_command] 成功获取diff，长度: {}",
+                    diff_content.len()
+                );
+
+                return Ok(diff_content.to_string());
+            }
+        }
+
+        // 如果HEAD diff为空，尝试获取staged diff
+
+        debug_log!("🔍 [get_file_diff_via_command] 尝试staged diff");
+
+        let staged_output = Self::create_hidden_command(&git_cmd)
+            .arg("diff")
+            .arg("--cached")
+            .arg("--")
+            .arg(file_path)
+            .current_dir(repo_path)
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow::anyhow!("执行git diff --cached失败: {}", e))?;
+
+        debug_log!(
+            "🔍 [get_file_diff_via_command] git diff --cached 状态: {}, stdout长度: {}",
+            staged_output.status.success(),
+            staged_output.stdout.len()
+        );
+
+        if staged_output.status.success() {
+            let diff_content = String::from_utf8_lossy(&staged_output.stdout);
+
+            if !diff_content.trim().is_empty() {
+                debug_log!(
+                    "✅ [get_file_diff_via_command] 成功获取staged diff，长度: {}",
+                    diff_content.len()
+                );
+
+                return Ok(diff_content.to_string());
+            }
+        }
+
+        // 最后尝试获取工作目录的变更（不与HEAD比较）
+
+        debug_log!("🔍 [get_file_diff_via_command] 尝试工作目录diff");
+
+        let workdir_output = Self::create_hidden_command(&git_cmd)
+            .arg("diff")
+            .arg("--")
+            .arg(file_path)
+            .current_dir(repo_path)
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow::anyhow!("执行git diff工作目录失败: {}", e))?;
+
+        debug_log!(
+            "🔍 [get_file_diff_via_command] git diff 状态: {}, stdout长度: {}",
+            workdir_output.status.success(),
+            workdir_output.stdout.len()
+        );
+
+        if workdir_output.status.success() {
+            let diff_content = String::from_utf8_lossy(&workdir_output.stdout);
+
+            if !diff_content.trim().is_empty() {
+                debug_log!(
+                    "✅ [get_file_diff_via_command] 成功获取工作目录diff，长度: {}",
+                    diff_content.len()
+                );
+
+                return Ok(diff_content.to_string());
+            }
+        }
+
+        // 尝试检查文件状态
+
+        debug_log!("🔍 [get_file_diff_via_command] 检查文件状态");
+
+        let status_output = Self::create_hidden_command(&git_cmd)
+            .arg("status")
+            .arg("--porcelain")
+            .arg("--")
+            .arg(file_path)
+            .current_dir(repo_path)
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow::anyhow!("执行git status失败: {}", e))?;
+
+        let status_content = String::from_utf8_lossy(&status_output.stdout);
+
+        debug_log!(
+            "🔍 [get_file_diff_via_command] 文件状态: '{}'",
+            status_content.trim()
+        );
+
+        if status_content.trim().is_empty() {
+            return Err(anyhow::anyhow!("文件无变更"));
+        } else {
+            return Err(anyhow::anyhow!(
+                "Git命令无法获取diff，但文件有状态变更: {}",
+                status_content.trim()
+            ));
+        }
+    }
+
+    /// 备用的文件diff获取方法
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-05
+
+    fn get_simple_file_diff_fallback(&self, file_path: &str) -> Result<String> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No repository opened"))?;
+
+        let repo = Repository::open(repo_path)
+            .map_err(|e| anyhow::anyhow!("无法打开Git仓库 {}: {}", repo_path, e))?;
+
+        // 使用更简单的方法：直接比较HEAD和工作目录
+
+        let head = repo.head()?;
+
+        let head_commit = head.peel_to_commit()?;
+
+        let head_tree = head_commit.tree()?;
+
+        let mut opts = DiffOptions::new();
+
+        opts.pathspec(file_path); // 只处理指定文件
+
+        opts.context_lines(3);
+
+        let diff = repo.diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut opts))?;
+
+        let mut file_diff = String::new();
+
+        diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+            let content = String::from_utf8_lossy(line.content());
+
+            file_diff.push_str(&content);
+
+            true
+        })?;
+
+        if file_diff.is_empty() {
+            return Err(anyhow::anyhow!("文件没有变更内容: {}", file_path));
+        }
+
+        Ok(file_diff)
+    }
+
+    /// 获取当前仓库引用
+
+    fn get_repository(&self) -> Result<Repository> {
+        let repo_path = self
+            .repo_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No repository opened"))?;
+
+        Ok(Repository::open(repo_path)?)
+    }
+
+    fn resolve_default_remote(repo: &Repository) -> Option<String> {
+        if repo.find_remote("origin").is_ok() {
+            return Some("origin".to_string());
+        }
+
+        if let Ok(head) = repo.head() {
+            if head.is_branch() {
+                if let Some(reference_name) = head.name() {
+                    if let Ok(remote_buf) = repo.branch_upstream_remote(reference_name) {
+                        if let Some(remote_name) = remote_buf.as_str() {
+                            let trimmed = remote_name.trim();
+
+                            if !trimmed.is_empty() {
+                                return Some(trimmed.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Ok(remotes) = repo.remotes() {
+            for remote_name in remotes.iter().flatten() {
+                let trimmed = remote_name.trim();
+
+                if !trimmed.is_empty() {
+                    return Some(trimmed.to_string());
+                }
+            }
+        }
+
+        None
+    }
+
+    fn get_default_remote_name(&self, repo_path: &str) -> Result<Option<String>> {
+        let repo = Repository::open(repo_path)?;
+
+        Ok(Self::resolve_default_remote(&repo))
+    }
+
+    fn require_remote_name(&self, repo_path: &str) -> Result<String> {
+        self.get_default_remote_name(repo_path)?
+            .ok_or_else(|| anyhow!("当前仓库未配置远程仓库，请先添加远程。"))
+    }
+
+    /// 获取Git状态，类似VSCode Git面板的分类显示
+
+    /// 智能选择最佳执行方式
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-08-06
+
+    pub fn get_status(&self) -> Result<GitStatusResult> {
+        debug_log!("开始获取Git状态，使用方式: {:?}", self.git_method);
+
+        // 强制优先使用Git命令，只有在完全不可用时才降级
+
+        match self.get_status_with_git_command() {
+            Ok(result) => {
+                debug_log!("Git命令方式成功");
+
+                return Ok(result);
+            }
+
+            Err(e) => {
+                error_log!("Git命令执行失败: {}", e);
+
+                error_log!("请检查Git是否正确安装并在PATH中可用");
+
+                // 只有在明确配置为Git2Api时才使用，否则返回错误
+
+                match self.git_method {
+                    GitMethod::Git2Api => {
+                        warn_log!("强制使用Git2库API作为最后手段");
+
+                        self.get_status_with_git2_api()
+                    }
+
+                    _ => Err(anyhow!("Git命令不可用: {}。请安装Git或检查PATH配置", e)),
+                }
+            }
+        }
+    }
+
+    /// 使用Git2库API获取状态（备选方案）
+
+    fn get_status_with_git2_api(&self) -> Result<GitStatusResult> {
+        let repo = self.get_repository()?;
+
+        // 获取当前分支
+
+        let head = repo.head()?;
+
+        let branch = head.shorthand().unwrap_or("unknown").to_string();
+
+        // 获取文件状态
+
+        let mut opts = StatusOptions::new();
+
+        opts.include_untracked(true);
+
+        opts.include_ignored(false);
+
+        let statuses = repo.statuses(Some(&mut opts))?;
+
+        let mut staged_files = Vec::new();
+
+        let mut unstaged_files = Vec::new();
+
+        let mut untracked_files = Vec::new();
+
+        let mut conflicted_files = Vec::new();
+
+        for entry in statuses.iter() {
+            let path = entry.path().unwrap_or("").to_string();
+
+            let status_flags = entry.status();
+
+            // 解析工作区状态
+
+            let working_tree_status = if status_flags.is_wt_modified() {
+                Some(FileStatusType::Modified)
+            } else if status_flags.is_wt_deleted() {
+                Some(FileStatusType::Deleted)
+            } else if status_flags.is_wt_new() {
+                Some(FileStatusType::Untracked)
+            } else {
+                None
+            };
+
+            // 解析暂存区状态
+
+            let index_status = if status_flags.is_index_modified() {
+                Some(FileStatusType::Modified)
+            } else if status_flags.is_index_new() {
+                Some(FileStatusType::Added)
+            } else if status_flags.is_index_deleted() {
+                Some(FileStatusType::Deleted)
+            } else {
+                None
+            };
+
+            let file_status = FileStatus {
+                path: path.clone(),
+
+                working_tree_status: working_tree_status.clone(),
+
+                index_status: index_status.clone(),
+
+                selected: false,
+
+                is_staged: index_status.is_some(),
+            };
+
+            // 分类文件
+
+            if status_flags.is_conflicted() {
+                conflicted_files.push(file_status);
+            } else if index_status.is_some() {
+                staged_files.push(file_status);
+            } else if working_tree_status == Some(FileStatusType::Untracked) {
+                untracked_files.push(file_status);
+            } else if working_tree_status.is_some() {
+                unstaged_files.push(file_status);
+            }
+        }
+
+        // 上游分支与领先/落后提交数
+
+        let (upstream, ahead, behind) = Self::upstream_tracking(&repo).unwrap_or((None, 0, 0));
+
+        Ok(GitStatusResult {
+            branch,
+
+            upstream,
+
+            has_changes: !staged_files.is_empty()
+                || !unstaged_files.is_empty()
+                || !untracked_files.is_empty(),
+
+            staged_files,
+
+            unstaged_files,
+
+            untracked_files,
+
+            conflicted_files,
+
+            ahead,
+
+            behind,
+        })
+    }
+
+    /// 当前分支的上游（如 origin/main）及领先/落后提交数；分离 HEAD 或未设置上游时为 (None, 0, 0)
+
+    fn upstream_tracking(repo: &Repository) -> Result<(Option<String>, u32, u32)> {
+        let head = match repo.head() {
+            Ok(head) if head.is_branch() => head,
+            _ => return Ok((None, 0, 0)),
+        };
+
+        let Some(name) = head.shorthand() else {
+            return Ok((None, 0, 0));
+        };
+
+        let branch = repo.find_branch(name, BranchType::Local)?;
+
+        let Ok(upstream) = branch.upstream() else {
+            return Ok((None, 0, 0));
+        };
+
+        let upstream_name = upstream.name()?.map(str::to_string);
+
+        let (ahead, behind) = match (head.target(), upstream.get().target()) {
+            (Some(local), Some(remote)) => repo.graph_ahead_behind(local, remote)?,
+            _ => (0, 0),
+        };
+
+        Ok((upstream_name, ahead as u32, behind as u32))
+    }
+
+    /// 暂存或取消暂存文件
+
+    /// 作者：Evilek
+
+    /// 编写日期：2025-01-25
+
+    /// 更新日期：2025-01-29 (添加删除文件和大文件处理逻辑)
+
+    pub fn stage_files(&self, request: &StageRequest) -> Result<GitOperationResult> {
+        // 强制优先使用Git命令
+
+        match self.stage_files_with_command(request) {
+            Ok(result) => Ok(result),
+
+            Err(e) => {
+                error_log!("Git stage命令失败: {}", e);
+
+                error_log!("文件暂存失败，请检查文件状态");
+
+                // 只有在明确配置为Git2Api时才使用
+
+                match self.git_method {
+                    GitMethod::Git2Api => {
+                        warn_log!("尝试使用Git2库API进行文件暂存");
+
+                        self.stage_files_with_git2_api(request)
+                    }
+
+                    _ => Err(anyhow!("文件暂存失败: {}。请使用Git命令行工具", e)),
+                }
+            }
+        }
+    }
+
+    /// 使用Git命令暂存文件
+
+    fn stage_files_with_command(&self, request: &StageRequest) -> Result<GitOperationResult> {
+        let repo_path = self
+            .get_repository_path()
+            .ok_or_else(|| anyhow!("仓库路径未设置"))?;
+
+        let git_command = self.get_git_command();
+
+        let mut success_count = 0;
+
+        let mut failed_files = Vec::new();
+
+        for file_path in &request.file_paths {
+            let mut args = vec!["add"];
+
+            if request.stage {
+                // 暂存文件
+
+                args.push(file_path);
+            } else {
+                // 取消暂存文件
+
+                args = vec!["reset", "HEAD", file_path];
+            }
+
+            let output = Self::create_hidden_command(&git_command)
+                .current_dir(&repo_path)
+                .args(&args)
+                .output_with(&self.process_runner())?;
+
+            if output.status.success() {
+                success_count += 1;
+            } else {
+                let error_msg = String::from_utf8_lossy(&output.stderr);
+
+                failed_files.push(format!("{}: {}", file_path, error_msg));
+            }
+        }
+
+        if failed_files.is_empty() {
+            Ok(GitOperationResult {
+                success: true,
+
+                message: if request.stage {
+                    format!("成功暂存 {} 个文件", success_count)
+                } else {
+                    format!("成功取消暂存 {} 个文件", success_count)
+                },
+
+                details: None,
+            })
+        } else {
+            Err(anyhow!("部分文件操作失败: {}", failed_files.join(", ")))
+        }
+    }
+
+    /// 使用Git2库API暂存文件（备选方案）
+
+    fn stage_files_with_git2_api(&self, request: &StageRequest) -> Result<GitOperationResult> {
+        let repo = self.get_repository()?;
+
+        let mut index = repo.index()?;
+
+        if request.stage {
+            // 暂存文件 - 需要区分不同类型的文件状态
+
+            let mut staged_count = 0;
+
+            let mut skipped_files = Vec::new();
+
+            for file_path in &request.file_paths {
+                let path = Path::new(file_path);
+
+                // 检查文件是否存在于工作目录
+
+                let file_exists = repo
+                    .workdir()
+                    .map(|workdir| workdir.join(path).exists())
+                    .unwrap_or(false);
+
+                // 检查文件是否在HEAD中存在
+
+                let file_in_head = match repo.head() {
+                    Ok(head) => match head.peel_to_commit() {
+                        Ok(commit) => match commit.tree() {
+                            Ok(tree) => tree.get_path(path).is_ok(),
+
+                            Err(_) => false,
+                        },
+
+                        Err(_) => false,
+                    },
+
+                    Err(_) => false,
+                };
+
+                // 检查文件大小（如果文件存在）
+
+                if file_exists {
+                    if let Some(workdir) = repo.workdir() {
+                        let full_path = workdir.join(path);
+
+                        if let Ok(metadata) = std::fs::metadata(&full_path) {
+                            let file_size = metadata.len();
+
+                            // 如果文件大于5MB，跳过并记录
+
+                            if file_size > 5 * 1024 * 1024 {
+                                skipped_files.push(format!(
+                                    "{} (文件过大: {:.1}MB)",
+                                    file_path,
+                                    file_size as f64 / (1024.0 * 1024.0)
+                                ));
+
+                                continue;
+                            }
+                        }
+                    }
+                }
+
+                // 根据文件状态选择合适的暂存方法
+
+                match (file_exists, file_in_head) {
+                    (false, true) => {
+                        // 文件被删除：从工作目录删除但在HEAD中存在
+
+                        index.remove_path(path)?;
+
+                        staged_count += 1;
+                    }
+
+                    (true, _) => {
+                        // 文件存在：新增或修改的文件
 
-        let mut builder = git2::build::RepoBuilder::new();
-        builder.fetch_options(fetch_options);
+                        match index.add_path(path) {
+                            Ok(_) => staged_count += 1,
 
-        if let Some(branch) = &request.branch {
-            let trimmed = branch.trim();
-            if !trimmed.is_empty() {
-                builder.branch(trimmed);
-            }
-        }
+                            Err(e) => {
+                                skipped_files.push(format!("{} (暂存失败: {})", file_path, e));
+                            }
+                        }
+                    }
 
-        match builder.clone(
-            request.repository_url.as_str(),
-            Path::new(&request.target_path),
-        ) {
-            Ok(repo) => {
-                if request.recursive {
-                    if let Err(error) = Self::update_submodules(&repo) {
-                        return Err((
-                            GitError::GitOperationError(error.message().to_string()),
-                            error.to_string(),
-                        ));
+                    (false, false) => {
+                        // 文件既不存在于工作目录也不存在于HEAD中，跳过
+
+                        skipped_files.push(format!("{} (文件不存在)", file_path));
                     }
                 }
-                Ok(())
             }
-            Err(error) => {
-                let classified = Self::classify_git2_error(&error);
-                Err((classified, error.to_string()))
-            }
-        }
-    }
 
-    fn update_submodules(repo: &Repository) -> Result<(), git2::Error> {
-        let mut submodules = repo.submodules()?;
-        for submodule in submodules.iter_mut() {
-            submodule.init(true)?;
-            submodule.update(true, None)?;
-        }
-        Ok(())
-    }
+            index.write()?;
 
-    fn classify_clone_error(stderr: &str) -> GitError {
-        let lower = stderr.to_lowercase();
+            let mut message = format!("Successfully staged {} file(s)", staged_count);
 
-        if lower.contains("could not resolve host")
-            || lower.contains("failed to connect")
-            || lower.contains("timed out")
-        {
-            GitError::NetworkError(stderr.trim().to_string())
-        } else if lower.contains("authentication failed")
-            || lower.contains("repository not found")
-            || lower.contains("remote branch")
-        {
-            GitError::GitOperationError(stderr.trim().to_string())
-        } else if lower.contains("permission denied")
-            || lower.contains("access is denied")
-            || lower.contains("no such file or directory")
-        {
-            GitError::FileSystemError(stderr.trim().to_string())
-        } else if lower.contains("already exists and is not an empty directory")
-            || lower.contains("is not empty")
-        {
-            GitError::ValidationError("目标目录已存在且非空".to_string())
-        } else {
-            GitError::GitOperationError(stderr.trim().to_string())
-        }
-    }
+            if !skipped_files.is_empty() {
+                message.push_str(&format!(", skipped {} file(s)", skipped_files.len()));
+            }
 
-    fn classify_git2_error(error: &git2::Error) -> GitError {
-        use git2::ErrorClass;
+            Ok(GitOperationResult {
+                success: true,
 
-        match error.class() {
-            ErrorClass::Net => GitError::NetworkError(error.message().to_string()),
-            ErrorClass::Os => GitError::FileSystemError(error.message().to_string()),
-            ErrorClass::Config => GitError::ConfigurationError(error.message().to_string()),
-            _ => GitError::GitOperationError(error.message().to_string()),
-        }
-    }
+                message,
 
-    pub fn open_repository(&mut self, path: &str) -> Result<()> {
-        let _repo = Repository::open(path)?;
+                details: if skipped_files.is_empty() {
+                    None
+                } else {
+                    Some(format!("跳过的文件:\n{}", skipped_files.join("\n")))
+                },
+            })
+        } else {
+            // 取消暂存文件 - 使用正确的reset方法
 
-        self.stop_repo_watcher();
+            let head = repo.head()?;
 
-        self.repo_path = Some(path.to_string());
+            let head_commit = head.peel_to_commit()?;
 
-        Ok(())
-    }
+            // 将指定文件从暂存区重置到HEAD状态
 
-    /// 获取当前仓库路径
+            repo.reset_default(Some(head_commit.as_object()), request.file_paths.iter())?;
 
-    /// 作者：Evilek
+            Ok(GitOperationResult {
+                success: true,
 
-    /// 编写日期：2025-08-04
+                message: format!("Successfully unstaged {} file(s)", request.file_paths.len()),
 
-    pub fn get_repository_path(&self) -> Option<String> {
-        self.repo_path.clone()
+                details: None,
+            })
+        }
     }
 
-    /// 获取单个文件的diff内容（简单版本）
+    /// 提交更改
 
-    /// 作者：Evilek
+    pub fn commit(&self, request: &CommitRequest) -> Result<GitOperationResult> {
+        // 强制优先使用Git命令
 
-    /// 编写日期：2025-08-05
+        match self.commit_with_command(request) {
+            Ok(result) => Ok(result),
 
-    pub fn get_simple_file_diff(&self, file_path: &str) -> Result<String> {
-        println!("🔍 [get_simple_file_diff] 开始处理文件: {}", file_path);
+            Err(e) => {
+                error_log!("Git commit命令失败: {}", e);
 
-        let start_time = std::time::Instant::now();
+                error_log!("提交失败，请检查暂存区状态和提交信息");
 
-        let repo_path = self
-            .repo_path
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No repository opened"))?;
+                // 只有在明确配置为Git2Api时才使用
 
-        println!("🔍 [get_simple_file_diff] 打开Git仓库: {}", repo_path);
+                match self.git_method {
+                    GitMethod::Git2Api => {
+                        warn_log!("尝试使用Git2库API进行提交");
 
-        let repo = Repository::open(repo_path)
-            .map_err(|e| anyhow::anyhow!("无法打开Git仓库 {}: {}", repo_path, e))?;
+                        self.commit_with_git2_api(request)
+                    }
 
-        println!(
-            "🔍 [get_simple_file_diff] 仓库打开耗时: {:?}",
-            start_time.elapsed()
-        );
+                    _ => Err(anyhow!("提交失败: {}。请使用Git命令行工具", e)),
+                }
+            }
+        }
+    }
 
-        // 性能优化：使用git命令行工具，比libgit2更快
+    /// 使用Git命令提交
 
-        let git_diff_start = std::time::Instant::now();
+    fn commit_with_command(&self, request: &CommitRequest) -> Result<GitOperationResult> {
+        let repo_path = self
+            .get_repository_path()
+            .ok_or_else(|| anyhow!("仓库路径未设置"))?;
 
-        let result = self.get_file_diff_via_command(repo_path, file_path);
+        let git_command = self.get_git_command();
 
-        println!(
-            "🔍 [get_simple_file_diff] Git命令耗时: {:?}",
-            git_diff_start.elapsed()
-        );
+        // 如果指定了文件，先暂存这些文件
 
-        if result.is_ok() {
-            println!(
-                "🔍 [get_simple_file_diff] 文件 {} 处理完成，总耗时: {:?}",
-                file_path,
-                start_time.elapsed()
-            );
+        if !request.selected_files.is_empty() {
+            for file_path in &request.selected_files {
+                let output = Self::create_hidden_command(&git_command)
+                    .current_dir(&repo_path)
+                    .args(&["add", file_path])
+                    .output_with(&self.process_runner())?;
 
-            return result;
+                if !output.status.success() {
+                    let error_msg = String::from_utf8_lossy(&output.stderr);
+
+                    return Err(anyhow!("暂存文件 {} 失败: {}", file_path, error_msg));
+                }
+            }
         }
 
-        println!("⚠️ [get_simple_file_diff] Git命令失败，回退到libgit2方法");
+        // 构建提交命令
 
-        let libgit2_start = std::time::Instant::now();
+        let mut args = vec!["commit", "-m", &request.message];
 
-        let head = repo
-            .head()
-            .map_err(|e| anyhow::anyhow!("无法获取HEAD引用: {}", e))?;
+        if request.amend {
+            args.insert(1, "--amend");
+        }
 
-        let head_commit = head
-            .peel_to_commit()
-            .map_err(|e| anyhow::anyhow!("无法获取HEAD提交: {}", e))?;
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(&repo_path)
+            .args(&args)
+            .output_with(&self.process_runner())?;
 
-        let head_tree = head_commit
-            .tree()
-            .map_err(|e| anyhow::anyhow!("无法获取HEAD树: {}", e))?;
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
 
-        // 获取工作目录状态
+            Ok(GitOperationResult {
+                success: true,
 
-        let mut opts = DiffOptions::new();
+                message: "提交成功".to_string(),
 
-        opts.include_untracked(true);
+                details: Some(stdout.to_string()),
+            })
+        } else {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
 
-        let diff = repo
-            .diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut opts))
-            .map_err(|e| anyhow::anyhow!("创建diff失败: {}", e))?;
+            Err(anyhow!("提交失败: {}", error_msg))
+        }
+    }
 
-        // 首先列出所有diff中的文件
+    /// 使用Git2库API提交（备选方案）
 
-        let mut all_files = Vec::new();
+    fn commit_with_git2_api(&self, request: &CommitRequest) -> Result<GitOperationResult> {
+        let repo = self.get_repository()?;
 
-        // 使用更安全的回调处理方式
+        let mut index = repo.index()?;
 
-        let foreach_result = diff.foreach(
-            &mut |delta, _progress| {
-                match delta.new_file().path() {
-                    Some(path) => {
-                        let delta_path = path.to_string_lossy().to_string();
+        // 如果指定了文件，先暂存这些文件
 
-                        all_files.push(delta_path.clone());
+        if !request.selected_files.is_empty() {
+            for file_path in &request.selected_files {
+                index.add_path(Path::new(file_path))?;
+            }
 
-                        true // 继续处理
-                    }
+            index.write()?;
+        }
 
-                    None => {
-                        true // 继续处理，即使这个delta没有路径
-                    }
-                }
-            },
-            None,
-            None,
-            None,
-        );
+        // 创建提交
 
-        if let Err(e) = foreach_result {
-            return Err(anyhow::anyhow!("遍历diff文件列表失败: {}", e));
-        }
+        let tree_id = index.write_tree()?;
 
-        // 查找指定文件的diff
+        let tree = repo.find_tree(tree_id)?;
 
-        let mut file_diff = String::new();
+        // 获取签名
 
-        let mut found_file = false;
+        let signature = self.get_signature(&repo)?;
 
-        // 使用更安全的回调处理方式，分离文件查找和内容处理
+        // 获取父提交
+
+        let parent_commit = if let Ok(head) = repo.head() {
+            if let Some(target) = head.target() {
+                Some(repo.find_commit(target)?)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
 
-        let diff_result = diff.foreach(
-            &mut |delta, _progress| {
-                match delta.new_file().path() {
-                    Some(path) => {
-                        let delta_path = path.to_string_lossy();
+        // 创建提交
 
-                        // 尝试多种路径匹配方式
+        let commit_id = if let Some(parent) = parent_commit {
+            if request.amend {
+                // 修正上次提交
 
-                        let delta_path_str = delta_path.as_ref();
+                repo.commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    &request.message,
+                    &tree,
+                    &[&parent],
+                )?
+            } else {
+                // 普通提交
 
-                        let is_match = delta_path_str == file_path
-                            || delta_path_str.ends_with(file_path)
-                            || file_path.ends_with(delta_path_str)
-                            || delta_path_str.replace('\\', "/") == file_path.replace('\\', "/");
+                repo.commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    &request.message,
+                    &tree,
+                    &[&parent],
+                )?
+            }
+        } else {
+            // 初始提交
 
-                        if is_match {
-                            found_file = true;
-                        }
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &request.message,
+                &tree,
+                &[],
+            )?
+        };
 
-                        true // 总是返回true，避免用户中断错误
-                    }
+        Ok(GitOperationResult {
+            success: true,
 
-                    None => {
-                        true // 总是返回true，避免用户中断错误
-                    }
-                }
-            },
-            None,
-            None,
-            Some(&mut |delta, _hunk, line| {
-                // 只处理匹配文件的diff行
+            message: format!("Commit created: {}", commit_id),
 
-                if let Some(path) = delta.new_file().path() {
-                    let delta_path = path.to_string_lossy();
+            details: Some(request.message.clone()),
+        })
+    }
 
-                    let delta_path_str = delta_path.as_ref();
+    /// 获取Git签名
 
-                    let is_match = delta_path_str == file_path
-                        || delta_path_str.ends_with(file_path)
-                        || file_path.ends_with(delta_path_str)
-                        || delta_path_str.replace('\\', "/") == file_path.replace('\\', "/");
+    fn get_signature(&self, repo: &Repository) -> Result<Signature<'_>> {
+        // 尝试从配置获取用户信息
 
-                    if !is_match {
-                        return true; // 不是目标文件，跳过这行
-                    }
-                }
+        let config = repo.config()?;
 
-                match line.origin() {
-                    '+' | '-' | ' ' => {
-                        file_diff.push(line.origin());
+        let name = config
+            .get_string("user.name")
+            .unwrap_or_else(|_| "GitMentor User".to_string());
 
-                        if let Ok(content) = std::str::from_utf8(line.content()) {
-                            file_diff.push_str(content);
-                        }
-                    }
+        let email = config
+            .get_string("user.email")
+            .unwrap_or_else(|_| "user@gitmentor.local".to_string());
 
-                    _ => {
+        Ok(Signature::now(&name, &email)?)
+    }
 
-                        // 跳过非内容行
-                    }
-                }
+    /// 回滚文件更改
 
-                true // 总是返回true继续处理
-            }),
-        );
+    pub fn revert_files(&self, request: &RevertRequest) -> Result<GitOperationResult> {
+        let repo = self.get_repository()?;
 
-        if let Err(e) = diff_result {
-            return Err(anyhow::anyhow!("获取文件diff内容失败: {}", e));
-        }
+        match request.revert_type {
+            RevertType::WorkingTree => {
+                // 回滚工作区更改到HEAD状态
 
-        if !found_file {
-            // 尝试备用方法：使用简化的路径匹配
+                let mut checkout_builder = git2::build::CheckoutBuilder::new();
 
-            return self.get_simple_file_diff_fallback(file_path);
-        }
+                checkout_builder.force();
 
-        if file_diff.is_empty() {
-            return self.get_simple_file_diff_fallback(file_path);
-        }
+                for file_path in &request.file_paths {
+                    checkout_builder.path(file_path);
+                }
 
-        println!(
-            "🔍 [get_simple_file_diff] 文件 {} libgit2处理完成，libgit2耗时: {:?}, 总耗时: {:?}",
-            file_path,
-            libgit2_start.elapsed(),
-            start_time.elapsed()
-        );
+                // 从HEAD检出文件
 
-        Ok(file_diff)
-    }
+                let head = repo.head()?;
 
-    /// 使用Git命令行工具获取文件diff（性能优化）
+                let head_commit = head.peel_to_commit()?;
 
-    /// Author: Evilek, Date: 2025-01-08
+                let head_tree = head_commit.tree()?;
 
-    fn get_file_diff_via_command(&self, repo_path: &str, file_path: &str) -> Result<String> {
-        // use std::process::Command;
+                repo.checkout_tree(head_tree.as_object(), Some(&mut checkout_builder))?;
 
-        // 使用缓存的Git路径，如果没有则回退到检测
+                Ok(GitOperationResult {
+                    success: true,
 
-        let git_cmd = if let Some(ref git_path) = self.git_path {
-            git_path.clone()
-        } else {
-            // 回退到简单检测
+                    message: format!(
+                        "Reverted {} file(s) in working tree",
+                        request.file_paths.len()
+                    ),
 
-            if cfg!(windows) {
-                "git.exe".to_string()
-            } else {
-                "git".to_string()
+                    details: None,
+                })
             }
-        };
 
-        println!(
-            "🔍 [get_file_diff_via_command] 使用缓存的Git路径: {} diff HEAD -- {}",
-            git_cmd, file_path
-        );
+            RevertType::Staged => {
+                // 回滚暂存区更改 - 将暂存区文件重置到HEAD状态，但保留工作区更改
 
-        // 首先尝试获取工作目录相对于HEAD的diff
+                let head = repo.head()?;
 
-        let output = Self::create_hidden_command(&git_cmd)
-            .arg("diff")
-            .arg("HEAD")
-            .arg("--")
-            .arg(file_path)
-            .current_dir(repo_path)
-            .output()
-            .map_err(|e| anyhow::anyhow!("执行git命令失败: {}", e))?;
+                let head_commit = head.peel_to_commit()?;
 
-        println!(
-            "🔍 [get_file_diff_via_command] git diff HEAD 状态: {}, stdout长度: {}, stderr: {}",
-            output.status.success(),
-            output.stdout.len(),
-            String::from_utf8_lossy(&output.stderr)
-        );
+                // 将指定文件从暂存区重置到HEAD状态（取消暂存）
 
-        if output.status.success() {
-            let diff_content = String::from_utf8_lossy(&output.stdout);
+                repo.reset_default(Some(head_commit.as_object()), request.file_paths.iter())?;
 
-            if !diff_content.trim().is_empty() {
-                println!(
-                    "✅ [get_file_diff_via_command] 成功获取diff，长度: {}",
-                    diff_content.len()
-                );
+                Ok(GitOperationResult {
+                    success: true,
 
-                return Ok(diff_content.to_string());
+                    message: format!(
+                        "Unstaged {} file(s) from staging area",
+                        request.file_paths.len()
+                    ),
+
+                    details: None,
+                })
             }
-        }
 
-        // 如果HEAD diff为空，尝试获取staged diff
+            RevertType::DiscardAll => {
+                // 撤销所有更改 - 先取消暂存，再回滚工作区到HEAD状态
 
-        println!("🔍 [get_file_diff_via_command] 尝试staged diff");
+                let head = repo.head()?;
 
-        let staged_output = Self::create_hidden_command(&git_cmd)
-            .arg("diff")
-            .arg("--cached")
-            .arg("--")
-            .arg(file_path)
-            .current_dir(repo_path)
-            .output()
-            .map_err(|e| anyhow::anyhow!("执行git diff --cached失败: {}", e))?;
+                let head_commit = head.peel_to_commit()?;
 
-        println!(
-            "🔍 [get_file_diff_via_command] git diff --cached 状态: {}, stdout长度: {}",
-            staged_output.status.success(),
-            staged_output.stdout.len()
-        );
+                let head_tree = head_commit.tree()?;
 
-        if staged_output.status.success() {
-            let diff_content = String::from_utf8_lossy(&staged_output.stdout);
+                // 1. 先取消暂存（重置索引到HEAD）
 
-            if !diff_content.trim().is_empty() {
-                println!(
-                    "✅ [get_file_diff_via_command] 成功获取staged diff，长度: {}",
-                    diff_content.len()
-                );
+                repo.reset_default(Some(head_commit.as_object()), request.file_paths.iter())?;
 
-                return Ok(diff_content.to_string());
-            }
-        }
+                // 2. 再回滚工作区到HEAD状态
 
-        // 最后尝试获取工作目录的变更（不与HEAD比较）
+                let mut checkout_builder = git2::build::CheckoutBuilder::new();
 
-        println!("🔍 [get_file_diff_via_command] 尝试工作目录diff");
+                checkout_builder.force();
 
-        let workdir_output = Self::create_hidden_command(&git_cmd)
-            .arg("diff")
-            .arg("--")
-            .arg(file_path)
-            .current_dir(repo_path)
-            .output()
-            .map_err(|e| anyhow::anyhow!("执行git diff工作目录失败: {}", e))?;
+                for file_path in &request.file_paths {
+                    checkout_builder.path(file_path);
+                }
 
-        println!(
-            "🔍 [get_file_diff_via_command] git diff 状态: {}, stdout长度: {}",
-            workdir_output.status.success(),
-            workdir_output.stdout.len()
-        );
+                repo.checkout_tree(head_tree.as_object(), Some(&mut checkout_builder))?;
 
-        if workdir_output.status.success() {
-            let diff_content = String::from_utf8_lossy(&workdir_output.stdout);
+                Ok(GitOperationResult {
+                    success: true,
 
-            if !diff_content.trim().is_empty() {
-                println!(
-                    "✅ [get_file_diff_via_command] 成功获取工作目录diff，长度: {}",
-                    diff_content.len()
-                );
+                    message: format!(
+                        "Discarded all changes for {} file(s)",
+                        request.file_paths.len()
+                    ),
 
-                return Ok(diff_content.to_string());
+                    details: None,
+                })
             }
-        }
 
-        // 尝试检查文件状态
-
-        println!("🔍 [get_file_diff_via_command] 检查文件状态");
-
-        let status_output = Self::create_hidden_command(&git_cmd)
-            .arg("status")
-            .arg("--porcelain")
-            .arg("--")
-            .arg(file_path)
-            .current_dir(repo_path)
-            .output()
-            .map_err(|e| anyhow::anyhow!("执行git status失败: {}", e))?;
+            RevertType::Commit => {
+                // 回滚提交（简化实现）
 
-        let status_content = String::from_utf8_lossy(&status_output.stdout);
+                Ok(GitOperationResult {
+                    success: false,
 
-        println!(
-            "🔍 [get_file_diff_via_command] 文件状态: '{}'",
-            status_content.trim()
-        );
+                    message: "Commit revert not implemented in MVP".to_string(),
 
-        if status_content.trim().is_empty() {
-            return Err(anyhow::anyhow!("文件无变更"));
-        } else {
-            return Err(anyhow::anyhow!(
-                "Git命令无法获取diff，但文件有状态变更: {}",
-                status_content.trim()
-            ));
+                    details: Some("This feature will be available in future versions".to_string()),
+                })
+            }
         }
     }
 
-    /// 备用的文件diff获取方法
-
-    /// 作者：Evilek
+    /// 获取提交历史
 
-    /// 编写日期：2025-08-05
+    pub fn get_commit_history(&self, limit: usize) -> Result<Vec<CommitInfo>> {
+        let repo = self.get_repository()?;
 
-    fn get_simple_file_diff_fallback(&self, file_path: &str) -> Result<String> {
-        let repo_path = self
-            .repo_path
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No repository opened"))?;
+        let mut revwalk = repo.revwalk()?;
 
-        let repo = Repository::open(repo_path)
-            .map_err(|e| anyhow::anyhow!("无法打开Git仓库 {}: {}", repo_path, e))?;
+        revwalk.push_head()?;
 
-        // 使用更简单的方法：直接比较HEAD和工作目录
+        revwalk.set_sorting(git2::Sort::TIME)?;
 
-        let head = repo.head()?;
+        let mut commits = Vec::new();
 
-        let head_commit = head.peel_to_commit()?;
+        for (i, oid) in revwalk.enumerate() {
+            if i >= limit {
+                break;
+            }
 
-        let head_tree = head_commit.tree()?;
+            let oid = oid?;
 
-        let mut opts = DiffOptions::new();
+            let commit = repo.find_commit(oid)?;
 
-        opts.pathspec(file_path); // 只处理指定文件
+            let commit_info = CommitInfo {
+                hash: oid.to_string(),
 
-        opts.context_lines(3);
+                short_hash: oid.to_string()[..8].to_string(),
 
-        let diff = repo.diff_tree_to_workdir_with_index(Some(&head_tree), Some(&mut opts))?;
+                message: commit.message().unwrap_or("").to_string(),
 
-        let mut file_diff = String::new();
+                author: commit.author().name().unwrap_or("").to_string(),
 
-        diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-            let content = String::from_utf8_lossy(line.content());
+                email: commit.author().email().unwrap_or("").to_string(),
 
-            file_diff.push_str(&content);
+                timestamp: commit.time().seconds(),
 
-            true
-        })?;
+                files_changed: Vec::new(), // 简化实现
+            };
 
-        if file_diff.is_empty() {
-            return Err(anyhow::anyhow!("文件没有变更内容: {}", file_path));
+            commits.push(commit_info);
         }
 
-        Ok(file_diff)
+        Ok(commits)
     }
 
-    /// 获取当前仓库引用
-
-    fn get_repository(&self) -> Result<Repository> {
-        let repo_path = self
-            .repo_path
-            .as_ref()
-            .ok_or_else(|| anyhow!("No repository opened"))?;
-
-        Ok(Repository::open(repo_path)?)
-    }
+    /// 获取当前分支最近的提交消息（完整消息，新的在前，跳过合并提交），用于学习提交风格
+    /// author_email 不为空时只保留该作者的提交
+    /// 作者：Evilek
+    /// 编写日期：2025-10-02
+    pub fn get_recent_commit_messages(
+        &self,
+        limit: usize,
+        author_email: Option<&str>,
+    ) -> Result<Vec<String>> {
+        // 按作者过滤时最多回溯的提交数，避免在大仓库中遍历全部历史
+        const MAX_SCAN: usize = 2_000;
 
-    fn resolve_default_remote(repo: &Repository) -> Option<String> {
-        if repo.find_remote("origin").is_ok() {
-            return Some("origin".to_string());
+        let repo = self.get_repository()?;
+        if limit == 0 || repo.head().is_err() {
+            return Ok(Vec::new());
         }
 
-        if let Ok(head) = repo.head() {
-            if head.is_branch() {
-                if let Some(reference_name) = head.name() {
-                    if let Ok(remote_buf) = repo.branch_upstream_remote(reference_name) {
-                        if let Some(remote_name) = remote_buf.as_str() {
-                            let trimmed = remote_name.trim();
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
 
-                            if !trimmed.is_empty() {
-                                return Some(trimmed.to_string());
-                            }
-                        }
-                    }
-                }
+        let mut messages = Vec::new();
+        for oid in revwalk.take(MAX_SCAN) {
+            let commit = repo.find_commit(oid?)?;
+            if commit.parent_count() > 1 {
+                continue;
             }
-        }
-
-        if let Ok(remotes) = repo.remotes() {
-            for remote_name in remotes.iter().flatten() {
-                let trimmed = remote_name.trim();
 
-                if !trimmed.is_empty() {
-                    return Some(trimmed.to_string());
+            if let Some(email) = author_email {
+                let matches = commit
+                    .author()
+                    .email()
+                    .is_some_and(|e| e.eq_ignore_ascii_case(email));
+                if !matches {
+                    continue;
                 }
             }
+
+            messages.push(commit.message().unwrap_or_default().trim().to_string());
+            if messages.len() >= limit {
+                break;
+            }
         }
 
-        None
+        Ok(messages)
     }
 
-    fn get_default_remote_name(&self, repo_path: &str) -> Result<Option<String>> {
-        let repo = Repository::open(repo_path)?;
-
-        Ok(Self::resolve_default_remote(&repo))
-    }
+    /// 分页查询提交历史（用于提交图）：基于 revwalk 游标分页，支持全部分支、引用装饰与多种过滤
+    /// 作者：Evilek
+    /// 编写日期：2025-09-29
+    pub fn query_commit_history(&self, query: &HistoryQuery) -> Result<HistoryPage> {
+        // 单页最多遍历的提交数，过滤条件很稀疏时先返回部分结果，避免长时间持锁
+        const MAX_SCAN_PER_PAGE: usize = 20_000;
 
-    fn require_remote_name(&self, repo_path: &str) -> Result<String> {
-        self.get_default_remote_name(repo_path)?
-            .ok_or_else(|| anyhow!("当前仓库未配置远程仓库，请先添加远程。"))
-    }
+        let repo = self.get_repository()?;
 
-    /// 获取Git状态，类似VSCode Git面板的分类显示
+        let page_size = query.page_size.unwrap_or(100).clamp(1, 1000);
 
-    /// 智能选择最佳执行方式
+        let message_regex = match query.message_pattern.as_deref().map(str::trim) {
+            Some(pattern) if !pattern.is_empty() => {
+                Some(regex::Regex::new(pattern).map_err(|e| anyhow!("无效的提交信息正则: {}", e))?)
+            }
+            _ => None,
+        };
 
-    /// 作者：Evilek
+        let author = query
+            .author
+            .as_deref()
+            .map(|author| author.trim().to_lowercase())
+            .filter(|author| !author.is_empty());
+
+        let path = query
+            .path
+            .as_deref()
+            .map(|path| path.trim().trim_matches('/').replace('\\', "/"))
+            .filter(|path| !path.is_empty());
+
+        let (skip, expected_oid) = match query.cursor.as_deref() {
+            Some(cursor) if !cursor.is_empty() => {
+                let (count, oid) = cursor
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("无效的历史游标: {}", cursor))?;
+
+                let count: usize = count
+                    .parse()
+                    .map_err(|_| anyhow!("无效的历史游标: {}", cursor))?;
+
+                (count, Some(git2::Oid::from_str(oid)?))
+            }
+            _ => (0, None),
+        };
 
-    /// 编写日期：2025-08-06
+        let order = self.history_walk_order(&repo, query)?;
 
-    pub fn get_status(&self) -> Result<GitStatusResult> {
-        println!("[DEBUG] 开始获取Git状态，使用方式: {:?}", self.git_method);
+        // 游标指向上一页最后遍历的提交，起点变化后顺序不同，游标随之失效
+        if skip > 0 && (skip > order.len() || Some(order[skip - 1]) != expected_oid) {
+            return Err(anyhow!("历史游标已失效（分支已变化），请重新加载"));
+        }
 
-        // 强制优先使用Git命令，只有在完全不可用时才降级
+        let mut last_oid = None;
 
-        match self.get_status_with_git_command() {
-            Ok(result) => {
-                println!("[DEBUG] Git命令方式成功");
+        let refs = Self::collect_commit_refs(&repo)?;
 
-                return Ok(result);
-            }
+        let mut commits = Vec::new();
 
-            Err(e) => {
-                println!("[ERROR] Git命令执行失败: {}", e);
+        let mut scanned = 0;
 
-                println!("[ERROR] 请检查Git是否正确安装并在PATH中可用");
+        let mut exhausted = true;
 
-                // 只有在明确配置为Git2Api时才使用，否则返回错误
+        for &oid in &order[skip..] {
+            scanned += 1;
 
-                match self.git_method {
-                    GitMethod::Git2Api => {
-                        println!("[WARN] 强制使用Git2库API作为最后手段");
+            last_oid = Some(oid);
 
-                        self.get_status_with_git2_api()
-                    }
+            let commit = repo.find_commit(oid)?;
 
-                    _ => Err(anyhow!("Git命令不可用: {}。请安装Git或检查PATH配置", e)),
-                }
+            if Self::commit_matches_query(
+                &commit,
+                query,
+                author.as_deref(),
+                message_regex.as_ref(),
+                path.as_deref(),
+            )? {
+                let hash = oid.to_string();
+
+                commits.push(GraphCommit {
+                    short_hash: hash[..8].to_string(),
+                    hash,
+                    message: commit.message().unwrap_or("").to_string(),
+                    author: commit.author().name().unwrap_or("").to_string(),
+                    email: commit.author().email().unwrap_or("").to_string(),
+                    timestamp: commit.time().seconds(),
+                    parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+                    refs: refs.get(&oid).cloned().unwrap_or_default(),
+                });
             }
-        }
-    }
-
-    /// 使用Git2库API获取状态（备选方案）
 
-    fn get_status_with_git2_api(&self) -> Result<GitStatusResult> {
-        let repo = self.get_repository()?;
+            if commits.len() >= page_size || scanned >= MAX_SCAN_PER_PAGE {
+                exhausted = false;
 
-        // 获取当前分支
+                break;
+            }
+        }
 
-        let head = repo.head()?;
+        let next_cursor = if exhausted {
+            None
+        } else {
+            last_oid.map(|oid| format!("{}:{}", skip + scanned, oid))
+        };
 
-        let branch = head.shorthand().unwrap_or("unknown").to_string();
+        Ok(HistoryPage {
+            commits,
+            next_cursor,
+            scanned,
+        })
+    }
 
-        // 获取文件状态
+    /// 历史的遍历顺序（拓扑 + 时间）：起点与上次相同时复用缓存，
+    /// 翻页只按偏移取，不会每页都从头排序、跳过之前的提交
+    fn history_walk_order(
+        &self,
+        repo: &Repository,
+        query: &HistoryQuery,
+    ) -> Result<Arc<Vec<git2::Oid>>> {
+        let tips = Self::history_tips(repo, query)?;
+        let key = (repo.path().to_string_lossy().to_string(), tips);
+
+        if let Some(walk) = self.history_walk.lock().unwrap().as_ref() {
+            if walk.key == key {
+                return Ok(walk.order.clone());
+            }
+        }
 
-        let mut opts = StatusOptions::new();
+        let mut revwalk = repo.revwalk()?;
 
-        opts.include_untracked(true);
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
 
-        opts.include_ignored(false);
+        for tip in &key.1 {
+            revwalk.push(*tip)?;
+        }
 
-        let statuses = repo.statuses(Some(&mut opts))?;
+        let order = Arc::new(revwalk.collect::<std::result::Result<Vec<_>, _>>()?);
 
-        let mut staged_files = Vec::new();
+        *self.history_walk.lock().unwrap() = Some(HistoryWalk {
+            key,
+            order: order.clone(),
+        });
 
-        let mut unstaged_files = Vec::new();
+        Ok(order)
+    }
 
-        let mut untracked_files = Vec::new();
+    /// 遍历起点：全部分支时为所有本地/远程分支、标签与 HEAD 指向的提交，否则为指定版本
+    fn history_tips(repo: &Repository, query: &HistoryQuery) -> Result<Vec<git2::Oid>> {
+        let mut tips = Vec::new();
 
-        let mut conflicted_files = Vec::new();
+        if query.all_branches {
+            for glob in ["refs/heads/*", "refs/remotes/*", "refs/tags/*"] {
+                for reference in repo.references_glob(glob)? {
+                    // 指向非提交对象的标签不参与遍历
+                    if let Ok(commit) = reference?.peel_to_commit() {
+                        tips.push(commit.id());
+                    }
+                }
+            }
 
-        for entry in statuses.iter() {
-            let path = entry.path().unwrap_or("").to_string();
+            // 分离HEAD时当前提交可能不在任何分支上
+            if let Ok(head) = repo.head() {
+                if let Some(oid) = head.target() {
+                    tips.push(oid);
+                }
+            }
+        } else {
+            let revision = query
+                .revision
+                .as_deref()
+                .filter(|rev| !rev.trim().is_empty())
+                .unwrap_or("HEAD");
 
-            let status_flags = entry.status();
+            tips.push(repo.revparse_single(revision)?.peel_to_commit()?.id());
+        }
 
-            // 解析工作区状态
+        tips.sort();
+        tips.dedup();
 
-            let working_tree_status = if status_flags.is_wt_modified() {
-                Some(FileStatusType::Modified)
-            } else if status_flags.is_wt_deleted() {
-                Some(FileStatusType::Deleted)
-            } else if status_flags.is_wt_new() {
-                Some(FileStatusType::Untracked)
-            } else {
-                None
-            };
+        Ok(tips)
+    }
 
-            // 解析暂存区状态
+    /// 判断提交是否满足查询条件，廉价条件在前，路径比较放在最后
+    fn commit_matches_query(
+        commit: &git2::Commit,
+        query: &HistoryQuery,
+        author: Option<&str>,
+        message_regex: Option<&regex::Regex>,
+        path: Option<&str>,
+    ) -> Result<bool> {
+        let is_merge = commit.parent_count() > 1;
 
-            let index_status = if status_flags.is_index_modified() {
-                Some(FileStatusType::Modified)
-            } else if status_flags.is_index_new() {
-                Some(FileStatusType::Added)
-            } else if status_flags.is_index_deleted() {
-                Some(FileStatusType::Deleted)
-            } else {
-                None
-            };
+        match query.merge_filter {
+            MergeFilter::MergesOnly if !is_merge => return Ok(false),
+            MergeFilter::NoMerges if is_merge => return Ok(false),
+            _ => {}
+        }
 
-            let file_status = FileStatus {
-                path: path.clone(),
+        let time = commit.time().seconds();
 
-                working_tree_status: working_tree_status.clone(),
+        if query.since.is_some_and(|since| time < since)
+            || query.until.is_some_and(|until| time > until)
+        {
+            return Ok(false);
+        }
 
-                index_status: index_status.clone(),
+        if let Some(author) = author {
+            let signature = commit.author();
 
-                selected: false,
+            let name = signature.name().unwrap_or("").to_lowercase();
 
-                is_staged: index_status.is_some(),
-            };
+            let email = signature.email().unwrap_or("").to_lowercase();
 
-            // 分类文件
+            if !name.contains(author) && !email.contains(author) {
+                return Ok(false);
+            }
+        }
 
-            if status_flags.is_conflicted() {
-                conflicted_files.push(file_status);
-            } else if index_status.is_some() {
-                staged_files.push(file_status);
-            } else if working_tree_status == Some(FileStatusType::Untracked) {
-                untracked_files.push(file_status);
-            } else if working_tree_status.is_some() {
-                unstaged_files.push(file_status);
+        if let Some(regex) = message_regex {
+            if !regex.is_match(commit.message().unwrap_or("")) {
+                return Ok(false);
             }
         }
 
-        // 获取远程分支信息（简化版本）
+        let Some(path) = path else {
+            return Ok(true);
+        };
+
+        // 与 git log -- <path> 的默认简化一致：与任一父提交在该路径上相同（TREESAME）则不显示
+        let entry_id = |tree: &git2::Tree| -> Option<git2::Oid> {
+            tree.get_path(Path::new(path)).ok().map(|entry| entry.id())
+        };
 
-        let (ahead, behind) = self.get_ahead_behind_count(&repo).unwrap_or((0, 0));
+        let current = entry_id(&commit.tree()?);
 
-        Ok(GitStatusResult {
-            branch,
+        if commit.parent_count() == 0 {
+            return Ok(current.is_some());
+        }
 
-            upstream: None, // TODO: 实现远程分支跟踪检测
+        for parent in commit.parents() {
+            if entry_id(&parent.tree()?) == current {
+                return Ok(false);
+            }
+        }
 
-            has_changes: !staged_files.is_empty()
-                || !unstaged_files.is_empty()
-                || !untracked_files.is_empty(),
+        Ok(true)
+    }
 
-            staged_files,
+    /// 收集所有引用并按指向的提交分组，用于提交图上的分支/标签装饰
+    fn collect_commit_refs(
+        repo: &Repository,
+    ) -> Result<std::collections::HashMap<git2::Oid, Vec<CommitRef>>> {
+        let mut refs: std::collections::HashMap<git2::Oid, Vec<CommitRef>> =
+            std::collections::HashMap::new();
 
-            unstaged_files,
+        if let Ok(head) = repo.head() {
+            if let Ok(commit) = head.peel_to_commit() {
+                refs.entry(commit.id()).or_default().push(CommitRef {
+                    name: "HEAD".to_string(),
+                    ref_type: CommitRefType::Head,
+                });
+            }
+        }
 
-            untracked_files,
+        for reference in repo.references()? {
+            let reference = reference?;
 
-            conflicted_files,
+            // 跳过 origin/HEAD 这类符号引用
+            if reference.kind() == Some(git2::ReferenceType::Symbolic) {
+                continue;
+            }
 
-            ahead,
+            let ref_type = if reference.is_branch() {
+                CommitRefType::LocalBranch
+            } else if reference.is_remote() {
+                CommitRefType::RemoteBranch
+            } else if reference.is_tag() {
+                CommitRefType::Tag
+            } else {
+                continue;
+            };
 
-            behind,
-        })
-    }
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
 
-    /// 获取领先/落后远程分支的提交数
+            let name = reference.shorthand().unwrap_or("").to_string();
 
-    fn get_ahead_behind_count(&self, _repo: &Repository) -> Result<(u32, u32)> {
-        // 简化实现，实际项目中可以更详细地处理
+            refs.entry(commit.id())
+                .or_default()
+                .push(CommitRef { name, ref_type });
+        }
 
-        Ok((0, 0))
+        Ok(refs)
     }
 
-    /// 暂存或取消暂存文件
-
+    /// 获取文件追溯信息（blame），跟随重命名与跨文件移动
     /// 作者：Evilek
+    /// 编写日期：2025-09-28
+    pub fn blame_file(&self, request: &BlameRequest) -> Result<BlameResult> {
+        if request.file_path.trim().is_empty() {
+            return Err(anyhow!("文件路径不能为空"));
+        }
 
-    /// 编写日期：2025-01-25
-
-    /// 更新日期：2025-01-29 (添加删除文件和大文件处理逻辑)
-
-    pub fn stage_files(&self, request: &StageRequest) -> Result<GitOperationResult> {
-        // 强制优先使用Git命令
-
-        match self.stage_files_with_command(request) {
+        // 优先使用Git命令（-M -C 能跟随移动与复制）
+        match self.blame_with_command(request) {
             Ok(result) => Ok(result),
 
             Err(e) => {
-                println!("[ERROR] Git stage命令失败: {}", e);
-
-                println!("[ERROR] 文件暂存失败，请检查文件状态");
-
-                // 只有在明确配置为Git2Api时才使用
+                error_log!("Git blame命令失败: {}", e);
 
                 match self.git_method {
                     GitMethod::Git2Api => {
-                        println!("[WARN] 尝试使用Git2库API进行文件暂存");
+                        warn_log!("尝试使用Git2库API获取追溯信息");
 
-                        self.stage_files_with_git2_api(request)
+                        self.blame_with_git2_api(request)
                     }
 
-                    _ => Err(anyhow!("文件暂存失败: {}。请使用Git命令行工具", e)),
+                    _ => Err(anyhow!("获取追溯信息失败: {}", e)),
                 }
             }
         }
     }
 
-    /// 使用Git命令暂存文件
-
-    fn stage_files_with_command(&self, request: &StageRequest) -> Result<GitOperationResult> {
+    /// 使用Git命令获取追溯信息（--porcelain 输出）
+    fn blame_with_command(&self, request: &BlameRequest) -> Result<BlameResult> {
         let repo_path = self
             .get_repository_path()
             .ok_or_else(|| anyhow!("仓库路径未设置"))?;
 
         let git_command = self.get_git_command();
 
-        let mut success_count = 0;
-
-        let mut failed_files = Vec::new();
-
-        for file_path in &request.file_paths {
-            let mut args = vec!["add"];
-
-            if request.stage {
-                // 暂存文件
-
-                args.push(file_path);
-            } else {
-                // 取消暂存文件
-
-                args = vec!["reset", "HEAD", file_path];
-            }
-
-            let output = Self::create_hidden_command(&git_command)
-                .current_dir(&repo_path)
-                .args(&args)
-                .output()?;
-
-            if output.status.success() {
-                success_count += 1;
-            } else {
-                let error_msg = String::from_utf8_lossy(&output.stderr);
+        let revision = request
+            .revision
+            .clone()
+            .filter(|rev| !rev.trim().is_empty())
+            .unwrap_or_else(|| "HEAD".to_string());
+
+        let mut args: Vec<String> = vec![
+            "-c".to_string(),
+            "core.quotePath=false".to_string(),
+            "blame".to_string(),
+            "--porcelain".to_string(),
+            "-M".to_string(),
+            "-C".to_string(),
+        ];
 
-                failed_files.push(format!("{}: {}", file_path, error_msg));
-            }
+        if let Some(range) = Self::format_line_range(request.start_line, request.end_line) {
+            args.push("-L".to_string());
+            args.push(range);
         }
 
-        if failed_files.is_empty() {
-            Ok(GitOperationResult {
-                success: true,
-
-                message: if request.stage {
-                    format!("成功暂存 {} 个文件", success_count)
-                } else {
-                    format!("成功取消暂存 {} 个文件", success_count)
-                },
-
-                details: None,
-            })
-        } else {
-            Err(anyhow!("部分文件操作失败: {}", failed_files.join(", ")))
-        }
-    }
+        args.push(revision.clone());
+        args.push("--".to_string());
+        args.push(request.file_path.clone());
 
-    /// 使用Git2库API暂存文件（备选方案）
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(&repo_path)
+            .args(&args)
+            .output_with(&self.process_runner())?;
 
-    fn stage_files_with_git2_api(&self, request: &StageRequest) -> Result<GitOperationResult> {
-        let repo = self.get_repository()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-        let mut index = repo.index()?;
+            return Err(anyhow!("git blame 执行失败: {}", stderr.trim()));
+        }
 
-        if request.stage {
-            // 暂存文件 - 需要区分不同类型的文件状态
+        let stdout = String::from_utf8_lossy(&output.stdout);
 
-            let mut staged_count = 0;
+        let hunks = Self::parse_blame_porcelain(&stdout);
 
-            let mut skipped_files = Vec::new();
+        let total_lines = hunks.iter().map(|hunk| hunk.line_count).sum();
 
-            for file_path in &request.file_paths {
-                let path = Path::new(file_path);
+        Ok(BlameResult {
+            file_path: request.file_path.clone(),
+            revision,
+            total_lines,
+            hunks,
+        })
+    }
 
-                // 检查文件是否存在于工作目录
+    /// 解析 `git blame --porcelain` 输出，相邻且来源连续的行合并为一个区间
+    fn parse_blame_porcelain(output: &str) -> Vec<BlameHunk> {
+        #[derive(Default, Clone)]
+        struct PorcelainCommit {
+            author: String,
+            email: String,
+            timestamp: i64,
+            summary: String,
+            filename: String,
+            boundary: bool,
+        }
 
-                let file_exists = repo
-                    .workdir()
-                    .map(|workdir| workdir.join(path).exists())
-                    .unwrap_or(false);
+        let mut commits: std::collections::HashMap<String, PorcelainCommit> =
+            std::collections::HashMap::new();
 
-                // 检查文件是否在HEAD中存在
+        // (commit, 原始行, 当前行, 原始路径)
+        let mut lines: Vec<(String, u32, u32, String)> = Vec::new();
 
-                let file_in_head = match repo.head() {
-                    Ok(head) => match head.peel_to_commit() {
-                        Ok(commit) => match commit.tree() {
-                            Ok(tree) => tree.get_path(path).is_ok(),
+        let mut current: Option<(String, u32, u32)> = None;
 
-                            Err(_) => false,
-                        },
+        let mut current_filename: Option<String> = None;
 
-                        Err(_) => false,
-                    },
+        for raw in output.lines() {
+            if let Some(_content) = raw.strip_prefix('\t') {
+                if let Some((sha, orig_line, final_line)) = current.take() {
+                    let filename = current_filename
+                        .take()
+                        .or_else(|| commits.get(&sha).map(|c| c.filename.clone()))
+                        .unwrap_or_default();
 
-                    Err(_) => false,
-                };
+                    lines.push((sha, orig_line, final_line, filename));
+                }
 
-                // 检查文件大小（如果文件存在）
+                continue;
+            }
 
-                if file_exists {
-                    if let Some(workdir) = repo.workdir() {
-                        let full_path = workdir.join(path);
+            if current.is_none() {
+                let parts: Vec<&str> = raw.split_whitespace().collect();
 
-                        if let Ok(metadata) = std::fs::metadata(&full_path) {
-                            let file_size = metadata.len();
+                if parts.len() >= 3
+                    && parts[0].len() >= 40
+                    && parts[0].chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    let orig_line = parts[1].parse().unwrap_or(0);
 
-                            // 如果文件大于5MB，跳过并记录
+                    let final_line = parts[2].parse().unwrap_or(0);
 
-                            if file_size > 5 * 1024 * 1024 {
-                                skipped_files.push(format!(
-                                    "{} (文件过大: {:.1}MB)",
-                                    file_path,
-                                    file_size as f64 / (1024.0 * 1024.0)
-                                ));
+                    commits.entry(parts[0].to_string()).or_default();
 
-                                continue;
-                            }
-                        }
-                    }
+                    current = Some((parts[0].to_string(), orig_line, final_line));
                 }
 
-                // 根据文件状态选择合适的暂存方法
+                continue;
+            }
 
-                match (file_exists, file_in_head) {
-                    (false, true) => {
-                        // 文件被删除：从工作目录删除但在HEAD中存在
+            let sha = match current.as_ref() {
+                Some((sha, _, _)) => sha.clone(),
+                None => continue,
+            };
 
-                        index.remove_path(path)?;
+            let entry = commits.entry(sha).or_default();
 
-                        staged_count += 1;
-                    }
+            let (key, value) = raw.split_once(' ').unwrap_or((raw, ""));
 
-                    (true, _) => {
-                        // 文件存在：新增或修改的文件
+            match key {
+                "author" => entry.author = value.to_string(),
+                "author-mail" => {
+                    entry.email = value
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_string()
+                }
+                "author-time" => entry.timestamp = value.parse().unwrap_or(0),
+                "summary" => entry.summary = value.to_string(),
+                "boundary" => entry.boundary = true,
+                "filename" => {
+                    entry.filename = value.to_string();
+                    current_filename = Some(value.to_string());
+                }
+                _ => {}
+            }
+        }
 
-                        match index.add_path(path) {
-                            Ok(_) => staged_count += 1,
+        let mut hunks: Vec<BlameHunk> = Vec::new();
 
-                            Err(e) => {
-                                skipped_files.push(format!("{} (暂存失败: {})", file_path, e));
-                            }
-                        }
-                    }
+        for (sha, orig_line, final_line, filename) in lines {
+            if let Some(last) = hunks.last_mut() {
+                let contiguous = last.commit_hash == sha
+                    && last.original_path == filename
+                    && last.start_line + last.line_count == final_line
+                    && last.original_start_line + last.line_count == orig_line;
 
-                    (false, false) => {
-                        // 文件既不存在于工作目录也不存在于HEAD中，跳过
+                if contiguous {
+                    last.line_count += 1;
 
-                        skipped_files.push(format!("{} (文件不存在)", file_path));
-                    }
+                    continue;
                 }
             }
 
-            index.write()?;
+            let meta = commits.get(&sha).cloned().unwrap_or_default();
+
+            hunks.push(BlameHunk {
+                start_line: final_line,
+                line_count: 1,
+                short_hash: sha.chars().take(8).collect(),
+                commit_hash: sha,
+                author: meta.author,
+                email: meta.email,
+                timestamp: meta.timestamp,
+                summary: meta.summary,
+                original_path: filename,
+                original_start_line: orig_line,
+                is_boundary: meta.boundary,
+            });
+        }
 
-            let mut message = format!("Successfully staged {} file(s)", staged_count);
+        hunks
+    }
 
-            if !skipped_files.is_empty() {
-                message.push_str(&format!(", skipped {} file(s)", skipped_files.len()));
-            }
+    /// 使用Git2库API获取追溯信息（备选方案）
+    fn blame_with_git2_api(&self, request: &BlameRequest) -> Result<BlameResult> {
+        let repo = self.get_repository()?;
 
-            Ok(GitOperationResult {
-                success: true,
+        let revision = request
+            .revision
+            .clone()
+            .filter(|rev| !rev.trim().is_empty())
+            .unwrap_or_else(|| "HEAD".to_string());
 
-                message,
+        let newest_commit = repo.revparse_single(&revision)?.peel_to_commit()?;
 
-                details: if skipped_files.is_empty() {
-                    None
-                } else {
-                    Some(format!("跳过的文件:\n{}", skipped_files.join("\n")))
-                },
-            })
-        } else {
-            // 取消暂存文件 - 使用正确的reset方法
+        let mut opts = git2::BlameOptions::new();
 
-            let head = repo.head()?;
+        opts.newest_commit(newest_commit.id())
+            .track_copies_same_file(true)
+            .track_copies_same_commit_moves(true)
+            .track_copies_same_commit_copies(true);
 
-            let head_commit = head.peel_to_commit()?;
+        if let Some(start) = request.start_line {
+            opts.min_line(start as usize);
+        }
 
-            // 将指定文件从暂存区重置到HEAD状态
+        if let Some(end) = request.end_line {
+            opts.max_line(end as usize);
+        }
 
-            repo.reset_default(Some(head_commit.as_object()), request.file_paths.iter())?;
+        let blame = repo.blame_file(Path::new(&request.file_path), Some(&mut opts))?;
 
-            Ok(GitOperationResult {
-                success: true,
+        let mut hunks = Vec::new();
 
-                message: format!("Successfully unstaged {} file(s)", request.file_paths.len()),
+        for hunk in blame.iter() {
+            let commit_id = hunk.final_commit_id();
 
-                details: None,
-            })
+            let summary = repo
+                .find_commit(commit_id)
+                .ok()
+                .and_then(|commit| commit.summary().map(|s| s.to_string()))
+                .unwrap_or_default();
+
+            let signature = hunk.final_signature();
+
+            let commit_hash = commit_id.to_string();
+
+            hunks.push(BlameHunk {
+                start_line: hunk.final_start_line() as u32,
+                line_count: hunk.lines_in_hunk() as u32,
+                short_hash: commit_hash.chars().take(8).collect(),
+                commit_hash,
+                author: signature.name().unwrap_or("").to_string(),
+                email: signature.email().unwrap_or("").to_string(),
+                timestamp: signature.when().seconds(),
+                summary,
+                original_path: hunk
+                    .path()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| request.file_path.clone()),
+                original_start_line: hunk.orig_start_line() as u32,
+                is_boundary: hunk.is_boundary(),
+            });
         }
-    }
 
-    /// 提交更改
-
-    pub fn commit(&self, request: &CommitRequest) -> Result<GitOperationResult> {
-        // 强制优先使用Git命令
+        let total_lines = hunks.iter().map(|hunk| hunk.line_count).sum();
 
-        match self.commit_with_command(request) {
-            Ok(result) => Ok(result),
+        Ok(BlameResult {
+            file_path: request.file_path.clone(),
+            revision,
+            total_lines,
+            hunks,
+        })
+    }
 
-            Err(e) => {
-                println!("[ERROR] Git commit命令失败: {}", e);
+    /// 获取单个文件的提交历史（等价于 git log --follow），附带每次提交对该文件的差异
+    /// 作者：Evilek
+    /// 编写日期：2025-09-28
+    pub fn get_file_history(&self, file_path: &str, limit: usize) -> Result<Vec<FileHistoryEntry>> {
+        if file_path.trim().is_empty() {
+            return Err(anyhow!("文件路径不能为空"));
+        }
 
-                println!("[ERROR] 提交失败，请检查暂存区状态和提交信息");
+        match self.get_file_history_with_command(file_path, limit) {
+            Ok(entries) => Ok(entries),
 
-                // 只有在明确配置为Git2Api时才使用
+            Err(e) => {
+                error_log!("Git log --follow 命令失败: {}", e);
 
                 match self.git_method {
                     GitMethod::Git2Api => {
-                        println!("[WARN] 尝试使用Git2库API进行提交");
+                        warn_log!("尝试使用Git2库API获取文件历史");
 
-                        self.commit_with_git2_api(request)
+                        self.get_file_history_with_git2_api(file_path, limit)
                     }
 
-                    _ => Err(anyhow!("提交失败: {}。请使用Git命令行工具", e)),
+                    _ => Err(anyhow!("获取文件历史失败: {}", e)),
                 }
             }
         }
     }
 
-    /// 使用Git命令提交
-
-    fn commit_with_command(&self, request: &CommitRequest) -> Result<GitOperationResult> {
+    fn get_file_history_with_command(
+        &self,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<FileHistoryEntry>> {
         let repo_path = self
             .get_repository_path()
             .ok_or_else(|| anyhow!("仓库路径未设置"))?;
 
-        let git_command = self.get_git_command();
+        let max_count = format!("--max-count={}", limit.max(1));
+
+        let args = [
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--follow",
+            "-M",
+            "--patch",
+            "--no-color",
+            "--no-ext-diff",
+            max_count.as_str(),
+            LOG_ENTRY_FORMAT,
+            "--",
+            file_path,
+        ];
 
-        // 如果指定了文件，先暂存这些文件
+        self.run_log_with_patches(&repo_path, &args, file_path)
+    }
 
-        if !request.selected_files.is_empty() {
-            for file_path in &request.selected_files {
-                let output = Self::create_hidden_command(&git_command)
-                    .current_dir(&repo_path)
-                    .args(&["add", file_path])
-                    .output()?;
+    /// 获取指定行区间的演变历史（git log -L）
+    /// 作者：Evilek
+    /// 编写日期：2025-09-28
+    pub fn get_line_history(
+        &self,
+        file_path: &str,
+        start_line: u32,
+        end_line: u32,
+        limit: usize,
+    ) -> Result<Vec<FileHistoryEntry>> {
+        if start_line == 0 || end_line < start_line {
+            return Err(anyhow!("无效的行区间: {}-{}", start_line, end_line));
+        }
+
+        match self.get_line_history_with_command(file_path, start_line, end_line, limit) {
+            Ok(entries) => Ok(entries),
+
+            Err(e) => {
+                error_log!("Git log -L 命令失败: {}", e);
 
-                if !output.status.success() {
-                    let error_msg = String::from_utf8_lossy(&output.stderr);
+                match self.git_method {
+                    GitMethod::Git2Api => {
+                        warn_log!("尝试使用Git2库API逐级追溯行历史");
 
-                    return Err(anyhow!("暂存文件 {} 失败: {}", file_path, error_msg));
+                        self.get_line_history_with_git2_api(file_path, start_line, end_line, limit)
+                    }
+
+                    _ => Err(anyhow!("获取行历史失败: {}", e)),
                 }
             }
         }
+    }
 
-        // 构建提交命令
+    fn get_line_history_with_command(
+        &self,
+        file_path: &str,
+        start_line: u32,
+        end_line: u32,
+        limit: usize,
+    ) -> Result<Vec<FileHistoryEntry>> {
+        let repo_path = self
+            .get_repository_path()
+            .ok_or_else(|| anyhow!("仓库路径未设置"))?;
 
-        let mut args = vec!["commit", "-m", &request.message];
+        let max_count = format!("--max-count={}", limit.max(1));
 
-        if request.amend {
-            args.insert(1, "--amend");
-        }
+        let line_range = format!("-L{},{}:{}", start_line, end_line, file_path);
 
-        let output = Self::create_hidden_command(&git_command)
-            .current_dir(&repo_path)
-            .args(&args)
-            .output()?;
+        let args = [
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--no-color",
+            "--no-ext-diff",
+            max_count.as_str(),
+            LOG_ENTRY_FORMAT,
+            line_range.as_str(),
+        ];
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+        self.run_log_with_patches(&repo_path, &args, file_path)
+    }
 
-            Ok(GitOperationResult {
-                success: true,
+    /// 执行带补丁输出的 git log，并解析为文件历史条目
+    fn run_log_with_patches(
+        &self,
+        repo_path: &str,
+        args: &[&str],
+        file_path: &str,
+    ) -> Result<Vec<FileHistoryEntry>> {
+        let git_command = self.get_git_command();
 
-                message: "提交成功".to_string(),
+        let output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(args)
+            .output_with(&self.process_runner())?;
 
-                details: Some(stdout.to_string()),
-            })
-        } else {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
 
-            Err(anyhow!("提交失败: {}", error_msg))
+            return Err(anyhow!("git log 执行失败: {}", stderr.trim()));
         }
-    }
-
-    /// 使用Git2库API提交（备选方案）
 
-    fn commit_with_git2_api(&self, request: &CommitRequest) -> Result<GitOperationResult> {
-        let repo = self.get_repository()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
 
-        let mut index = repo.index()?;
+        Ok(Self::parse_log_entries_with_patches(&stdout, file_path))
+    }
 
-        // 如果指定了文件，先暂存这些文件
+    /// 解析 LOG_ENTRY_FORMAT 格式的 git log 输出（每条记录后跟随补丁）
+    fn parse_log_entries_with_patches(output: &str, file_path: &str) -> Vec<FileHistoryEntry> {
+        let mut entries = Vec::new();
 
-        if !request.selected_files.is_empty() {
-            for file_path in &request.selected_files {
-                index.add_path(Path::new(file_path))?;
+        for record in output.split('\x1e') {
+            if record.trim().is_empty() {
+                continue;
             }
 
-            index.write()?;
-        }
+            let parts: Vec<&str> = record.splitn(7, '\x1f').collect();
 
-        // 创建提交
+            if parts.len() < 7 {
+                continue;
+            }
 
-        let tree_id = index.write_tree()?;
+            let patch_files = parse_patch_files(parts[6]);
 
-        let tree = repo.find_tree(tree_id)?;
+            // git log -L / --follow 在一次提交中只会输出目标文件，取第一个即可
+            let patch = patch_files.into_iter().next();
 
-        // 获取签名
+            let (path, old_path, change_type, insertions, deletions, hunks) = match patch {
+                Some(patch) => {
+                    let old_path = if patch.old_path != patch.new_path {
+                        Some(patch.old_path.clone())
+                    } else {
+                        None
+                    };
 
-        let signature = self.get_signature(&repo)?;
+                    let path = if matches!(patch.change_type, FileChangeType::Deleted) {
+                        patch.old_path.clone()
+                    } else {
+                        patch.new_path.clone()
+                    };
 
-        // 获取父提交
+                    (
+                        path,
+                        old_path,
+                        patch.change_type,
+                        patch.insertions,
+                        patch.deletions,
+                        patch.hunks,
+                    )
+                }
 
-        let parent_commit = if let Ok(head) = repo.head() {
-            if let Some(target) = head.target() {
-                Some(repo.find_commit(target)?)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+                None => (
+                    file_path.to_string(),
+                    None,
+                    FileChangeType::Modified,
+                    0,
+                    0,
+                    Vec::new(),
+                ),
+            };
 
-        // 创建提交
+            entries.push(FileHistoryEntry {
+                commit: CommitInfo {
+                    hash: parts[0].trim().to_string(),
+                    short_hash: parts[1].trim().to_string(),
+                    author: parts[2].to_string(),
+                    email: parts[3].to_string(),
+                    timestamp: parts[4].trim().parse().unwrap_or(0),
+                    message: parts[5].trim().to_string(),
+                    files_changed: vec![path.clone()],
+                },
+                path,
+                old_path,
+                change_type,
+                insertions,
+                deletions,
+                hunks,
+            });
+        }
 
-        let commit_id = if let Some(parent) = parent_commit {
-            if request.amend {
-                // 修正上次提交
+        entries
+    }
 
-                repo.commit(
-                    Some("HEAD"),
-                    &signature,
-                    &signature,
-                    &request.message,
-                    &tree,
-                    &[&parent],
-                )?
-            } else {
-                // 普通提交
+    /// 使用Git2库API获取文件历史：逐个提交与首个父提交比较，遇到新增时检测重命名来源
+    fn get_file_history_with_git2_api(
+        &self,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<FileHistoryEntry>> {
+        let repo = self.get_repository()?;
 
-                repo.commit(
-                    Some("HEAD"),
-                    &signature,
-                    &signature,
-                    &request.message,
-                    &tree,
-                    &[&parent],
-                )?
-            }
-        } else {
-            // 初始提交
+        let mut revwalk = repo.revwalk()?;
 
-            repo.commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                &request.message,
-                &tree,
-                &[],
-            )?
-        };
+        revwalk.push_head()?;
 
-        Ok(GitOperationResult {
-            success: true,
+        revwalk.set_sorting(git2::Sort::TIME | git2::Sort::TOPOLOGICAL)?;
 
-            message: format!("Commit created: {}", commit_id),
+        let mut current_path = file_path.to_string();
 
-            details: Some(request.message.clone()),
-        })
-    }
+        let mut entries = Vec::new();
 
-    /// 获取Git签名
+        for oid in revwalk {
+            if entries.len() >= limit.max(1) {
+                break;
+            }
 
-    fn get_signature(&self, repo: &Repository) -> Result<Signature<'_>> {
-        // 尝试从配置获取用户信息
+            let commit = repo.find_commit(oid?)?;
 
-        let config = repo.config()?;
+            let tree = commit.tree()?;
 
-        let name = config
-            .get_string("user.name")
-            .unwrap_or_else(|_| "GitMentor User".to_string());
+            let parent_tree = match commit.parents().next() {
+                Some(parent) => Some(parent.tree()?),
+                None => None,
+            };
 
-        let email = config
-            .get_string("user.email")
-            .unwrap_or_else(|_| "user@gitmentor.local".to_string());
+            let mut diff_options = DiffOptions::new();
 
-        Ok(Signature::now(&name, &email)?)
-    }
+            diff_options.pathspec(&current_path).context_lines(3);
 
-    /// 回滚文件更改
+            let diff =
+                repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_options))?;
 
-    pub fn revert_files(&self, request: &RevertRequest) -> Result<GitOperationResult> {
-        let repo = self.get_repository()?;
+            if diff.deltas().len() == 0 {
+                continue;
+            }
 
-        match request.revert_type {
-            RevertType::WorkingTree => {
-                // 回滚工作区更改到HEAD状态
+            let mut delta_status = diff.get_delta(0).map(|delta| delta.status());
 
-                let mut checkout_builder = git2::build::CheckoutBuilder::new();
+            let mut old_path = None;
 
-                checkout_builder.force();
+            let mut diff = diff;
 
-                for file_path in &request.file_paths {
-                    checkout_builder.path(file_path);
-                }
+            // 新增时做一次全量相似度检测，确认是否由其他路径重命名而来
+            if delta_status == Some(git2::Delta::Added) && parent_tree.is_some() {
+                let mut full_diff =
+                    repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
 
-                // 从HEAD检出文件
+                let mut find_options = git2::DiffFindOptions::new();
 
-                let head = repo.head()?;
+                find_options.renames(true);
 
-                let head_commit = head.peel_to_commit()?;
+                full_diff.find_similar(Some(&mut find_options))?;
 
-                let head_tree = head_commit.tree()?;
+                let rename_source = full_diff.deltas().find_map(|delta| {
+                    let new_path = delta.new_file().path()?.to_string_lossy().to_string();
 
-                repo.checkout_tree(head_tree.as_object(), Some(&mut checkout_builder))?;
+                    if delta.status() == git2::Delta::Renamed && new_path == current_path {
+                        delta
+                            .old_file()
+                            .path()
+                            .map(|p| p.to_string_lossy().to_string())
+                    } else {
+                        None
+                    }
+                });
 
-                Ok(GitOperationResult {
-                    success: true,
+                if let Some(source) = rename_source {
+                    let mut rename_options = DiffOptions::new();
 
-                    message: format!(
-                        "Reverted {} file(s) in working tree",
-                        request.file_paths.len()
-                    ),
+                    rename_options
+                        .pathspec(&source)
+                        .pathspec(&current_path)
+                        .context_lines(3);
 
-                    details: None,
-                })
-            }
+                    let mut rename_diff = repo.diff_tree_to_tree(
+                        parent_tree.as_ref(),
+                        Some(&tree),
+                        Some(&mut rename_options),
+                    )?;
 
-            RevertType::Staged => {
-                // 回滚暂存区更改 - 将暂存区文件重置到HEAD状态，但保留工作区更改
+                    rename_diff.find_similar(Some(&mut find_options))?;
 
-                let head = repo.head()?;
+                    diff = rename_diff;
 
-                let head_commit = head.peel_to_commit()?;
+                    delta_status = Some(git2::Delta::Renamed);
 
-                // 将指定文件从暂存区重置到HEAD状态（取消暂存）
+                    old_path = Some(source);
+                }
+            }
 
-                repo.reset_default(Some(head_commit.as_object()), request.file_paths.iter())?;
+            let (hunks, insertions, deletions) = collect_patch_hunks(&diff)?;
 
-                Ok(GitOperationResult {
-                    success: true,
+            let change_type = match delta_status {
+                Some(git2::Delta::Added) => FileChangeType::Added,
+                Some(git2::Delta::Deleted) => FileChangeType::Deleted,
+                Some(git2::Delta::Renamed) => FileChangeType::Renamed,
+                Some(git2::Delta::Copied) => FileChangeType::Copied,
+                _ => FileChangeType::Modified,
+            };
 
-                    message: format!(
-                        "Unstaged {} file(s) from staging area",
-                        request.file_paths.len()
-                    ),
+            let path = current_path.clone();
 
-                    details: None,
-                })
-            }
+            let hash = commit.id().to_string();
 
-            RevertType::DiscardAll => {
-                // 撤销所有更改 - 先取消暂存，再回滚工作区到HEAD状态
+            entries.push(FileHistoryEntry {
+                commit: CommitInfo {
+                    short_hash: hash.chars().take(8).collect(),
+                    hash,
+                    message: commit.message().unwrap_or("").trim().to_string(),
+                    author: commit.author().name().unwrap_or("").to_string(),
+                    email: commit.author().email().unwrap_or("").to_string(),
+                    timestamp: commit.time().seconds(),
+                    files_changed: vec![path.clone()],
+                },
+                path,
+                old_path: old_path.clone(),
+                change_type: change_type.clone(),
+                insertions,
+                deletions,
+                hunks,
+            });
 
-                let head = repo.head()?;
+            match (change_type, old_path) {
+                (FileChangeType::Renamed, Some(source)) => current_path = source,
+                (FileChangeType::Added, _) => break,
+                _ => {}
+            }
+        }
 
-                let head_commit = head.peel_to_commit()?;
+        Ok(entries)
+    }
 
-                let head_tree = head_commit.tree()?;
+    /// 使用Git2库API逐级追溯行历史：blame 出最后修改提交后，在其父提交上继续追溯原始行
+    fn get_line_history_with_git2_api(
+        &self,
+        file_path: &str,
+        start_line: u32,
+        end_line: u32,
+        limit: usize,
+    ) -> Result<Vec<FileHistoryEntry>> {
+        let repo = self.get_repository()?;
 
-                // 1. 先取消暂存（重置索引到HEAD）
+        let mut entries: Vec<FileHistoryEntry> = Vec::new();
 
-                repo.reset_default(Some(head_commit.as_object()), request.file_paths.iter())?;
+        let mut seen = std::collections::HashSet::new();
 
-                // 2. 再回滚工作区到HEAD状态
+        let mut revision = "HEAD".to_string();
 
-                let mut checkout_builder = git2::build::CheckoutBuilder::new();
+        let mut path = file_path.to_string();
 
-                checkout_builder.force();
+        let (mut start, mut end) = (start_line, end_line);
 
-                for file_path in &request.file_paths {
-                    checkout_builder.path(file_path);
-                }
+        while entries.len() < limit.max(1) {
+            let blame = self.blame_with_git2_api(&BlameRequest {
+                file_path: path.clone(),
+                revision: Some(revision.clone()),
+                start_line: Some(start),
+                end_line: Some(end),
+            })?;
 
-                repo.checkout_tree(head_tree.as_object(), Some(&mut checkout_builder))?;
+            // 取区间内最新的一次修改，继续向前追溯
+            let newest = match blame
+                .hunks
+                .iter()
+                .filter(|hunk| !seen.contains(&hunk.commit_hash))
+                .max_by_key(|hunk| hunk.timestamp)
+            {
+                Some(hunk) => hunk.clone(),
+                None => break,
+            };
 
-                Ok(GitOperationResult {
-                    success: true,
+            seen.insert(newest.commit_hash.clone());
 
-                    message: format!(
-                        "Discarded all changes for {} file(s)",
-                        request.file_paths.len()
-                    ),
+            let commit = repo.find_commit(git2::Oid::from_str(&newest.commit_hash)?)?;
 
-                    details: None,
-                })
+            if let Some(entry) =
+                self.get_file_history_with_git2_api_at(&repo, &commit, &newest.original_path)?
+            {
+                entries.push(entry);
             }
 
-            RevertType::Commit => {
-                // 回滚提交（简化实现）
-
-                Ok(GitOperationResult {
-                    success: false,
-
-                    message: "Commit revert not implemented in MVP".to_string(),
-
-                    details: Some("This feature will be available in future versions".to_string()),
-                })
+            if newest.is_boundary || commit.parent_count() == 0 {
+                break;
             }
-        }
-    }
 
-    /// 获取提交历史
+            revision = format!("{}^", newest.commit_hash);
 
-    pub fn get_commit_history(&self, limit: usize) -> Result<Vec<CommitInfo>> {
-        let repo = self.get_repository()?;
+            path = newest.original_path.clone();
 
-        let mut revwalk = repo.revwalk()?;
+            start = newest.original_start_line.max(1);
 
-        revwalk.push_head()?;
+            end = start + newest.line_count.saturating_sub(1);
+        }
 
-        revwalk.set_sorting(git2::Sort::TIME)?;
+        Ok(entries)
+    }
 
-        let mut commits = Vec::new();
+    /// 构建单个提交对指定文件的历史条目（Git2 方式）
+    fn get_file_history_with_git2_api_at(
+        &self,
+        repo: &Repository,
+        commit: &git2::Commit,
+        path: &str,
+    ) -> Result<Option<FileHistoryEntry>> {
+        let tree = commit.tree()?;
+
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
 
-        for (i, oid) in revwalk.enumerate() {
-            if i >= limit {
-                break;
-            }
+        let mut diff_options = DiffOptions::new();
 
-            let oid = oid?;
+        diff_options.pathspec(path).context_lines(3);
 
-            let commit = repo.find_commit(oid)?;
+        let diff =
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_options))?;
 
-            let commit_info = CommitInfo {
-                hash: oid.to_string(),
+        let change_type = match diff.get_delta(0).map(|delta| delta.status()) {
+            Some(git2::Delta::Added) => FileChangeType::Added,
+            Some(git2::Delta::Deleted) => FileChangeType::Deleted,
+            Some(_) => FileChangeType::Modified,
+            None => return Ok(None),
+        };
 
-                short_hash: oid.to_string()[..8].to_string(),
+        let (hunks, insertions, deletions) = collect_patch_hunks(&diff)?;
 
-                message: commit.message().unwrap_or("").to_string(),
+        let hash = commit.id().to_string();
 
+        Ok(Some(FileHistoryEntry {
+            commit: CommitInfo {
+                short_hash: hash.chars().take(8).collect(),
+                hash,
+                message: commit.message().unwrap_or("").trim().to_string(),
                 author: commit.author().name().unwrap_or("").to_string(),
-
                 email: commit.author().email().unwrap_or("").to_string(),
-
                 timestamp: commit.time().seconds(),
+                files_changed: vec![path.to_string()],
+            },
+            path: path.to_string(),
+            old_path: None,
+            change_type,
+            insertions,
+            deletions,
+            hunks,
+        }))
+    }
 
-                files_changed: Vec::new(), // 简化实现
-            };
-
-            commits.push(commit_info);
+    fn format_line_range(start_line: Option<u32>, end_line: Option<u32>) -> Option<String> {
+        match (start_line, end_line) {
+            (Some(start), Some(end)) => Some(format!("{},{}", start.max(1), end.max(start))),
+            (Some(start), None) => Some(format!("{},", start.max(1))),
+            (None, Some(end)) => Some(format!("1,{}", end.max(1))),
+            (None, None) => None,
         }
-
-        Ok(commits)
     }
 
     /// 获取分支列表
@@ -3950,15 +6222,15 @@ impl GitEngine {
             Ok(result) => Ok(result),
 
             Err(e) => {
-                println!("[ERROR] Git checkout命令失败: {}", e);
+                error_log!("Git checkout命令失败: {}", e);
 
-                println!("[ERROR] 分支切换失败，请检查分支名称和Git状态");
+                error_log!("分支切换失败，请检查分支名称和Git状态");
 
                 // 只有在明确配置为Git2Api时才使用
 
                 match self.git_method {
                     GitMethod::Git2Api => {
-                        println!("[WARN] 尝试使用Git2库API进行分支切换");
+                        warn_log!("尝试使用Git2库API进行分支切换");
 
                         self.checkout_branch_with_git2_api(branch_name, is_remote)
                     }
@@ -3995,7 +6267,7 @@ impl GitEngine {
             let output = Self::create_hidden_command(&git_command)
                 .current_dir(&repo_path)
                 .args(&["checkout", "-b", local_branch_name, branch_name])
-                .output()?;
+                .output_with(&self.process_runner())?;
 
             if output.status.success() {
                 Ok(GitOperationResult {
@@ -4019,7 +6291,7 @@ impl GitEngine {
             let output = Self::create_hidden_command(&git_command)
                 .current_dir(&repo_path)
                 .args(&["checkout", branch_name])
-                .output()?;
+                .output_with(&self.process_runner())?;
 
             if output.status.success() {
                 Ok(GitOperationResult {
@@ -4120,15 +6392,15 @@ impl GitEngine {
             Ok(result) => Ok(result),
 
             Err(e) => {
-                println!("[ERROR] Git pull命令失败: {}", e);
+                error_log!("Git pull命令失败: {}", e);
 
-                println!("[ERROR] 拉取失败，可能存在合并冲突或网络问题");
+                error_log!("拉取失败，可能存在合并冲突或网络问题");
 
                 // 只有在明确配置为Git2Api时才使用
 
                 match self.git_method {
                     GitMethod::Git2Api => {
-                        println!("[WARN] 尝试使用Git2库API进行拉取（功能有限）");
+                        warn_log!("尝试使用Git2库API进行拉取");
 
                         self.pull_with_git2_api()
                     }
@@ -4151,7 +6423,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(&repo_path)
             .args(&["pull"])
-            .output()?;
+            .output_with(&self.process_runner())?;
 
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -4170,95 +6442,395 @@ impl GitEngine {
         }
     }
 
-    /// 使用Git2库API拉取
-
+    /// 使用Git2库API拉取：读取上游配置，fetch 后按 pull.rebase / pull.ff 快进、合并或变基
+    /// 作者：Evilek
+    /// 编写日期：2025-10-02
     fn pull_with_git2_api(&self) -> Result<GitOperationResult> {
         let repo = self.get_repository()?;
 
-        // 第一步：Fetch
-
-        let remote_name = Self::resolve_default_remote(&repo)
-            .ok_or_else(|| anyhow!("当前仓库未配置远程仓库，请先添加远程。"))?;
-
-        let mut remote = repo.find_remote(&remote_name).or_else(|_| {
-            let remotes = repo.remotes()?;
+        let head = repo.head()?;
 
-            if let Some(remote_name) = remotes.get(0) {
-                repo.find_remote(remote_name)
-            } else {
-                Err(git2::Error::from_str("没有找到远程仓库"))
-            }
-        })?;
+        if !head.is_branch() {
+            return Err(anyhow!("当前处于分离HEAD状态，无法拉取"));
+        }
 
-        // 设置fetch回调
+        let branch_ref = head.name().unwrap_or("").to_string();
 
-        let mut callbacks = git2::RemoteCallbacks::new();
+        let branch_name = head.shorthand().unwrap_or("HEAD").to_string();
 
-        callbacks.credentials(|_url, username_from_url, _allowed_types| {
-            if let Some(username) = username_from_url {
-                git2::Cred::ssh_key_from_agent(username)
-            } else {
-                git2::Cred::default()
-            }
-        });
+        // 第一步：解析上游（branch.<name>.remote / branch.<name>.merge），未配置时沿用默认远程的同名分支
 
-        let mut fetch_options = git2::FetchOptions::new();
+        let (remote_name, upstream_ref_name) = Self::resolve_pull_upstream(&repo, &branch_ref)?;
 
-        fetch_options.remote_callbacks(callbacks);
+        // 第二步：Fetch（上游为本地分支时跳过）
 
-        // 执行fetch
+        let mut remote_url = ".".to_string();
 
-        remote.fetch(&[] as &[&str], Some(&mut fetch_options), None)?;
+        if remote_name != "." {
+            let mut remote = repo.find_remote(&remote_name)?;
 
-        // 第二步：Merge
+            remote_url = remote.url().unwrap_or("").to_string();
 
-        let head = repo.head()?;
+            let provider = self.credential_provider(Some(&repo), &remote_url);
 
-        let branch_name = head.shorthand().unwrap_or("HEAD");
+            let mut fetch_options = git2::FetchOptions::new();
 
-        let upstream_branch = format!("refs/remotes/{}/{}", remote_name, branch_name);
+            fetch_options.remote_callbacks(provider.remote_callbacks());
 
-        // 检查是否有upstream分支
+            remote
+                .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+                .map_err(|e| anyhow::Error::new(provider.classify_error(&e)))?;
+        }
 
-        let upstream_ref = repo.find_reference(&upstream_branch)?;
+        let upstream_ref = repo.find_reference(&upstream_ref_name).map_err(|_| {
+            anyhow!(
+                "上游分支 {} 不存在，请确认远程分支是否已被删除",
+                upstream_ref_name
+            )
+        })?;
 
         let upstream_commit = repo.reference_to_annotated_commit(&upstream_ref)?;
 
-        // 执行merge分析
+        let settings = Self::read_pull_settings(&repo, &branch_name)?;
 
-        let analysis = repo.merge_analysis(&[&upstream_commit])?;
+        // 第三步：分析并合并
 
-        if analysis.0.is_up_to_date() {
-            Ok(GitOperationResult {
+        let (analysis, _) = repo.merge_analysis(&[&upstream_commit])?;
+
+        if analysis.is_up_to_date() {
+            return Ok(GitOperationResult {
                 success: true,
 
                 message: "当前分支已是最新".to_string(),
 
                 details: Some("无需拉取".to_string()),
-            })
-        } else if analysis.0.is_fast_forward() {
-            // 快进合并
-
-            let mut reference = repo.find_reference(&format!("refs/heads/{}", branch_name))?;
-
-            reference.set_target(upstream_commit.id(), "Fast-forward merge")?;
+            });
+        }
 
-            repo.set_head(&format!("refs/heads/{}", branch_name))?;
+        let upstream_short = upstream_ref
+            .shorthand()
+            .unwrap_or(&upstream_ref_name)
+            .to_string();
 
-            repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+        if analysis.is_fast_forward()
+            && (settings.rebase || settings.fast_forward != PullFastForward::Never)
+        {
+            self.fast_forward_branch(&repo, &branch_ref, upstream_commit.id())?;
 
-            Ok(GitOperationResult {
+            return Ok(GitOperationResult {
                 success: true,
 
                 message: format!("成功快进合并分支 {}", branch_name),
 
-                details: Some("执行了快进合并".to_string()),
+                details: Some(format!("已快进到 {}", upstream_short)),
+            });
+        }
+
+        if settings.rebase {
+            return self.rebase_onto_upstream(
+                &repo,
+                &branch_name,
+                &upstream_commit,
+                &upstream_short,
+            );
+        }
+
+        if settings.fast_forward == PullFastForward::Only {
+            return Err(anyhow::Error::new(GitError::ConfigurationError(format!(
+                "分支 {} 与上游 {} 已分叉，pull.ff=only 不允许非快进合并，请改用变基或合并",
+                branch_name, upstream_short
+            ))));
+        }
+
+        let upstream_branch_name = upstream_ref_name
+            .strip_prefix("refs/heads/")
+            .map(|name| name.to_string())
+            .or_else(|| {
+                repo.config()
+                    .ok()?
+                    .get_string(&format!("branch.{}.merge", branch_name))
+                    .ok()
+                    .map(|merge| merge.trim_start_matches("refs/heads/").to_string())
             })
+            .unwrap_or_else(|| branch_name.clone());
+
+        let message = if remote_name == "." {
+            format!(
+                "Merge branch '{}' into {}",
+                upstream_branch_name, branch_name
+            )
         } else {
-            // 需要普通合并，这比较复杂，建议使用Git命令
+            format!("Merge branch '{}' of {}", upstream_branch_name, remote_url)
+        };
+
+        self.merge_upstream(&repo, &upstream_commit, &message, &upstream_short)
+    }
+
+    /// 解析拉取的上游：返回 (远程名, 上游引用全名)
+    fn resolve_pull_upstream(repo: &Repository, branch_ref: &str) -> Result<(String, String)> {
+        if let (Ok(remote), Ok(upstream)) = (
+            repo.branch_upstream_remote(branch_ref),
+            repo.branch_upstream_name(branch_ref),
+        ) {
+            if let (Some(remote), Some(upstream)) = (remote.as_str(), upstream.as_str()) {
+                return Ok((remote.to_string(), upstream.to_string()));
+            }
+        }
+
+        let branch_name = branch_ref.trim_start_matches("refs/heads/");
+
+        let remote_name = Self::resolve_default_remote(repo)
+            .ok_or_else(|| anyhow!("当前仓库未配置远程仓库，请先添加远程。"))?;
+
+        warn_log!(
+            "分支 {} 未配置上游，使用 {}/{}",
+            branch_name,
+            remote_name,
+            branch_name
+        );
+
+        Ok((
+            remote_name.clone(),
+            format!("refs/remotes/{}/{}", remote_name, branch_name),
+        ))
+    }
+
+    /// 读取 branch.<name>.rebase / pull.rebase 与 pull.ff
+    fn read_pull_settings(repo: &Repository, branch_name: &str) -> Result<PullSettings> {
+        let config = repo.config()?;
+
+        let parse_rebase = |key: &str| -> Option<bool> {
+            let value = config.get_string(key).ok()?;
+
+            match value.trim().to_lowercase().as_str() {
+                "false" | "no" | "off" | "0" => Some(false),
+                // true / merges / interactive 都按变基处理
+                _ => Some(true),
+            }
+        };
+
+        let rebase = parse_rebase(&format!("branch.{}.rebase", branch_name))
+            .or_else(|| parse_rebase("pull.rebase"))
+            .unwrap_or(false);
+
+        let fast_forward = match config.get_string("pull.ff") {
+            Ok(value) => match value.trim().to_lowercase().as_str() {
+                "only" => PullFastForward::Only,
+                "false" | "no" | "off" | "0" => PullFastForward::Never,
+                _ => PullFastForward::Allow,
+            },
+            Err(_) => PullFastForward::Allow,
+        };
+
+        Ok(PullSettings {
+            rebase,
+            fast_forward,
+        })
+    }
+
+    /// 快进当前分支：先安全检出目标树（不覆盖本地修改），再移动分支引用
+    fn fast_forward_branch(
+        &self,
+        repo: &Repository,
+        branch_ref: &str,
+        target: git2::Oid,
+    ) -> Result<()> {
+        let target_commit = repo.find_commit(target)?;
+
+        let mut checkout = git2::build::CheckoutBuilder::new();
+
+        checkout.safe();
+
+        repo.checkout_tree(target_commit.as_object(), Some(&mut checkout))
+            .map_err(|e| anyhow!("快进失败，本地修改会被覆盖，请先提交或贮藏: {}", e))?;
+
+        let mut reference = repo.find_reference(branch_ref)?;
+
+        reference.set_target(target, "pull: Fast-forward")?;
+
+        repo.set_head(branch_ref)?;
+
+        Ok(())
+    }
+
+    /// 合并上游并生成合并提交；出现冲突时保留冲突状态（与 git pull 一致），由用户解决后提交
+    fn merge_upstream(
+        &self,
+        repo: &Repository,
+        upstream_commit: &git2::AnnotatedCommit,
+        message: &str,
+        upstream_short: &str,
+    ) -> Result<GitOperationResult> {
+        let signature = repo.signature().map_err(|_| {
+            anyhow::Error::new(GitError::ConfigurationError(
+                "未配置 user.name / user.email，无法创建合并提交".to_string(),
+            ))
+        })?;
+
+        let mut merge_options = git2::MergeOptions::new();
+
+        let mut checkout = git2::build::CheckoutBuilder::new();
+
+        checkout
+            .safe()
+            .allow_conflicts(true)
+            .conflict_style_merge(true);
+
+        repo.merge(
+            &[upstream_commit],
+            Some(&mut merge_options),
+            Some(&mut checkout),
+        )
+        .map_err(|e| anyhow!("合并失败，本地修改可能会被覆盖，请先提交或贮藏: {}", e))?;
+
+        let mut index = repo.index()?;
+
+        if index.has_conflicts() {
+            let conflicts = Self::collect_conflicted_paths(&index)?;
+
+            return Err(anyhow!(
+                "合并 {} 时出现冲突，请解决以下文件的冲突后提交：\n{}",
+                upstream_short,
+                conflicts.join("\n")
+            ));
+        }
 
-            Err(anyhow!("需要合并提交，建议使用Git命令执行pull操作"))
+        let tree = repo.find_tree(index.write_tree()?)?;
+
+        let head_commit = repo.head()?.peel_to_commit()?;
+
+        let their_commit = repo.find_commit(upstream_commit.id())?;
+
+        let merge_commit = repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &[&head_commit, &their_commit],
+        )?;
+
+        repo.cleanup_state()?;
+
+        Ok(GitOperationResult {
+            success: true,
+
+            message: format!("成功合并 {}", upstream_short),
+
+            details: Some(format!("合并提交: {}", &merge_commit.to_string()[..8])),
+        })
+    }
+
+    /// 将本地提交变基到上游；冲突时中止变基并恢复原状态
+    fn rebase_onto_upstream(
+        &self,
+        repo: &Repository,
+        branch_name: &str,
+        upstream_commit: &git2::AnnotatedCommit,
+        upstream_short: &str,
+    ) -> Result<GitOperationResult> {
+        let signature = repo.signature().map_err(|_| {
+            anyhow::Error::new(GitError::ConfigurationError(
+                "未配置 user.name / user.email，无法执行变基".to_string(),
+            ))
+        })?;
+
+        let mut status_options = StatusOptions::new();
+
+        status_options.include_untracked(false);
+
+        if !repo.statuses(Some(&mut status_options))?.is_empty() {
+            return Err(anyhow!("存在未提交的更改，无法变基，请先提交或贮藏"));
+        }
+
+        let head = repo.reference_to_annotated_commit(&repo.head()?)?;
+
+        let mut rebase_options = git2::RebaseOptions::new();
+
+        let mut rebase = repo.rebase(
+            Some(&head),
+            Some(upstream_commit),
+            None,
+            Some(&mut rebase_options),
+        )?;
+
+        let mut applied = 0;
+
+        let mut skipped = 0;
+
+        while let Some(operation) = rebase.next() {
+            let operation = match operation {
+                Ok(operation) => operation,
+                Err(e) => {
+                    rebase.abort()?;
+
+                    return Err(anyhow!("变基失败，已中止: {}", e));
+                }
+            };
+
+            let index = repo.index()?;
+
+            if index.has_conflicts() {
+                let conflicts = Self::collect_conflicted_paths(&index)?;
+
+                let commit_id = operation.id().to_string();
+
+                rebase.abort()?;
+
+                return Err(anyhow!(
+                    "变基到 {} 时提交 {} 出现冲突，已中止变基：\n{}",
+                    upstream_short,
+                    &commit_id[..8],
+                    conflicts.join("\n")
+                ));
+            }
+
+            match rebase.commit(None, &signature, None) {
+                Ok(_) => applied += 1,
+
+                // 上游已包含相同修改，跳过空提交
+                Err(e) if e.code() == git2::ErrorCode::Applied => skipped += 1,
+
+                Err(e) => {
+                    rebase.abort()?;
+
+                    return Err(anyhow!("变基提交失败，已中止: {}", e));
+                }
+            }
+        }
+
+        rebase.finish(Some(&signature))?;
+
+        Ok(GitOperationResult {
+            success: true,
+
+            message: format!("成功将分支 {} 变基到 {}", branch_name, upstream_short),
+
+            details: Some(if skipped > 0 {
+                format!(
+                    "重放 {} 个提交，跳过 {} 个已存在于上游的提交",
+                    applied, skipped
+                )
+            } else {
+                format!("重放 {} 个提交", applied)
+            }),
+        })
+    }
+
+    fn collect_conflicted_paths(index: &git2::Index) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+
+            let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+
+            if let Some(entry) = entry {
+                paths.push(String::from_utf8_lossy(&entry.path).to_string());
+            }
         }
+
+        Ok(paths)
     }
 
     /// 推送当前分支
@@ -4274,15 +6846,15 @@ impl GitEngine {
             Ok(result) => Ok(result),
 
             Err(e) => {
-                println!("[ERROR] Git push命令失败: {}", e);
+                error_log!("Git push命令失败: {}", e);
 
-                println!("[ERROR] 推送失败，可能需要先拉取或存在权限问题");
+                error_log!("推送失败，可能需要先拉取或存在权限问题");
 
                 // 只有在明确配置为Git2Api时才使用
 
                 match self.git_method {
                     GitMethod::Git2Api => {
-                        println!("[WARN] 尝试使用Git2库API进行推送（需要正确的认证配置）");
+                        warn_log!("尝试使用Git2库API进行推送（需要正确的认证配置）");
 
                         self.push_with_git2_api(force)
                     }
@@ -4311,7 +6883,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(&repo_path)
             .args(&args)
-            .output()?;
+            .output_with(&self.process_runner())?;
 
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -4378,35 +6950,27 @@ impl GitEngine {
             format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name)
         };
 
-        // 设置推送选项和回调
-
-        let mut push_options = git2::PushOptions::new();
+        // 设置认证回调（凭据与主机密钥校验）
 
-        // 设置认证回调
+        let remote_url = remote.url().unwrap_or("").to_string();
 
-        let mut callbacks = git2::RemoteCallbacks::new();
+        let provider = self.credential_provider(Some(&repo), &remote_url);
 
-        callbacks.credentials(|_url, username_from_url, _allowed_types| {
-            // 尝试使用SSH密钥
+        // 设置推送选项和回调
 
-            if let Some(username) = username_from_url {
-                git2::Cred::ssh_key_from_agent(username)
-            } else {
-                // 尝试使用默认凭据
+        let mut push_options = git2::PushOptions::new();
 
-                git2::Cred::default()
-            }
-        });
+        let mut callbacks = provider.remote_callbacks();
 
         // 设置推送进度回调
 
         callbacks.push_update_reference(|refname, status| {
             if let Some(msg) = status {
-                println!("推送更新失败 {}: {}", refname, msg);
+                info_log!("推送更新失败 {}: {}", refname, msg);
 
                 Err(git2::Error::from_str("推送更新失败"))
             } else {
-                println!("推送更新成功: {}", refname);
+                info_log!("推送更新成功: {}", refname);
 
                 Ok(())
             }
@@ -4430,11 +6994,13 @@ impl GitEngine {
             }),
 
             Err(e) => {
-                // 如果Git2推送失败，提供更详细的错误信息
+                // 认证/主机密钥问题返回配置错误，其余提供更详细的错误信息
 
-                let error_msg = format!("Git2推送失败: {}。建议使用系统Git命令进行推送", e);
+                match provider.classify_error(&e) {
+                    error @ GitError::ConfigurationError(_) => Err(anyhow::Error::new(error)),
 
-                Err(anyhow!(error_msg))
+                    _ => Err(anyhow!("Git2推送失败: {}。建议使用系统Git命令进行推送", e)),
+                }
             }
         }
     }
@@ -4452,15 +7018,15 @@ impl GitEngine {
             Ok(result) => Ok(result),
 
             Err(e) => {
-                println!("[ERROR] Git fetch命令失败: {}", e);
+                error_log!("Git fetch命令失败: {}", e);
 
-                println!("[ERROR] 获取远程更新失败，请检查网络连接");
+                error_log!("获取远程更新失败，请检查网络连接");
 
                 // 只有在明确配置为Git2Api时才使用
 
                 match self.git_method {
                     GitMethod::Git2Api => {
-                        println!("[WARN] 尝试使用Git2库API进行fetch");
+                        warn_log!("尝试使用Git2库API进行fetch");
 
                         self.fetch_with_git2_api(remote_name)
                     }
@@ -4489,7 +7055,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(&repo_path)
             .args(&args)
-            .output()?;
+            .output_with(&self.process_runner())?;
 
         if output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -4522,7 +7088,17 @@ impl GitEngine {
 
         let mut remote = repo.find_remote(&remote_name)?;
 
-        remote.fetch(&[] as &[&str], None, None)?;
+        let remote_url = remote.url().unwrap_or("").to_string();
+
+        let provider = self.credential_provider(Some(&repo), &remote_url);
+
+        let mut fetch_options = git2::FetchOptions::new();
+
+        fetch_options.remote_callbacks(provider.remote_callbacks());
+
+        remote
+            .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+            .map_err(|e| anyhow::Error::new(provider.classify_error(&e)))?;
 
         Ok(GitOperationResult {
             success: true,
@@ -4672,6 +7248,150 @@ impl GitEngine {
         Ok(diff_output)
     }
 
+    /// 收集渲染提交模板所需的仓库信息：暂存文件状态与增删行数、分支与上游、最近提交、语言占比、作者
+    /// file_paths 为空时取全部暂存文件
+    /// 作者：Evilek
+    /// 编写日期：2025-10-01
+    pub fn get_commit_template_facts(
+        &self,
+        file_paths: &[String],
+        recent_limit: usize,
+    ) -> Result<CommitTemplateFacts> {
+        // 语言占比只列出前几名，其余对提示词没有意义
+        const MAX_LANGUAGES: usize = 5;
+
+        let repo = self.get_repository()?;
+
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree()?),
+            Err(_) => None, // 尚无提交的新仓库
+        };
+
+        let mut diff_options = DiffOptions::new();
+        for file_path in file_paths {
+            diff_options.pathspec(file_path);
+        }
+
+        let index = repo.index()?;
+        let mut diff =
+            repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), Some(&mut diff_options))?;
+        diff.find_similar(None)?;
+
+        let mut files = Vec::new();
+        for (idx, delta) in diff.deltas().enumerate() {
+            let path_of = |file: git2::DiffFile| {
+                file.path()
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default()
+            };
+
+            let status = match delta.status() {
+                git2::Delta::Added => "added",
+                git2::Delta::Deleted => "deleted",
+                git2::Delta::Renamed => "renamed",
+                git2::Delta::Copied => "copied",
+                git2::Delta::Typechange => "typechange",
+                _ => "modified",
+            };
+
+            let old_path = matches!(delta.status(), git2::Delta::Renamed | git2::Delta::Copied)
+                .then(|| path_of(delta.old_file()));
+
+            let path = if delta.status() == git2::Delta::Deleted {
+                path_of(delta.old_file())
+            } else {
+                path_of(delta.new_file())
+            };
+
+            let (additions, deletions, is_binary) = match git2::Patch::from_diff(&diff, idx)? {
+                Some(patch) => {
+                    let (_, additions, deletions) = patch.line_stats()?;
+                    (additions, deletions, delta.flags().is_binary())
+                }
+                None => (0, 0, true),
+            };
+
+            files.push(StagedFileChange {
+                path,
+                old_path,
+                status: status.to_string(),
+                additions,
+                deletions,
+                is_binary,
+            });
+        }
+
+        let (branch_name, upstream) = match repo.head() {
+            Ok(head) if head.is_branch() => {
+                let name = head.shorthand().map(|s| s.to_string());
+                let upstream = name.as_deref().and_then(|name| {
+                    let branch = repo.find_branch(name, BranchType::Local).ok()?;
+                    let upstream = branch.upstream().ok()?;
+                    upstream.name().ok().flatten().map(|s| s.to_string())
+                });
+                (name, upstream)
+            }
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                // 新仓库的HEAD仍指向一个分支名
+                let name = repo
+                    .find_reference("HEAD")
+                    .ok()
+                    .and_then(|r| r.symbolic_target().map(|s| s.to_string()))
+                    .map(|s| s.trim_start_matches("refs/heads/").to_string());
+                (name, None)
+            }
+            _ => (None, None),
+        };
+
+        let mut recent_commits = Vec::new();
+        if recent_limit > 0 && head_tree.is_some() {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push_head()?;
+            for oid in revwalk.take(recent_limit) {
+                let commit = repo.find_commit(oid?)?;
+                if let Some(summary) = commit.summary() {
+                    recent_commits.push(summary.to_string());
+                }
+            }
+        }
+
+        let mut language_counts: std::collections::HashMap<String, usize> =
+            std::collections::HashMap::new();
+        let mut tracked_files = 0usize;
+        for entry in index.iter() {
+            let path = String::from_utf8_lossy(&entry.path).to_string();
+            tracked_files += 1;
+            if let Some(language) = self.detect_file_language(&path) {
+                *language_counts.entry(language).or_default() += 1;
+            }
+        }
+
+        let mut languages: Vec<LanguageShare> = language_counts
+            .into_iter()
+            .map(|(language, count)| LanguageShare {
+                language,
+                files: count,
+                percentage: (count as f64 * 1000.0 / tracked_files as f64).round() / 10.0,
+            })
+            .collect();
+        languages.sort_by(|a, b| b.files.cmp(&a.files).then(a.language.cmp(&b.language)));
+        languages.truncate(MAX_LANGUAGES);
+
+        let author = repo.signature().ok().map(|signature| CommitAuthor {
+            name: signature.name().unwrap_or_default().to_string(),
+            email: signature.email().unwrap_or_default().to_string(),
+        });
+
+        Ok(CommitTemplateFacts {
+            files,
+            branch_name,
+            upstream,
+            recent_commits,
+            languages,
+            author,
+        })
+    }
+
     /// 获取单个文件的diff内容（用于分层提交）
 
     /// 作者：Evilek
@@ -5574,7 +8294,7 @@ impl GitEngine {
             if let Ok(output) = Self::create_hidden_command(&git_command)
                 .current_dir(&path)
                 .args(&["rev-parse", "--git-dir"])
-                .output()
+                .output_with(&self.process_runner())
             {
                 if output.status.success() {
                     let name = Path::new(&path)
@@ -5588,7 +8308,7 @@ impl GitEngine {
                     let is_bare = Self::create_hidden_command(&git_command)
                         .current_dir(&path)
                         .args(&["rev-parse", "--is-bare-repository"])
-                        .output()
+                        .output_with(&self.process_runner())
                         .map(|out| String::from_utf8_lossy(&out.stdout).trim() == "true")
                         .unwrap_or(false);
 
@@ -5623,7 +8343,7 @@ impl GitEngine {
             if let Ok(output) = Self::create_hidden_command(&git_command)
                 .current_dir(&repo_path)
                 .args(&["log", "--format=%an|%ae", "--all"])
-                .output()
+                .output_with(&self.process_runner())
             {
                 if output.status.success() {
                     let log_output = String::from_utf8_lossy(&output.stdout);
@@ -5705,7 +8425,7 @@ impl GitEngine {
             if let Ok(output) = Self::create_hidden_command(&git_command)
                 .current_dir(repo_path)
                 .args(&args)
-                .output()
+                .output_with(&self.process_runner())
             {
                 if output.status.success() {
                     let log_output = String::from_utf8_lossy(&output.stdout);
@@ -5785,11 +8505,11 @@ impl GitEngine {
 
         if parts.len() >= 6 {
             Some((
-                parts[0].to_string(), // hash
-                parts[1].to_string(), // short_hash
-                parts[2].to_string(), // author
-                parts[3].to_string(), // email
-                parts[4].to_string(), // timestamp
+                parts[0].to_string(),             // hash
+                parts[1].to_string(),             // short_hash
+                parts[2].to_string(),             // author
+                parts[3].to_string(),             // email
+                parts[4].to_string(),             // timestamp
                 parts[5..].join("|").to_string(), // message (可能包含|字符)
             ))
         } else {
@@ -5815,7 +8535,7 @@ impl GitEngine {
         if let Ok(output) = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(&["show", "--name-only", "--format=", commit_hash])
-            .output()
+            .output_with(&self.process_runner())
         {
             if output.status.success() {
                 let files_output = String::from_utf8_lossy(&output.stdout);
@@ -5846,7 +8566,7 @@ impl GitEngine {
                 "--no-patch",
                 commit_id,
             ])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to get commit info: {}", e))?;
 
         if !output.status.success() {
@@ -5892,54 +8612,173 @@ impl GitEngine {
 
     /// 获取指定仓库的提交差异
 
-    pub fn get_commit_diff(&self, repo_path: &str, commit_id: &str) -> Result<FileDiffResult> {
-        let git_command = self.get_git_command();
-
-        // 获取文件变更列表
-
-        let files_output = Self::create_hidden_command(&git_command)
-            .current_dir(repo_path)
-            .args(&["show", "--name-only", "--format=", commit_id])
-            .output()
-            .map_err(|e| anyhow!("Failed to get changed files: {}", e))?;
+    pub fn get_commit_diff(&self, repo_path: &str, commit_id: &str) -> Result<CommitDiffResult> {
+        self.get_commit_diff_against(repo_path, commit_id, None)
+    }
 
-        let mut files_changed = Vec::new();
+    /// 获取提交相对指定父提交的差异；parent_index 从 0 开始，默认第一父提交
+    /// 合并提交可选择其他父提交，根提交相对空树
+    /// 作者：Evilek
+    /// 编写日期：2025-10-08
+    pub fn get_commit_diff_against(
+        &self,
+        repo_path: &str,
+        commit_id: &str,
+        parent_index: Option<usize>,
+    ) -> Result<CommitDiffResult> {
+        match self.git_method {
+            GitMethod::SystemGit | GitMethod::BundledGit => {
+                self.get_commit_diff_with_command(repo_path, commit_id, parent_index)
+            }
+            GitMethod::Git2Api => {
+                self.get_commit_diff_with_git2(repo_path, commit_id, parent_index)
+            }
+        }
+    }
 
-        if files_output.status.success() {
-            let output = String::from_utf8_lossy(&files_output.stdout);
+    fn get_commit_diff_with_command(
+        &self,
+        repo_path: &str,
+        commit_id: &str,
+        parent_index: Option<usize>,
+    ) -> Result<CommitDiffResult> {
+        let git_command = self.get_git_command();
 
-            for line in output.lines() {
-                let line = line.trim();
+        // 第一行输出为 "<commit> <parent1> <parent2> ..."
+        let parents_output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(["rev-list", "--parents", "-n", "1", commit_id])
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow!("Failed to resolve commit parents: {}", e))?;
+        if !parents_output.status.success() {
+            let stderr = String::from_utf8_lossy(&parents_output.stderr);
+            return Err(anyhow!("无法解析提交 {}: {}", commit_id, stderr.trim()));
+        }
+        let stdout = String::from_utf8_lossy(&parents_output.stdout);
+        let mut ids = stdout.split_whitespace().map(str::to_string);
+        let full_id = ids
+            .next()
+            .ok_or_else(|| anyhow!("无法解析提交 {}", commit_id))?;
+        let parents: Vec<String> = ids.collect();
+        let parent_id = Self::select_diff_parent(&parents, parent_index)?;
+
+        let mut args = vec![
+            "-c",
+            "core.quotePath=false",
+            "diff-tree",
+            "-r",
+            "-p",
+            "-M",
+            "-C",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-commit-id",
+        ];
+        match parent_id.as_deref() {
+            Some(parent) => args.push(parent),
+            None => args.push("--root"),
+        }
+        args.push(full_id.as_str());
 
-                if !line.is_empty() {
-                    files_changed.push(line.to_string());
-                }
-            }
+        let diff_output = Self::create_hidden_command(&git_command)
+            .current_dir(repo_path)
+            .args(&args)
+            .output_with(&self.process_runner())
+            .map_err(|e| anyhow!("Failed to get commit diff: {}", e))?;
+        if !diff_output.status.success() {
+            let stderr = String::from_utf8_lossy(&diff_output.stderr);
+            return Err(anyhow!("获取提交差异失败: {}", stderr.trim()));
         }
 
-        // 简化处理：返回基本的差异信息
+        let patch = String::from_utf8_lossy(&diff_output.stdout);
 
-        Ok(FileDiffResult {
-            file_path: files_changed.first().cloned().unwrap_or_default(),
+        Ok(self.build_commit_diff(full_id, parent_id, parents.len(), parse_patch_files(&patch)))
+    }
 
-            old_content: None,
+    fn get_commit_diff_with_git2(
+        &self,
+        repo_path: &str,
+        commit_id: &str,
+        parent_index: Option<usize>,
+    ) -> Result<CommitDiffResult> {
+        let repo = Repository::open(repo_path)?;
+        let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+        let parents: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
+        let parent_id = Self::select_diff_parent(&parents, parent_index)?;
 
-            new_content: None,
+        let parent_tree = match &parent_id {
+            Some(id) => Some(repo.find_commit(git2::Oid::from_str(id)?)?.tree()?),
+            None => None,
+        };
+        let tree = commit.tree()?;
 
-            old_file_name: None,
+        let mut options = DiffOptions::new();
+        options.context_lines(3);
+        let mut diff =
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))?;
 
-            new_file_name: None,
+        let mut find_options = git2::DiffFindOptions::new();
+        find_options.renames(true).copies(true);
+        diff.find_similar(Some(&mut find_options))?;
 
-            file_language: None,
+        let files = collect_patch_files(&diff)?;
 
-            hunks: Vec::new(),
+        Ok(self.build_commit_diff(commit.id().to_string(), parent_id, parents.len(), files))
+    }
 
-            is_binary: false,
+    /// 按 parent_index 选择对比的父提交，根提交返回 None
+    fn select_diff_parent(
+        parents: &[String],
+        parent_index: Option<usize>,
+    ) -> Result<Option<String>> {
+        match parent_index {
+            Some(index) if index >= parents.len() => Err(anyhow!(
+                "父提交序号 {} 超出范围（共 {} 个父提交）",
+                index,
+                parents.len()
+            )),
+            Some(index) => Ok(Some(parents[index].clone())),
+            None => Ok(parents.first().cloned()),
+        }
+    }
 
-            is_new_file: false,
+    fn build_commit_diff(
+        &self,
+        commit_id: String,
+        parent_id: Option<String>,
+        parent_count: usize,
+        patch_files: Vec<PatchFile>,
+    ) -> CommitDiffResult {
+        let files: Vec<CommitFileDiff> = patch_files
+            .into_iter()
+            .map(|file| {
+                let renamed = matches!(
+                    file.change_type,
+                    FileChangeType::Renamed | FileChangeType::Copied
+                );
+                CommitFileDiff {
+                    file_language: self.detect_file_language(&file.new_path),
+                    old_path: renamed.then_some(file.old_path),
+                    file_path: file.new_path,
+                    change_type: file.change_type,
+                    similarity: file.similarity,
+                    is_binary: file.is_binary,
+                    insertions: file.insertions,
+                    deletions: file.deletions,
+                    hunks: file.hunks,
+                }
+            })
+            .collect();
 
-            is_deleted_file: false,
-        })
+        CommitDiffResult {
+            commit_id,
+            parent_id,
+            parent_count,
+            files_changed: files.len() as u32,
+            insertions: files.iter().map(|file| file.insertions).sum(),
+            deletions: files.iter().map(|file| file.deletions).sum(),
+            files,
+        }
     }
 
     /// 获取日期范围内的提交列表
@@ -5955,9 +8794,10 @@ impl GitEngine {
     ) -> Result<Vec<CommitInfo>> {
         let git_command = self.get_git_command();
 
-        println!(
+        info_log!(
             "执行 git log 命令获取 {} 至 {} 的提交",
-            start_date, end_date
+            start_date,
+            end_date
         );
 
         // 尝试使用更宽松的日期格式，添加时间部分
@@ -5966,9 +8806,10 @@ impl GitEngine {
 
         let end_with_time = format!("{} 23:59:59", end_date);
 
-        println!(
+        info_log!(
             "使用带时间的日期格式: {} 至 {}",
-            start_with_time, end_with_time
+            start_with_time,
+            end_with_time
         );
 
         let output = Self::create_hidden_command(&git_command)
@@ -5980,13 +8821,13 @@ impl GitEngine {
                 "--format=%H|%h|%an|%ae|%ct|%s",
                 "--date=iso",
             ])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to get commits in date range: {}", e))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
 
-            println!("Git log 命令失败: {}", stderr);
+            info_log!("Git log 命令失败: {}", stderr);
 
             return Err(anyhow!("Git command failed: {}", stderr));
         }
@@ -5995,26 +8836,26 @@ impl GitEngine {
 
         let output = String::from_utf8_lossy(&output.stdout);
 
-        println!("Git log 输出行数: {}", output.lines().count());
+        info_log!("Git log 输出行数: {}", output.lines().count());
 
         // 如果没有找到提交，输出一些调试信息
 
         if output.lines().count() == 0 {
-            println!("调试信息：尝试获取最近的提交...");
+            info_log!("调试信息：尝试获取最近的提交...");
 
             let debug_output = Self::create_hidden_command(&git_command)
                 .current_dir(repo_path)
                 .args(&["log", "--oneline", "-5"])
-                .output()
+                .output_with(&self.process_runner())
                 .map_err(|e| anyhow!("Failed to get recent commits: {}", e))?;
 
             if debug_output.status.success() {
                 let recent_commits = String::from_utf8_lossy(&debug_output.stdout);
 
-                println!("最近的5个提交：");
+                info_log!("最近的5个提交：");
 
                 for line in recent_commits.lines().take(5) {
-                    println!("  {}", line);
+                    info_log!("  {}", line);
                 }
             }
 
@@ -6023,7 +8864,7 @@ impl GitEngine {
             let total_output = Self::create_hidden_command(&git_command)
                 .current_dir(repo_path)
                 .args(&["rev-list", "--count", "HEAD"])
-                .output()
+                .output_with(&self.process_runner())
                 .map_err(|e| anyhow!("Failed to get total commits: {}", e))?;
 
             if total_output.status.success() {
@@ -6031,7 +8872,7 @@ impl GitEngine {
 
                 let total_count = total_output_str.trim();
 
-                println!("仓库总提交数: {}", total_count);
+                info_log!("仓库总提交数: {}", total_count);
             }
         }
 
@@ -6077,7 +8918,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(&["remote", "-v"])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to get remote configuration: {}", e))?;
 
         if !output.status.success() {
@@ -6149,7 +8990,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(&["remote", "add", name, url])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to add remote: {}", e))?;
 
         if output.status.success() {
@@ -6181,7 +9022,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(&["remote", "set-url", name, url])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to update remote: {}", e))?;
 
         if output.status.success() {
@@ -6213,7 +9054,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(&["remote", "remove", name])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to remove remote: {}", e))?;
 
         if output.status.success() {
@@ -6251,7 +9092,7 @@ impl GitEngine {
         let output = Self::create_hidden_command(&git_command)
             .current_dir(repo_path)
             .args(&["branch", "--set-upstream-to", &upstream_ref, branch])
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to set branch upstream: {}", e))?;
 
         if output.status.success() {
@@ -6304,7 +9145,7 @@ impl GitEngine {
 
         let output = Self::create_hidden_command(&git_command)
             .args(&args)
-            .output()
+            .output_with(&self.process_runner())
             .map_err(|e| anyhow!("Failed to validate remote connection: {}", e))?;
 
         Ok(output.status.success())
@@ -6329,3 +9170,1136 @@ fn parse_remote_line(line: &str) -> Option<(String, String, String)> {
         None
     }
 }
+
+/// 带补丁输出的 git log 记录格式：记录分隔符 0x1e，字段分隔符 0x1f，最后一个字段为补丁内容
+const LOG_ENTRY_FORMAT: &str = "--format=%x1e%H%x1f%h%x1f%an%x1f%ae%x1f%at%x1f%B%x1f";
+
+/// 统一差异（unified diff）中的单个文件补丁
+/// 作者：Evilek
+/// 编写日期：2025-09-28
+#[derive(Debug, Clone)]
+struct PatchFile {
+    old_path: String,
+    new_path: String,
+    change_type: FileChangeType,
+    /// 重命名/复制的相似度
+    similarity: Option<u32>,
+    is_binary: bool,
+    insertions: u32,
+    deletions: u32,
+    hunks: Vec<DiffHunk>,
+}
+
+/// 解析 `diff --git` 格式的补丁文本，按文件拆分并生成行级差异
+fn parse_patch_files(patch: &str) -> Vec<PatchFile> {
+    let mut files: Vec<PatchFile> = Vec::new();
+
+    let mut old_line = 0u32;
+    let mut new_line = 0u32;
+
+    for raw in patch.lines() {
+        if let Some(rest) = raw.strip_prefix("diff --git ") {
+            let (old_path, new_path) = parse_diff_git_header(rest);
+
+            files.push(PatchFile {
+                old_path,
+                new_path,
+                change_type: FileChangeType::Modified,
+                similarity: None,
+                is_binary: false,
+                insertions: 0,
+                deletions: 0,
+                hunks: Vec::new(),
+            });
+
+            continue;
+        }
+
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if raw.starts_with("new file mode") {
+            file.change_type = FileChangeType::Added;
+        } else if raw.starts_with("deleted file mode") {
+            file.change_type = FileChangeType::Deleted;
+        } else if let Some(value) = raw.strip_prefix("similarity index ") {
+            file.similarity = value.trim_end_matches('%').parse().ok();
+        } else if let Some(path) = raw.strip_prefix("rename from ") {
+            file.old_path = path.to_string();
+            file.change_type = FileChangeType::Renamed;
+        } else if let Some(path) = raw.strip_prefix("rename to ") {
+            file.new_path = path.to_string();
+            file.change_type = FileChangeType::Renamed;
+        } else if let Some(path) = raw.strip_prefix("copy from ") {
+            file.old_path = path.to_string();
+            file.change_type = FileChangeType::Copied;
+        } else if let Some(path) = raw.strip_prefix("copy to ") {
+            file.new_path = path.to_string();
+            file.change_type = FileChangeType::Copied;
+        } else if raw.starts_with("Binary files ") || raw == "GIT binary patch" {
+            file.is_binary = true;
+        } else if raw.starts_with("--- ") && file.hunks.is_empty() {
+            if let Some(path) = strip_patch_path(&raw[4..]) {
+                file.old_path = path;
+            }
+        } else if raw.starts_with("+++ ") && file.hunks.is_empty() {
+            if let Some(path) = strip_patch_path(&raw[4..]) {
+                file.new_path = path;
+            }
+        } else if raw.starts_with("@@") {
+            if let Some(hunk) = parse_hunk_header(raw) {
+                old_line = hunk.old_start;
+                new_line = hunk.new_start;
+                file.hunks.push(hunk);
+            }
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            let (line_type, content) = match raw.chars().next() {
+                Some('+') => (DiffLineType::Insert, &raw[1..]),
+                Some('-') => (DiffLineType::Delete, &raw[1..]),
+                Some(' ') => (DiffLineType::Context, &raw[1..]),
+                Some('\\') => continue, // "\ No newline at end of file"
+                None => (DiffLineType::Context, ""),
+                _ => continue,
+            };
+
+            let (old_number, new_number) = match line_type {
+                DiffLineType::Insert => {
+                    file.insertions += 1;
+                    new_line += 1;
+                    (None, Some(new_line - 1))
+                }
+                DiffLineType::Delete => {
+                    file.deletions += 1;
+                    old_line += 1;
+                    (Some(old_line - 1), None)
+                }
+                DiffLineType::Context => {
+                    old_line += 1;
+                    new_line += 1;
+                    (Some(old_line - 1), Some(new_line - 1))
+                }
+            };
+
+            hunk.lines.push(DiffLine {
+                line_type,
+                content: content.trim_end_matches('\r').to_string(),
+                old_line_number: old_number,
+                new_line_number: new_number,
+            });
+        }
+    }
+
+    files
+}
+
+/// 解析 `a/<old> b/<new>` 形式的文件头，路径含空格时以 " b/" 为界
+fn parse_diff_git_header(rest: &str) -> (String, String) {
+    let rest = rest.trim();
+
+    if let Some(index) = rest.find(" b/") {
+        let old_path = rest[..index].trim_start_matches("a/").to_string();
+        let new_path = rest[index + 3..].to_string();
+        return (old_path, new_path);
+    }
+
+    (rest.to_string(), rest.to_string())
+}
+
+fn strip_patch_path(path: &str) -> Option<String> {
+    let path = path.trim_end();
+
+    if path == "/dev/null" {
+        return None;
+    }
+
+    Some(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path)
+            .to_string(),
+    )
+}
+
+/// 解析 `@@ -a,b +c,d @@` 区块头
+fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    let inner = line.trim_start_matches('@').trim_start();
+    let inner = &inner[..inner.find("@@")?];
+
+    let mut parts = inner.split_whitespace();
+
+    let parse_range = |range: &str| -> Option<(u32, u32)> {
+        let mut pieces = range.splitn(2, ',');
+        let start = pieces.next()?.parse().ok()?;
+        let count = match pieces.next() {
+            Some(count) => count.parse().ok()?,
+            None => 1,
+        };
+        Some((start, count))
+    };
+
+    let (old_start, old_lines) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_lines) = parse_range(parts.next()?.strip_prefix('+')?)?;
+
+    Some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: Vec::new(),
+    })
+}
+
+/// 将 git2 差异渲染为补丁文本后复用统一解析逻辑，按文件返回
+fn collect_patch_files(diff: &git2::Diff) -> Result<Vec<PatchFile>> {
+    let mut patch_text = String::new();
+
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        let content = String::from_utf8_lossy(line.content());
+
+        match line.origin() {
+            '+' | '-' | ' ' => {
+                patch_text.push(line.origin());
+                patch_text.push_str(&content);
+            }
+            _ => patch_text.push_str(&content),
+        }
+
+        if !patch_text.ends_with('\n') {
+            patch_text.push('\n');
+        }
+
+        true
+    })?;
+
+    Ok(parse_patch_files(&patch_text))
+}
+
+/// 合并所有文件的区块，返回 (区块, 新增行数, 删除行数)
+fn collect_patch_hunks(diff: &git2::Diff) -> Result<(Vec<DiffHunk>, u32, u32)> {
+    let files = collect_patch_files(diff)?;
+
+    let mut hunks = Vec::new();
+    let (mut insertions, mut deletions) = (0, 0);
+
+    for file in files {
+        insertions += file.insertions;
+        deletions += file.deletions;
+        hunks.extend(file.hunks);
+    }
+
+    Ok((hunks, insertions, deletions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::git_types::{GitflowQaStatus, GitflowStepStatus};
+
+    #[test]
+    fn parse_patch_tracks_renames_and_line_numbers() {
+        let patch = concat!(
+            "diff --git a/src/old.rs b/src/new.rs\n",
+            "similarity index 90%\n",
+            "rename from src/old.rs\n",
+            "rename to src/new.rs\n",
+            "--- a/src/old.rs\n",
+            "+++ b/src/new.rs\n",
+            "@@ -3,3 +3,4 @@ fn main() {\n",
+            " let a = 1;\n",
+            "-let b = 2;\n",
+            "+let b = 3;\n",
+            "+let c = 4;\n",
+            " }\n",
+        );
+
+        let files = parse_patch_files(patch);
+
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!(file.old_path, "src/old.rs");
+        assert_eq!(file.new_path, "src/new.rs");
+        assert!(matches!(file.change_type, FileChangeType::Renamed));
+        assert_eq!((file.insertions, file.deletions), (2, 1));
+
+        let lines = &file.hunks[0].lines;
+        assert_eq!(lines[1].old_line_number, Some(4));
+        assert_eq!(lines[2].new_line_number, Some(4));
+        assert_eq!(lines[4].old_line_number, Some(5));
+        assert_eq!(lines[4].new_line_number, Some(6));
+    }
+
+    /// 在临时目录中构造线性历史：每个提交写入一个文件，返回 (目录, 引擎)
+    fn linear_repo(files: &[&str]) -> (tempfile::TempDir, GitEngine) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let signature = Signature::now("Tester", "tester@example.com").unwrap();
+
+        for (index, file) in files.iter().enumerate() {
+            fs::write(dir.path().join(file), format!("content {}", index)).unwrap();
+
+            let mut repo_index = repo.index().unwrap();
+            repo_index.add_path(Path::new(file)).unwrap();
+            repo_index.write().unwrap();
+            let tree = repo.find_tree(repo_index.write_tree().unwrap()).unwrap();
+
+            let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &format!("commit {} touches {}", index, file),
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        }
+
+        let mut engine = GitEngine::new();
+        engine
+            .open_repository(dir.path().to_str().unwrap())
+            .unwrap();
+        (dir, engine)
+    }
+
+    #[test]
+    fn gitflow_init_creates_develop_and_persists_git_flow_keys() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let main_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        assert!(!engine.list_gitflow_branches().unwrap().initialized);
+
+        engine.init_gitflow(None).unwrap();
+        assert!(repo.find_branch("develop", BranchType::Local).is_ok());
+
+        let config = repo.config().unwrap().snapshot().unwrap();
+        assert_eq!(
+            config.get_string("gitflow.branch.master").unwrap(),
+            main_branch
+        );
+        assert_eq!(
+            config.get_string("gitflow.branch.develop").unwrap(),
+            "develop"
+        );
+        assert_eq!(
+            config.get_string("gitflow.prefix.support").unwrap(),
+            "support/"
+        );
+
+        let mut custom = engine.load_gitflow_config().unwrap();
+        custom.feature_prefix = "feat/".to_string();
+        custom.version_tag_prefix = "v".to_string();
+        engine.save_gitflow_config(&custom).unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feat/login", &head, false).unwrap();
+        repo.branch("support/1.x", &head, false).unwrap();
+
+        let summary = engine.list_gitflow_branches().unwrap();
+        assert!(summary.initialized);
+        assert_eq!(summary.config, custom);
+        let types: Vec<_> = summary.branches.iter().map(|b| &b.branch_type).collect();
+        assert_eq!(
+            types,
+            vec![&GitflowBranchType::Feature, &GitflowBranchType::Support]
+        );
+        assert_eq!(summary.branches[1].base, main_branch);
+
+        custom.bugfix_prefix = "feat/fix/".to_string();
+        assert!(engine.save_gitflow_config(&custom).is_err());
+    }
+
+    #[test]
+    fn release_pipeline_bumps_tags_and_back_merges() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut git_config = repo.config().unwrap();
+        git_config.set_str("user.name", "Tester").unwrap();
+        git_config
+            .set_str("user.email", "tester@example.com")
+            .unwrap();
+
+        commit_file(
+            &repo,
+            "package.json",
+            "{\n  \"name\": \"app\",\n  \"version\": \"1.0.0\"\n}\n",
+            "chore: add package manifest",
+        );
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let signature = Signature::now("Tester", "tester@example.com").unwrap();
+        repo.tag(
+            "v1.0.0",
+            head.as_object(),
+            &signature,
+            "Release v1.0.0",
+            false,
+        )
+        .unwrap();
+
+        engine.init_gitflow(None).unwrap();
+        let mut config = engine.load_gitflow_config().unwrap();
+        config.version_tag_prefix = "v".to_string();
+        engine.save_gitflow_config(&config).unwrap();
+
+        repo.branch("release/next", &head, false).unwrap();
+        repo.set_head("refs/heads/release/next").unwrap();
+        commit_file(&repo, "login.rs", "fn login() {}", "feat(auth): add login");
+        commit_file(&repo, "fix.rs", "fn fix() {}", "fix: handle empty password");
+        repo.set_head(&format!("refs/heads/{}", config.develop_branch))
+            .unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let mut request = GitflowActionRequest {
+            branch_name: "release/next".to_string(),
+            action: "release_pipeline".to_string(),
+            pull_request: None,
+            release: Some(ReleaseOptions {
+                dry_run: true,
+                ..Default::default()
+            }),
+            metadata: None,
+        };
+
+        let preview = engine.execute_gitflow_action(&request).unwrap();
+        assert!(preview.message.contains("v1.1.0"));
+        assert!(repo.find_reference("refs/tags/v1.1.0").is_err());
+
+        let plan = engine
+            .plan_release("release/next", &ReleaseOptions::default())
+            .unwrap();
+        assert_eq!(plan.previous_tag.as_deref(), Some("v1.0.0"));
+        assert_eq!(plan.bump, Some(VersionBump::Minor));
+        assert_eq!(plan.version_files, vec!["package.json"]);
+        assert_eq!(plan.commit_count, 2);
+
+        request.release = Some(ReleaseOptions::default());
+        engine.execute_gitflow_action(&request).unwrap();
+
+        let tag = repo
+            .find_reference("refs/tags/v1.1.0")
+            .unwrap()
+            .peel_to_tag()
+            .unwrap();
+        assert!(tag.message().unwrap().contains("- **auth:** add login"));
+
+        let main_tip = repo
+            .find_branch(&config.main_branch, BranchType::Local)
+            .unwrap()
+            .into_reference()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(tag.target_id(), main_tip.id());
+        let manifest = main_tip
+            .tree()
+            .unwrap()
+            .get_path(Path::new("package.json"))
+            .unwrap()
+            .to_object(&repo)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+        assert!(String::from_utf8_lossy(manifest.content()).contains("\"version\": \"1.1.0\""));
+
+        let develop_tip = repo
+            .find_branch(&config.develop_branch, BranchType::Local)
+            .unwrap()
+            .into_reference()
+            .peel_to_commit()
+            .unwrap();
+        assert!(repo
+            .graph_descendant_of(develop_tip.id(), tag.target_id())
+            .unwrap());
+        assert!(repo.find_branch("release/next", BranchType::Local).is_err());
+    }
+
+    #[test]
+    fn plan_release_reads_versions_from_release_branch() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+
+        commit_file(
+            &repo,
+            "package.json",
+            "{\n  \"name\": \"app\",\n  \"version\": \"1.0.0\"\n}\n",
+            "chore: add package manifest",
+        );
+        engine.init_gitflow(None).unwrap();
+        let config = engine.load_gitflow_config().unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("release/next", &head, false).unwrap();
+        repo.set_head("refs/heads/release/next").unwrap();
+        commit_file(
+            &repo,
+            "package.json",
+            "{\n  \"name\": \"app\",\n  \"version\": \"2.0.0\"\n}\n",
+            "feat: bump major for the new api",
+        );
+        repo.set_head(&format!("refs/heads/{}", config.develop_branch))
+            .unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        assert!(fs::read_to_string(dir.path().join("package.json"))
+            .unwrap()
+            .contains("1.0.0"));
+
+        let plan = engine
+            .plan_release("release/next", &ReleaseOptions::default())
+            .unwrap();
+        assert_eq!(plan.current_version, "2.0.0");
+        assert_eq!(plan.next_version, "2.1.0");
+        assert_eq!(plan.version_files, vec!["package.json"]);
+    }
+
+    #[test]
+    fn failed_gitflow_action_rolls_back_refs_and_worktree() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut git_config = repo.config().unwrap();
+        git_config.set_str("user.name", "Tester").unwrap();
+        git_config
+            .set_str("user.email", "tester@example.com")
+            .unwrap();
+
+        engine.init_gitflow(None).unwrap();
+        let config = engine.load_gitflow_config().unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("hotfix/crash", &head, false).unwrap();
+
+        // develop 与 hotfix 修改同一文件，回流 develop 时冲突
+        let checkout = |branch: &str| {
+            repo.set_head(&format!("refs/heads/{}", branch)).unwrap();
+            repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+                .unwrap();
+        };
+        checkout(&config.develop_branch);
+        commit_file(&repo, "app.rs", "develop", "feat: develop change");
+        checkout("hotfix/crash");
+        commit_file(&repo, "app.rs", "hotfix", "fix: crash");
+        checkout(&config.main_branch);
+
+        let main_before = repo.head().unwrap().target().unwrap();
+        fs::write(dir.path().join("README.md"), "local edit").unwrap();
+        fs::write(dir.path().join("notes.txt"), "untracked").unwrap();
+
+        let result = engine
+            .execute_gitflow_action(&GitflowActionRequest {
+                branch_name: "hotfix/crash".to_string(),
+                action: "finish_hotfix".to_string(),
+                pull_request: None,
+                release: None,
+                metadata: None,
+            })
+            .unwrap();
+
+        assert!(!result.success);
+        assert!(result.rolled_back);
+        let statuses: Vec<_> = result.steps.iter().map(|step| step.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                GitflowStepStatus::Succeeded, // 自动储藏
+                GitflowStepStatus::Succeeded, // 合并到 main
+                GitflowStepStatus::Failed,    // 合并到 develop 冲突
+                GitflowStepStatus::Succeeded, // 回滚引用与工作区
+                GitflowStepStatus::Succeeded, // 恢复储藏
+            ]
+        );
+
+        let repo = Repository::open(dir.path()).unwrap();
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(
+            repo.head().unwrap().shorthand(),
+            Some(config.main_branch.as_str())
+        );
+        assert_eq!(repo.head().unwrap().target().unwrap(), main_before);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("README.md")).unwrap(),
+            "local edit"
+        );
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(!dir.path().join("app.rs").exists());
+    }
+
+    #[test]
+    fn failed_read_only_gitflow_action_keeps_worktree() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        engine.init_gitflow(None).unwrap();
+
+        fs::write(dir.path().join("README.md"), "local edit").unwrap();
+        fs::write(dir.path().join("notes.txt"), "untracked").unwrap();
+
+        let result = engine
+            .execute_gitflow_action(&GitflowActionRequest {
+                branch_name: "feature/missing".to_string(),
+                action: "update_qa_status".to_string(),
+                pull_request: None,
+                release: None,
+                metadata: None,
+            })
+            .unwrap();
+
+        assert!(!result.success);
+        assert!(!result.rolled_back);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("README.md")).unwrap(),
+            "local edit"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+            "untracked"
+        );
+        let mut repo = Repository::open(dir.path()).unwrap();
+        let mut stashes = 0;
+        repo.stash_foreach(|_, _, _| {
+            stashes += 1;
+            true
+        })
+        .unwrap();
+        assert_eq!(stashes, 0);
+    }
+
+    #[test]
+    fn commit_template_facts_describe_staged_changes() {
+        let (dir, engine) = linear_repo(&["main.rs", "lib.rs", "notes.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.name", "Tester")
+            .unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.email", "tester@example.com")
+            .unwrap();
+
+        fs::write(dir.path().join("main.rs"), "line one\nline two\n").unwrap();
+        fs::write(dir.path().join("new.rs"), "fn new() {}\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("main.rs")).unwrap();
+        index.add_path(Path::new("new.rs")).unwrap();
+        index.write().unwrap();
+
+        let facts = engine.get_commit_template_facts(&[], 2).unwrap();
+
+        assert_eq!(facts.files.len(), 2);
+        let main = facts.files.iter().find(|f| f.path == "main.rs").unwrap();
+        assert_eq!(
+            (main.status.as_str(), main.additions, main.deletions),
+            ("modified", 2, 1)
+        );
+        let added = facts.files.iter().find(|f| f.path == "new.rs").unwrap();
+        assert_eq!(added.status, "added");
+
+        assert!(facts.branch_name.is_some());
+        assert_eq!(facts.upstream, None);
+        assert_eq!(
+            facts.recent_commits,
+            vec!["commit 2 touches notes.md", "commit 1 touches lib.rs"]
+        );
+        assert_eq!(facts.languages[0].language, "rust");
+        assert_eq!(facts.languages[0].files, 3);
+        assert_eq!(facts.languages[0].percentage, 75.0);
+        assert_eq!(facts.author.unwrap().email, "tester@example.com");
+    }
+
+    #[test]
+    fn query_commit_history_pages_with_cursor_and_filters() {
+        let (_dir, engine) = linear_repo(&["a.txt", "b.txt", "a.txt", "c.txt", "a.txt"]);
+
+        let mut query = HistoryQuery {
+            page_size: Some(2),
+            ..Default::default()
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = engine.query_commit_history(&query).unwrap();
+            seen.extend(page.commits.iter().map(|c| c.message.clone()));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen.len(), 5);
+        assert!(seen[0].starts_with("commit 4"));
+        assert!(seen[4].starts_with("commit 0"));
+
+        let first = engine
+            .query_commit_history(&HistoryQuery::default())
+            .unwrap();
+        assert!(first.commits[0]
+            .refs
+            .iter()
+            .any(|r| r.ref_type == CommitRefType::Head));
+        assert_eq!(first.commits[4].parents.len(), 0);
+        assert_eq!(first.commits[0].parents[0], first.commits[1].hash);
+
+        let by_path = engine
+            .query_commit_history(&HistoryQuery {
+                path: Some("a.txt".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_path.commits.len(), 3);
+
+        let by_message = engine
+            .query_commit_history(&HistoryQuery {
+                message_pattern: Some("touches (b|c)".to_string()),
+                merge_filter: MergeFilter::NoMerges,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(by_message.commits.len(), 2);
+
+        let stale = HistoryQuery {
+            cursor: Some(format!("2:{}", "0".repeat(40))),
+            ..Default::default()
+        };
+        assert!(engine.query_commit_history(&stale).is_err());
+    }
+
+    #[test]
+    fn query_commit_history_reuses_walk_between_pages() {
+        let (dir, engine) = linear_repo(&["a.txt", "b.txt", "c.txt", "d.txt"]);
+        let repo = Repository::open(dir.path()).unwrap();
+
+        let mut query = HistoryQuery {
+            page_size: Some(1),
+            ..Default::default()
+        };
+        let first = engine.query_commit_history(&query).unwrap();
+        let walk = engine
+            .history_walk
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .order
+            .clone();
+        assert_eq!(walk.len(), 4);
+
+        query.cursor = first.next_cursor.clone();
+        let second = engine.query_commit_history(&query).unwrap();
+        assert_eq!(second.scanned, 1);
+        assert!(Arc::ptr_eq(
+            &walk,
+            &engine.history_walk.lock().unwrap().as_ref().unwrap().order
+        ));
+
+        // 起点变化（新提交）后重新遍历，旧游标失效
+        commit_file(&repo, "e.txt", "new", "commit 4");
+        assert!(engine.query_commit_history(&query).is_err());
+        query.cursor = None;
+        let fresh = engine.query_commit_history(&query).unwrap();
+        assert!(fresh.commits[0].message.starts_with("commit 4"));
+    }
+
+    fn commit_file(repo: &Repository, file: &str, content: &str, message: &str) {
+        let workdir = repo.workdir().unwrap();
+        fs::write(workdir.join(file), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+        let signature = Signature::now("Tester", "tester@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn commit_diff_lists_every_file_in_cli_and_git2_modes() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let repo_path = dir.path().to_str().unwrap();
+        let source: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        commit_file(&repo, "src/lib.rs", &source, "add lib");
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+
+        // 同一提交内修改、重命名并新增二进制文件
+        fs::write(dir.path().join("README.md"), "content 0\nmore\n").unwrap();
+        fs::remove_file(dir.path().join("src/lib.rs")).unwrap();
+        fs::write(
+            dir.path().join("src/core.rs"),
+            source.replace("line 20", "line twenty"),
+        )
+        .unwrap();
+        fs::write(dir.path().join("logo.png"), [0u8, 159, 146, 150, 0, 1]).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("src/lib.rs")).unwrap();
+        for file in ["README.md", "src/core.rs", "logo.png"] {
+            index.add_path(Path::new(file)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Tester", "tester@example.com").unwrap();
+        let commit = repo
+            .find_commit(
+                repo.commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    "reshape",
+                    &tree,
+                    &[&base],
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let commit_id = commit.id().to_string();
+
+        let cli = engine
+            .get_commit_diff_with_command(repo_path, &commit_id, None)
+            .unwrap();
+        let git2 = engine
+            .get_commit_diff_with_git2(repo_path, &commit_id, None)
+            .unwrap();
+        for diff in [&cli, &git2] {
+            assert_eq!(diff.parent_id, Some(base.id().to_string()));
+            assert_eq!(diff.parent_count, 1);
+            assert_eq!(diff.files_changed, 3);
+            assert_eq!((diff.insertions, diff.deletions), (3, 2));
+
+            let file = |path: &str| diff.files.iter().find(|f| f.file_path == path).unwrap();
+            let readme = file("README.md");
+            assert!(matches!(readme.change_type, FileChangeType::Modified));
+            assert_eq!((readme.insertions, readme.deletions), (2, 1));
+
+            let renamed = file("src/core.rs");
+            assert!(matches!(renamed.change_type, FileChangeType::Renamed));
+            assert_eq!(renamed.old_path.as_deref(), Some("src/lib.rs"));
+            assert!(renamed.similarity.unwrap() >= 90);
+            assert_eq!(renamed.file_language.as_deref(), Some("rust"));
+            let last = renamed.hunks[0].lines.last().unwrap();
+            assert_eq!(last.content, "line twenty");
+            assert_eq!(last.new_line_number, Some(20));
+
+            let logo = file("logo.png");
+            assert!(logo.is_binary);
+            assert!(matches!(logo.change_type, FileChangeType::Added));
+            assert!(logo.hunks.is_empty());
+        }
+
+        // 合并提交可选择父提交；根提交相对空树
+        let merge_id = repo
+            .commit(
+                None,
+                &signature,
+                &signature,
+                "merge",
+                &tree,
+                &[&commit, &base],
+            )
+            .unwrap()
+            .to_string();
+        let root_id = repo.revparse_single("HEAD~2").unwrap().id().to_string();
+        for mode in [GitMethod::SystemGit, GitMethod::Git2Api] {
+            let diff = |id: &str, parent: Option<usize>| match mode {
+                GitMethod::Git2Api => engine.get_commit_diff_with_git2(repo_path, id, parent),
+                _ => engine.get_commit_diff_with_command(repo_path, id, parent),
+            };
+
+            let first_parent = diff(&merge_id, None).unwrap();
+            assert_eq!(first_parent.parent_count, 2);
+            assert!(first_parent.files.is_empty());
+            let second_parent = diff(&merge_id, Some(1)).unwrap();
+            assert_eq!(second_parent.parent_id, Some(base.id().to_string()));
+            assert_eq!(second_parent.files_changed, 3);
+            assert!(diff(&merge_id, Some(2)).is_err());
+
+            let root = diff(&root_id, None).unwrap();
+            assert_eq!(root.parent_id, None);
+            assert_eq!(root.files[0].file_path, "README.md");
+            assert!(matches!(root.files[0].change_type, FileChangeType::Added));
+        }
+    }
+
+    /// 构造 origin 与本地克隆各自新增一个提交的分叉场景
+    fn diverged_clone(pull_config: &[(&str, &str)]) -> (tempfile::TempDir, GitEngine) {
+        let dir = tempfile::tempdir().unwrap();
+        let origin_path = dir.path().join("origin");
+        let local_path = dir.path().join("local");
+
+        let origin = Repository::init(&origin_path).unwrap();
+        commit_file(&origin, "base.txt", "base", "initial");
+
+        let local = Repository::clone(origin_path.to_str().unwrap(), &local_path).unwrap();
+        let mut config = local.config().unwrap();
+        config.set_str("user.name", "Tester").unwrap();
+        config.set_str("user.email", "tester@example.com").unwrap();
+        for (key, value) in pull_config {
+            config.set_str(key, value).unwrap();
+        }
+
+        commit_file(&origin, "remote.txt", "remote", "remote change");
+        commit_file(&local, "local.txt", "local", "local change");
+
+        let mut engine = GitEngine::new();
+        engine
+            .open_repository(local_path.to_str().unwrap())
+            .unwrap();
+        (dir, engine)
+    }
+
+    #[test]
+    fn branch_metadata_feeds_listing_digest_and_syncs_with_remote() {
+        let (dir, engine) = diverged_clone(&[]);
+        let origin = Repository::open(dir.path().join("origin")).unwrap();
+        let local = engine.get_repository().unwrap();
+        let head = local.head().unwrap().peel_to_commit().unwrap();
+        local.branch("develop", &head, false).unwrap();
+
+        branch_metadata::update(
+            &origin,
+            "feature/remote",
+            &GitflowBranchMetadata {
+                owner: Some("bob".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        engine
+            .create_gitflow_branch(&GitflowCreateRequest {
+                branch_type: GitflowBranchType::Feature,
+                branch_name: "feature/login".to_string(),
+                base_branch: None,
+                auto_push: false,
+                metadata: std::collections::HashMap::from([(
+                    "owner".to_string(),
+                    "alice".to_string(),
+                )]),
+            })
+            .unwrap();
+
+        let result = engine
+            .execute_gitflow_action(&GitflowActionRequest {
+                branch_name: "feature/login".to_string(),
+                action: "update_qa_status".to_string(),
+                pull_request: None,
+                release: None,
+                metadata: Some(GitflowBranchMetadata {
+                    qa_status: Some(GitflowQaStatus::Failed),
+                    risk_notes: Some("token refresh untested".to_string()),
+                    ..Default::default()
+                }),
+            })
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+
+        let summary = engine.list_gitflow_branches().unwrap();
+        let login = summary
+            .branches
+            .iter()
+            .find(|branch| branch.name == "feature/login")
+            .unwrap();
+        assert_eq!(login.metadata.owner.as_deref(), Some("alice"));
+        assert_eq!(login.metadata.qa_status, Some(GitflowQaStatus::Failed));
+
+        let report = engine
+            .execute_gitflow_action(&GitflowActionRequest {
+                branch_name: "feature/login".to_string(),
+                action: "generate_status_report".to_string(),
+                pull_request: None,
+                release: None,
+                metadata: None,
+            })
+            .unwrap()
+            .report
+            .unwrap();
+        assert!(report.contains("QA 状态：未通过"));
+        assert!(report.contains("风险说明：token refresh untested"));
+
+        engine.sync_branch_metadata().unwrap();
+        assert_eq!(
+            engine
+                .get_branch_metadata("feature/remote")
+                .unwrap()
+                .owner
+                .as_deref(),
+            Some("bob")
+        );
+        let remote_entries = branch_metadata::load_all(&origin).unwrap();
+        assert_eq!(
+            remote_entries["feature/login"].owner.as_deref(),
+            Some("alice")
+        );
+    }
+
+    #[test]
+    fn sync_branch_metadata_uses_git2_in_git2_api_mode() {
+        // libgit2 只能推送到裸仓库
+        let dir = tempfile::tempdir().unwrap();
+        let origin_path = dir.path().join("origin.git");
+        let origin = Repository::init_bare(&origin_path).unwrap();
+        let local_path = dir.path().join("local");
+        let local = Repository::init(&local_path).unwrap();
+        local
+            .remote("origin", origin_path.to_str().unwrap())
+            .unwrap();
+
+        let mut engine = GitEngine::new_with_config(GitConfig {
+            execution_mode: GitExecutionMode::Git2Api,
+            ..Default::default()
+        });
+        engine
+            .open_repository(local_path.to_str().unwrap())
+            .unwrap();
+        let owner = |name: &str| GitflowBranchMetadata {
+            owner: Some(name.to_string()),
+            ..Default::default()
+        };
+
+        // 远程还没有元数据：只推送本地记录
+        branch_metadata::update(&local, "feature/a", &owner("alice")).unwrap();
+        engine.sync_branch_metadata().unwrap();
+        assert_eq!(
+            branch_metadata::load(&origin, "feature/a")
+                .unwrap()
+                .unwrap()
+                .owner
+                .as_deref(),
+            Some("alice")
+        );
+
+        branch_metadata::update(&origin, "feature/b", &owner("bob")).unwrap();
+        branch_metadata::update(&local, "feature/c", &owner("carol")).unwrap();
+        let result = engine.sync_branch_metadata().unwrap();
+        assert!(result.details.unwrap().contains("1 个分支"));
+
+        let merged = branch_metadata::load_all(&local).unwrap();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(branch_metadata::load_all(&origin).unwrap(), merged);
+    }
+
+    #[test]
+    fn gitflow_report_context_covers_branch_only_commits_and_diffstat() {
+        let (dir, engine) = linear_repo(&["README.md"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        engine.init_gitflow(None).unwrap();
+
+        let develop = repo
+            .find_branch("develop", BranchType::Local)
+            .unwrap()
+            .into_reference()
+            .peel_to_commit()
+            .unwrap();
+        repo.branch("feature/report", &develop, false).unwrap();
+        repo.set_head("refs/heads/feature/report").unwrap();
+        commit_file(
+            &repo,
+            "report.rs",
+            "fn a() {}\nfn b() {}\nfn c() {}\n",
+            "feat: report",
+        );
+        commit_file(&repo, "README.md", "changed", "docs: readme");
+
+        let changes = GitflowBranchMetadata {
+            owner: Some("alice".to_string()),
+            ..Default::default()
+        };
+        engine
+            .update_branch_metadata("feature/report", &changes)
+            .unwrap();
+
+        let context = engine.gitflow_report_context("feature/report").unwrap();
+
+        assert_eq!(context.base_branch, "develop");
+        assert_eq!(context.branch_type, Some(GitflowBranchType::Feature));
+        assert_eq!(context.divergence.ahead, 2);
+        assert_eq!(
+            context
+                .commits
+                .iter()
+                .map(|commit| commit.summary.as_str())
+                .collect::<Vec<_>>(),
+            vec!["docs: readme", "feat: report"]
+        );
+        assert_eq!(context.files_changed, 2);
+        assert_eq!(context.diffstat[0].path, "report.rs");
+        assert_eq!(context.diffstat[0].insertions, 3);
+        assert_eq!(context.insertions, 4);
+        assert_eq!(context.deletions, 1);
+        assert_eq!(context.metadata.owner.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn git2_pull_creates_merge_commit_for_diverged_branch() {
+        let (_dir, engine) = diverged_clone(&[]);
+
+        let result = engine.pull_with_git2_api().unwrap();
+        assert!(result.success);
+
+        let repo = engine.get_repository().unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert!(head.message().unwrap().starts_with("Merge branch"));
+        assert!(repo.workdir().unwrap().join("remote.txt").exists());
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+    }
+
+    #[test]
+    fn git2_pull_rebases_when_configured() {
+        let (_dir, engine) = diverged_clone(&[("pull.rebase", "true")]);
+
+        engine.pull_with_git2_api().unwrap();
+
+        let repo = engine.get_repository().unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(head.message().unwrap(), "local change");
+        assert_eq!(head.parent(0).unwrap().message().unwrap(), "remote change");
+        assert!(repo.head().unwrap().is_branch());
+    }
+
+    #[test]
+    fn git2_pull_respects_ff_only() {
+        let (_dir, engine) = diverged_clone(&[("pull.ff", "only")]);
+
+        let error = engine.pull_with_git2_api().unwrap_err();
+        assert!(error.to_string().contains("pull.ff=only"));
+
+        let repo = engine.get_repository().unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message().unwrap(), "local change");
+    }
+
+    #[test]
+    fn parse_blame_porcelain_merges_contiguous_lines() {
+        let sha = "a".repeat(40);
+        let other = "b".repeat(40);
+        let output = format!(
+            concat!(
+                "{sha} 1 1 2\nauthor Alice\nauthor-mail <alice@example.com>\n",
+                "author-time 1700000000\nsummary init\nfilename src/lib.rs\n\tline one\n",
+                "{sha} 2 2\n\tline two\n",
+                "{other} 7 3 1\nauthor Bob\nauthor-mail <bob@example.com>\n",
+                "author-time 1700000100\nsummary move\nboundary\nfilename src/old.rs\n\tline three\n",
+            ),
+            sha = sha,
+            other = other
+        );
+
+        let hunks = GitEngine::parse_blame_porcelain(&output);
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].line_count, 2);
+        assert_eq!(hunks[0].email, "alice@example.com");
+        assert_eq!(hunks[1].original_path, "src/old.rs");
+        assert_eq!(hunks[1].original_start_line, 7);
+        assert!(hunks[1].is_boundary);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::core::git_config::GitConfig;
+use crate::debug_log;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Git 子进程执行器：超时（GitConfig.command_timeout）、取消令牌、进程树清理与详细日志
+// Author: Evilek, Date: 2025-09-30
+// git 还会拉起 ssh、credential helper 等子进程，超时/取消时必须整棵树一起结束，否则照样挂住
+
+/// 轮询子进程状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// 取消令牌，可跨线程克隆共享
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Git进程执行器
+#[derive(Debug, Clone)]
+pub struct GitProcessRunner {
+    timeout: Option<Duration>,
+    verbose: bool,
+    cancel_token: Option<CancellationToken>,
+}
+
+impl Default for GitProcessRunner {
+    fn default() -> Self {
+        Self::from_config(&GitConfig::default())
+    }
+}
+
+impl GitProcessRunner {
+    /// 按Git配置创建执行器，command_timeout 为 0 表示不限时
+    pub fn from_config(config: &GitConfig) -> Self {
+        Self {
+            timeout: (config.command_timeout > 0)
+                .then(|| Duration::from_secs(config.command_timeout)),
+            verbose: config.enable_verbose_logging,
+            cancel_token: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_cancel_token(mut self, token: Option<CancellationToken>) -> Self {
+        self.cancel_token = token;
+        self
+    }
+
+    /// 执行命令并收集输出，行为与 `Command::output` 一致，但受超时与取消控制
+    pub fn output(&self, command: &mut Command) -> io::Result<Output> {
+        let description = describe_command(command);
+
+        if self.cancel_token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!("Git命令已取消: {}", description),
+            ));
+        }
+
+        // 禁止 git 在无终端环境下等待交互输入（否则凭据提示会一直挂起）
+        command
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        isolate_process_group(command);
+
+        let started = Instant::now();
+
+        if self.verbose {
+            debug_log!("[GIT] 执行: {}", description);
+        }
+
+        let mut child = command.spawn()?;
+
+        // 独立线程读取输出，避免管道写满导致子进程阻塞
+        let stdout_reader = spawn_reader(child.stdout.take());
+        let stderr_reader = spawn_reader(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+
+            let timed_out = self.timeout.is_some_and(|t| started.elapsed() >= t);
+            let cancelled = self.cancel_token.as_ref().is_some_and(|t| t.is_cancelled());
+
+            if timed_out || cancelled {
+                kill_process_tree(&mut child);
+
+                let (kind, reason) = if cancelled {
+                    (io::ErrorKind::Interrupted, "已取消".to_string())
+                } else {
+                    (
+                        io::ErrorKind::TimedOut,
+                        format!("超时（{}秒）", self.timeout.unwrap_or_default().as_secs()),
+                    )
+                };
+
+                if self.verbose {
+                    debug_log!(
+                        "[GIT] {}: {} ({} ms)",
+                        reason,
+                        description,
+                        started.elapsed().as_millis()
+                    );
+                }
+
+                return Err(io::Error::new(
+                    kind,
+                    format!("Git命令{}: {}", reason, description),
+                ));
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        let stdout = stdout_reader.join().unwrap_or_default();
+        let stderr = stderr_reader.join().unwrap_or_default();
+
+        if self.verbose {
+            debug_log!(
+                "[GIT] 完成: {} (exit: {:?}, {} ms)",
+                description,
+                status.code(),
+                started.elapsed().as_millis()
+            );
+        }
+
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+/// 为 `Command` 提供带超时/取消的输出方法，便于替换原有的 `.output()` 调用
+pub trait GitCommandExt {
+    fn output_with(&mut self, runner: &GitProcessRunner) -> io::Result<Output>;
+}
+
+impl GitCommandExt for Command {
+    fn output_with(&mut self, runner: &GitProcessRunner) -> io::Result<Output> {
+        runner.output(self)
+    }
+}
+
+/// 正在执行的Git操作登记表：前端传入 operation_id，取消命令据此触发对应令牌
+#[derive(Debug, Default)]
+pub struct GitOperationRegistry {
+    operations: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl GitOperationRegistry {
+    /// 登记操作并返回其取消令牌，同名操作会被替换
+    pub fn register(&self, operation_id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        if let Ok(mut operations) = self.operations.lock() {
+            operations.insert(operation_id.to_string(), token.clone());
+        }
+        token
+    }
+
+    /// 取消操作，返回是否找到该操作
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.operations.lock() {
+            Ok(operations) => match operations.get(operation_id) {
+                Some(token) => {
+                    token.cancel();
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    pub fn finish(&self, operation_id: &str) {
+        if let Ok(mut operations) = self.operations.lock() {
+            operations.remove(operation_id);
+        }
+    }
+}
+
+fn spawn_reader<R: Read + Send + 'static>(source: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut source) = source {
+            let _ = source.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+fn describe_command(command: &Command) -> String {
+    let mut parts = vec![command.get_program().to_string_lossy().to_string()];
+    parts.extend(
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string()),
+    );
+    parts.join(" ")
+}
+
+/// 让子进程成为新进程组的组长，超时后可以整组结束
+#[cfg(unix)]
+fn isolate_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn isolate_process_group(_command: &mut Command) {}
+
+/// 结束子进程及其派生的所有进程
+fn kill_process_tree(child: &mut Child) {
+    let pid = child.id().to_string();
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+        let _ = Command::new("taskkill")
+            .args(["/PID", pid.as_str(), "/T", "/F"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    #[cfg(unix)]
+    {
+        // 负 pid 表示整个进程组
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runner_kills_process_group_on_timeout() {
+        let runner = GitProcessRunner::default().with_timeout(Some(Duration::from_millis(200)));
+        let started = Instant::now();
+
+        let error = Command::new("sh")
+            .args(["-c", "sleep 5 & sleep 5"])
+            .output_with(&runner)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn runner_stops_when_cancelled() {
+        let registry = GitOperationRegistry::default();
+        let token = registry.register("op");
+        let runner = GitProcessRunner::default().with_cancel_token(Some(token));
+
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            registry.cancel("op")
+        });
+
+        let error = Command::new("sleep")
+            .arg("5")
+            .output_with(&runner)
+            .unwrap_err();
+
+        assert!(canceller.join().unwrap());
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn runner_collects_output() {
+        let output = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2; exit 3"])
+            .output_with(&GitProcessRunner::default())
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "out");
+        assert_eq!(String::from_utf8_lossy(&output.stderr).trim(), "err");
+    }
+}
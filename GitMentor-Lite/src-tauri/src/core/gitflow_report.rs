@@ -0,0 +1,270 @@
+use crate::core::branch_metadata;
+use crate::types::git_types::{AnalysisConfig, GitflowReport, GitflowReportContext, Report};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+// Gitflow AI 报告：用版本化模板渲染分支上下文，要求模型返回 JSON 章节并解析
+// Author: Evilek, Date: 2025-10-06
+// 输出格式写在系统提示词里而不是模板里，用户改模板不会弄坏解析
+
+/// 报告的结构化章节
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ReportSections {
+    pub summary: String,
+    pub risks: Vec<String>,
+    pub timeline: Vec<String>,
+    #[serde(alias = "actionItems", alias = "action_items")]
+    pub action_items: Vec<String>,
+}
+
+/// 系统提示词：约定输出语言与 JSON 结构
+pub fn system_prompt(language: &str) -> String {
+    format!(
+        "你是熟悉 Gitflow 的研发负责人助手，负责根据分支数据撰写报告。\
+         请使用{}撰写内容，只依据提供的数据，不要编造提交或人员。\
+         只返回一个 JSON 对象，不要添加其他文字，结构为：\
+         {{\"summary\": \"段落文本\", \"risks\": [\"...\"], \"timeline\": [\"...\"], \"actionItems\": [\"...\"]}}",
+        language
+    )
+}
+
+/// 解析失败时追加的重试提示
+pub const REPAIR_PROMPT: &str =
+    "上面的回答不是有效的 JSON。请只返回包含 summary、risks、timeline、actionItems 四个字段的 JSON 对象。";
+
+/// 使用 Handlebars 渲染报告模板；模板可访问 GitflowReportContext 的字段（camelCase）以及 metadataLines、language
+pub fn render_prompt(
+    template: &str,
+    context: &GitflowReportContext,
+    language: &str,
+) -> Result<String> {
+    let mut data = serde_json::to_value(context)?;
+    if let Some(object) = data.as_object_mut() {
+        object.insert(
+            "metadataLines".to_string(),
+            serde_json::json!(branch_metadata::digest_lines(&context.metadata)),
+        );
+        object.insert("language".to_string(), serde_json::json!(language));
+    }
+
+    let mut registry = handlebars::Handlebars::new();
+    registry.register_escape_fn(handlebars::no_escape);
+    registry
+        .render_template(template, &data)
+        .map_err(|e| anyhow!("渲染报告模板失败: {}", e))
+}
+
+/// 解析模型输出：优先取 JSON（允许包在代码块里），否则按 Markdown 标题拆分章节
+pub fn parse_sections(response: &str) -> Option<ReportSections> {
+    let sections = parse_json(response).or_else(|| parse_markdown(response))?;
+    if sections.summary.trim().is_empty() {
+        return None;
+    }
+    Some(sections)
+}
+
+fn parse_json(response: &str) -> Option<ReportSections> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    if end <= start {
+        return None;
+    }
+    serde_json::from_str(&response[start..=end]).ok()
+}
+
+fn parse_markdown(response: &str) -> Option<ReportSections> {
+    let mut sections = ReportSections::default();
+    let mut current: Option<&str> = None;
+    let mut summary = Vec::new();
+
+    for line in response.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            let title = trimmed.trim_start_matches('#').trim().to_lowercase();
+            current = [
+                ("summary", ["summary", "摘要", "概述"]),
+                ("risks", ["risk", "风险", "问题"]),
+                ("timeline", ["timeline", "时间线", "过程"]),
+                ("action_items", ["action", "行动", "待办"]),
+            ]
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| title.contains(key)))
+            .map(|(section, _)| *section);
+            continue;
+        }
+
+        let item = strip_list_marker(trimmed);
+        if item.is_empty() {
+            continue;
+        }
+
+        match current {
+            Some("summary") => summary.push(trimmed.to_string()),
+            Some("risks") => sections.risks.push(item.to_string()),
+            Some("timeline") => sections.timeline.push(item.to_string()),
+            Some("action_items") => sections.action_items.push(item.to_string()),
+            _ => {}
+        }
+    }
+
+    sections.summary = summary.join("\n");
+    Some(sections)
+}
+
+/// 去掉 `- ` / `* ` / `1. ` 列表标记
+fn strip_list_marker(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return rest.trim();
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match line[digits..].strip_prefix(". ") {
+        Some(rest) if digits > 0 => rest.trim(),
+        _ => line,
+    }
+}
+
+/// 按章节拼出 Markdown 正文
+pub fn to_markdown(title: &str, sections: &ReportSections) -> String {
+    let mut content = format!("# {}\n\n## 摘要\n\n{}\n", title, sections.summary.trim());
+
+    for (heading, items) in [
+        ("风险", &sections.risks),
+        ("时间线", &sections.timeline),
+        ("行动项", &sections.action_items),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+        content.push_str(&format!("\n## {}\n\n", heading));
+        for item in items {
+            content.push_str(&format!("- {}\n", item));
+        }
+    }
+
+    content
+}
+
+/// 转换为报告存储使用的格式，日期范围取分支提交的起止日期
+pub fn to_saved_report(report: &GitflowReport, context: &GitflowReportContext) -> Report {
+    let date = |value: &String| value.get(..10).unwrap_or_default().to_string();
+    let end_date = context
+        .commits
+        .first()
+        .map(|commit| date(&commit.date))
+        .unwrap_or_else(|| date(&report.generated_at));
+    let start_date = context
+        .commits
+        .last()
+        .map(|commit| date(&commit.date))
+        .unwrap_or_else(|| end_date.clone());
+
+    let mut authors: Vec<String> = context.commits.iter().map(|c| c.author.clone()).collect();
+    authors.sort();
+    authors.dedup();
+
+    Report {
+        id: format!(
+            "gitflow-{}-{}",
+            report.kind.action().trim_start_matches("generate_"),
+            chrono::DateTime::parse_from_rfc3339(&report.generated_at)
+                .map(|time| time.timestamp_millis())
+                .unwrap_or_default()
+        ),
+        title: format!("{}：{}", report.kind.label(), report.branch_name),
+        content: report.content.clone(),
+        format: "markdown".to_string(),
+        created_at: report.generated_at.clone(),
+        config: AnalysisConfig {
+            repoPaths: vec![report.repo_path.clone()],
+            userEmails: authors,
+            startDate: start_date,
+            endDate: end_date,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::git_types::{
+        GitflowBranchMetadata, GitflowBranchType, GitflowDivergence, GitflowFileStat,
+        GitflowQaStatus, GitflowReportCommit,
+    };
+
+    fn context() -> GitflowReportContext {
+        GitflowReportContext {
+            repo_path: "/work/app".to_string(),
+            branch_name: "feature/login".to_string(),
+            branch_type: Some(GitflowBranchType::Feature),
+            base_branch: "develop".to_string(),
+            divergence: GitflowDivergence {
+                ahead: 2,
+                behind: 1,
+            },
+            metadata: GitflowBranchMetadata {
+                qa_status: Some(GitflowQaStatus::InProgress),
+                owner: Some("alice".to_string()),
+                ..Default::default()
+            },
+            commits: vec![
+                GitflowReportCommit {
+                    id: "b2c3d4e".to_string(),
+                    summary: "feat: add token refresh".to_string(),
+                    author: "alice".to_string(),
+                    date: "2025-10-05T10:00:00+08:00".to_string(),
+                },
+                GitflowReportCommit {
+                    id: "a1b2c3d".to_string(),
+                    summary: "feat: login form".to_string(),
+                    author: "bob".to_string(),
+                    date: "2025-10-03T09:00:00+08:00".to_string(),
+                },
+            ],
+            diffstat: vec![GitflowFileStat {
+                path: "src/auth.rs".to_string(),
+                insertions: 40,
+                deletions: 3,
+            }],
+            files_changed: 1,
+            insertions: 40,
+            deletions: 3,
+        }
+    }
+
+    #[test]
+    fn renders_builtin_template_with_branch_context() {
+        let prompt = render_prompt(
+            include_str!("../../templates/gitflow_status_report.hbs"),
+            &context(),
+            "简体中文",
+        )
+        .unwrap();
+
+        assert!(prompt.contains("**分支**: feature/login"));
+        assert!(prompt.contains("领先 2 个提交，落后 1 个提交"));
+        assert!(prompt.contains("- QA 状态：测试中"));
+        assert!(prompt.contains("- b2c3d4e feat: add token refresh（alice"));
+        assert!(prompt.contains("- src/auth.rs (+40 / -3)"));
+    }
+
+    #[test]
+    fn parses_fenced_json_and_markdown_fallback() {
+        let json = "```json\n{\"summary\": \"登录功能待联调\", \"risks\": [\"token 刷新未测试\"], \"actionItems\": [\"补充集成测试\"]}\n```";
+        let sections = parse_sections(json).unwrap();
+        assert_eq!(sections.summary, "登录功能待联调");
+        assert_eq!(sections.risks, vec!["token 刷新未测试"]);
+        assert!(sections.timeline.is_empty());
+        assert_eq!(sections.action_items, vec!["补充集成测试"]);
+
+        let markdown = "## 摘要\n登录功能待联调\n\n## 风险\n- token 刷新未测试\n\n## 行动项\n1. 补充集成测试\n";
+        assert_eq!(parse_sections(markdown), Some(sections.clone()));
+
+        assert_eq!(parse_sections("无法生成"), None);
+
+        let content = to_markdown("状态播报：feature/login", &sections);
+        assert!(content.starts_with("# 状态播报：feature/login\n\n## 摘要\n\n登录功能待联调\n"));
+        assert!(content.contains("\n## 风险\n\n- token 刷新未测试\n"));
+        assert!(!content.contains("时间线"));
+    }
+}
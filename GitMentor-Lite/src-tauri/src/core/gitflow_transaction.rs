@@ -0,0 +1,360 @@
+use crate::types::git_types::{GitOperationResult, GitflowStepLog, GitflowStepStatus};
+use anyhow::{anyhow, Result};
+use git2::{Oid, Repository, Signature, StashApplyOptions, StashFlags, StatusOptions};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+// Gitflow 操作事务：执行前记录 HEAD、本地分支/标签引用并自动储藏未提交修改，
+// 任一步骤失败时原子地恢复引用、工作区与储藏
+// Author: Evilek, Date: 2025-10-05
+// 远程推送/删除无法撤销，回滚时在步骤日志中列出，由用户决定是否手动处理
+
+const AUTO_STASH_MESSAGE: &str = "GitMentor gitflow auto-stash";
+
+/// 可写入步骤日志的输出
+pub trait StepOutput {
+    fn summary(&self) -> String;
+}
+
+impl StepOutput for () {
+    fn summary(&self) -> String {
+        String::new()
+    }
+}
+
+impl StepOutput for String {
+    fn summary(&self) -> String {
+        self.trim().to_string()
+    }
+}
+
+impl StepOutput for GitOperationResult {
+    fn summary(&self) -> String {
+        self.details.clone().unwrap_or_else(|| self.message.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum HeadSnapshot {
+    Branch(String), // 完整引用名，如 refs/heads/develop；也覆盖尚无提交的分支
+    Detached(Oid),
+}
+
+/// 开始时的仓库快照；只读操作没有快照，失败时也不触碰仓库
+struct RepoSnapshot {
+    head: HeadSnapshot,
+    refs: BTreeMap<String, Oid>,
+    /// 开始时工作区是否干净（无修改、无未跟踪文件）
+    worktree_clean: bool,
+}
+
+pub struct GitflowTransaction {
+    repo_path: PathBuf,
+    snapshot: Option<RepoSnapshot>,
+    stash: Option<Oid>,
+    steps: Vec<GitflowStepLog>,
+    remote_changes: Vec<String>,
+}
+
+impl GitflowTransaction {
+    /// 只记录步骤日志，不做快照与储藏，失败时不回滚（只读操作）
+    pub fn log_only(repo_path: &str) -> Self {
+        Self {
+            repo_path: PathBuf::from(repo_path),
+            snapshot: None,
+            stash: None,
+            steps: Vec::new(),
+            remote_changes: Vec::new(),
+        }
+    }
+
+    /// 记录快照；工作区有修改（含未跟踪文件）时先自动储藏
+    pub fn begin(repo_path: &str) -> Result<Self> {
+        let mut repo = Repository::open(repo_path)?;
+
+        let head = match repo.head() {
+            Ok(head) if head.is_branch() => HeadSnapshot::Branch(
+                head.name()
+                    .ok_or_else(|| anyhow!("当前分支名不是有效的 UTF-8"))?
+                    .to_string(),
+            ),
+            Ok(head) => {
+                HeadSnapshot::Detached(head.target().ok_or_else(|| anyhow!("无法解析当前 HEAD"))?)
+            }
+            Err(_) => {
+                let target = repo.find_reference("HEAD")?;
+                HeadSnapshot::Branch(target.symbolic_target().unwrap_or("HEAD").to_string())
+            }
+        };
+
+        let refs = collect_refs(&repo)?;
+        let worktree_clean = !has_local_changes(&repo)?;
+
+        let mut transaction = Self {
+            repo_path: PathBuf::from(repo_path),
+            snapshot: Some(RepoSnapshot {
+                head,
+                refs,
+                worktree_clean,
+            }),
+            stash: None,
+            steps: Vec::new(),
+            remote_changes: Vec::new(),
+        };
+
+        if !worktree_clean {
+            let signature = repo
+                .signature()
+                .or_else(|_| Signature::now("GitMentor", "gitmentor@localhost"))?;
+
+            let stash = repo.stash_save(
+                &signature,
+                AUTO_STASH_MESSAGE,
+                Some(StashFlags::INCLUDE_UNTRACKED),
+            );
+
+            match stash {
+                Ok(oid) => {
+                    transaction.stash = Some(oid);
+                    transaction.record("自动储藏未提交的修改", GitflowStepStatus::Succeeded, "");
+                }
+                Err(e) => return Err(anyhow!("自动储藏未提交的修改失败: {}", e)),
+            }
+        }
+
+        Ok(transaction)
+    }
+
+    /// 执行一个本地步骤并记录结果
+    pub fn step<T: StepOutput>(
+        &mut self,
+        name: impl Into<String>,
+        action: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let name = name.into();
+        match action() {
+            Ok(value) => {
+                self.record(&name, GitflowStepStatus::Succeeded, &value.summary());
+                Ok(value)
+            }
+            Err(e) => {
+                self.record(&name, GitflowStepStatus::Failed, &e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// 执行一个会修改远程的步骤；成功后记为无法回滚的远程变更
+    pub fn remote_step<T: StepOutput>(
+        &mut self,
+        name: impl Into<String>,
+        action: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let name = name.into();
+        let value = self.step(name.clone(), action)?;
+        self.remote_changes.push(name);
+        Ok(value)
+    }
+
+    pub fn skip(&mut self, name: impl Into<String>, reason: &str) {
+        self.record(&name.into(), GitflowStepStatus::Skipped, reason);
+    }
+
+    /// 成功结束：restore_stash 为 true 时把自动储藏的修改恢复到当前分支
+    pub fn commit(mut self, restore_stash: bool) -> Vec<GitflowStepLog> {
+        if let Some(stash) = self.stash {
+            if restore_stash {
+                let result = Repository::open(&self.repo_path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|mut repo| pop_stash(&mut repo, stash));
+                match result {
+                    Ok(()) => self.record("恢复自动储藏的修改", GitflowStepStatus::Succeeded, ""),
+                    Err(e) => self.record(
+                        "恢复自动储藏的修改",
+                        GitflowStepStatus::Failed,
+                        &format!("{}，修改仍保留在储藏 {} 中", e, stash),
+                    ),
+                }
+            } else {
+                self.record(
+                    "恢复自动储藏的修改",
+                    GitflowStepStatus::Skipped,
+                    &format!("原分支已不存在，修改保留在储藏 {} 中", stash),
+                );
+            }
+        }
+
+        self.steps
+    }
+
+    /// 是否会在失败时回滚（只读操作不会）
+    pub fn can_roll_back(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// 失败结束：恢复引用、HEAD、工作区与储藏，返回包含回滚步骤的完整日志；
+    /// 只读操作直接返回已有日志
+    pub fn rollback(mut self) -> Vec<GitflowStepLog> {
+        let Some(snapshot) = self.snapshot.take() else {
+            return self.steps;
+        };
+
+        let result = Repository::open(&self.repo_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|repo| self.restore_refs_and_worktree(&repo, &snapshot));
+
+        match result {
+            Ok(restored) => self.record(
+                "回滚本地分支、标签与工作区",
+                GitflowStepStatus::Succeeded,
+                &restored,
+            ),
+            Err(e) => self.record(
+                "回滚本地分支、标签与工作区",
+                GitflowStepStatus::Failed,
+                &e.to_string(),
+            ),
+        }
+
+        if let Some(stash) = self.stash {
+            let result = Repository::open(&self.repo_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|mut repo| pop_stash(&mut repo, stash));
+            match result {
+                Ok(()) => self.record("恢复自动储藏的修改", GitflowStepStatus::Succeeded, ""),
+                Err(e) => self.record(
+                    "恢复自动储藏的修改",
+                    GitflowStepStatus::Failed,
+                    &format!("{}，修改仍保留在储藏 {} 中", e, stash),
+                ),
+            }
+        }
+
+        if !self.remote_changes.is_empty() {
+            let changes = self.remote_changes.join("；");
+            self.record(
+                "远程变更无法自动回滚",
+                GitflowStepStatus::Skipped,
+                &format!("以下操作已作用于远程，请按需手动处理：{}", changes),
+            );
+        }
+
+        self.steps
+    }
+
+    fn restore_refs_and_worktree(
+        &self,
+        repo: &Repository,
+        snapshot: &RepoSnapshot,
+    ) -> Result<String> {
+        repo.cleanup_state()?; // 清理未完成的合并
+
+        let current = collect_refs(repo)?;
+        let names: BTreeSet<&String> = current.keys().chain(snapshot.refs.keys()).collect();
+
+        let mut changed = Vec::new();
+        let mut transaction = repo.transaction()?;
+        for name in &names {
+            transaction.lock_ref(name)?;
+        }
+        for name in names {
+            match (snapshot.refs.get(name), current.get(name)) {
+                (Some(old), Some(new)) if old == new => {}
+                (Some(old), _) => {
+                    transaction.set_target(name, *old, None, "gitflow rollback")?;
+                    changed.push(name.clone());
+                }
+                (None, Some(_)) => {
+                    transaction.remove(name)?;
+                    changed.push(name.clone());
+                }
+                (None, None) => {}
+            }
+        }
+        transaction.commit()?;
+
+        match &snapshot.head {
+            HeadSnapshot::Branch(name) => repo.set_head(name)?,
+            HeadSnapshot::Detached(oid) => repo.set_head_detached(*oid)?,
+        }
+
+        // 只有开始时工作区本来干净或已储藏（稍后恢复）时，现有修改与未跟踪文件才都由本次操作产生，
+        // 可以强制重置；否则不碰工作区，避免删掉用户的修改
+        if !snapshot.worktree_clean && self.stash.is_none() {
+            return Ok(format!(
+                "{}；工作区含未储藏的修改，未重置工作区",
+                restored_summary(&changed)
+            ));
+        }
+
+        if let Ok(head_commit) = repo.head().and_then(|head| head.peel_to_commit()) {
+            let mut checkout = git2::build::CheckoutBuilder::new();
+            checkout.force().remove_untracked(true);
+            repo.reset(
+                head_commit.as_object(),
+                git2::ResetType::Hard,
+                Some(&mut checkout),
+            )?;
+        }
+
+        Ok(restored_summary(&changed))
+    }
+
+    fn record(&mut self, step: &str, status: GitflowStepStatus, output: &str) {
+        self.steps.push(GitflowStepLog {
+            step: step.to_string(),
+            status,
+            output: output.to_string(),
+        });
+    }
+}
+
+fn restored_summary(changed: &[String]) -> String {
+    if changed.is_empty() {
+        "引用未发生变化".to_string()
+    } else {
+        format!("已恢复：{}", changed.join("、"))
+    }
+}
+
+/// 本地分支与标签（不含符号引用）
+fn collect_refs(repo: &Repository) -> Result<BTreeMap<String, Oid>> {
+    let mut refs = BTreeMap::new();
+    for pattern in ["refs/heads/*", "refs/tags/*"] {
+        for reference in repo.references_glob(pattern)? {
+            let reference = reference?;
+            if let (Some(name), Some(target)) = (reference.name(), reference.target()) {
+                refs.insert(name.to_string(), target);
+            }
+        }
+    }
+    Ok(refs)
+}
+
+fn has_local_changes(repo: &Repository) -> Result<bool> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    Ok(!repo.statuses(Some(&mut options))?.is_empty())
+}
+
+/// 按 oid 找到自动储藏并弹出，保留暂存区状态
+fn pop_stash(repo: &mut Repository, stash: Oid) -> Result<()> {
+    let mut index = None;
+    repo.stash_foreach(|position, _, oid| {
+        if *oid == stash {
+            index = Some(position);
+            false
+        } else {
+            true
+        }
+    })?;
+
+    let index = index.ok_or_else(|| anyhow!("未找到自动储藏 {}", stash))?;
+    let mut options = StashApplyOptions::new();
+    options.reinstantiate_index();
+    repo.stash_pop(index, Some(&mut options))?;
+    Ok(())
+}
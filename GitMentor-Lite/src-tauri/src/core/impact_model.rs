@@ -0,0 +1,587 @@
+use crate::core::repo_workspace::{canonical_repo_path, repo_id_for_path};
+use crate::types::git_types::{
+    CommitDiffResult, DiffLineType, FileChangeType, ImpactAssessment, ImpactCategory, ImpactFactor,
+    ImpactLevel, ImpactRules,
+};
+use anyhow::{anyhow, Context, Result};
+use git2::{DiffOptions, Repository};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 提交影响模型：按路径分类、改动量、公共接口、依赖清单、热点文件和被删除的测试打分
+// Author: Evilek, Date: 2025-10-08
+// 每个信号都记录为 ImpactFactor，分析结果里能看到分数怎么来的
+
+/// 随仓库提交共享的规则文件，只从 HEAD 读取，不会写入工作区
+pub const SHARED_RULES_FILE: &str = ".gitmentor/impact-rules.json";
+
+/// 本机保存的规则放在应用数据目录，按仓库 id 区分
+pub fn rules_path(base_dir: &Path, repo_path: &str) -> Result<PathBuf> {
+    let canonical = canonical_repo_path(repo_path)?;
+    Ok(base_dir
+        .join(".gitmentor")
+        .join("impact-rules")
+        .join(format!("{}.json", repo_id_for_path(&canonical))))
+}
+
+/// 读取仓库的评分规则：优先本机保存的规则，其次 HEAD 中提交的共享规则，都没有时使用默认规则
+pub fn load_rules(base_dir: &Path, repo_path: &str) -> Result<ImpactRules> {
+    let path = rules_path(base_dir, repo_path)?;
+    let (content, source) = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("读取影响评分规则失败: {}", path.display()))?;
+        (content, path.display().to_string())
+    } else {
+        match committed_rules(repo_path)? {
+            Some(content) => (content, format!("HEAD:{}", SHARED_RULES_FILE)),
+            None => return Ok(ImpactRules::default()),
+        }
+    };
+
+    let rules: ImpactRules = serde_json::from_str(&content)
+        .with_context(|| format!("解析影响评分规则失败: {}", source))?;
+    CompiledRules::new(&rules)?;
+    Ok(rules)
+}
+
+/// 校验正则后保存规则到应用数据目录
+pub fn save_rules(base_dir: &Path, repo_path: &str, rules: &ImpactRules) -> Result<()> {
+    CompiledRules::new(rules)?;
+    let path = rules_path(base_dir, repo_path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(rules)?)?;
+    Ok(())
+}
+
+fn committed_rules(repo_path: &str) -> Result<Option<String>> {
+    let repo = Repository::discover(repo_path)?;
+    // 尚无提交或 HEAD 中没有规则文件
+    let Ok(tree) = repo.head().and_then(|head| head.peel_to_tree()) else {
+        return Ok(None);
+    };
+    let Ok(entry) = tree.get_path(Path::new(SHARED_RULES_FILE)) else {
+        return Ok(None);
+    };
+
+    let blob = entry.to_object(&repo)?.peel_to_blob()?;
+    Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+}
+
+struct CompiledRules {
+    categories: Vec<(ImpactCategory, Vec<Regex>, u32)>,
+    api: Vec<Regex>,
+    tests: Vec<Regex>,
+}
+
+impl CompiledRules {
+    fn new(rules: &ImpactRules) -> Result<Self> {
+        let categories = rules
+            .categories
+            .iter()
+            .map(|rule| Ok((rule.category, compile(&rule.patterns)?, rule.weight)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            categories,
+            api: compile(&rules.api_patterns)?,
+            tests: compile(&rules.test_patterns)?,
+        })
+    }
+
+    /// 文件归入第一个匹配的分类
+    fn category_of(&self, path: &str) -> Option<(ImpactCategory, u32)> {
+        self.categories
+            .iter()
+            .find(|(_, patterns, _)| patterns.iter().any(|pattern| pattern.is_match(path)))
+            .map(|(category, _, weight)| (*category, *weight))
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(|e| anyhow!("无效的正则 {}: {}", pattern, e)))
+        .collect()
+}
+
+/// 统计 paths 在 commit 之前 window 个提交（沿第一父提交）中的修改次数
+pub fn change_frequency(
+    repo: &Repository,
+    commit_id: &str,
+    paths: &[String],
+    window: usize,
+) -> Result<HashMap<String, u32>> {
+    let mut counts = HashMap::new();
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+    if paths.is_empty() || window == 0 || commit.parent_count() == 0 {
+        return Ok(counts);
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(commit.parent_id(0)?)?;
+    revwalk.simplify_first_parent()?;
+
+    for oid in revwalk.take(window) {
+        let current = repo.find_commit(oid?)?;
+        let parent_tree = match current.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let mut options = DiffOptions::new();
+        for path in paths {
+            options.pathspec(path);
+        }
+        options.disable_pathspec_match(true);
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&current.tree()?),
+            Some(&mut options),
+        )?;
+
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path().or_else(|| delta.old_file().path()) {
+                *counts
+                    .entry(path.to_string_lossy().replace('\\', "/"))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+/// 按规则评估提交影响；history 为 change_frequency 的结果
+pub fn assess(
+    rules: &ImpactRules,
+    diff: &CommitDiffResult,
+    history: &HashMap<String, u32>,
+) -> Result<ImpactAssessment> {
+    let compiled = CompiledRules::new(rules)?;
+    let mut factors = Vec::new();
+
+    // 路径分类：每个分类只计一次
+    let mut categories: BTreeMap<String, (ImpactCategory, u32, Vec<&str>)> = BTreeMap::new();
+    for file in &diff.files {
+        if let Some((category, weight)) = compiled.category_of(&file.file_path) {
+            categories
+                .entry(category_key(category).to_string())
+                .or_insert((category, weight, Vec::new()))
+                .2
+                .push(&file.file_path);
+        }
+    }
+    for (key, (category, weight, files)) in &categories {
+        factors.push(ImpactFactor {
+            signal: format!("category:{}", key),
+            points: *weight,
+            detail: format!(
+                "{} {} 个：{}",
+                category_label(*category),
+                files.len(),
+                preview(files)
+            ),
+        });
+    }
+
+    // 改动量：取达到的最高档
+    let churn = diff.insertions + diff.deletions;
+    if let Some(step) = rules
+        .churn_steps
+        .iter()
+        .filter(|step| churn >= step.lines)
+        .max_by_key(|step| step.lines)
+    {
+        factors.push(ImpactFactor {
+            signal: "churn".to_string(),
+            points: step.points,
+            detail: format!(
+                "改动 {} 行（+{} / -{}），达到 {} 行档位",
+                churn, diff.insertions, diff.deletions, step.lines
+            ),
+        });
+    }
+
+    // 公共接口：测试文件之外新增或删除的公共声明
+    let mut api_changes = 0u32;
+    let mut api_files = Vec::new();
+    for file in &diff.files {
+        if compiled
+            .category_of(&file.file_path)
+            .map(|(category, _)| category)
+            == Some(ImpactCategory::Tests)
+        {
+            continue;
+        }
+        let count = changed_lines(file)
+            .filter(|(_, content)| compiled.api.iter().any(|pattern| pattern.is_match(content)))
+            .count() as u32;
+        if count > 0 {
+            api_changes += count;
+            api_files.push(file.file_path.as_str());
+        }
+    }
+    if api_changes > 0 {
+        factors.push(ImpactFactor {
+            signal: "public_api".to_string(),
+            points: (api_changes * rules.api_weight).min(rules.api_max_points),
+            detail: format!(
+                "公共接口声明变更 {} 处：{}",
+                api_changes,
+                preview(&api_files)
+            ),
+        });
+    }
+
+    // 依赖清单
+    let manifests: Vec<&str> = diff
+        .files
+        .iter()
+        .map(|file| file.file_path.as_str())
+        .filter(|path| {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            rules
+                .dependency_manifests
+                .iter()
+                .any(|manifest| manifest == name)
+        })
+        .collect();
+    if !manifests.is_empty() {
+        factors.push(ImpactFactor {
+            signal: "dependencies".to_string(),
+            points: rules.dependency_weight,
+            detail: format!("依赖清单变更：{}", preview(&manifests)),
+        });
+    }
+
+    // 热点文件
+    let mut hot_files: Vec<(&str, u32)> = diff
+        .files
+        .iter()
+        .filter_map(|file| {
+            let count = *history.get(&file.file_path)?;
+            (count >= rules.hot_file_min_changes).then_some((file.file_path.as_str(), count))
+        })
+        .collect();
+    hot_files.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    if !hot_files.is_empty() {
+        let listed: Vec<String> = hot_files
+            .iter()
+            .map(|(path, count)| format!("{}（{} 次）", path, count))
+            .collect();
+        factors.push(ImpactFactor {
+            signal: "hot_files".to_string(),
+            points: (hot_files.len() as u32 * rules.hot_file_weight).min(rules.hot_file_max_points),
+            detail: format!(
+                "最近 {} 个提交中频繁修改的文件：{}",
+                rules.hot_file_window,
+                preview(&listed.iter().map(String::as_str).collect::<Vec<_>>())
+            ),
+        });
+    }
+
+    // 被删除的测试：删除测试文件，或测试声明的删除多于新增
+    let mut deleted_tests = Vec::new();
+    for file in &diff.files {
+        let is_test_file = compiled
+            .category_of(&file.file_path)
+            .map(|(category, _)| category)
+            == Some(ImpactCategory::Tests);
+        if is_test_file && matches!(file.change_type, FileChangeType::Deleted) {
+            deleted_tests.push(format!("{}（文件已删除）", file.file_path));
+            continue;
+        }
+
+        let (mut removed, mut added) = (0i64, 0i64);
+        for (line_type, content) in changed_lines(file) {
+            if compiled
+                .tests
+                .iter()
+                .any(|pattern| pattern.is_match(content))
+            {
+                match line_type {
+                    DiffLineType::Delete => removed += 1,
+                    _ => added += 1,
+                }
+            }
+        }
+        if removed > added {
+            deleted_tests.push(format!(
+                "{}（减少 {} 个测试）",
+                file.file_path,
+                removed - added
+            ));
+        }
+    }
+    if !deleted_tests.is_empty() {
+        factors.push(ImpactFactor {
+            signal: "deleted_tests".to_string(),
+            points: rules.deleted_test_weight,
+            detail: format!(
+                "删除测试：{}",
+                preview(&deleted_tests.iter().map(String::as_str).collect::<Vec<_>>())
+            ),
+        });
+    }
+
+    let score = factors.iter().map(|factor| factor.points).sum();
+    let level = if score >= rules.critical_threshold {
+        ImpactLevel::Critical
+    } else if score >= rules.high_threshold {
+        ImpactLevel::High
+    } else if score >= rules.medium_threshold {
+        ImpactLevel::Medium
+    } else {
+        ImpactLevel::Low
+    };
+
+    Ok(ImpactAssessment {
+        score,
+        level,
+        factors,
+    })
+}
+
+/// 根据评分依据生成标签
+pub fn tags(assessment: &ImpactAssessment) -> Vec<String> {
+    assessment
+        .factors
+        .iter()
+        .filter_map(|factor| match factor.signal.as_str() {
+            "category:core" => Some("核心代码"),
+            "category:config" => Some("配置变更"),
+            "category:tests" => Some("测试相关"),
+            "category:docs" => Some("文档更新"),
+            "category:migrations" => Some("数据库迁移"),
+            "public_api" => Some("公共接口变更"),
+            "dependencies" => Some("依赖变更"),
+            "hot_files" => Some("热点文件"),
+            "deleted_tests" => Some("删除测试"),
+            _ => None,
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn category_key(category: ImpactCategory) -> &'static str {
+    match category {
+        ImpactCategory::Core => "core",
+        ImpactCategory::Config => "config",
+        ImpactCategory::Tests => "tests",
+        ImpactCategory::Docs => "docs",
+        ImpactCategory::Migrations => "migrations",
+    }
+}
+
+fn category_label(category: ImpactCategory) -> &'static str {
+    match category {
+        ImpactCategory::Core => "核心代码文件",
+        ImpactCategory::Config => "配置文件",
+        ImpactCategory::Tests => "测试文件",
+        ImpactCategory::Docs => "文档文件",
+        ImpactCategory::Migrations => "数据库迁移",
+    }
+}
+
+fn changed_lines(
+    file: &crate::types::git_types::CommitFileDiff,
+) -> impl Iterator<Item = (DiffLineType, &str)> {
+    file.hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter(|line| !matches!(line.line_type, DiffLineType::Context))
+        .map(|line| (line.line_type.clone(), line.content.as_str()))
+}
+
+/// 列出前 3 项，其余以“等 N 个”表示
+fn preview(items: &[&str]) -> String {
+    let mut text = items.iter().take(3).copied().collect::<Vec<_>>().join(", ");
+    if items.len() > 3 {
+        text.push_str(&format!(" 等 {} 个", items.len()));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::{commit_files, init_repo};
+    use crate::types::git_types::{CommitFileDiff, DiffHunk, DiffLine};
+
+    fn file(
+        path: &str,
+        change_type: FileChangeType,
+        lines: &[(DiffLineType, &str)],
+    ) -> CommitFileDiff {
+        CommitFileDiff {
+            file_path: path.to_string(),
+            old_path: None,
+            change_type,
+            similarity: None,
+            file_language: None,
+            is_binary: false,
+            insertions: lines
+                .iter()
+                .filter(|(t, _)| matches!(t, DiffLineType::Insert))
+                .count() as u32,
+            deletions: lines
+                .iter()
+                .filter(|(t, _)| matches!(t, DiffLineType::Delete))
+                .count() as u32,
+            hunks: vec![DiffHunk {
+                old_start: 1,
+                old_lines: 0,
+                new_start: 1,
+                new_lines: 0,
+                lines: lines
+                    .iter()
+                    .map(|(line_type, content)| DiffLine {
+                        line_type: line_type.clone(),
+                        content: content.to_string(),
+                        old_line_number: None,
+                        new_line_number: None,
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    fn diff(files: Vec<CommitFileDiff>) -> CommitDiffResult {
+        CommitDiffResult {
+            commit_id: "abc".to_string(),
+            parent_id: None,
+            parent_count: 1,
+            files_changed: files.len() as u32,
+            insertions: files.iter().map(|f| f.insertions).sum(),
+            deletions: files.iter().map(|f| f.deletions).sum(),
+            files,
+        }
+    }
+
+    #[test]
+    fn bug_fix_in_docs_stays_low_while_core_api_changes_score_high() {
+        let rules = ImpactRules::default();
+
+        let docs_only = diff(vec![file(
+            "docs/guide.md",
+            FileChangeType::Modified,
+            &[(DiffLineType::Insert, "fix typo")],
+        )]);
+        let assessment = assess(&rules, &docs_only, &HashMap::new()).unwrap();
+        assert_eq!(assessment.level, ImpactLevel::Low);
+        assert_eq!(tags(&assessment), vec!["文档更新"]);
+
+        let risky = diff(vec![
+            file(
+                "src/core/engine.rs",
+                FileChangeType::Modified,
+                &[
+                    (DiffLineType::Delete, "pub fn run(&self) {"),
+                    (DiffLineType::Insert, "pub fn run(&self, force: bool) {"),
+                ],
+            ),
+            file(
+                "Cargo.toml",
+                FileChangeType::Modified,
+                &[(DiffLineType::Insert, "serde = \"1\"")],
+            ),
+            file(
+                "src/core/engine_test.rs",
+                FileChangeType::Modified,
+                &[
+                    (DiffLineType::Delete, "#[test]"),
+                    (DiffLineType::Delete, "fn runs() {}"),
+                ],
+            ),
+        ]);
+        let history = HashMap::from([("src/core/engine.rs".to_string(), 9)]);
+        let assessment = assess(&rules, &risky, &history).unwrap();
+
+        let signals: Vec<(&str, u32)> = assessment
+            .factors
+            .iter()
+            .map(|factor| (factor.signal.as_str(), factor.points))
+            .collect();
+        assert_eq!(
+            signals,
+            vec![
+                ("category:config", 10),
+                ("category:core", 20),
+                ("category:tests", 0),
+                ("public_api", 10),
+                ("dependencies", 15),
+                ("hot_files", 5),
+                ("deleted_tests", 20),
+            ]
+        );
+        assert_eq!(assessment.score, 80);
+        assert_eq!(assessment.level, ImpactLevel::Critical);
+        assert!(assessment.factors[5]
+            .detail
+            .contains("src/core/engine.rs（9 次）"));
+    }
+
+    #[test]
+    fn rules_round_trip_per_repository_and_reject_bad_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("app");
+        let repo_dir = dir.path().join("repo");
+        let other_dir = dir.path().join("other");
+        init_repo(&repo_dir);
+        init_repo(&other_dir);
+        let repo_path = repo_dir.to_str().unwrap();
+        assert_eq!(
+            load_rules(&app_dir, repo_path).unwrap(),
+            ImpactRules::default()
+        );
+
+        let rules = ImpactRules {
+            high_threshold: 30,
+            ..Default::default()
+        };
+        save_rules(&app_dir, repo_path, &rules).unwrap();
+        assert_eq!(load_rules(&app_dir, repo_path).unwrap().high_threshold, 30);
+        assert_eq!(
+            load_rules(&app_dir, other_dir.to_str().unwrap()).unwrap(),
+            ImpactRules::default()
+        );
+        // 规则不写入仓库工作区
+        assert!(!repo_dir.join(".gitmentor").exists());
+
+        let broken = ImpactRules {
+            api_patterns: vec!["(".to_string()],
+            ..Default::default()
+        };
+        assert!(save_rules(&app_dir, repo_path, &broken).is_err());
+    }
+
+    #[test]
+    fn reads_committed_shared_rules_from_head_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("app");
+        let repo_dir = dir.path().join("repo");
+        init_repo(&repo_dir);
+        let repo_path = repo_dir.to_str().unwrap();
+
+        let shared = serde_json::to_string(&ImpactRules {
+            high_threshold: 40,
+            ..Default::default()
+        })
+        .unwrap();
+        commit_files(&repo_dir, &[(SHARED_RULES_FILE, &shared)], "share rules");
+        // 未提交的修改不生效
+        fs::write(repo_dir.join(SHARED_RULES_FILE), "{ not json").unwrap();
+        assert_eq!(load_rules(&app_dir, repo_path).unwrap().high_threshold, 40);
+
+        let local = ImpactRules {
+            high_threshold: 25,
+            ..Default::default()
+        };
+        save_rules(&app_dir, repo_path, &local).unwrap();
+        assert_eq!(load_rules(&app_dir, repo_path).unwrap().high_threshold, 25);
+    }
+}
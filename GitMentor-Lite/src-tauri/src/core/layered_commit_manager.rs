@@ -1,3 +1,4 @@
+use crate::debug_log;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -9,9 +10,12 @@ use crate::core::ai_manager::AIManager;
 use crate::core::ai_provider::{AIRequest, ChatMessage};
 use crate::core::conversation_logger::StepInfo;
 use crate::core::git_engine::GitEngine;
-use crate::core::prompt_manager::{CommitContext, PromptManager};
+use crate::core::prompt_manager::{parse_issue_keys, CommitContext};
 use crate::utils::token_counter::TokenCounter;
 
+/// 分层提交上下文中附带的最近提交数量
+const RECENT_COMMIT_LIMIT: usize = 10;
+
 /**
  * 分层提交管理器
  * 作者：Evilek
@@ -147,9 +151,12 @@ impl LayeredCommitManager {
         let session_id = Uuid::new_v4().to_string();
         let start_time = std::time::Instant::now();
 
+        // 各阶段共享的上下文：全局语言设置、真实分支名与仓库信息
+        let base_context = self.build_base_context(&staged_files, branch_name).await;
+
         // 第一步：获取每个文件的diff
         let files_with_diffs = self
-            .get_files_with_diffs(&staged_files, template_id)
+            .get_files_with_diffs(&staged_files, template_id, &base_context.language)
             .await?;
         let total_files = files_with_diffs.len();
 
@@ -184,6 +191,7 @@ impl LayeredCommitManager {
                     file_path,
                     diff_content,
                     template_id,
+                    &base_context,
                     &session_id,
                     index as u32 + 1,
                     total_files as u32,
@@ -206,7 +214,7 @@ impl LayeredCommitManager {
             .generate_final_commit_message_with_stream(
                 template_id,
                 &file_summaries,
-                branch_name,
+                &base_context,
                 &session_id,
                 repository_path.clone(),
                 &progress_callback,
@@ -235,6 +243,7 @@ impl LayeredCommitManager {
         &self,
         staged_files: &[String],
         template_id: &str,
+        language: &str,
     ) -> Result<Vec<(String, String)>> {
         let git_engine = self.git_engine.read().await;
         let mut files_with_diffs = Vec::new();
@@ -252,6 +261,7 @@ impl LayeredCommitManager {
                                 &actual_path,
                                 &diff_content,
                                 template_id,
+                                language,
                             )
                             .await?;
                         files_with_diffs.push((actual_path, truncated_content));
@@ -275,6 +285,7 @@ impl LayeredCommitManager {
                                 &actual_path,
                                 &diff_content,
                                 template_id,
+                                language,
                             )
                             .await?;
                         for (index, content) in split_contents.into_iter().enumerate() {
@@ -313,6 +324,7 @@ impl LayeredCommitManager {
         file_path: &str,
         diff_content: &str,
         template_id: &str,
+        base_context: &CommitContext,
         session_id: &str,
         step_index: u32,
         total_steps: u32,
@@ -322,26 +334,14 @@ impl LayeredCommitManager {
     where
         F: Fn(LayeredCommitProgress) + Send + Sync,
     {
-        let ai_manager = self.ai_manager.read().await;
-        let config = ai_manager.get_config().await;
-
-        // 使用统一的模板系统生成提示词（重构优化）
-        // Author: Evilek, Date: 2025-01-08
+        // 使用用户持久化的模板库生成提示词，自定义/编辑过的模板同样生效
         // 移除批量文件处理逻辑，改为单文件独立处理 - Author: Evilek, Date: 2025-01-09
-        let context = CommitContext {
-            diff: diff_content.to_string(),
-            staged_files: vec![file_path.to_string()],
-            branch_name: None,
-            commit_type: None,
-            max_length: None,
-            language: "zh-CN".to_string(),
-        };
+        let messages = self
+            .build_file_analysis_messages(template_id, file_path, diff_content, base_context)
+            .await?;
 
-        // 使用PromptManager生成消息，统一模板系统
-        let prompt_manager = PromptManager::new();
-        let messages = prompt_manager
-            .generate_file_analysis_messages(template_id, file_path, diff_content, &context)
-            .map_err(|e| anyhow::anyhow!("生成文件分析消息失败: {}", e))?;
+        let ai_manager = self.ai_manager.read().await;
+        let config = ai_manager.get_config().await;
 
         // 转换为AIRequest格式，移除max_tokens限制确保完整输出 - Author: Evilek, Date: 2025-01-10
         let request = AIRequest {
@@ -402,6 +402,16 @@ impl LayeredCommitManager {
             }
         };
 
+        // 按提交规范校验最终消息，必要时重新提示或自动修正
+        let (response, _) = ai_manager
+            .enforce_commit_lint(
+                Some(template_id),
+                &request,
+                response,
+                &base_context.issue_keys,
+            )
+            .await;
+
         let processing_time = start_time.elapsed().as_millis() as u64;
 
         // 模拟流式显示AI的真实响应内容 - Author: Evilek, Date: 2025-01-10
@@ -484,31 +494,20 @@ impl LayeredCommitManager {
         file_path: &str,
         diff_content: &str,
         template_id: &str,
+        base_context: &CommitContext,
         session_id: &str,
         step_index: u32,
         total_steps: u32,
         repository_path: Option<String>,
     ) -> Result<SingleFileResult> {
-        let ai_manager = self.ai_manager.read().await;
-        let config = ai_manager.get_config().await;
-
-        // 使用统一的模板系统生成提示词（重构优化）
-        // Author: Evilek, Date: 2025-01-08
+        // 使用用户持久化的模板库生成提示词，自定义/编辑过的模板同样生效
         // 移除批量文件处理逻辑，改为单文件独立处理 - Author: Evilek, Date: 2025-01-09
-        let context = CommitContext {
-            diff: diff_content.to_string(),
-            staged_files: vec![file_path.to_string()],
-            branch_name: None,
-            commit_type: None,
-            max_length: None,
-            language: Self::convert_ai_language_to_code(&config.base.language),
-        };
+        let messages = self
+            .build_file_analysis_messages(template_id, file_path, diff_content, base_context)
+            .await?;
 
-        // 使用PromptManager生成消息，统一模板系统
-        let prompt_manager = PromptManager::new();
-        let messages = prompt_manager
-            .generate_file_analysis_messages(template_id, file_path, diff_content, &context)
-            .map_err(|e| anyhow::anyhow!("生成文件分析消息失败: {}", e))?;
+        let ai_manager = self.ai_manager.read().await;
+        let config = ai_manager.get_config().await;
 
         // 转换为AIRequest格式 - Author: Evilek, Date: 2025-01-10
         let request = AIRequest {
@@ -564,7 +563,7 @@ impl LayeredCommitManager {
         &self,
         template_id: &str,
         file_summaries: &[FileSummary],
-        _branch_name: Option<String>,
+        base_context: &CommitContext,
         session_id: &str,
         repository_path: Option<String>,
         progress_callback: &F,
@@ -572,42 +571,11 @@ impl LayeredCommitManager {
     where
         F: Fn(LayeredCommitProgress) + Send + Sync,
     {
-        let ai_manager = self.ai_manager.read().await;
-
-        // 构建汇总的diff内容
-        let summary_content = file_summaries
-            .iter()
-            .map(|fs| format!("文件: {}\n摘要: {}", fs.file_path, fs.summary))
-            .collect::<Vec<_>>()
-            .join("\n\n");
-
-        // 获取模板内容并构建最终总结的提示词
-        let prompt_manager = ai_manager.get_prompt_manager().await;
-        let _template = prompt_manager
-            .get_template(template_id)
-            .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", template_id))?;
-
-        // 构建上下文
-        let context = CommitContext {
-            diff: summary_content.clone(),
-            staged_files: file_summaries
-                .iter()
-                .map(|fs| fs.file_path.clone())
-                .collect(),
-            branch_name: None,
-            commit_type: None,
-            max_length: None,
-            language: "zh-CN".to_string(),
-        };
+        let messages = self
+            .build_summary_messages(template_id, file_summaries, base_context)
+            .await?;
 
-        // 使用统一生成的消息（重构优化）
-        let file_summary_strs: Vec<&str> = file_summaries
-            .iter()
-            .map(|fs| fs.summary.as_str())
-            .collect();
-        let messages = prompt_manager
-            .generate_summary_messages(template_id, &context, &file_summary_strs)
-            .map_err(|e| anyhow::anyhow!("生成总结消息失败: {}", e))?;
+        let ai_manager = self.ai_manager.read().await;
 
         // 使用统一生成的消息（重构优化），移除max_tokens限制 - Author: Evilek, Date: 2025-01-10
         let config = ai_manager.get_config().await;
@@ -752,48 +720,15 @@ impl LayeredCommitManager {
         &self,
         template_id: &str,
         file_summaries: &[FileSummary],
-        _branch_name: Option<String>,
+        base_context: &CommitContext,
         session_id: &str,
         repository_path: Option<String>,
     ) -> Result<FinalCommitResult> {
-        let ai_manager = self.ai_manager.read().await;
-
-        // 构建汇总的diff内容
-        let summary_content = file_summaries
-            .iter()
-            .map(|fs| format!("文件: {}\n摘要: {}", fs.file_path, fs.summary))
-            .collect::<Vec<_>>()
-            .join("\n\n");
-
-        // 获取模板内容并构建最终总结的提示词
-        let prompt_manager = ai_manager.get_prompt_manager().await;
-        let _template = prompt_manager
-            .get_template(template_id)
-            .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", template_id))?;
-
-        // 构建临时的CommitContext用于语言处理
-        let temp_context = crate::core::prompt_manager::CommitContext {
-            diff: summary_content.clone(),
-            staged_files: file_summaries
-                .iter()
-                .map(|fs| fs.file_path.clone())
-                .collect(),
-            branch_name: _branch_name.clone(),
-            commit_type: None,
-            max_length: None,
-            language: {
-                // 从AI配置中获取语言设置
-                let config = ai_manager.get_config().await;
-                Self::convert_ai_language_to_code(&config.base.language)
-            },
-        };
+        let messages = self
+            .build_summary_messages(template_id, file_summaries, base_context)
+            .await?;
 
-        // 使用统一的总结消息生成系统（重构优化）
-        // Author: Evilek, Date: 2025-01-08
-        let file_summaries: Vec<&str> = file_summaries.iter().map(|s| s.summary.as_str()).collect();
-        let messages = prompt_manager
-            .generate_summary_messages(template_id, &temp_context, &file_summaries)
-            .map_err(|e| anyhow::anyhow!("生成总结消息失败: {}", e))?;
+        let ai_manager = self.ai_manager.read().await;
 
         // 使用统一生成的消息（重构优化）
         let config = ai_manager.get_config().await;
@@ -838,6 +773,120 @@ impl LayeredCommitManager {
         })
     }
 
+    /// 构建各阶段共享的提交上下文：全局语言设置、真实分支名（调用方未传入时从仓库读取）与仓库信息
+    /// 作者：Evilek
+    /// 编写日期：2025-10-03
+    async fn build_base_context(
+        &self,
+        staged_files: &[String],
+        branch_name: Option<String>,
+    ) -> CommitContext {
+        let language = {
+            let ai_manager = self.ai_manager.read().await;
+            Self::convert_ai_language_to_code(&ai_manager.get_config().await.base.language)
+        };
+
+        let actual_files: Vec<String> = staged_files
+            .iter()
+            .map(|f| strip_file_marker(f).to_string())
+            .collect();
+
+        let mut context = CommitContext {
+            staged_files: actual_files.clone(),
+            branch_name: branch_name.filter(|b| !b.trim().is_empty()),
+            language,
+            ..Default::default()
+        };
+
+        let git_engine = self.git_engine.read().await;
+        match git_engine.get_commit_template_facts(&actual_files, RECENT_COMMIT_LIMIT) {
+            Ok(facts) => context.apply_repository_facts(facts),
+            Err(_) => {
+                context.issue_keys = context
+                    .branch_name
+                    .as_deref()
+                    .map(parse_issue_keys)
+                    .unwrap_or_default();
+            }
+        }
+
+        context
+    }
+
+    /// 使用 AIManager 中持久化的模板库生成单文件分析消息
+    /// 作者：Evilek
+    /// 编写日期：2025-10-03
+    async fn build_file_analysis_messages(
+        &self,
+        template_id: &str,
+        file_path: &str,
+        diff_content: &str,
+        base_context: &CommitContext,
+    ) -> Result<Vec<ChatMessage>> {
+        let actual_path = strip_file_marker(file_path);
+        let context = CommitContext {
+            diff: diff_content.to_string(),
+            staged_files: vec![file_path.to_string()],
+            files: base_context
+                .files
+                .iter()
+                .filter(|f| f.path == actual_path)
+                .cloned()
+                .collect(),
+            ..base_context.clone()
+        };
+
+        let ai_manager = self.ai_manager.read().await;
+        let prompt_manager = ai_manager.get_prompt_manager().await;
+        prompt_manager
+            .generate_file_analysis_messages(template_id, file_path, diff_content, &context)
+            .map_err(|e| anyhow::anyhow!("生成文件分析消息失败: {}", e))
+    }
+
+    /// 使用 AIManager 中持久化的模板库生成总结阶段消息
+    /// 作者：Evilek
+    /// 编写日期：2025-10-03
+    async fn build_summary_messages(
+        &self,
+        template_id: &str,
+        file_summaries: &[FileSummary],
+        base_context: &CommitContext,
+    ) -> Result<Vec<ChatMessage>> {
+        let labels = PhaseLabels::for_language(&base_context.language);
+
+        // 构建汇总的diff内容
+        let summary_content = file_summaries
+            .iter()
+            .map(|fs| {
+                format!(
+                    "{}: {}\n{}: {}",
+                    labels.file, fs.file_path, labels.summary, fs.summary
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let context = CommitContext {
+            diff: summary_content,
+            staged_files: file_summaries
+                .iter()
+                .map(|fs| fs.file_path.clone())
+                .collect(),
+            ..base_context.clone()
+        };
+
+        let file_summary_strs: Vec<&str> = file_summaries
+            .iter()
+            .map(|fs| fs.summary.as_str())
+            .collect();
+
+        let ai_manager = self.ai_manager.read().await;
+        let prompt_manager = ai_manager.get_prompt_manager().await;
+        prompt_manager
+            .generate_summary_messages(template_id, &context, &file_summary_strs)
+            .map_err(|e| anyhow::anyhow!("生成总结消息失败: {}", e))
+    }
+
     /// 获取模型的最大token限制
     async fn get_model_max_tokens(&self, model_id: &str) -> Result<Option<u32>> {
         // 简化实现，返回常见模型的token限制
@@ -863,7 +912,10 @@ impl LayeredCommitManager {
         file_path: &str,
         diff_content: &str,
         template_id: &str,
+        language: &str,
     ) -> Result<String> {
+        let labels = PhaseLabels::for_language(language);
+
         // 获取模板的max_tokens配置作为截取依据
         // Author: Evilek, Date: 2025-01-09 - 修复PromptManager实例化问题，使用AI管理器中的实例
         let ai_manager = self.ai_manager.read().await;
@@ -878,7 +930,7 @@ impl LayeredCommitManager {
 
         // 预估文件名和格式开销的token数
         let file_context_tokens =
-            TokenCounter::estimate_tokens(&format!("文件: {}\n\n", file_path)) + 50;
+            TokenCounter::estimate_tokens(&format!("{}: {}\n\n", labels.file, file_path)) + 50;
 
         let lines: Vec<&str> = diff_content.lines().collect();
         let total_lines = lines.len();
@@ -898,14 +950,11 @@ impl LayeredCommitManager {
         let truncated_line_count = truncated_lines.len();
 
         // 添加文件名上下文和截取说明
-        let result = if truncated_line_count < total_lines {
-            format!(
-                "文件: {}\n\n{}\n\n# 文件内容已截取，显示前{}行（共{}行）",
-                file_path, truncated_content, truncated_line_count, total_lines
-            )
-        } else {
-            format!("文件: {}\n\n{}", file_path, truncated_content)
-        };
+        let mut result = format!("{}: {}\n\n{}", labels.file, file_path, truncated_content);
+        if truncated_line_count < total_lines {
+            result.push_str("\n\n");
+            result.push_str(&(labels.truncated_note)(truncated_line_count, total_lines));
+        }
 
         Ok(result)
     }
@@ -962,7 +1011,10 @@ impl LayeredCommitManager {
         file_path: &str,
         diff_content: &str,
         template_id: &str,
+        language: &str,
     ) -> Result<Vec<String>> {
+        let labels = PhaseLabels::for_language(language);
+
         // 获取模板的max_tokens配置作为分割依据
         // Author: Evilek, Date: 2025-01-09 - 修复PromptManager实例化问题，使用AI管理器中的实例
         let ai_manager = self.ai_manager.read().await;
@@ -972,15 +1024,16 @@ impl LayeredCommitManager {
             .and_then(|(max_tokens, _)| max_tokens)
             .unwrap_or(1000); // 修复：增加默认值到1000 tokens，避免过度分割
 
-        println!(
+        debug_log!(
             "🔍 [split_file_content_with_template] 模板 {} 的max_tokens: {}",
-            template_id, template_max_tokens
+            template_id,
+            template_max_tokens
         );
 
         // 使用模板的max_tokens作为分割的安全限制（保留30%余量给文件名和格式）
         let safe_limit = (template_max_tokens as f32 * 0.7) as u32;
 
-        println!(
+        debug_log!(
             "🔍 [split_file_content_with_template] 分割安全限制: {} tokens",
             safe_limit
         );
@@ -992,7 +1045,7 @@ impl LayeredCommitManager {
 
         // 预估文件名和格式开销的token数
         let file_context_tokens =
-            TokenCounter::estimate_tokens(&format!("文件: {}\n\n", file_path)) + 50;
+            TokenCounter::estimate_tokens(&format!("{}: {}\n\n", labels.file, file_path)) + 50;
 
         for line in &lines {
             let line_tokens = TokenCounter::estimate_tokens(line);
@@ -1002,8 +1055,12 @@ impl LayeredCommitManager {
                 && !current_chunk.is_empty()
             {
                 // 为每个分割部分添加文件名上下文
-                let chunk_with_context =
-                    format!("文件: {}\n\n{}", file_path, current_chunk.join("\n"));
+                let chunk_with_context = format!(
+                    "{}: {}\n\n{}",
+                    labels.file,
+                    file_path,
+                    current_chunk.join("\n")
+                );
                 split_contents.push(chunk_with_context);
                 current_chunk.clear();
                 current_tokens = 0;
@@ -1015,7 +1072,12 @@ impl LayeredCommitManager {
 
         // 添加最后一个块
         if !current_chunk.is_empty() {
-            let chunk_with_context = format!("文件: {}\n\n{}", file_path, current_chunk.join("\n"));
+            let chunk_with_context = format!(
+                "{}: {}\n\n{}",
+                labels.file,
+                file_path,
+                current_chunk.join("\n")
+            );
             split_contents.push(chunk_with_context);
         }
 
@@ -1023,12 +1085,8 @@ impl LayeredCommitManager {
         if split_contents.len() > 1 {
             let total_parts = split_contents.len();
             for (index, content) in split_contents.iter_mut().enumerate() {
-                content.push_str(&format!(
-                    "\n\n# 这是文件 {} 的第{}部分（共{}部分）",
-                    file_path,
-                    index + 1,
-                    total_parts
-                ));
+                content.push_str("\n\n");
+                content.push_str(&(labels.part_note)(file_path, index + 1, total_parts));
             }
         }
 
@@ -1096,7 +1154,6 @@ impl LayeredCommitManager {
     /// 将AI配置中的语言名称转换为语言代码
     /// Author: Evilek, Date: 2025-01-08
     /// 统一语言转换逻辑，避免代码重复
-    #[allow(dead_code)]
     fn convert_ai_language_to_code(language_name: &str) -> String {
         match language_name {
             "Simplified Chinese" => "zh-CN",
@@ -1139,3 +1196,143 @@ struct FinalCommitResult {
     /// 编写日期：2025-01-10
     reasoning_content: Option<String>,
 }
+
+/// 分层提交附加在提示词中的固定文本，随全局语言切换中英文
+/// 作者：Evilek
+/// 编写日期：2025-10-03
+struct PhaseLabels {
+    file: &'static str,
+    summary: &'static str,
+    truncated_note: fn(usize, usize) -> String,
+    part_note: fn(&str, usize, usize) -> String,
+}
+
+impl PhaseLabels {
+    fn for_language(language: &str) -> Self {
+        if language.starts_with("zh") {
+            Self {
+                file: "文件",
+                summary: "摘要",
+                truncated_note: |shown, total| {
+                    format!("# 文件内容已截取，显示前{}行（共{}行）", shown, total)
+                },
+                part_note: |file, part, total| {
+                    format!("# 这是文件 {} 的第{}部分（共{}部分）", file, part, total)
+                },
+            }
+        } else {
+            Self {
+                file: "File",
+                summary: "Summary",
+                truncated_note: |shown, total| {
+                    format!(
+                        "# File content truncated: showing the first {} of {} lines",
+                        shown, total
+                    )
+                },
+                part_note: |file, part, total| {
+                    format!("# This is part {} of {} of file {}", part, total, file)
+                },
+            }
+        }
+    }
+}
+
+/// 去掉前端附加的 #truncated / #split / #partN 标记，得到真实文件路径
+fn strip_file_marker(file_path: &str) -> &str {
+    ["#truncated", "#split", "#part"]
+        .iter()
+        .filter_map(|marker| file_path.find(marker))
+        .min()
+        .map_or(file_path, |index| &file_path[..index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn manager_with_custom_template(dir: &std::path::Path) -> LayeredCommitManager {
+        let ai_manager = AIManager::new(dir.join("ai_config.json")).unwrap();
+
+        let mut config = ai_manager.get_config().await;
+        config.base.language = "English".to_string();
+        ai_manager.update_config(config).await.unwrap();
+
+        let mut template = ai_manager
+            .get_prompt_manager()
+            .await
+            .get_template("standard")
+            .unwrap()
+            .clone();
+        template.id = "team".to_string();
+        template.max_tokens = Some(200);
+        template.file_analysis_user_prompt =
+            "TEAM FILE {{join staged_files \", \"}} on {{branch_name}}\n{{diff}}".to_string();
+        template.summary_user_prompt = "TEAM SUMMARY on {{branch_name}}\n{{diff}}".to_string();
+        ai_manager.create_custom_template(template).await.unwrap();
+
+        LayeredCommitManager::new(
+            Arc::new(RwLock::new(ai_manager)),
+            Arc::new(RwLock::new(GitEngine::new())),
+        )
+    }
+
+    #[tokio::test]
+    async fn every_phase_uses_shared_template_language_and_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_with_custom_template(dir.path()).await;
+
+        let base = manager
+            .build_base_context(
+                &["src/a.rs#truncated".to_string()],
+                Some("feature/PROJ-7-login".to_string()),
+            )
+            .await;
+        assert_eq!(base.language, "en");
+        assert_eq!(base.staged_files, vec!["src/a.rs"]);
+        assert_eq!(base.issue_keys, vec!["PROJ-7"]);
+
+        let messages = manager
+            .build_file_analysis_messages("team", "src/a.rs", "+let a = 1;", &base)
+            .await
+            .unwrap();
+        let user = &messages[1].content;
+        assert!(user.starts_with("TEAM FILE src/a.rs on feature/PROJ-7-login\n+let a = 1;"));
+        assert!(user.contains("Must generate commit messages in English"));
+
+        let summaries = vec![FileSummary {
+            file_path: "src/a.rs".to_string(),
+            summary: "Add a".to_string(),
+            tokens_used: 0,
+        }];
+        let messages = manager
+            .build_summary_messages("team", &summaries, &base)
+            .await
+            .unwrap();
+        let user = &messages[1].content;
+        assert!(user.starts_with("TEAM SUMMARY on feature/PROJ-7-login\n"));
+        assert!(user.contains("File: src/a.rs\nSummary: Add a"));
+
+        // 截取与分割使用同一模板的 max_tokens，并输出对应语言的说明
+        let long_diff = (0..400)
+            .map(|i| format!("+line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let truncated = manager
+            .truncate_new_file_content_with_template("src/a.rs", &long_diff, "team", "en")
+            .await
+            .unwrap();
+        assert!(truncated.starts_with("File: src/a.rs\n\n+line 0"));
+        assert!(truncated.contains("# File content truncated: showing the first"));
+
+        let parts = manager
+            .split_file_content_with_template("src/a.rs", &long_diff, "team", "en")
+            .await
+            .unwrap();
+        assert!(parts.len() > 1);
+        assert!(parts[0].ends_with(&format!(
+            "# This is part 1 of {} of file src/a.rs",
+            parts.len()
+        )));
+    }
+}
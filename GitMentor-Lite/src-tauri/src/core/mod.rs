@@ -1,10 +1,25 @@
 // 核心模块聚合：职责边界说明
 // Author: Evilek, Date: 2025-08-11
 // - ai_manager / ai_provider / providers：AI 提供商与请求调度
+// - app_logger：结构化应用日志（分级、模块过滤、轮转文件、日志查询与诊断包）
 // - prompt_manager：提示词模板与两段式处理
+// - branch_metadata：Gitflow 分支元数据（QA 状态、工单、负责人、风险），存于 refs/gitmentor 命名空间
+// - commit_lint：提交消息规范校验（类型、长度、大小写、正文换行、trailer、工单号）与确定性修正
+// - commit_style：从最近提交学习仓库的提交风格（few-shot 示例）
 // - git_engine / git_config：Git 操作与执行模式管理
+// - git_process：Git 子进程执行（超时、取消、进程树清理）
+// - git_credentials：Git2 网络认证凭据与 known_hosts 校验
+// - gitflow_transaction：Gitflow 操作的快照、自动储藏与失败回滚
+// - gitflow_report：Gitflow 状态播报/复盘的模板渲染与 AI 输出解析
+// - forge：GitHub / GitLab / Gitea API（创建 PR/MR）
+// - impact_model：提交影响评分（路径分类、改动量、公共接口、依赖、热点文件、删除的测试），规则按仓库配置
 // - layered_commit_manager：分层提交编排与取消
 // - conversation_logger：AI 请求/响应记录
+// - release_pipeline：Gitflow 发布的版本推导、版本文件改写与发布说明
+// - repo_registry：仓库登记簿（最近、收藏、显示名、分组、每仓库默认设置）与目录扫描
+// - repo_watcher：仓库文件监控（遵循 gitignore、按路径合并、区分索引/HEAD/引用变化，推送状态增量）
+// - repo_workspace：多仓库工作区（按 id 管理各仓库的引擎、监控与配置，跨仓库状态汇总）
+// - test_support：测试用临时仓库夹具（仅 cfg(test)）
 // - report_engine：日报生成与缓存管理
 // - ai_response_cache：AI响应缓存管理
 // - versioned_template_manager：模板版本管理系统
@@ -15,15 +30,31 @@ pub mod ai_config;
 pub mod ai_manager;
 pub mod ai_provider;
 pub mod ai_response_cache;
+pub mod app_logger;
+pub mod branch_metadata;
+pub mod commit_lint;
+pub mod commit_style;
 pub mod conversation_logger;
+pub mod forge;
 pub mod git_config;
+pub mod git_credentials;
 pub mod git_engine;
+pub mod git_process;
+pub mod gitflow_report;
+pub mod gitflow_transaction;
+pub mod impact_model;
 pub mod layered_commit_manager;
 pub mod llm_client;
 pub mod prompt_manager;
 pub mod providers;
+pub mod release_pipeline;
+pub mod repo_registry;
+pub mod repo_watcher;
+pub mod repo_workspace;
 pub mod report_engine;
 pub mod response_cleaner;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod unified_template_manager;
 pub mod update_manager;
 pub mod versioned_template_manager;
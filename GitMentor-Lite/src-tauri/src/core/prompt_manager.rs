@@ -1,5 +1,8 @@
 use anyhow::Result;
 use chrono;
+use handlebars::handlebars_helper;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -7,6 +10,8 @@ use std::fs;
 use std::path::PathBuf;
 
 use crate::core::ai_provider::ChatMessage;
+use crate::core::commit_style::CommitStyleProfile;
+use crate::types::git_types::{CommitAuthor, CommitTemplateFacts, LanguageShare, StagedFileChange};
 
 /**
  * AI提示模板管理器
@@ -73,7 +78,24 @@ pub struct CommitType {
     pub example_scopes: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 提交模板的渲染上下文，用户提示词模板使用 Handlebars 语法访问以下字段：
+///
+/// - `diff`：代码差异（总结阶段为各文件的分析结果），可用 `{{truncate diff 3000}}` 按行截断
+/// - `staged_files`：本次涉及的文件路径列表
+/// - `files`：文件变更明细，每项含 `path`、`old_path`、`status`、`additions`、`deletions`、`is_binary`
+/// - `file_count` / `total_additions` / `total_deletions`：由 `files` 汇总得出
+/// - `branch_name` / `upstream`：当前分支与其上游，未知时为空
+/// - `issue_keys`：从分支名解析出的问题编号，如 `PROJ-123`、`#45`
+/// - `recent_commits`：最近提交的标题行
+/// - `languages`：仓库语言占比，每项含 `language`、`files`、`percentage`
+/// - `author`：提交作者，含 `name`、`email`
+/// - `style`：从最近提交学习到的提交风格（未启用 use_recent_commits 时为空），含 `conventional`、
+///   `common_types`、`uses_gitmoji`、`ticket_prefix`、`language`、`examples` 等
+/// - `commit_type` / `max_length` / `language`
+///
+/// 支持 `{{#if}}`、`{{#each}}`、`{{#unless}}` 等内置助手以及 `{{join list ", "}}`。
+/// 旧版 `{diff}`、`{staged_files}`、`{branch_name}`、`{commit_type}` 占位符仍然可用。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CommitContext {
     pub diff: String,
     pub staged_files: Vec<String>,
@@ -81,6 +103,46 @@ pub struct CommitContext {
     pub commit_type: Option<String>,
     pub max_length: Option<usize>,
     pub language: String,
+
+    // 以下为仓库上下文，由 apply_repository_facts 填充
+    #[serde(default)]
+    pub files: Vec<StagedFileChange>,
+    #[serde(default)]
+    pub upstream: Option<String>,
+    #[serde(default)]
+    pub issue_keys: Vec<String>,
+    #[serde(default)]
+    pub recent_commits: Vec<String>,
+    #[serde(default)]
+    pub languages: Vec<LanguageShare>,
+    #[serde(default)]
+    pub author: Option<CommitAuthor>,
+    #[serde(default)]
+    pub style: Option<CommitStyleProfile>,
+}
+
+impl CommitContext {
+    /// 合并从仓库收集的信息，调用方显式传入的分支名优先
+    /// 作者：Evilek
+    /// 编写日期：2025-10-01
+    pub fn apply_repository_facts(&mut self, facts: CommitTemplateFacts) {
+        if self.branch_name.is_none() {
+            self.branch_name = facts.branch_name;
+        }
+        if self.staged_files.is_empty() {
+            self.staged_files = facts.files.iter().map(|f| f.path.clone()).collect();
+        }
+        self.files = facts.files;
+        self.upstream = facts.upstream;
+        self.recent_commits = facts.recent_commits;
+        self.languages = facts.languages;
+        self.author = facts.author;
+        self.issue_keys = self
+            .branch_name
+            .as_deref()
+            .map(parse_issue_keys)
+            .unwrap_or_default();
+    }
 }
 
 /// 模板配置文件结构
@@ -96,7 +158,7 @@ pub struct TemplateConfig {
 impl Default for TemplateConfig {
     fn default() -> Self {
         Self {
-            version: "1.1.0".to_string(), // 更新版本号以支持两段式提示词
+            version: "1.2.0".to_string(), // 1.2.0：提示词改用 Handlebars 模板
             last_updated: chrono::Utc::now().to_rfc3339(),
             templates: HashMap::new(),
         }
@@ -115,7 +177,7 @@ impl PromptManager {
         let mut manager = Self {
             templates: HashMap::new(),
             config_path: None,
-            current_version: "1.1.0".to_string(), // 更新版本号以支持两段式提示词
+            current_version: "1.2.0".to_string(), // 1.2.0：提示词改用 Handlebars 模板
         };
 
         // 加载默认模板
@@ -130,7 +192,7 @@ impl PromptManager {
         let mut manager = Self {
             templates: HashMap::new(),
             config_path: Some(config_path.clone()),
-            current_version: "1.1.0".to_string(), // 更新版本号以支持两段式提示词
+            current_version: "1.2.0".to_string(), // 1.2.0：提示词改用 Handlebars 模板
         };
 
         // 尝试加载现有配置
@@ -155,8 +217,17 @@ impl PromptManager {
 
             // 检查版本是否需要更新
             if config.version != self.current_version {
-                // 版本不匹配，需要更新默认模板
+                // 版本不匹配，需要更新默认模板；用户自定义模板与改动过的内置模板原样保留（旧版占位符渲染时自动转换）
                 self.load_default_templates();
+                for (id, mut template) in config.templates {
+                    let keep = template.is_custom == Some(true)
+                        || (self.templates.contains_key(&id)
+                            && Self::is_user_edited_default(&template));
+                    if keep {
+                        template.version = Some(self.current_version.clone());
+                        self.templates.insert(id, template);
+                    }
+                }
                 self.save_to_config()?;
             } else {
                 self.templates = config.templates;
@@ -190,14 +261,42 @@ impl PromptManager {
     /// 计算模板内容哈希
     /// 作者：Evilek
     /// 编写日期：2025-01-29
-    #[allow(dead_code)]
+    /// 更新日期：2025-10-20 (覆盖两段式提示词字段)
     fn calculate_template_hash(template: &PromptTemplate) -> String {
         let mut hasher = Sha256::new();
         hasher.update(template.system_prompt.as_bytes());
         hasher.update(template.user_prompt_template.as_bytes());
+        for prompt in [
+            &template.file_analysis_system_prompt,
+            &template.file_analysis_user_prompt,
+            &template.summary_system_prompt,
+            &template.summary_user_prompt,
+        ] {
+            hasher.update(prompt.as_bytes());
+        }
         format!("{:x}", hasher.finalize())
     }
 
+    /// 判断已保存的内置模板是否被用户改动过
+    ///
+    /// 内置模板记录出厂内容的哈希，内容哈希与之不同即视为用户改动；
+    /// 旧版配置没有哈希，只能以保存时间晚于创建时间作为改动依据。
+    fn is_user_edited_default(template: &PromptTemplate) -> bool {
+        if let Some(shipped_hash) = &template.template_hash {
+            return *shipped_hash != Self::calculate_template_hash(template);
+        }
+
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+        };
+        match (parse(&template.created_at), parse(&template.updated_at)) {
+            (Some(created), Some(updated)) => updated - created > chrono::Duration::seconds(1),
+            _ => false,
+        }
+    }
+
     /// 获取默认的提交类型配置
     /// 作者：Evilek
     /// 编写日期：2025-01-29
@@ -323,10 +422,16 @@ impl PromptManager {
             user_prompt_template: r#"请为以下代码变更生成提交消息：
 
 变更的文件：
-{staged_files}
+{{#each files}}
+- [{{status}}] {{path}}{{#if old_path}} (from {{old_path}}){{/if}} +{{additions}}/-{{deletions}}
+{{else}}
+{{#each staged_files}}
+- {{this}}
+{{/each}}
+{{/each}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请生成一个简洁明了的提交消息。"#.to_string(),
 
@@ -345,10 +450,10 @@ impl PromptManager {
 
             file_analysis_user_prompt: r#"请分析以下文件的变更：
 
-文件路径：{staged_files}
+文件路径：{{join staged_files ", "}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请用80字以内简洁分析这个文件的具体变更内容和目的。"#.to_string(),
 
@@ -376,7 +481,7 @@ config.js: 更新配置参数
 
             summary_user_prompt: r#"基于以下文件变更分析，生成统一的提交消息：
 
-{diff}
+{{diff}}
 
 请按照指定格式生成提交消息：第一行简短总结，空行后每个文件一行简介。"#.to_string(),
             language: "FOLLOW_GLOBAL".to_string(),
@@ -422,10 +527,16 @@ config.js: 更新配置参数
             user_prompt_template: r#"请为以下代码变更生成中文提交消息：
 
 变更的文件：
-{staged_files}
+{{#each files}}
+- [{{status}}] {{path}}{{#if old_path}} (from {{old_path}}){{/if}} +{{additions}}/-{{deletions}}
+{{else}}
+{{#each staged_files}}
+- {{this}}
+{{/each}}
+{{/each}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请生成一个简洁明了的中文提交消息。"#
                 .to_string(),
@@ -446,10 +557,10 @@ config.js: 更新配置参数
 
             file_analysis_user_prompt: r#"请简洁分析以下文件的变更：
 
-文件路径：{staged_files}
+文件路径：{{join staged_files ", "}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请用简洁的中文描述这个文件的变更内容。"#
                 .to_string(),
@@ -475,7 +586,7 @@ config.js: 更新配置参数
 
             summary_user_prompt: r#"基于以下文件变更分析，生成简洁的中文提交消息：
 
-{diff}
+{{diff}}
 
 请生成一个简洁明了的中文提交消息。"#
                 .to_string(),
@@ -521,12 +632,23 @@ config.js: 更新配置参数
 直接输出提交消息，无需其他内容。"#.to_string(),
             user_prompt_template: r#"请为以下代码变更生成详细的提交消息：
 
-分支：{branch_name}
+{{#if branch_name}}
+分支：{{branch_name}}{{#if upstream}}（跟踪 {{upstream}}）{{/if}}
+{{/if}}
+{{#if issue_keys}}
+关联问题：{{join issue_keys ", "}}
+{{/if}}
 变更的文件：
-{staged_files}
+{{#each files}}
+- [{{status}}] {{path}}{{#if old_path}} (from {{old_path}}){{/if}} +{{additions}}/-{{deletions}}
+{{else}}
+{{#each staged_files}}
+- {{this}}
+{{/each}}
+{{/each}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请生成包含摘要和详细描述的提交消息。"#.to_string(),
 
@@ -545,10 +667,10 @@ config.js: 更新配置参数
 
             file_analysis_user_prompt: r#"请详细分析以下文件的变更：
 
-文件路径：{staged_files}
+文件路径：{{join staged_files ", "}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请提供这个文件变更的详细分析，包括变更类型、具体内容、目的和可能的影响。"#.to_string(),
 
@@ -573,7 +695,7 @@ config.js: 更新配置参数
 
             summary_user_prompt: r#"基于以下详细的文件变更分析，生成包含摘要和详细描述的提交消息：
 
-{diff}
+{{diff}}
 
 请生成包含摘要和详细描述的提交消息。"#.to_string(),
             language: "FOLLOW_GLOBAL".to_string(),
@@ -617,10 +739,16 @@ config.js: 更新配置参数
             user_prompt_template: r#"请为以下代码变更生成约定式提交消息：
 
 变更的文件：
-{staged_files}
+{{#each files}}
+- [{{status}}] {{path}}{{#if old_path}} (from {{old_path}}){{/if}} +{{additions}}/-{{deletions}}
+{{else}}
+{{#each staged_files}}
+- {{this}}
+{{/each}}
+{{/each}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请分析变更类型并生成符合约定式提交规范的消息。"#.to_string(),
 
@@ -638,10 +766,10 @@ config.js: 更新配置参数
 
             file_analysis_user_prompt: r#"请分析以下文件的变更类型：
 
-文件路径：{staged_files}
+文件路径：{{join staged_files ", "}}
 
 代码差异：
-{diff}
+{{truncate diff 3000}}
 
 请识别这个文件变更的约定式提交类型和具体内容。"#.to_string(),
 
@@ -664,7 +792,7 @@ config.js: 更新配置参数
 
             summary_user_prompt: r#"基于以下文件变更分析，生成符合约定式提交规范的消息：
 
-{diff}
+{{diff}}
 
 请分析变更类型并生成符合约定式提交规范的消息。"#.to_string(),
             language: "FOLLOW_GLOBAL".to_string(),
@@ -682,6 +810,13 @@ config.js: 更新配置参数
             template_hash: None, // 简化处理，不计算hash
         };
         self.add_template(conventional_template);
+
+        // 记录出厂内容哈希，版本迁移时据此识别用户改动
+        for template in self.templates.values_mut() {
+            if template.is_custom == Some(false) && template.template_hash.is_none() {
+                template.template_hash = Some(Self::calculate_template_hash(template));
+            }
+        }
     }
 
     pub fn add_template(&mut self, template: PromptTemplate) {
@@ -821,11 +956,7 @@ config.js: 更新配置参数
             let _summaries_text = file_summaries.join("\n\n");
             CommitContext {
                 diff: format!("Original Diff:\n{}", context.diff),
-                staged_files: context.staged_files.clone(),
-                branch_name: context.branch_name.clone(),
-                commit_type: context.commit_type.clone(),
-                max_length: context.max_length,
-                language: context.language.clone(),
+                ..context.clone()
             }
         } else {
             context.clone()
@@ -923,51 +1054,124 @@ config.js: 更新配置参数
         }
     }
 
+    /// 使用 Handlebars 渲染提示词模板，可用变量见 `CommitContext`
+    /// 作者：Evilek
+    /// 编写日期：2025-10-01
     fn render_template(&self, template: &str, context: &CommitContext) -> Result<String> {
-        let mut rendered = template.to_string();
-
-        // 替换变量
-        rendered = rendered.replace("{diff}", &self.truncate_diff(&context.diff, 3000));
-        rendered = rendered.replace("{staged_files}", &context.staged_files.join("\n"));
+        let registry = Self::template_registry(false);
+        registry
+            .render_template(
+                &convert_legacy_placeholders(template),
+                &Self::template_data(context),
+            )
+            .map_err(|e| anyhow::anyhow!("模板渲染失败: {}", e))
+    }
 
-        if let Some(branch) = &context.branch_name {
-            rendered = rendered.replace("{branch_name}", branch);
-        } else {
-            rendered = rendered.replace("{branch_name}", "main");
-        }
+    /// 创建注册了自定义助手的 Handlebars 实例；提示词不是HTML，关闭转义
+    fn template_registry(strict: bool) -> handlebars::Handlebars<'static> {
+        let mut registry = handlebars::Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape);
+        registry.set_strict_mode(strict);
+        registry.register_helper("truncate", Box::new(truncate));
+        registry.register_helper("join", Box::new(join));
+        registry
+    }
 
-        if let Some(commit_type) = &context.commit_type {
-            rendered = rendered.replace("{commit_type}", commit_type);
+    /// 模板数据：上下文字段加上汇总统计
+    fn template_data(context: &CommitContext) -> serde_json::Value {
+        let mut data = serde_json::to_value(context).unwrap_or_default();
+        if let Some(map) = data.as_object_mut() {
+            map.insert("file_count".to_string(), context.files.len().into());
+            map.insert(
+                "total_additions".to_string(),
+                context
+                    .files
+                    .iter()
+                    .map(|f| f.additions)
+                    .sum::<usize>()
+                    .into(),
+            );
+            map.insert(
+                "total_deletions".to_string(),
+                context
+                    .files
+                    .iter()
+                    .map(|f| f.deletions)
+                    .sum::<usize>()
+                    .into(),
+            );
         }
-
-        Ok(rendered)
+        data
     }
 
-    fn truncate_diff(&self, diff: &str, max_length: usize) -> String {
-        if diff.len() <= max_length {
-            return diff.to_string();
+    /// 校验模板中所有提示词字段：语法错误、未知变量或助手都会被拒绝
+    /// 作者：Evilek
+    /// 编写日期：2025-10-01
+    pub fn validate_template(template: &PromptTemplate) -> Result<()> {
+        let registry = Self::template_registry(true);
+        let sample = Self::template_data(&sample_commit_context());
+
+        let fields = [
+            ("user_prompt_template", &template.user_prompt_template),
+            (
+                "file_analysis_user_prompt",
+                &template.file_analysis_user_prompt,
+            ),
+            ("summary_user_prompt", &template.summary_user_prompt),
+        ];
+
+        for (field, content) in fields {
+            if content.trim().is_empty() {
+                continue;
+            }
+            registry
+                .render_template(&convert_legacy_placeholders(content), &sample)
+                .map_err(|e| anyhow::anyhow!("模板字段 {} 无效: {}", field, e))?;
         }
 
-        let lines: Vec<&str> = diff.lines().collect();
-        let mut result = String::new();
-        let mut current_length = 0;
+        Ok(())
+    }
 
-        for line in lines {
-            if current_length + line.len() + 1 > max_length {
-                result.push_str("\n... (diff truncated)");
-                break;
+    /// 预览模板：template_content 不为空时渲染该内容（编辑器中尚未保存的模板），
+    /// 否则渲染指定模板的用户提示词
+    /// 作者：Evilek
+    /// 编写日期：2025-10-01
+    pub fn preview_template(
+        &self,
+        template_id: &str,
+        template_content: Option<&str>,
+        context: &CommitContext,
+    ) -> Result<String> {
+        let content = match template_content {
+            Some(content) => content.to_string(),
+            None => {
+                let template = self
+                    .get_template(template_id)
+                    .ok_or_else(|| anyhow::anyhow!("Template '{}' not found", template_id))?;
+                if template.user_prompt_template.is_empty() {
+                    template.summary_user_prompt.clone()
+                } else {
+                    template.user_prompt_template.clone()
+                }
             }
+        };
 
-            if !result.is_empty() {
-                result.push('\n');
-                current_length += 1;
-            }
+        self.render_template(&content, context)
+    }
 
-            result.push_str(line);
-            current_length += line.len();
-        }
+    /// 返回模板渲染使用的完整数据，供前端展示可用变量
+    pub fn template_variables(context: &CommitContext) -> serde_json::Value {
+        Self::template_data(context)
+    }
 
-        result
+    /// 是否需要为该模板学习仓库提交风格：全局开关开启且模板启用了 use_recent_commits
+    /// 作者：Evilek
+    /// 编写日期：2025-10-02
+    pub fn uses_recent_commits(&self, template_id: &str, globally_enabled: bool) -> bool {
+        globally_enabled
+            && self
+                .get_template(template_id)
+                .is_some_and(|t| t.use_recent_commits == Some(true))
     }
 
     pub fn get_template_config(&self, template_id: &str) -> Option<(Option<u32>, Option<f32>)> {
@@ -979,6 +1183,8 @@ config.js: 更新配置参数
     /// 作者：Evilek
     /// 编写日期：2025-01-29
     pub fn update_template(&mut self, template: PromptTemplate) -> Result<()> {
+        Self::validate_template(&template)?;
+
         let mut updated_template = template;
         updated_template.updated_at = Some(chrono::Utc::now().to_rfc3339());
 
@@ -1024,6 +1230,8 @@ config.js: 更新配置参数
     /// 作者：Evilek
     /// 编写日期：2025-01-29
     pub fn create_custom_template(&mut self, mut template: PromptTemplate) -> Result<()> {
+        Self::validate_template(&template)?;
+
         // 确保是自定义模板
         template.is_custom = Some(true);
         template.created_at = Some(chrono::Utc::now().to_rfc3339());
@@ -1130,6 +1338,13 @@ config.js: 更新配置参数
                 .push_str("\n\nIMPORTANT: If there are multiple file changes, please generate separate commit messages for each major change.");
         }
 
+        // 仓库提交风格与历史示例，只对生成提交消息的阶段有意义
+        if phase != "file_analysis" {
+            if let Some(style) = &context.style {
+                system_prompt.push_str(&style.to_prompt_section(&context.issue_keys));
+            }
+        }
+
         // 语言声明已移至用户提示词末尾，不再添加到系统提示词
         // Author: Evilek, Date: 2025-01-08 - 防失忆优化
 
@@ -1302,3 +1517,276 @@ fn extract_config_guidance_from_system_prompt(system_prompt: &str) -> String {
 
     guidance_parts.join("")
 }
+
+// 提示词模板助手：{{truncate diff 3000}}、{{join issue_keys ", "}}
+handlebars_helper!(truncate: |text: str, max_length: u64| {
+    truncate_lines(text, max_length as usize)
+});
+handlebars_helper!(join: |items: array, separator: str| {
+    items
+        .iter()
+        .map(|item| match item {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+});
+
+/// 按行截断文本，超出部分以提示行代替
+fn truncate_lines(text: &str, max_length: usize) -> String {
+    if text.len() <= max_length {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    for line in text.lines() {
+        let separator = usize::from(!result.is_empty());
+        if result.len() + separator + line.len() > max_length {
+            result.push_str("\n... (diff truncated)");
+            break;
+        }
+        if separator == 1 {
+            result.push('\n');
+        }
+        result.push_str(line);
+    }
+
+    result
+}
+
+/// 将旧版单花括号占位符转换为 Handlebars 表达式，已是 `{{...}}` 的内容保持不变
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+fn convert_legacy_placeholders(template: &str) -> String {
+    const LEGACY: [(&str, &str); 4] = [
+        ("{diff}", "{{truncate diff 3000}}"),
+        (
+            "{staged_files}",
+            "{{#each staged_files}}{{#unless @first}}\n{{/unless}}{{this}}{{/each}}",
+        ),
+        ("{branch_name}", "{{branch_name}}"),
+        ("{commit_type}", "{{commit_type}}"),
+    ];
+
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    'outer: while let Some(pos) = rest.find('{') {
+        let preceded_by_brace = result.ends_with('{') || rest[..pos].ends_with('{');
+        for (legacy, replacement) in LEGACY {
+            let candidate = &rest[pos..];
+            if !preceded_by_brace
+                && candidate.starts_with(legacy)
+                && !candidate[legacy.len()..].starts_with('}')
+            {
+                result.push_str(&rest[..pos]);
+                result.push_str(replacement);
+                rest = &candidate[legacy.len()..];
+                continue 'outer;
+            }
+        }
+        result.push_str(&rest[..=pos]);
+        rest = &rest[pos + 1..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+static ISSUE_JIRA_KEY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b([A-Z][A-Z0-9]+-\d+)\b").expect("valid regex"));
+static ISSUE_SEGMENT_KEY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z][A-Za-z0-9]+)-(\d+)\b").expect("valid regex"));
+static ISSUE_SEGMENT_NUMBER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^#?(\d+)(?:[-_.]|$)").expect("valid regex"));
+
+/// 从分支名中解析问题编号：
+/// - 任意位置的大写 Jira 风格编号，如 `feature/PROJ-123-login` → `PROJ-123`
+/// - 分支段开头的小写编号，如 `bugfix/proj-42` → `PROJ-42`
+/// - 分支段开头的纯数字或 `#数字`，如 `fix/123-crash` → `#123`
+///
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+pub fn parse_issue_keys(branch_name: &str) -> Vec<String> {
+    // gitflow 前缀本身不是项目编号
+    const IGNORED_PREFIXES: [&str; 7] = [
+        "feature", "bugfix", "hotfix", "release", "support", "fix", "issue",
+    ];
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut push = |key: String| {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    };
+
+    for segment in branch_name.split('/') {
+        if let Some(captures) = ISSUE_SEGMENT_KEY.captures(segment) {
+            let project = captures[1].to_lowercase();
+            if !IGNORED_PREFIXES.contains(&project.as_str()) {
+                push(format!("{}-{}", project.to_uppercase(), &captures[2]));
+            }
+        }
+        if let Some(captures) = ISSUE_SEGMENT_NUMBER.captures(segment) {
+            push(format!("#{}", &captures[1]));
+        }
+        for captures in ISSUE_JIRA_KEY.captures_iter(segment) {
+            let key = captures[1].to_string();
+            let project = key.split('-').next().unwrap_or_default().to_lowercase();
+            if !IGNORED_PREFIXES.contains(&project.as_str()) {
+                push(key);
+            }
+        }
+    }
+
+    keys
+}
+
+/// 校验模板时使用的示例上下文，确保循环与条件分支都会被执行
+fn sample_commit_context() -> CommitContext {
+    CommitContext {
+        diff: "diff --git a/src/main.rs b/src/main.rs".to_string(),
+        staged_files: vec!["src/main.rs".to_string()],
+        branch_name: Some("feature/PROJ-1-sample".to_string()),
+        commit_type: Some("feat".to_string()),
+        max_length: Some(72),
+        language: "en".to_string(),
+        files: vec![StagedFileChange {
+            path: "src/main.rs".to_string(),
+            old_path: Some("src/old_main.rs".to_string()),
+            status: "renamed".to_string(),
+            additions: 1,
+            deletions: 1,
+            is_binary: false,
+        }],
+        upstream: Some("origin/feature/PROJ-1-sample".to_string()),
+        issue_keys: vec!["PROJ-1".to_string()],
+        recent_commits: vec!["Initial commit".to_string()],
+        languages: vec![LanguageShare {
+            language: "rust".to_string(),
+            files: 1,
+            percentage: 100.0,
+        }],
+        author: Some(CommitAuthor {
+            name: "Sample".to_string(),
+            email: "sample@example.com".to_string(),
+        }),
+        style: Some(CommitStyleProfile {
+            sample_size: 1,
+            examples: vec!["feat: initial commit".to_string()],
+            ..Default::default()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_issue_keys_from_branch_names() {
+        assert_eq!(parse_issue_keys("feature/PROJ-123-login"), vec!["PROJ-123"]);
+        assert_eq!(parse_issue_keys("bugfix/proj-42-crash"), vec!["PROJ-42"]);
+        assert_eq!(parse_issue_keys("fix/123-crash"), vec!["#123"]);
+        assert_eq!(parse_issue_keys("hotfix-2"), Vec::<String>::new());
+        assert!(parse_issue_keys("main").is_empty());
+    }
+
+    #[test]
+    fn version_migration_keeps_edited_default_templates() {
+        let shipped = PromptManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("prompt_templates.json");
+
+        // 旧版配置：standard 被用户改过（无哈希，保存时间晚于创建时间），detailed 未改动
+        let mut edited = shipped.get_template("standard").unwrap().clone();
+        edited.template_hash = None;
+        edited.system_prompt = "我的系统提示词".to_string();
+        edited.created_at = Some("2025-01-01T00:00:00+00:00".to_string());
+        edited.updated_at = Some("2025-02-01T00:00:00+00:00".to_string());
+        let mut untouched = shipped.get_template("detailed").unwrap().clone();
+        untouched.template_hash = None;
+        untouched.system_prompt = "旧版出厂提示词".to_string();
+        untouched.created_at = Some("2025-01-01T00:00:00+00:00".to_string());
+        untouched.updated_at = Some("2025-01-01T00:00:00+00:00".to_string());
+        // 新版配置中带哈希的内置模板，内容与哈希不一致即为用户改动
+        let mut hashed = shipped.get_template("conventional").unwrap().clone();
+        hashed.user_prompt_template = "{{diff}}".to_string();
+
+        let config = TemplateConfig {
+            version: "1.1.0".to_string(),
+            templates: [edited, untouched, hashed]
+                .into_iter()
+                .map(|t| (t.id.clone(), t))
+                .collect(),
+            ..Default::default()
+        };
+        fs::write(&config_path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let manager = PromptManager::new_with_config(config_path).unwrap();
+        let standard = manager.get_template("standard").unwrap();
+        assert_eq!(standard.system_prompt, "我的系统提示词");
+        assert_eq!(standard.version.as_deref(), Some("1.2.0"));
+        assert_eq!(
+            manager.get_template("detailed").unwrap().system_prompt,
+            shipped.get_template("detailed").unwrap().system_prompt
+        );
+        assert_eq!(
+            manager.get_template("conventional").unwrap().user_prompt_template,
+            "{{diff}}"
+        );
+        assert!(manager.get_template("chinese").is_some());
+    }
+
+    #[test]
+    fn converts_legacy_placeholders_only() {
+        let converted = convert_legacy_placeholders("分支：{branch_name} {{branch_name}} {other}");
+        assert_eq!(converted, "分支：{{branch_name}} {{branch_name}} {other}");
+    }
+
+    #[test]
+    fn renders_default_template_with_file_list() {
+        let manager = PromptManager::new();
+        let template = manager.get_template("detailed").unwrap();
+        let context = sample_commit_context();
+
+        let rendered = manager
+            .render_template(&template.user_prompt_template, &context)
+            .unwrap();
+
+        assert!(
+            rendered.contains("分支：feature/PROJ-1-sample（跟踪 origin/feature/PROJ-1-sample）\n")
+        );
+        assert!(rendered.contains("关联问题：PROJ-1\n"));
+        assert!(rendered.contains("- [renamed] src/main.rs (from src/old_main.rs) +1/-1\n"));
+
+        // 没有文件明细时回退到路径列表，没有分支时不输出分支行
+        let legacy_context = CommitContext {
+            diff: "diff".to_string(),
+            staged_files: vec!["a.txt".to_string(), "b.txt".to_string()],
+            ..Default::default()
+        };
+        let rendered = manager
+            .render_template(&template.user_prompt_template, &legacy_context)
+            .unwrap();
+        assert!(!rendered.contains("分支"));
+        assert!(rendered.contains("变更的文件：\n- a.txt\n- b.txt\n"));
+    }
+
+    #[test]
+    fn rejects_invalid_templates_on_save() {
+        let mut manager = PromptManager::new();
+        let mut template = manager.get_template("standard").unwrap().clone();
+        template.id = "custom".to_string();
+
+        template.user_prompt_template = "{{#each files}}{{path}}".to_string();
+        assert!(manager.create_custom_template(template.clone()).is_err());
+
+        template.user_prompt_template = "{{brnach_name}}".to_string();
+        assert!(manager.create_custom_template(template.clone()).is_err());
+
+        template.user_prompt_template = "{{#if upstream}}{{upstream}}{{/if}} {diff}".to_string();
+        assert!(manager.create_custom_template(template).is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{debug_log, error_log, warn_log};
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -95,10 +96,10 @@ impl OpenAIProvider {
         headers.insert("Content-Type", "application/json".parse().unwrap());
 
         if !self.config.api_key.is_empty() {
-            headers.insert(
-                "Authorization",
-                format!("Bearer {}", self.config.api_key).parse().unwrap(),
-            );
+            let mut authorization: reqwest::header::HeaderValue =
+                format!("Bearer {}", self.config.api_key).parse().unwrap();
+            authorization.set_sensitive(true);
+            headers.insert("Authorization", authorization);
         }
 
         headers
@@ -133,11 +134,11 @@ impl AIProvider for OpenAIProvider {
         };
 
         let url = &format!("{}/chat/completions", self.config.base_url);
-        println!("🔍 [OpenAI] 请求URL: {}", url);
-        println!("🔍 [OpenAI] 请求头: {:?}", self.get_headers());
-        println!("🔍 [OpenAI] 请求模型: {} (流式: 是)", openai_request.model);
+        debug_log!("🔍 [OpenAI] 请求URL: {}", url);
+        debug_log!("🔍 [OpenAI] 请求头: {:?}", self.get_headers().keys().collect::<Vec<_>>());
+        debug_log!("🔍 [OpenAI] 请求模型: {} (流式: 是)", openai_request.model);
 
-        println!("🔍 [OpenAI] 开始流式请求...");
+        debug_log!("🔍 [OpenAI] 开始流式请求...");
 
         let request_future = self
             .client
@@ -149,11 +150,11 @@ impl AIProvider for OpenAIProvider {
         let mut response = match tokio::time::timeout(self.request_timeout, request_future).await {
             Ok(Ok(resp)) => resp,
             Ok(Err(err)) => {
-                println!("❌ [OpenAI] 流式请求失败: {}", err);
+                error_log!("❌ [OpenAI] 流式请求失败: {}", err);
                 return Err(err.into());
             }
             Err(_) => {
-                println!(
+                warn_log!(
                     "⏱️ [OpenAI] 流式请求超时，等待时间: {} 秒",
                     self.request_timeout.as_secs()
                 );
@@ -167,12 +168,12 @@ impl AIProvider for OpenAIProvider {
         let status = response.status();
         let headers = response.headers().clone();
 
-        println!("🔍 [OpenAI] 流式请求HTTP状态码: {}", status);
-        println!("🔍 [OpenAI] 流式请求响应头: {:?}", headers);
+        debug_log!("🔍 [OpenAI] 流式请求HTTP状态码: {}", status);
+        debug_log!("🔍 [OpenAI] 流式请求响应头: {:?}", headers);
 
         if !status.is_success() {
             let error_text = response.text().await?;
-            println!("❌ [OpenAI] 流式请求错误: {}", error_text);
+            error_log!("❌ [OpenAI] 流式请求错误: {}", error_text);
             return Err(anyhow::anyhow!("OpenAI streaming error: {}", error_text));
         }
 
@@ -187,11 +188,11 @@ impl AIProvider for OpenAIProvider {
                     Ok(Ok(Some(chunk))) => chunk,
                     Ok(Ok(None)) => break,
                     Ok(Err(err)) => {
-                        println!("❌ [OpenAI] 流式读取失败: {}", err);
+                        error_log!("❌ [OpenAI] 流式读取失败: {}", err);
                         return Err(err.into());
                     }
                     Err(_) => {
-                        println!(
+                        warn_log!(
                             "⏱️ [OpenAI] 流式响应超时，等待时间: {} 秒",
                             self.request_timeout.as_secs()
                         );
@@ -210,7 +211,7 @@ impl AIProvider for OpenAIProvider {
                     let data = &line[6..]; // 移除 "data: " 前缀
 
                     if data.trim() == "[DONE]" {
-                        println!("🔍 [OpenAI] 流式响应完成");
+                        debug_log!("🔍 [OpenAI] 流式响应完成");
                         continue;
                     }
 
@@ -231,9 +232,8 @@ impl AIProvider for OpenAIProvider {
                                         delta.get("content").and_then(|c| c.as_str())
                                     {
                                         final_content.push_str(content);
-                                        // 实时打印接收到的内容（用于调试）
-                                        print!("{}", content);
-                                        std::io::Write::flush(&mut std::io::stdout()).unwrap();
+                                        // 流式片段只在 trace 级别记录，避免刷屏
+                                        log::trace!("{}", content);
                                     }
                                 }
 
@@ -248,7 +248,7 @@ impl AIProvider for OpenAIProvider {
             }
         }
 
-        println!(
+        debug_log!(
             "\n🔍 [OpenAI] 流式接收完成，总长度: {}",
             final_content.len()
         );
@@ -266,14 +266,14 @@ impl AIProvider for OpenAIProvider {
 
         // 如果有思考内容，打印日志
         if let Some(ref reasoning) = reasoning_content {
-            println!("🔍 [DEBUG] 提取到思考内容，长度: {}", reasoning.len());
+            debug_log!("🔍 [DEBUG] 提取到思考内容，长度: {}", reasoning.len());
         }
 
         // 然后对实际内容进行进一步清理
         use crate::core::response_cleaner::ResponseCleaner;
         let cleaned_content = ResponseCleaner::clean_commit_message(&actual_content);
 
-        println!(
+        debug_log!(
             "🔍 [DEBUG] 原始响应长度: {}, 移除思考后长度: {}, 清理后长度: {}",
             final_content.len(),
             actual_content.len(),
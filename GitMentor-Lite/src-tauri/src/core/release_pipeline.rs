@@ -0,0 +1,369 @@
+use crate::types::git_types::VersionBump;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+
+// Gitflow 发布流水线的纯逻辑：语义化版本、按约定式提交推导版本升级、版本文件改写与发布说明
+// Author: Evilek, Date: 2025-10-04
+// 执行（提交、合并、打标签）在 git_engine 中完成，这里不碰仓库
+
+/// 需要同步版本号的文件名（按仓库中已跟踪的文件查找）
+pub const VERSION_FILE_NAMES: [&str; 3] = ["Cargo.toml", "package.json", "tauri.conf.json"];
+
+/// 发布说明写入仓库根目录的变更日志
+pub const CHANGELOG_FILE: &str = "CHANGELOG.md";
+
+/// 语义化版本，预发布部分只参与比较不参与升级
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemVer {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl SemVer {
+    /// 解析 1.2.3 / v1.2.3 / 1.2.3-rc.1，忽略 +build 元数据
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_start_matches(['v', 'V']);
+        let text = text.split('+').next().unwrap_or(text);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        let version = Self {
+            major: parts.next()??,
+            minor: parts.next()??,
+            patch: parts.next()??,
+            pre,
+        };
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(version)
+    }
+
+    pub fn bump(&self, bump: VersionBump) -> Self {
+        // 预发布版本直接发布为对应的正式版本
+        if self.pre.is_some() {
+            let release = Self {
+                pre: None,
+                ..self.clone()
+            };
+            let already_bumped = match bump {
+                VersionBump::Major => self.minor == 0 && self.patch == 0,
+                VersionBump::Minor => self.patch == 0,
+                VersionBump::Patch => true,
+            };
+            if already_bumped {
+                return release;
+            }
+        }
+
+        match bump {
+            VersionBump::Major => Self {
+                major: self.major + 1,
+                minor: 0,
+                patch: 0,
+                pre: None,
+            },
+            VersionBump::Minor => Self {
+                major: self.major,
+                minor: self.minor + 1,
+                patch: 0,
+                pre: None,
+            },
+            VersionBump::Patch => Self {
+                major: self.major,
+                minor: self.minor,
+                patch: self.patch + 1,
+                pre: None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            })
+    }
+}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 解析后的约定式提交标题
+struct ConventionalCommit<'a> {
+    kind: &'a str,
+    scope: Option<&'a str>,
+    breaking: bool,
+    description: &'a str,
+}
+
+static CONVENTIONAL_HEADER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\w+)(?:\(([^)]+)\))?(!)?: (.+)$").expect("valid conventional regex")
+});
+
+fn parse_conventional(message: &str) -> Option<ConventionalCommit<'_>> {
+    let summary = message.lines().next().unwrap_or_default().trim();
+    let captures = CONVENTIONAL_HEADER.captures(summary)?;
+
+    Some(ConventionalCommit {
+        kind: captures.get(1)?.as_str(),
+        scope: captures.get(2).map(|m| m.as_str()),
+        breaking: captures.get(3).is_some() || has_breaking_footer(message),
+        description: captures.get(4)?.as_str(),
+    })
+}
+
+fn has_breaking_footer(message: &str) -> bool {
+    message
+        .lines()
+        .skip(1)
+        .any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"))
+}
+
+/// 根据上次发布以来的提交推导版本升级：破坏性变更 → major，feat → minor，其余 → patch
+pub fn bump_from_commits(messages: &[String]) -> Option<VersionBump> {
+    messages
+        .iter()
+        .map(|message| match parse_conventional(message) {
+            Some(commit) if commit.breaking => VersionBump::Major,
+            Some(commit) if commit.kind == "feat" => VersionBump::Minor,
+            _ if has_breaking_footer(message) => VersionBump::Major,
+            _ => VersionBump::Patch,
+        })
+        .max()
+}
+
+/// 生成 Markdown 发布说明，按提交类型分组
+pub fn release_notes(tag_name: &str, date: &str, messages: &[String]) -> String {
+    const SECTIONS: [(&str, &str); 5] = [
+        ("breaking", "Breaking Changes"),
+        ("feat", "Features"),
+        ("fix", "Bug Fixes"),
+        ("perf", "Performance"),
+        ("other", "Other Changes"),
+    ];
+
+    let mut grouped: Vec<(&str, Vec<String>)> =
+        SECTIONS.iter().map(|(key, _)| (*key, Vec::new())).collect();
+
+    for message in messages {
+        let (section, line) = match parse_conventional(message) {
+            Some(commit) => {
+                let line = match commit.scope {
+                    Some(scope) => format!("**{}:** {}", scope, commit.description),
+                    None => commit.description.to_string(),
+                };
+                let section = if commit.breaking {
+                    "breaking"
+                } else {
+                    match commit.kind {
+                        "feat" | "fix" | "perf" => commit.kind,
+                        _ => "other",
+                    }
+                };
+                (section, line)
+            }
+            None => (
+                "other",
+                message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            ),
+        };
+
+        if let Some((_, lines)) = grouped.iter_mut().find(|(key, _)| *key == section) {
+            lines.push(line);
+        }
+    }
+
+    let mut notes = format!("## {} ({})\n", tag_name, date);
+    for ((_, lines), (_, title)) in grouped.iter().zip(SECTIONS.iter()) {
+        if lines.is_empty() {
+            continue;
+        }
+        notes.push_str(&format!("\n### {}\n\n", title));
+        for line in lines {
+            notes.push_str(&format!("- {}\n", line));
+        }
+    }
+
+    notes
+}
+
+/// 把发布说明插入 CHANGELOG.md 顶部（保留已有的一级标题）
+pub fn prepend_changelog(existing: Option<&str>, notes: &str) -> String {
+    match existing.map(str::trim_start) {
+        Some(existing) if existing.starts_with("# ") => {
+            let (title, rest) = existing.split_once('\n').unwrap_or((existing, ""));
+            format!("{}\n\n{}\n{}", title, notes, rest.trim_start())
+        }
+        Some(existing) if !existing.is_empty() => format!("{}\n{}", notes, existing),
+        _ => format!("# Changelog\n\n{}", notes),
+    }
+}
+
+/// 读取版本文件中的版本号
+pub fn read_file_version(file_name: &str, content: &str) -> Option<SemVer> {
+    find_version_span(file_name, content)
+        .and_then(|(start, end)| SemVer::parse(&content[start..end]))
+}
+
+/// 改写版本文件中的版本号，返回新内容；文件中没有可改写的版本字段时返回 None
+pub fn bump_file_version(file_name: &str, content: &str, version: &SemVer) -> Option<String> {
+    let (start, end) = find_version_span(file_name, content)?;
+    Some(format!(
+        "{}{}{}",
+        &content[..start],
+        version,
+        &content[end..]
+    ))
+}
+
+static CARGO_VERSION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^version\s*=\s*"([^"]+)""#).expect("valid cargo version regex"));
+static JSON_VERSION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""version"\s*:\s*"([^"]+)""#).expect("valid json version regex"));
+
+/// 版本号在文件中的字节范围：Cargo.toml 只看 [package] 段，JSON 取第一个 "version" 字段
+fn find_version_span(file_name: &str, content: &str) -> Option<(usize, usize)> {
+    if file_name == "Cargo.toml" {
+        let mut in_package = false;
+        let mut offset = 0;
+
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_package = trimmed == "[package]";
+            } else if in_package {
+                if let Some(version) = CARGO_VERSION.captures(trimmed).and_then(|c| c.get(1)) {
+                    let indent = line.len() - line.trim_start().len();
+                    let start = offset + indent + version.start();
+                    return Some((start, start + version.len()));
+                }
+            }
+            offset += line.len();
+        }
+        None
+    } else {
+        JSON_VERSION
+            .captures(content)
+            .and_then(|c| c.get(1))
+            .map(|m| (m.start(), m.end()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn derives_bump_from_conventional_commits() {
+        assert_eq!(
+            bump_from_commits(&messages(&["fix: a", "docs: b"])),
+            Some(VersionBump::Patch)
+        );
+        assert_eq!(
+            bump_from_commits(&messages(&["fix: a", "feat(ui): b"])),
+            Some(VersionBump::Minor)
+        );
+        assert_eq!(
+            bump_from_commits(&messages(&["feat!: drop v1 api", "fix: c"])),
+            Some(VersionBump::Major)
+        );
+        assert_eq!(
+            bump_from_commits(&messages(&["refactor: x\n\nBREAKING CHANGE: config moved"])),
+            Some(VersionBump::Major)
+        );
+        assert_eq!(bump_from_commits(&[]), None);
+
+        let version = SemVer::parse("v1.4.2").unwrap();
+        assert_eq!(version.bump(VersionBump::Minor).to_string(), "1.5.0");
+        assert_eq!(
+            SemVer::parse("2.0.0-rc.1")
+                .unwrap()
+                .bump(VersionBump::Major)
+                .to_string(),
+            "2.0.0"
+        );
+        assert!(SemVer::parse("1.0.0-rc.1").unwrap() < SemVer::parse("1.0.0").unwrap());
+    }
+
+    #[test]
+    fn bumps_only_the_package_version() {
+        let cargo = "[package]\nname = \"app\"\nversion = \"0.4.25\"\n\n[dependencies]\nserde = { version = \"1.0\" }\n";
+        let version = SemVer::parse("0.5.0").unwrap();
+
+        assert_eq!(
+            read_file_version("Cargo.toml", cargo),
+            SemVer::parse("0.4.25")
+        );
+        let bumped = bump_file_version("Cargo.toml", cargo, &version).unwrap();
+        assert!(bumped.contains("version = \"0.5.0\""));
+        assert!(bumped.contains("serde = { version = \"1.0\" }"));
+
+        let package =
+            "{\n  \"name\": \"app\",\n  \"version\": \"0.4.25\",\n  \"dependencies\": {}\n}\n";
+        assert_eq!(
+            bump_file_version("package.json", package, &version).unwrap(),
+            package.replace("0.4.25", "0.5.0")
+        );
+    }
+
+    #[test]
+    fn groups_release_notes_by_type() {
+        let notes = release_notes(
+            "v1.1.0",
+            "2025-10-04",
+            &messages(&[
+                "feat(ui): add dark mode",
+                "fix: crash on start",
+                "chore: bump deps",
+            ]),
+        );
+
+        assert_eq!(
+            notes,
+            "## v1.1.0 (2025-10-04)\n\n### Features\n\n- **ui:** add dark mode\n\n### Bug Fixes\n\n- crash on start\n\n### Other Changes\n\n- bump deps\n"
+        );
+
+        let changelog = prepend_changelog(Some("# Changelog\n\n## v1.0.0\n"), &notes);
+        assert!(changelog.starts_with("# Changelog\n\n## v1.1.0"));
+        assert!(changelog.ends_with("## v1.0.0\n"));
+    }
+}
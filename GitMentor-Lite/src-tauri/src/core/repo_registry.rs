@@ -0,0 +1,665 @@
+// 仓库登记簿：最近打开、收藏置顶、显示名、分组与每个仓库的默认设置（模板、语言、Gitflow），持久化到 repositories.json
+// Author: Evilek, Date: 2025-10-13
+// id 与 repo_workspace 一致（规范化路径的哈希）；目录扫描也放这里，按深度和忽略规则找 Git 仓库
+
+use crate::core::repo_workspace::{canonical_repo_path, repo_id_for_path};
+use crate::types::git_types::GitflowConfig;
+use crate::utils::config_file::load_json_or_backup;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 不收藏、没有自定义信息的最近仓库最多保留的数量
+const MAX_RECENT: usize = 30;
+
+/// 扫描默认跳过的目录
+const DEFAULT_SCAN_IGNORE: &[&str] = &[
+    "node_modules",
+    "target",
+    "vendor",
+    "dist",
+    "build",
+    "out",
+    ".cache",
+    ".venv",
+    "venv",
+    "__pycache__",
+    "Library",
+    "AppData",
+];
+
+/// 仓库默认设置，空值表示沿用全局设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepoDefaults {
+    /// 提交信息模板 id
+    pub template_id: Option<String>,
+    /// 提交信息 / 报告语言，如 zh-CN、en
+    pub language: Option<String>,
+    /// 仓库尚未初始化 Gitflow 时使用的分支与前缀配置
+    pub gitflow: Option<GitflowConfig>,
+}
+
+impl RepoDefaults {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// 登记的仓库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegisteredRepository {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    /// 用户自定义的显示名
+    pub display_name: Option<String>,
+    pub group: Option<String>,
+    pub pinned: bool,
+    pub last_opened_at: Option<String>,
+    /// 上次打开 / 关闭时所在分支
+    pub last_branch: Option<String>,
+    pub open_count: u32,
+    pub added_at: String,
+    pub defaults: RepoDefaults,
+}
+
+impl Default for RegisteredRepository {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            path: String::new(),
+            name: String::new(),
+            display_name: None,
+            group: None,
+            pinned: false,
+            last_opened_at: None,
+            last_branch: None,
+            open_count: 0,
+            added_at: Utc::now().to_rfc3339(),
+            defaults: RepoDefaults::default(),
+        }
+    }
+}
+
+impl RegisteredRepository {
+    /// 只是打开过、没有任何用户设置的条目，超出最近列表上限时可以淘汰
+    fn is_plain_recent(&self) -> bool {
+        !self.pinned
+            && self.display_name.is_none()
+            && self.group.is_none()
+            && self.defaults.is_empty()
+    }
+}
+
+/// 用户可编辑的登记信息；字段为 None 表示不修改，空字符串表示清除
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepositoryRegistryUpdate {
+    pub display_name: Option<String>,
+    pub group: Option<String>,
+    pub pinned: Option<bool>,
+}
+
+/// 目录扫描选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepoScanOptions {
+    /// 相对根目录的最大深度，根目录本身为 0
+    pub max_depth: usize,
+    /// 跳过的目录名，支持 * / ? 通配（如 tmp-*）；为空时使用内置列表
+    pub ignore: Vec<String>,
+    /// 是否进入以 . 开头的隐藏目录
+    pub include_hidden: bool,
+    /// 最多返回的仓库数
+    pub max_results: usize,
+}
+
+impl Default for RepoScanOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            ignore: Vec::new(),
+            include_hidden: false,
+            max_results: 500,
+        }
+    }
+}
+
+/// 扫描发现的仓库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredRepository {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub depth: usize,
+    pub bare: bool,
+    /// 是否已在登记簿中
+    pub registered: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RegistryFile {
+    repositories: Vec<RegisteredRepository>,
+}
+
+pub struct RepoRegistry {
+    repositories: Vec<RegisteredRepository>,
+    registry_path: PathBuf,
+}
+
+impl RepoRegistry {
+    pub fn new(registry_path: PathBuf) -> Result<Self> {
+        // 文件损坏时备份后从空列表开始，避免下次保存覆盖收藏与默认设置
+        let file: RegistryFile = load_json_or_backup(&registry_path)?;
+
+        Ok(Self {
+            repositories: file.repositories,
+            registry_path,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.registry_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = RegistryFile {
+            repositories: self.repositories.clone(),
+        };
+        let content = serde_json::to_string_pretty(&file)?;
+
+        // 先写临时文件再改名，避免写一半留下坏文件
+        let temp = self.registry_path.with_extension("json.tmp");
+        fs::write(&temp, content)?;
+        fs::rename(&temp, &self.registry_path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RegisteredRepository> {
+        self.repositories.iter().find(|repo| repo.id == id)
+    }
+
+    /// 收藏在前，其余按最近打开时间倒序
+    pub fn list(&self) -> Vec<RegisteredRepository> {
+        let mut repositories = self.repositories.clone();
+        repositories.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| b.last_opened_at.cmp(&a.last_opened_at))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        repositories
+    }
+
+    /// 所有分组名（去重、排序）
+    pub fn groups(&self) -> Vec<String> {
+        self.repositories
+            .iter()
+            .filter_map(|repo| repo.group.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// 登记仓库（不记为打开），已登记时返回原条目
+    pub fn register(&mut self, path: &str) -> Result<RegisteredRepository> {
+        let canonical = canonical_repo_path(path)?;
+        let index = self.ensure_entry(&canonical);
+        let entry = self.repositories[index].clone();
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// 记录一次打开：更新时间、次数和当前分支
+    pub fn record_open(
+        &mut self,
+        path: &str,
+        branch: Option<String>,
+    ) -> Result<RegisteredRepository> {
+        let canonical = canonical_repo_path(path)?;
+        let index = self.ensure_entry(&canonical);
+
+        let entry = &mut self.repositories[index];
+        entry.last_opened_at = Some(Utc::now().to_rfc3339());
+        entry.open_count += 1;
+        if branch.is_some() {
+            entry.last_branch = branch;
+        }
+        let entry = entry.clone();
+
+        self.prune_recent();
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// 记录仓库当前分支（关闭仓库时调用）
+    pub fn record_branch(&mut self, id: &str, branch: String) -> Result<()> {
+        let entry = self.entry_mut(id)?;
+        entry.last_branch = Some(branch);
+        self.save()
+    }
+
+    pub fn update(
+        &mut self,
+        id: &str,
+        update: RepositoryRegistryUpdate,
+    ) -> Result<RegisteredRepository> {
+        let entry = self.entry_mut(id)?;
+
+        if let Some(display_name) = update.display_name {
+            entry.display_name = non_empty(display_name);
+        }
+        if let Some(group) = update.group {
+            entry.group = non_empty(group);
+        }
+        if let Some(pinned) = update.pinned {
+            entry.pinned = pinned;
+        }
+
+        let entry = entry.clone();
+        self.save()?;
+        Ok(entry)
+    }
+
+    pub fn set_defaults(
+        &mut self,
+        id: &str,
+        defaults: RepoDefaults,
+    ) -> Result<RegisteredRepository> {
+        let entry = self.entry_mut(id)?;
+        entry.defaults = RepoDefaults {
+            template_id: defaults.template_id.and_then(non_empty),
+            language: defaults.language.and_then(non_empty),
+            gitflow: defaults.gitflow,
+        };
+
+        let entry = entry.clone();
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// 仓库默认设置，未登记时为空
+    pub fn defaults(&self, id: &str) -> RepoDefaults {
+        self.get(id)
+            .map(|repo| repo.defaults.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<()> {
+        let before = self.repositories.len();
+        self.repositories.retain(|repo| repo.id != id);
+        if self.repositories.len() == before {
+            return Err(anyhow!("仓库未登记: {}", id));
+        }
+        self.save()
+    }
+
+    fn ensure_entry(&mut self, canonical: &str) -> usize {
+        let id = repo_id_for_path(canonical);
+        if let Some(index) = self.repositories.iter().position(|repo| repo.id == id) {
+            // 路径大小写或分隔符可能变化，以最新的为准
+            self.repositories[index].path = canonical.to_string();
+            return index;
+        }
+
+        self.repositories.push(RegisteredRepository {
+            id,
+            path: canonical.to_string(),
+            name: file_name(Path::new(canonical)),
+            ..RegisteredRepository::default()
+        });
+        self.repositories.len() - 1
+    }
+
+    fn entry_mut(&mut self, id: &str) -> Result<&mut RegisteredRepository> {
+        self.repositories
+            .iter_mut()
+            .find(|repo| repo.id == id)
+            .ok_or_else(|| anyhow!("仓库未登记: {}", id))
+    }
+
+    /// 只淘汰最久未打开的普通条目，收藏、分组和有默认设置的条目一直保留
+    fn prune_recent(&mut self) {
+        let mut plain: Vec<(Option<String>, String)> = self
+            .repositories
+            .iter()
+            .filter(|repo| repo.is_plain_recent())
+            .map(|repo| (repo.last_opened_at.clone(), repo.id.clone()))
+            .collect();
+        if plain.len() <= MAX_RECENT {
+            return;
+        }
+
+        plain.sort();
+        let excess = plain.len() - MAX_RECENT;
+        let evicted: BTreeSet<String> = plain.into_iter().take(excess).map(|(_, id)| id).collect();
+        self.repositories.retain(|repo| !evicted.contains(&repo.id));
+    }
+}
+
+/// 按深度优先遍历目录找 Git 仓库；找到仓库后不再进入其内部（子模块不单独列出）
+pub fn scan_repositories(
+    root: &Path,
+    options: &RepoScanOptions,
+) -> Result<Vec<DiscoveredRepository>> {
+    if !root.is_dir() {
+        return Err(anyhow!("扫描目录不存在: {}", root.display()));
+    }
+
+    let ignore: Vec<String> = if options.ignore.is_empty() {
+        DEFAULT_SCAN_IGNORE.iter().map(|s| s.to_string()).collect()
+    } else {
+        options.ignore.clone()
+    };
+
+    let mut found = Vec::new();
+    let mut stack = vec![(root.to_path_buf(), 0usize)];
+
+    while let Some((dir, depth)) = stack.pop() {
+        if found.len() >= options.max_results {
+            break;
+        }
+
+        if let Some(bare) = repository_kind(&dir) {
+            let path = canonical_repo_path(&dir.to_string_lossy())
+                .unwrap_or_else(|_| dir.to_string_lossy().to_string());
+            found.push(DiscoveredRepository {
+                id: repo_id_for_path(&path),
+                name: file_name(&dir),
+                path,
+                depth,
+                bare,
+                registered: false,
+            });
+            continue;
+        }
+
+        if depth >= options.max_depth {
+            continue;
+        }
+
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        let mut children: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false))
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let name = file_name(path);
+                (options.include_hidden || !name.starts_with('.'))
+                    && !ignore.iter().any(|pattern| wildcard_match(pattern, &name))
+            })
+            .collect();
+
+        // 倒序入栈，保证按名称顺序出栈
+        children.sort();
+        stack.extend(children.into_iter().rev().map(|path| (path, depth + 1)));
+    }
+
+    Ok(found)
+}
+
+/// 目录是 Git 仓库时返回是否为裸仓库
+fn repository_kind(dir: &Path) -> Option<bool> {
+    // .git 目录或 worktree / 子模块的 .git 文件
+    if dir.join(".git").exists() {
+        return Some(false);
+    }
+
+    let bare =
+        dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir();
+    bare.then_some(true)
+}
+
+/// 简单通配：* 匹配任意串、? 匹配单个字符，大小写不敏感
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == name[n] || pattern[p] == '?') {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            mark = n;
+            p += 1;
+        } else if let Some(star) = star {
+            p = star + 1;
+            mark += 1;
+            n = mark;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 仓库当前分支名，分离 HEAD 或读取失败时为 None
+pub fn current_branch(path: &str) -> Option<String> {
+    let repo = git2::Repository::open(path).ok()?;
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    head.shorthand().map(str::to_string)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let trimmed = value.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::init_repo;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_registry_recent_pins_and_defaults() {
+        let temp = TempDir::new().unwrap();
+        let (alpha, beta) = (temp.path().join("alpha"), temp.path().join("beta"));
+        init_repo(&alpha);
+        init_repo(&beta);
+        let registry_path = temp.path().join("config").join("repositories.json");
+
+        let mut registry = RepoRegistry::new(registry_path.clone()).unwrap();
+        let first = registry
+            .record_open(alpha.to_str().unwrap(), Some("main".to_string()))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let second = registry
+            .record_open(beta.to_str().unwrap(), Some("develop".to_string()))
+            .unwrap();
+        registry
+            .record_open(alpha.join(".").to_str().unwrap(), None)
+            .unwrap();
+
+        // 同一仓库不重复登记，分支沿用上次记录
+        assert_eq!(registry.list().len(), 2);
+        assert_eq!(registry.list()[0].id, first.id);
+        assert_eq!(registry.get(&first.id).unwrap().open_count, 2);
+        assert_eq!(
+            registry.get(&first.id).unwrap().last_branch.as_deref(),
+            Some("main")
+        );
+
+        // 收藏排在最前
+        registry
+            .update(
+                &second.id,
+                RepositoryRegistryUpdate {
+                    display_name: Some("  Beta 服务 ".to_string()),
+                    group: Some("后端".to_string()),
+                    pinned: Some(true),
+                },
+            )
+            .unwrap();
+        assert_eq!(registry.list()[0].id, second.id);
+        assert_eq!(registry.groups(), vec!["后端".to_string()]);
+
+        registry
+            .set_defaults(
+                &second.id,
+                RepoDefaults {
+                    template_id: Some("conventional".to_string()),
+                    language: Some(" ".to_string()),
+                    gitflow: Some(GitflowConfig {
+                        main_branch: "master".to_string(),
+                        ..GitflowConfig::default()
+                    }),
+                },
+            )
+            .unwrap();
+        registry
+            .record_branch(&second.id, "feature/x".to_string())
+            .unwrap();
+
+        // 重新加载后设置仍在
+        let reloaded = RepoRegistry::new(registry_path).unwrap();
+        let beta_entry = reloaded.get(&second.id).unwrap();
+        assert_eq!(beta_entry.display_name.as_deref(), Some("Beta 服务"));
+        assert!(beta_entry.pinned);
+        assert_eq!(beta_entry.last_branch.as_deref(), Some("feature/x"));
+        let defaults = reloaded.defaults(&second.id);
+        assert_eq!(defaults.template_id.as_deref(), Some("conventional"));
+        assert_eq!(defaults.language, None);
+        assert_eq!(defaults.gitflow.unwrap().main_branch, "master");
+        assert!(reloaded.defaults("missing").is_empty());
+
+        // 清空分组
+        let mut reloaded = reloaded;
+        reloaded
+            .update(
+                &second.id,
+                RepositoryRegistryUpdate {
+                    group: Some(String::new()),
+                    ..RepositoryRegistryUpdate::default()
+                },
+            )
+            .unwrap();
+        assert!(reloaded.groups().is_empty());
+        reloaded.remove(&first.id).unwrap();
+        assert!(reloaded.remove(&first.id).is_err());
+        assert_eq!(reloaded.list().len(), 1);
+    }
+
+    #[test]
+    fn test_registry_prunes_only_plain_recents() {
+        let temp = TempDir::new().unwrap();
+        let mut registry = RepoRegistry::new(temp.path().join("repositories.json")).unwrap();
+
+        let pinned_dir = temp.path().join("pinned");
+        init_repo(&pinned_dir);
+        let pinned = registry
+            .record_open(pinned_dir.to_str().unwrap(), None)
+            .unwrap();
+        registry
+            .update(
+                &pinned.id,
+                RepositoryRegistryUpdate {
+                    pinned: Some(true),
+                    ..RepositoryRegistryUpdate::default()
+                },
+            )
+            .unwrap();
+
+        for i in 0..MAX_RECENT + 3 {
+            let dir = temp.path().join(format!("repo-{:02}", i));
+            init_repo(&dir);
+            registry.record_open(dir.to_str().unwrap(), None).unwrap();
+        }
+
+        assert_eq!(registry.list().len(), MAX_RECENT + 1);
+        assert!(registry.get(&pinned.id).is_some());
+    }
+
+    #[test]
+    fn test_corrupt_registry_loads_empty() {
+        let temp = TempDir::new().unwrap();
+        let registry_path = temp.path().join("repositories.json");
+        fs::write(&registry_path, "{ \"repositories\": [").unwrap();
+
+        // 备份细节由 config_file 的测试覆盖
+        let registry = RepoRegistry::new(registry_path).unwrap();
+        assert!(registry.list().is_empty());
+    }
+
+    #[test]
+    fn test_scan_repositories_with_depth_and_ignore() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        init_repo(&root.join("work/app"));
+        init_repo(&root.join("work/app/sub")); // 仓库内部不再深入
+        init_repo(&root.join("work/deep/a/b/c"));
+        init_repo(&root.join("node_modules/pkg"));
+        init_repo(&root.join(".hidden/repo"));
+        init_repo(&root.join("tmp-1/repo"));
+        git2::Repository::init_bare(root.join("mirrors/lib.git")).unwrap();
+
+        let names = |found: &[DiscoveredRepository]| {
+            found
+                .iter()
+                .map(|repo| repo.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let found = scan_repositories(root, &RepoScanOptions::default()).unwrap();
+        assert_eq!(names(&found), vec!["lib.git", "repo", "app"]);
+        assert!(found[0].bare);
+        assert_eq!(found[2].depth, 2);
+
+        let options = RepoScanOptions {
+            max_depth: 5,
+            ignore: vec!["tmp-*".to_string(), "mirrors".to_string()],
+            include_hidden: true,
+            ..RepoScanOptions::default()
+        };
+        let found = scan_repositories(root, &options).unwrap();
+        assert_eq!(names(&found), vec!["repo", "pkg", "app", "c"]);
+
+        let limited = RepoScanOptions {
+            max_results: 1,
+            ..options
+        };
+        assert_eq!(scan_repositories(root, &limited).unwrap().len(), 1);
+        assert!(scan_repositories(&root.join("missing"), &RepoScanOptions::default()).is_err());
+
+        let mut registry = RepoRegistry::new(root.join("repositories.json")).unwrap();
+        registry
+            .register(root.join("work/app").to_str().unwrap())
+            .unwrap();
+        let found = scan_repositories(&root.join("work"), &RepoScanOptions::default()).unwrap();
+        let app = found.iter().find(|repo| repo.name == "app").unwrap();
+        assert!(registry.get(&app.id).is_some());
+        let c = found.iter().find(|repo| repo.name == "c").unwrap();
+        assert!(registry.get(&c.id).is_none());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("tmp-*", "TMP-cache"));
+        assert!(wildcard_match("*.bak", "old.bak"));
+        assert!(wildcard_match("build?", "build2"));
+        assert!(!wildcard_match("build?", "build"));
+        assert!(!wildcard_match("target", "targets"));
+    }
+}
@@ -0,0 +1,744 @@
+// 仓库文件监控：遵循 .gitignore / .git/info/exclude，按路径合并事件，区分索引 / HEAD / 引用变化与工作区编辑
+// Author: Evilek, Date: 2025-10-14
+// 工作区编辑只推送受影响路径的状态增量（git-status::delta）；索引、HEAD、引用变化（应用外的提交、检出）才让前端整体刷新（git-status::dirty）
+
+use crate::core::repo_workspace::repo_id_for_path;
+use crate::debug_log;
+use crate::types::git_types::{FileStatus, FileStatusType};
+use anyhow::{anyhow, Result};
+use git2::{Repository, Status, StatusOptions};
+use notify::{
+    Config as NotifyConfig, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+pub const DIRTY_EVENT: &str = "git-status::dirty";
+pub const DELTA_EVENT: &str = "git-status::delta";
+
+/// 最后一个事件之后静默多久再推送
+const QUIET_PERIOD: Duration = Duration::from_millis(200);
+/// 持续有事件时，一批最多攒这么久
+const MAX_BATCH_DELAY: Duration = Duration::from_millis(1000);
+/// 一批改动路径超过这个数就不算增量，让前端整体刷新
+const MAX_DELTA_PATHS: usize = 200;
+/// 工作区整体递归监控：FSEvents 每次添加监控都会重建事件流，Windows 每个目录占一个句柄，
+/// 这两个平台只挂一个递归监控、按 .gitignore 过滤事件；inotify 才逐个目录监控以跳过被忽略的目录
+const RECURSIVE_WORKTREE_WATCH: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// 需要整体刷新的变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoChangeKind {
+    /// 暂存区（应用外 git add / reset）
+    Index,
+    /// HEAD 指向或进行中的合并 / 变基等状态（检出、合并）
+    Head,
+    /// 分支、标签、远程引用（提交、拉取、推送）
+    Refs,
+    /// 工作区一次改动路径过多
+    #[serde(rename = "worktree")]
+    WorkTree,
+}
+
+/// git-status::dirty 负载
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoDirtyPayload {
+    pub repository: String,
+    #[serde(rename = "repoId")]
+    pub repo_id: String,
+    /// 兼容旧字段：本批主要变化类型
+    #[serde(rename = "eventKind")]
+    pub event_kind: RepoChangeKind,
+    pub changes: Vec<RepoChangeKind>,
+}
+
+/// 单个文件的新状态，四个列表里为 None 的表示不在该列表
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStatusChange {
+    pub path: String,
+    pub staged: Option<FileStatus>,
+    pub unstaged: Option<FileStatus>,
+    pub untracked: Option<FileStatus>,
+    pub conflicted: Option<FileStatus>,
+}
+
+/// git-status::delta 负载：先清掉 cleared 中每个路径（及其下级路径）的旧条目，再按 changes 放入新条目
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusDeltaPayload {
+    pub repository: String,
+    #[serde(rename = "repoId")]
+    pub repo_id: String,
+    pub cleared: Vec<String>,
+    pub changes: Vec<FileStatusChange>,
+}
+
+#[derive(Debug, Clone)]
+pub enum RepoWatchEvent {
+    Dirty(RepoDirtyPayload),
+    Delta(StatusDeltaPayload),
+}
+
+pub type RepoEventSink = Box<dyn Fn(RepoWatchEvent) + Send + 'static>;
+
+enum WatchMessage {
+    Notify(Event),
+    Stop,
+}
+
+/// 事件路径的归类结果
+#[derive(Debug, PartialEq, Eq)]
+enum PathClass {
+    Git(RepoChangeKind),
+    WorkTree(String),
+    Skip,
+}
+
+/// 监控句柄，drop 时停止监控线程
+#[derive(Debug)]
+pub struct RepoWatcher {
+    control: Sender<WatchMessage>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl RepoWatcher {
+    pub fn start(repo_path: &str, sink: RepoEventSink) -> Result<Self> {
+        let repo = Repository::open(repo_path)?;
+        if repo.is_bare() {
+            return Err(anyhow!("Bare repository cannot be watched"));
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let notify_tx = tx.clone();
+        let watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| match res {
+                Ok(event) => {
+                    let _ = notify_tx.send(WatchMessage::Notify(event));
+                }
+                Err(err) => debug_log!("[DEBUG] 仓库文件监控出现错误: {}", err),
+            },
+            NotifyConfig::default().with_poll_interval(Duration::from_secs(2)),
+        )
+        .map_err(|e| anyhow!("Failed to start repository watcher: {}", e))?;
+
+        let layout = RepoLayout::new(&repo)?;
+        let repository = repo_path.to_string();
+        let worker = std::thread::Builder::new()
+            .name("gitmentor-repo-watcher".to_string())
+            .spawn(move || {
+                let mut worker = WatchWorker {
+                    repo_id: repo_id_for_path(&repository),
+                    repository,
+                    repo,
+                    layout,
+                    watcher,
+                    sink,
+                    batch: WatchBatch::default(),
+                };
+                worker.run(rx);
+            })?;
+
+        Ok(Self {
+            control: tx,
+            worker: Some(worker),
+        })
+    }
+}
+
+impl Drop for RepoWatcher {
+    fn drop(&mut self) {
+        let _ = self.control.send(WatchMessage::Stop);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// 工作区、git 目录与公共目录（linked worktree 的引用在公共目录）
+struct RepoLayout {
+    workdir: PathBuf,
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+}
+
+impl RepoLayout {
+    fn new(repo: &Repository) -> Result<Self> {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("Bare repository cannot be watched"))?;
+        let git_dir = repo.path().to_path_buf();
+        let common_dir = fs::read_to_string(git_dir.join("commondir"))
+            .map(|dir| git_dir.join(dir.trim()))
+            .unwrap_or_else(|_| git_dir.clone());
+
+        Ok(Self {
+            workdir: normalize(workdir),
+            git_dir: normalize(&git_dir),
+            common_dir: normalize(&common_dir),
+        })
+    }
+
+    fn classify(&self, repo: &Repository, path: &Path) -> PathClass {
+        if let Some(kind) = self.classify_git_path(path) {
+            return kind;
+        }
+
+        let Ok(relative) = path.strip_prefix(&self.workdir) else {
+            return PathClass::Skip;
+        };
+        let relative = to_repo_path(relative);
+        if relative.is_empty() || relative == ".git" || relative.starts_with(".git/") {
+            return PathClass::Skip;
+        }
+
+        let probe = if path.is_dir() {
+            format!("{}/", relative)
+        } else {
+            relative.clone()
+        };
+        if repo.is_path_ignored(&probe).unwrap_or(false) {
+            return PathClass::Skip;
+        }
+
+        PathClass::WorkTree(relative)
+    }
+
+    fn classify_git_path(&self, path: &Path) -> Option<PathClass> {
+        let in_git_dir = path.strip_prefix(&self.git_dir).ok();
+        let in_common_dir = path.strip_prefix(&self.common_dir).ok();
+        let relative = to_repo_path(in_git_dir.or(in_common_dir)?);
+
+        // 锁文件只是过程，改名落地时会有正式文件的事件
+        if relative.ends_with(".lock") {
+            return Some(PathClass::Skip);
+        }
+
+        let first = relative.split('/').next().unwrap_or_default();
+        let kind = match first {
+            "index" => RepoChangeKind::Index,
+            "HEAD" | "ORIG_HEAD" | "MERGE_HEAD" | "CHERRY_PICK_HEAD" | "REVERT_HEAD"
+            | "rebase-merge" | "rebase-apply" => RepoChangeKind::Head,
+            "refs" | "packed-refs" | "FETCH_HEAD" => RepoChangeKind::Refs,
+            _ => return Some(PathClass::Skip),
+        };
+        Some(PathClass::Git(kind))
+    }
+}
+
+#[derive(Default)]
+struct WatchBatch {
+    kinds: BTreeSet<RepoChangeKind>,
+    paths: BTreeSet<String>,
+    started: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl WatchBatch {
+    fn touch(&mut self) {
+        let now = Instant::now();
+        self.started.get_or_insert(now);
+        self.last_event = Some(now);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.kinds.is_empty() && self.paths.is_empty()
+    }
+
+    /// 距离应推送还要等多久；None 表示没有待推送的改动
+    fn wait_time(&self) -> Option<Duration> {
+        let (started, last_event) = (self.started?, self.last_event?);
+        let quiet = QUIET_PERIOD.saturating_sub(last_event.elapsed());
+        let cap = MAX_BATCH_DELAY.saturating_sub(started.elapsed());
+        Some(quiet.min(cap))
+    }
+}
+
+struct WatchWorker {
+    repository: String,
+    repo_id: String,
+    repo: Repository,
+    layout: RepoLayout,
+    watcher: RecommendedWatcher,
+    sink: RepoEventSink,
+    batch: WatchBatch,
+}
+
+impl WatchWorker {
+    fn run(&mut self, rx: mpsc::Receiver<WatchMessage>) {
+        debug_log!("[DEBUG] 启动仓库文件监控: {}", self.repository);
+        self.watch_git_dirs();
+        let workdir = self.layout.workdir.clone();
+        let watched = self.watch_tree(&workdir);
+        debug_log!("[DEBUG] 仓库文件监控就绪，目录数: {}", watched);
+
+        loop {
+            let message = match self.batch.wait_time() {
+                Some(wait) => rx.recv_timeout(wait),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match message {
+                Ok(WatchMessage::Notify(event)) => self.handle_event(event),
+                Ok(WatchMessage::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => self.flush(),
+            }
+
+            if self.batch.wait_time() == Some(Duration::ZERO) {
+                self.flush();
+            }
+        }
+
+        debug_log!("[DEBUG] 停止仓库文件监控: {}", self.repository);
+    }
+
+    fn watch_git_dirs(&mut self) {
+        let (git_dir, common_dir) = (self.layout.git_dir.clone(), self.layout.common_dir.clone());
+        // 递归监控工作区时，位于工作区内的 git 目录已被覆盖
+        let covered =
+            |dir: &Path| RECURSIVE_WORKTREE_WATCH && dir.starts_with(&self.layout.workdir);
+        let (git_dir_covered, common_dir_covered) = (covered(&git_dir), covered(&common_dir));
+
+        if !git_dir_covered {
+            self.watch(&git_dir, RecursiveMode::NonRecursive);
+        }
+        if !common_dir_covered {
+            if common_dir != git_dir {
+                self.watch(&common_dir, RecursiveMode::NonRecursive);
+            }
+            self.watch(&common_dir.join("refs"), RecursiveMode::Recursive);
+        }
+    }
+
+    /// inotify 下逐个目录非递归监控，跳过被忽略的目录、.git 和嵌套仓库；
+    /// 其他平台整体递归监控，由 handle_event 过滤。返回监控的目录数
+    fn watch_tree(&mut self, root: &Path) -> usize {
+        if RECURSIVE_WORKTREE_WATCH {
+            return usize::from(self.watch(root, RecursiveMode::Recursive));
+        }
+
+        let mut count = 0;
+        let mut stack = vec![root.to_path_buf()];
+
+        while let Some(dir) = stack.pop() {
+            if !self.watch(&dir, RecursiveMode::NonRecursive) {
+                continue;
+            }
+            count += 1;
+
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let is_dir = entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false);
+                let path = entry.path();
+                if is_dir
+                    && entry.file_name() != ".git"
+                    && !path.join(".git").exists()
+                    && matches!(
+                        self.layout.classify(&self.repo, &path),
+                        PathClass::WorkTree(_)
+                    )
+                {
+                    stack.push(path);
+                }
+            }
+        }
+
+        count
+    }
+
+    fn watch(&mut self, path: &Path, mode: RecursiveMode) -> bool {
+        match self.watcher.watch(path, mode) {
+            Ok(()) => true,
+            Err(err) => {
+                debug_log!("[DEBUG] 无法监控目录 {}: {}", path.display(), err);
+                false
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        if !matches!(
+            event.kind,
+            EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) | EventKind::Any
+        ) {
+            return;
+        }
+
+        for path in &event.paths {
+            match self.layout.classify(&self.repo, path) {
+                PathClass::Git(kind) => {
+                    self.batch.kinds.insert(kind);
+                    self.batch.touch();
+                }
+                PathClass::WorkTree(relative) => {
+                    if RECURSIVE_WORKTREE_WATCH {
+                        // 递归监控会带上嵌套仓库里的事件
+                        if self.in_nested_repo(path) {
+                            continue;
+                        }
+                    } else if path.is_dir() && !path.join(".git").exists() {
+                        // 新建或移入的目录需要补上监控
+                        self.watch_tree(path);
+                    }
+                    self.batch.paths.insert(relative);
+                    self.batch.touch();
+                }
+                PathClass::Skip => {}
+            }
+        }
+    }
+
+    /// 路径是否位于工作区内的嵌套仓库（子模块、独立克隆）中
+    fn in_nested_repo(&self, path: &Path) -> bool {
+        path.ancestors()
+            .take_while(|dir| *dir != self.layout.workdir)
+            .any(|dir| dir.join(".git").exists())
+    }
+
+    fn flush(&mut self) {
+        let batch = std::mem::take(&mut self.batch);
+        if batch.is_empty() {
+            return;
+        }
+
+        if let Some(event) = self.build_event(batch) {
+            (self.sink)(event);
+        }
+    }
+
+    fn build_event(&self, batch: WatchBatch) -> Option<RepoWatchEvent> {
+        let mut kinds = batch.kinds;
+        if !kinds.is_empty() || batch.paths.len() > MAX_DELTA_PATHS {
+            if !batch.paths.is_empty() {
+                kinds.insert(RepoChangeKind::WorkTree);
+            }
+            let changes: Vec<_> = kinds.into_iter().collect();
+            return Some(RepoWatchEvent::Dirty(RepoDirtyPayload {
+                repository: self.repository.clone(),
+                repo_id: self.repo_id.clone(),
+                event_kind: changes[0],
+                changes,
+            }));
+        }
+
+        let cleared: Vec<String> = batch.paths.into_iter().collect();
+        match status_changes(&self.repo, &cleared) {
+            Ok(changes) => Some(RepoWatchEvent::Delta(StatusDeltaPayload {
+                repository: self.repository.clone(),
+                repo_id: self.repo_id.clone(),
+                cleared,
+                changes,
+            })),
+            Err(err) => {
+                debug_log!("[DEBUG] 计算状态增量失败，改为整体刷新: {}", err);
+                Some(RepoWatchEvent::Dirty(RepoDirtyPayload {
+                    repository: self.repository.clone(),
+                    repo_id: self.repo_id.clone(),
+                    event_kind: RepoChangeKind::WorkTree,
+                    changes: vec![RepoChangeKind::WorkTree],
+                }))
+            }
+        }
+    }
+}
+
+/// 只查询给定路径（目录含其下文件）的状态；路径按字面匹配，`[id].tsx` 之类不当作通配符
+fn status_changes(repo: &Repository, paths: &[String]) -> Result<Vec<FileStatusChange>> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .disable_pathspec_match(true);
+    for path in paths {
+        opts.pathspec(path);
+    }
+
+    let statuses = repo.statuses(Some(&mut opts))?;
+    let mut changes = BTreeMap::new();
+    for entry in statuses.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        changes.insert(path.to_string(), file_status_change(path, entry.status()));
+    }
+
+    Ok(changes.into_values().collect())
+}
+
+/// 与 get_status 的分类保持一致：冲突 / 暂存 / 未跟踪 / 未暂存；同时有暂存和工作区改动的文件两边都出现
+fn file_status_change(path: &str, status: Status) -> FileStatusChange {
+    let index_status = if status.is_index_new() {
+        Some(FileStatusType::Added)
+    } else if status.is_index_deleted() {
+        Some(FileStatusType::Deleted)
+    } else if status.is_index_renamed() {
+        Some(FileStatusType::Renamed)
+    } else if status.is_index_modified() || status.is_index_typechange() {
+        Some(FileStatusType::Modified)
+    } else {
+        None
+    };
+
+    let working_tree_status = if status.is_wt_new() {
+        Some(FileStatusType::Untracked)
+    } else if status.is_wt_deleted() {
+        Some(FileStatusType::Deleted)
+    } else if status.is_wt_renamed() {
+        Some(FileStatusType::Renamed)
+    } else if status.is_wt_modified() || status.is_wt_typechange() {
+        Some(FileStatusType::Modified)
+    } else {
+        None
+    };
+
+    let file_status = |is_staged: bool| FileStatus {
+        path: path.to_string(),
+        working_tree_status: working_tree_status.clone(),
+        index_status: index_status.clone(),
+        selected: false,
+        is_staged,
+    };
+
+    let mut change = FileStatusChange {
+        path: path.to_string(),
+        staged: None,
+        unstaged: None,
+        untracked: None,
+        conflicted: None,
+    };
+
+    if status.is_conflicted() {
+        change.conflicted = Some(FileStatus {
+            working_tree_status: Some(FileStatusType::Conflicted),
+            ..file_status(false)
+        });
+        return change;
+    }
+
+    if index_status.is_some() {
+        change.staged = Some(file_status(true));
+    }
+    match working_tree_status {
+        Some(FileStatusType::Untracked) => change.untracked = Some(file_status(false)),
+        Some(_) => change.unstaged = Some(file_status(false)),
+        None => {}
+    }
+    change
+}
+
+/// 相对路径统一用 / 分隔，与 git 输出一致
+fn to_repo_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// notify 上报的是规范化后的绝对路径，比较前把仓库路径也规范化
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::{commit_files, git};
+    use tempfile::TempDir;
+
+    fn init_repo() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        crate::core::test_support::init_repo(dir);
+        fs::create_dir_all(dir.join(".git/info")).unwrap();
+        fs::write(dir.join(".git/info/exclude"), "scratch/\n").unwrap();
+        commit_files(
+            dir,
+            &[
+                (".gitignore", "target/\n*.log\n"),
+                ("tracked.txt", "one\n"),
+                ("gone.txt", "bye\n"),
+            ],
+            "init",
+        );
+        temp
+    }
+
+    fn channel_sink() -> (RepoEventSink, mpsc::Receiver<RepoWatchEvent>) {
+        let (tx, rx) = mpsc::channel();
+        let sink: RepoEventSink = Box::new(move |event| {
+            let _ = tx.send(event);
+        });
+        (sink, rx)
+    }
+
+    #[test]
+    fn test_classify_paths() {
+        let temp = init_repo();
+        let root = normalize(temp.path());
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::create_dir_all(root.join("scratch")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+
+        let repo = Repository::open(&root).unwrap();
+        let layout = RepoLayout::new(&repo).unwrap();
+        let classify = |relative: &str| layout.classify(&repo, &root.join(relative));
+
+        assert_eq!(
+            classify(".git/index"),
+            PathClass::Git(RepoChangeKind::Index)
+        );
+        assert_eq!(classify(".git/index.lock"), PathClass::Skip);
+        assert_eq!(classify(".git/HEAD"), PathClass::Git(RepoChangeKind::Head));
+        assert_eq!(
+            classify(".git/MERGE_HEAD"),
+            PathClass::Git(RepoChangeKind::Head)
+        );
+        assert_eq!(
+            classify(".git/refs/heads/main"),
+            PathClass::Git(RepoChangeKind::Refs)
+        );
+        assert_eq!(
+            classify(".git/packed-refs"),
+            PathClass::Git(RepoChangeKind::Refs)
+        );
+        assert_eq!(classify(".git/objects/ab/cdef"), PathClass::Skip);
+        assert_eq!(classify(".git/logs/HEAD"), PathClass::Skip);
+
+        assert_eq!(classify("target"), PathClass::Skip);
+        assert_eq!(classify("target/debug/app"), PathClass::Skip);
+        assert_eq!(classify("target/debug/deps/lib.rlib"), PathClass::Skip);
+        assert_eq!(classify("build.log"), PathClass::Skip);
+        assert_eq!(classify("scratch"), PathClass::Skip);
+        assert_eq!(classify("src"), PathClass::WorkTree("src".to_string()));
+        assert_eq!(
+            classify("src/main.rs"),
+            PathClass::WorkTree("src/main.rs".to_string())
+        );
+        assert_eq!(classify(""), PathClass::Skip);
+    }
+
+    #[test]
+    fn test_status_changes_for_paths() {
+        let temp = init_repo();
+        let root = temp.path();
+        fs::write(root.join("tracked.txt"), "two\n").unwrap();
+        git(root, &["add", "tracked.txt"]);
+        fs::write(root.join("tracked.txt"), "three\n").unwrap();
+        fs::remove_file(root.join("gone.txt")).unwrap();
+        fs::create_dir_all(root.join("new/inner")).unwrap();
+        fs::write(root.join("new/inner/file.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("debug.log"), "noise\n").unwrap();
+        fs::create_dir_all(root.join("pages")).unwrap();
+        fs::write(root.join("pages/[id].tsx"), "export {}\n").unwrap();
+        fs::write(root.join("pages/i.tsx"), "export {}\n").unwrap();
+
+        let repo = Repository::open(root).unwrap();
+        let paths = vec![
+            "pages/[id].tsx".to_string(),
+            "debug.log".to_string(),
+            "gone.txt".to_string(),
+            "missing.txt".to_string(),
+            "new".to_string(),
+            "tracked.txt".to_string(),
+        ];
+        let changes = status_changes(&repo, &paths).unwrap();
+        let by_path = |path: &str| changes.iter().find(|change| change.path == path);
+
+        let tracked = by_path("tracked.txt").unwrap();
+        assert!(tracked.staged.as_ref().unwrap().is_staged);
+        assert!(tracked.unstaged.is_some());
+        assert!(tracked.untracked.is_none());
+
+        let gone = by_path("gone.txt").unwrap();
+        assert_eq!(
+            gone.unstaged.as_ref().unwrap().working_tree_status,
+            Some(FileStatusType::Deleted)
+        );
+
+        // 目录路径展开为其中的文件
+        assert!(by_path("new/inner/file.rs").unwrap().untracked.is_some());
+        assert!(by_path("debug.log").is_none());
+        assert!(by_path("missing.txt").is_none());
+        // 方括号按字面匹配，不会把 pages/i.tsx 当作 [id] 通配命中
+        assert!(by_path("pages/[id].tsx").unwrap().untracked.is_some());
+        assert!(by_path("pages/i.tsx").is_none());
+        assert_eq!(changes.len(), 4);
+    }
+
+    #[test]
+    fn test_watcher_emits_deltas_and_git_changes() {
+        let temp = init_repo();
+        let root = normalize(temp.path());
+        fs::create_dir_all(root.join("target")).unwrap();
+
+        let (sink, rx) = channel_sink();
+        let watcher = RepoWatcher::start(root.to_str().unwrap(), sink).unwrap();
+        // 等待初始目录监控就绪
+        std::thread::sleep(Duration::from_millis(300));
+
+        let next = || {
+            rx.recv_timeout(Duration::from_secs(5))
+                .expect("watch event")
+        };
+
+        // 被忽略的目录不产生事件，工作区编辑只推送增量
+        fs::write(root.join("target/out.bin"), "x").unwrap();
+        fs::write(root.join("tracked.txt"), "edited\n").unwrap();
+        match next() {
+            RepoWatchEvent::Delta(delta) => {
+                assert_eq!(delta.cleared, vec!["tracked.txt".to_string()]);
+                assert_eq!(delta.changes.len(), 1);
+                assert!(delta.changes[0].unstaged.is_some());
+                assert_eq!(delta.repo_id, repo_id_for_path(root.to_str().unwrap()));
+            }
+            other => panic!("expected delta, got {:?}", other),
+        }
+
+        // 新建目录后其中的文件也能被监控到
+        fs::create_dir_all(root.join("docs")).unwrap();
+        std::thread::sleep(Duration::from_millis(400));
+        let _ = rx.try_recv();
+        fs::write(root.join("docs/guide.md"), "# guide\n").unwrap();
+        let mut saw_guide = false;
+        while let Ok(RepoWatchEvent::Delta(delta)) = rx.recv_timeout(Duration::from_secs(2)) {
+            if delta
+                .changes
+                .iter()
+                .any(|change| change.path == "docs/guide.md")
+            {
+                saw_guide = true;
+                break;
+            }
+        }
+        assert!(saw_guide);
+
+        // 应用外提交：索引与引用变化，要求整体刷新
+        git(&root, &["commit", "-q", "-am", "outside"]);
+        let mut kinds = BTreeSet::new();
+        while let Ok(event) = rx.recv_timeout(Duration::from_secs(2)) {
+            if let RepoWatchEvent::Dirty(dirty) = event {
+                kinds.extend(dirty.changes);
+            }
+        }
+        assert!(kinds.contains(&RepoChangeKind::Index));
+        assert!(kinds.contains(&RepoChangeKind::Refs));
+
+        // 检出新分支改变 HEAD
+        git(&root, &["checkout", "-q", "-b", "feature"]);
+        let mut kinds = BTreeSet::new();
+        while let Ok(event) = rx.recv_timeout(Duration::from_secs(2)) {
+            if let RepoWatchEvent::Dirty(dirty) = event {
+                kinds.extend(dirty.changes);
+            }
+        }
+        assert!(kinds.contains(&RepoChangeKind::Head));
+
+        drop(watcher);
+    }
+}
@@ -0,0 +1,573 @@
+// 多仓库工作区：同时打开多个仓库，按 id 管理各自的 GitEngine、文件监控和 Git 配置
+// Author: Evilek, Date: 2025-10-12
+// 命令层传 repo_id 取引擎，不传就用当前活动仓库；跨仓库汇总状态（未提交 / 未推送）也在这里
+
+use crate::core::git_config::GitConfig;
+use crate::core::git_credentials::GitCredentialSettings;
+use crate::core::git_engine::GitEngine;
+use crate::types::git_types::GitStatusResult;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use tokio::sync::Mutex;
+
+/// 工作区中已打开的仓库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRepository {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub opened_at: String,
+    pub active: bool,
+    /// 是否使用仓库专属 Git 配置（否则跟随全局配置）
+    pub custom_config: bool,
+}
+
+/// 单个仓库的汇总状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRepoStatus {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    pub ahead: u32,
+    pub behind: u32,
+    /// 有未提交的改动（含未跟踪文件）
+    pub uncommitted: bool,
+    /// 有未推送的提交，或当前分支没有上游
+    pub unpushed: bool,
+    pub error: Option<String>,
+}
+
+/// 工作区汇总状态：哪些仓库有未提交 / 未推送的工作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceStatus {
+    pub repositories: Vec<WorkspaceRepoStatus>,
+    pub total: usize,
+    pub with_uncommitted: usize,
+    pub with_unpushed: usize,
+    pub with_errors: usize,
+}
+
+struct WorkspaceRepo {
+    path: String,
+    name: String,
+    opened_at: String,
+    custom_config: Option<GitConfig>,
+    engine: Arc<Mutex<GitEngine>>,
+}
+
+#[derive(Default)]
+struct WorkspaceState {
+    repos: BTreeMap<String, WorkspaceRepo>,
+    active: Option<String>,
+}
+
+/// 多仓库工作区，作为 Tauri 状态托管
+pub struct RepoWorkspace {
+    /// 未打开仓库的模板引擎：新仓库从它克隆，复用 Git 路径探测结果、全局配置和凭据
+    base: StdMutex<GitEngine>,
+    state: StdMutex<WorkspaceState>,
+}
+
+impl RepoWorkspace {
+    pub fn new(base: GitEngine) -> Self {
+        Self {
+            base: StdMutex::new(base),
+            state: StdMutex::new(WorkspaceState::default()),
+        }
+    }
+
+    /// 打开仓库并设为活动仓库；已打开时直接激活，返回同一个 id
+    pub fn open(&self, path: &str) -> Result<WorkspaceRepository> {
+        let canonical = canonical_repo_path(path)?;
+        let id = repo_id_for_path(&canonical);
+
+        let mut state = self.lock_state()?;
+        if !state.repos.contains_key(&id) {
+            let mut engine = self.base_engine()?;
+            engine.open_repository(&canonical)?;
+
+            state.repos.insert(
+                id.clone(),
+                WorkspaceRepo {
+                    name: repo_display_name(&canonical),
+                    path: canonical,
+                    opened_at: Utc::now().to_rfc3339(),
+                    custom_config: None,
+                    engine: Arc::new(Mutex::new(engine)),
+                },
+            );
+        }
+        state.active = Some(id.clone());
+
+        Ok(Self::describe(&state, &id))
+    }
+
+    /// 关闭仓库（停止监控）；关闭的是活动仓库时切到最近打开的另一个
+    pub async fn close(&self, id: &str) -> Result<()> {
+        let removed = {
+            let mut state = self.lock_state()?;
+            let removed = state
+                .repos
+                .remove(id)
+                .ok_or_else(|| anyhow!("仓库未打开: {}", id))?;
+            if state.active.as_deref() == Some(id) {
+                state.active = state
+                    .repos
+                    .iter()
+                    .max_by(|a, b| a.1.opened_at.cmp(&b.1.opened_at))
+                    .map(|(id, _)| id.clone());
+            }
+            removed
+        };
+
+        removed.engine.lock().await.close_repository();
+        Ok(())
+    }
+
+    /// 切换活动仓库
+    pub fn set_active(&self, id: &str) -> Result<WorkspaceRepository> {
+        let mut state = self.lock_state()?;
+        if !state.repos.contains_key(id) {
+            return Err(anyhow!("仓库未打开: {}", id));
+        }
+        state.active = Some(id.to_string());
+        Ok(Self::describe(&state, id))
+    }
+
+    pub fn active_id(&self) -> Option<String> {
+        self.lock_state()
+            .ok()
+            .and_then(|state| state.active.clone())
+    }
+
+    /// 按打开时间列出工作区中的仓库
+    pub fn list(&self) -> Vec<WorkspaceRepository> {
+        let Ok(state) = self.lock_state() else {
+            return Vec::new();
+        };
+        let mut repos: Vec<_> = state
+            .repos
+            .keys()
+            .map(|id| Self::describe(&state, id))
+            .collect();
+        repos.sort_by(|a, b| a.opened_at.cmp(&b.opened_at));
+        repos
+    }
+
+    /// 取仓库引擎；id 为空时取活动仓库
+    pub fn engine(&self, id: Option<&str>) -> Result<Arc<Mutex<GitEngine>>> {
+        let state = self.lock_state()?;
+        let id = match id.filter(|id| !id.is_empty()) {
+            Some(id) => id.to_string(),
+            None => state
+                .active
+                .clone()
+                .ok_or_else(|| anyhow!("No repository opened"))?,
+        };
+
+        state
+            .repos
+            .get(&id)
+            .map(|repo| repo.engine.clone())
+            .ok_or_else(|| anyhow!("仓库未打开: {}", id))
+    }
+
+    /// 不绑定仓库的引擎副本，用于克隆、远程校验和按路径分析（日报）等操作，不占用仓库引擎的锁
+    pub fn base_engine(&self) -> Result<GitEngine> {
+        self.base
+            .lock()
+            .map(|engine| engine.clone())
+            .map_err(|_| anyhow!("工作区状态锁已损坏"))
+    }
+
+    /// 更新全局 Git 配置：模板引擎和所有未使用专属配置的仓库跟随
+    pub async fn apply_global_config(&self, config: GitConfig) -> Result<()> {
+        self.base
+            .lock()
+            .map_err(|_| anyhow!("工作区状态锁已损坏"))?
+            .update_config(config.clone());
+
+        let engines: Vec<_> = {
+            let state = self.lock_state()?;
+            state
+                .repos
+                .values()
+                .filter(|repo| repo.custom_config.is_none())
+                .map(|repo| repo.engine.clone())
+                .collect()
+        };
+        for engine in engines {
+            engine.lock().await.update_config(config.clone());
+        }
+        Ok(())
+    }
+
+    /// 设置仓库专属 Git 配置；传 None 恢复跟随全局配置
+    pub async fn set_repo_config(&self, id: &str, config: Option<GitConfig>) -> Result<()> {
+        let effective = match &config {
+            Some(config) => config.clone(),
+            None => self.base_engine()?.get_config().clone(),
+        };
+
+        let engine = {
+            let mut state = self.lock_state()?;
+            let repo = state
+                .repos
+                .get_mut(id)
+                .ok_or_else(|| anyhow!("仓库未打开: {}", id))?;
+            repo.custom_config = config;
+            repo.engine.clone()
+        };
+
+        engine.lock().await.update_config(effective);
+        Ok(())
+    }
+
+    /// 仓库当前生效的 Git 配置
+    pub async fn repo_config(&self, id: Option<&str>) -> Result<GitConfig> {
+        let engine = self.engine(id)?;
+        let config = engine.lock().await.get_config().clone();
+        Ok(config)
+    }
+
+    /// 凭据设置对所有仓库生效
+    pub async fn apply_credential_settings(&self, settings: GitCredentialSettings) -> Result<()> {
+        self.base
+            .lock()
+            .map_err(|_| anyhow!("工作区状态锁已损坏"))?
+            .update_credential_settings(settings.clone());
+
+        let engines: Vec<_> = {
+            let state = self.lock_state()?;
+            state
+                .repos
+                .values()
+                .map(|repo| repo.engine.clone())
+                .collect()
+        };
+        for engine in engines {
+            engine
+                .lock()
+                .await
+                .update_credential_settings(settings.clone());
+        }
+        Ok(())
+    }
+
+    /// 并发获取所有已打开仓库的状态并汇总
+    pub async fn status(&self) -> Result<WorkspaceStatus> {
+        let repos: Vec<_> = {
+            let state = self.lock_state()?;
+            state
+                .repos
+                .iter()
+                .map(|(id, repo)| {
+                    (
+                        id.clone(),
+                        repo.path.clone(),
+                        repo.name.clone(),
+                        repo.opened_at.clone(),
+                        repo.engine.clone(),
+                    )
+                })
+                .collect()
+        };
+
+        let tasks: Vec<_> = repos
+            .into_iter()
+            .map(|(id, path, name, opened_at, engine)| {
+                let task = tokio::task::spawn_blocking(move || engine.blocking_lock().get_status());
+                (id, path, name, opened_at, task)
+            })
+            .collect();
+
+        let mut statuses = Vec::with_capacity(tasks.len());
+        for (id, path, name, opened_at, task) in tasks {
+            let result = match task.await {
+                Ok(result) => result,
+                Err(e) => Err(anyhow!("状态任务失败: {}", e)),
+            };
+            statuses.push((opened_at, summarize_status(id, path, name, result)));
+        }
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(WorkspaceStatus::from_repositories(
+            statuses.into_iter().map(|(_, status)| status).collect(),
+        ))
+    }
+
+    fn describe(state: &WorkspaceState, id: &str) -> WorkspaceRepository {
+        let repo = &state.repos[id];
+        WorkspaceRepository {
+            id: id.to_string(),
+            path: repo.path.clone(),
+            name: repo.name.clone(),
+            opened_at: repo.opened_at.clone(),
+            active: state.active.as_deref() == Some(id),
+            custom_config: repo.custom_config.is_some(),
+        }
+    }
+
+    fn lock_state(&self) -> Result<std::sync::MutexGuard<'_, WorkspaceState>> {
+        self.state.lock().map_err(|_| anyhow!("工作区状态锁已损坏"))
+    }
+}
+
+impl WorkspaceStatus {
+    fn from_repositories(repositories: Vec<WorkspaceRepoStatus>) -> Self {
+        Self {
+            total: repositories.len(),
+            with_uncommitted: repositories.iter().filter(|repo| repo.uncommitted).count(),
+            with_unpushed: repositories.iter().filter(|repo| repo.unpushed).count(),
+            with_errors: repositories
+                .iter()
+                .filter(|repo| repo.error.is_some())
+                .count(),
+            repositories,
+        }
+    }
+}
+
+fn summarize_status(
+    id: String,
+    path: String,
+    name: String,
+    result: Result<GitStatusResult>,
+) -> WorkspaceRepoStatus {
+    let mut summary = WorkspaceRepoStatus {
+        id,
+        path,
+        name,
+        branch: None,
+        upstream: None,
+        staged: 0,
+        unstaged: 0,
+        untracked: 0,
+        conflicted: 0,
+        ahead: 0,
+        behind: 0,
+        uncommitted: false,
+        unpushed: false,
+        error: None,
+    };
+
+    match result {
+        Ok(status) => {
+            summary.staged = status.staged_files.len();
+            summary.unstaged = status.unstaged_files.len();
+            summary.untracked = status.untracked_files.len();
+            summary.conflicted = status.conflicted_files.len();
+            summary.ahead = status.ahead;
+            summary.behind = status.behind;
+            summary.uncommitted =
+                summary.staged + summary.unstaged + summary.untracked + summary.conflicted > 0;
+            summary.unpushed = status.ahead > 0 || status.upstream.is_none();
+            summary.branch = Some(status.branch);
+            summary.upstream = status.upstream;
+        }
+        Err(e) => summary.error = Some(e.to_string()),
+    }
+
+    summary
+}
+
+/// 仓库 id：规范化路径的 SHA-256 前 16 位，同一路径每次打开得到同一个 id
+pub fn repo_id_for_path(path: &str) -> String {
+    let normalized = path.trim_end_matches(['/', '\\']);
+    let digest = Sha256::digest(normalized.as_bytes());
+    digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 规范化仓库路径（解析符号链接、去掉 Windows 的 \\?\ 前缀），子目录会上溯到工作区根目录
+pub fn canonical_repo_path(path: &str) -> Result<String> {
+    let repo =
+        git2::Repository::discover(path).map_err(|e| anyhow!("不是 Git 仓库: {} ({})", path, e))?;
+    let root: PathBuf = match repo.workdir() {
+        Some(workdir) => workdir.to_path_buf(),
+        None => repo.path().to_path_buf(),
+    };
+    let canonical = root.canonicalize().unwrap_or(root);
+    let text = canonical.to_string_lossy().to_string();
+    let text = text
+        .strip_prefix(r"\\?\")
+        .map(str::to_string)
+        .unwrap_or(text);
+    Ok(text.trim_end_matches(['/', '\\']).to_string())
+}
+
+fn repo_display_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::git_config::GitExecutionMode;
+    use crate::core::test_support::{commit_files, git};
+    use tempfile::TempDir;
+
+    fn init_repo(dir: &Path) {
+        crate::core::test_support::init_repo(dir);
+        commit_files(dir, &[("README.md", "hello\n")], "init");
+    }
+
+    #[tokio::test]
+    async fn test_workspace_tracks_multiple_repositories() {
+        let temp = TempDir::new().unwrap();
+        let (clean, dirty, ahead) = (
+            temp.path().join("clean"),
+            temp.path().join("dirty"),
+            temp.path().join("ahead"),
+        );
+
+        // ahead 跟踪 clean 的 main，再多提交一次
+        init_repo(&clean);
+        init_repo(&dirty);
+        git(
+            temp.path(),
+            &[
+                "clone",
+                "-q",
+                clean.to_str().unwrap(),
+                ahead.to_str().unwrap(),
+            ],
+        );
+        git(&ahead, &["config", "user.email", "dev@example.com"]);
+        git(&ahead, &["config", "user.name", "Dev"]);
+        commit_files(&ahead, &[("a.txt", "a\n")], "local");
+        std::fs::write(dirty.join("new.txt"), "new\n").unwrap();
+
+        let config = GitConfig {
+            execution_mode: GitExecutionMode::SystemGit,
+            ..GitConfig::default()
+        };
+        let workspace = RepoWorkspace::new(GitEngine::new_with_config(config));
+        assert!(workspace.engine(None).is_err());
+
+        let first = workspace.open(clean.to_str().unwrap()).unwrap();
+        let second = workspace
+            .open(dirty.join("README.md").parent().unwrap().to_str().unwrap())
+            .unwrap();
+        let third = workspace.open(ahead.to_str().unwrap()).unwrap();
+        assert_eq!(workspace.list().len(), 3);
+        assert_eq!(workspace.active_id().as_deref(), Some(third.id.as_str()));
+
+        // 重复打开不产生新条目，只切换活动仓库
+        let again = workspace.open(clean.to_str().unwrap()).unwrap();
+        assert_eq!(again.id, first.id);
+        assert_eq!(workspace.list().len(), 3);
+        assert!(workspace
+            .list()
+            .iter()
+            .any(|repo| repo.id == first.id && repo.active));
+
+        // 每个仓库有独立引擎
+        let engine = workspace.engine(Some(&second.id)).unwrap();
+        let path = engine.lock().await.get_repository_path().unwrap();
+        assert_eq!(repo_id_for_path(&path), second.id);
+
+        let status = workspace.status().await.unwrap();
+        assert_eq!(status.total, 3);
+        let by_id = |id: &str| {
+            status
+                .repositories
+                .iter()
+                .find(|repo| repo.id == id)
+                .unwrap()
+        };
+        assert!(!by_id(&first.id).uncommitted);
+        assert!(by_id(&second.id).uncommitted);
+        assert_eq!(by_id(&second.id).untracked, 1);
+        assert_eq!(by_id(&third.id).ahead, 1);
+        assert!(by_id(&third.id).unpushed);
+        assert!(!by_id(&third.id).uncommitted);
+        assert_eq!(status.with_uncommitted, 1);
+        // clean / dirty 没有上游，也算未推送
+        assert_eq!(status.with_unpushed, 3);
+
+        workspace.close(&first.id).await.unwrap();
+        assert_eq!(workspace.list().len(), 2);
+        assert!(workspace.active_id().is_some());
+        assert!(workspace.engine(Some(&first.id)).is_err());
+        for repo in workspace.list() {
+            workspace.close(&repo.id).await.unwrap();
+        }
+        assert!(workspace.engine(None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_workspace_per_repo_config() {
+        let temp = TempDir::new().unwrap();
+        let (one, two) = (temp.path().join("one"), temp.path().join("two"));
+        init_repo(&one);
+        init_repo(&two);
+
+        let workspace = RepoWorkspace::new(GitEngine::new_with_config(GitConfig::default()));
+        let one = workspace.open(one.to_str().unwrap()).unwrap();
+        let two = workspace.open(two.to_str().unwrap()).unwrap();
+
+        let custom = GitConfig {
+            command_timeout: 5,
+            ..GitConfig::default()
+        };
+        workspace
+            .set_repo_config(&one.id, Some(custom))
+            .await
+            .unwrap();
+
+        let global = GitConfig {
+            command_timeout: 42,
+            ..GitConfig::default()
+        };
+        workspace.apply_global_config(global).await.unwrap();
+
+        assert_eq!(
+            workspace
+                .repo_config(Some(&one.id))
+                .await
+                .unwrap()
+                .command_timeout,
+            5
+        );
+        assert_eq!(
+            workspace
+                .repo_config(Some(&two.id))
+                .await
+                .unwrap()
+                .command_timeout,
+            42
+        );
+        assert!(workspace
+            .list()
+            .iter()
+            .any(|repo| repo.id == one.id && repo.custom_config));
+
+        workspace.set_repo_config(&one.id, None).await.unwrap();
+        assert_eq!(
+            workspace
+                .repo_config(Some(&one.id))
+                .await
+                .unwrap()
+                .command_timeout,
+            42
+        );
+    }
+}
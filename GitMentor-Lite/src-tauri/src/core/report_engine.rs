@@ -1,8 +1,12 @@
 use crate::core::ai_analysis_prompts::PromptTemplateManager;
 use crate::core::ai_manager::AIManager;
+use crate::core::ai_provider::{AIRequest, ChatMessage};
+use crate::core::impact_model;
+use crate::error_log;
 use crate::types::git_types::{
-    AIAnalysisConfig, AIAnalysisResult, AnalysisDepth, CommitDetailAnalysis, CommitFileChange,
-    FileChangeType, ImpactLevel, TemplateConfig, TemplateType,
+    AIAnalysisConfig, AIAnalysisResult, AnalysisDepth, CommitDetailAnalysis, CommitDiffResult,
+    CommitFileChange, ImpactAssessment, ImpactFactor, ImpactLevel, ImpactRules, Report, ReportMeta,
+    RiskAssessment, RiskLevel, TemplateConfig, TemplateType,
 };
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
@@ -11,6 +15,87 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// AI 提交分析的最大请求次数（首次 + 修复重试）
+const MAX_ANALYSIS_ATTEMPTS: usize = 3;
+
+/// 解析失败时追加的修复提示
+const ANALYSIS_REPAIR_PROMPT: &str =
+    "上面的回答不符合要求的 JSON 结构。请只返回一个 JSON 对象，包含 summary、keyFindings、suggestions、risk（level、description、mitigation）字段，不要添加其他文字。";
+
+/// 提交分析的系统提示词：约定输出语言与 JSON 结构
+fn commit_analysis_system_prompt(language: &str) -> String {
+    format!(
+        "你是资深代码评审专家，负责分析单个 Git 提交。请使用{}撰写内容，只依据提供的提交信息与差异。\
+         只返回一个 JSON 对象，不要添加其他文字，结构为：\
+         {{\"summary\": \"变更摘要\", \"keyFindings\": [\"...\"], \"suggestions\": [\"...\"], \
+         \"risk\": {{\"level\": \"low|medium|high|critical\", \"description\": \"风险说明\", \"mitigation\": [\"...\"]}}}}",
+        language
+    )
+}
+
+/// 模型返回的提交分析（字段名兼容 camelCase 与 snake_case）
+#[derive(Debug, serde::Deserialize)]
+struct ParsedCommitAnalysis {
+    summary: String,
+    #[serde(default, alias = "key_findings")]
+    #[serde(rename = "keyFindings")]
+    key_findings: Vec<String>,
+    #[serde(default)]
+    suggestions: Vec<String>,
+    risk: ParsedRisk,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ParsedRisk {
+    #[serde(deserialize_with = "deserialize_risk_level")]
+    level: RiskLevel,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    mitigation: Vec<String>,
+}
+
+fn deserialize_risk_level<'de, D>(deserializer: D) -> std::result::Result<RiskLevel, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    match value.trim().to_lowercase().as_str() {
+        "low" | "低" => Ok(RiskLevel::Low),
+        "medium" | "中" | "中等" => Ok(RiskLevel::Medium),
+        "high" | "高" => Ok(RiskLevel::High),
+        "critical" | "严重" => Ok(RiskLevel::Critical),
+        other => Err(serde::de::Error::custom(format!(
+            "risk.level 必须是 low/medium/high/critical，实际为 {}",
+            other
+        ))),
+    }
+}
+
+/// 从模型输出中取出 JSON 对象（允许包在代码块里）并校验必填字段，错误信息用于修复提示
+fn parse_commit_analysis(response: &str) -> std::result::Result<ParsedCommitAnalysis, String> {
+    let start = response.find('{').ok_or("回答中没有 JSON 对象")?;
+    let end = response
+        .rfind('}')
+        .filter(|end| *end > start)
+        .ok_or("JSON 对象不完整")?;
+    let mut parsed: ParsedCommitAnalysis =
+        serde_json::from_str(&response[start..=end]).map_err(|e| format!("JSON 无效: {}", e))?;
+
+    parsed.summary = parsed.summary.trim().to_string();
+    if parsed.summary.is_empty() {
+        return Err("summary 不能为空".to_string());
+    }
+    for items in [
+        &mut parsed.key_findings,
+        &mut parsed.suggestions,
+        &mut parsed.risk.mitigation,
+    ] {
+        items.retain(|item| !item.trim().is_empty());
+    }
+    Ok(parsed)
+}
+
 /// 缓存管理器 - 负责提交分析的本地缓存
 pub struct CacheManager {
     pub cache_dir: PathBuf,
@@ -101,6 +186,91 @@ impl CacheManager {
     }
 }
 
+/// 报告存储 - 把生成的报告（日报、Gitflow 报告）保存为本地 JSON 文件
+pub struct ReportStore {
+    pub reports_dir: PathBuf,
+}
+
+impl ReportStore {
+    pub fn new(base_dir: &Path) -> Result<Self> {
+        let reports_dir = base_dir.join(".gitmentor").join("reports");
+        fs::create_dir_all(&reports_dir).context("Failed to create reports dir")?;
+        Ok(Self { reports_dir })
+    }
+
+    fn report_path(&self, report_id: &str) -> PathBuf {
+        // 报告ID来自前端，只保留安全字符，避免写到目录外
+        let file_name: String = report_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.reports_dir.join(format!("{}.json", file_name))
+    }
+
+    /// 保存报告，同一ID覆盖旧内容
+    pub fn save(&self, report: &Report) -> Result<()> {
+        let content = serde_json::to_string_pretty(report).context("Failed to serialize report")?;
+        fs::write(self.report_path(&report.id), content).context("Failed to write report")?;
+        Ok(())
+    }
+
+    /// 历史报告列表，新的在前；无法解析的文件跳过
+    pub fn list(&self) -> Result<Vec<ReportMeta>> {
+        let mut reports = Vec::new();
+
+        for entry in fs::read_dir(&self.reports_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+
+            let report = match fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Report>(&content).ok())
+            {
+                Some(report) => report,
+                None => continue,
+            };
+
+            let day_count = match (
+                chrono::NaiveDate::parse_from_str(&report.config.startDate, "%Y-%m-%d"),
+                chrono::NaiveDate::parse_from_str(&report.config.endDate, "%Y-%m-%d"),
+            ) {
+                (Ok(start), Ok(end)) if end >= start => (end - start).num_days() as u32 + 1,
+                _ => 0,
+            };
+
+            reports.push(ReportMeta {
+                id: report.id,
+                title: report.title,
+                created_at: report.created_at,
+                repos: report.config.repoPaths,
+                users: report.config.userEmails,
+                day_count,
+            });
+        }
+
+        reports.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(reports)
+    }
+
+    /// 删除报告，返回是否存在
+    pub fn delete(&self, report_id: &str) -> Result<bool> {
+        let path = self.report_path(report_id);
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(path).context("Failed to delete report")?;
+        Ok(true)
+    }
+}
+
 /// 模板管理器 - 负责管理报告模板
 pub struct TemplateManager {
     templates_dir: PathBuf,
@@ -301,6 +471,7 @@ impl TemplateManager {
 
 /// 分析引擎 - 负责分析提交内容
 pub struct AnalysisEngine {
+    base_dir: PathBuf,
     cache_manager: CacheManager,
     template_manager: TemplateManager,
     ai_manager: Option<Arc<RwLock<AIManager>>>,
@@ -316,6 +487,7 @@ impl AnalysisEngine {
         let prompt_manager = PromptTemplateManager::new();
 
         Ok(Self {
+            base_dir: base_dir.to_path_buf(),
             cache_manager,
             template_manager,
             ai_manager: None,
@@ -342,7 +514,7 @@ impl AnalysisEngine {
         repo_path: &str,
         commit_id: &str,
         commit_info: &crate::types::git_types::CommitInfo,
-        diff_info: Option<&crate::types::git_types::FileDiffResult>,
+        diff_info: Option<&CommitDiffResult>,
     ) -> Result<CommitDetailAnalysis> {
         // 检查缓存
         if let Some(cached) = self
@@ -377,7 +549,7 @@ impl AnalysisEngine {
                         self.merge_ai_analysis(&mut analysis, ai_result);
                     }
                     Err(e) => {
-                        eprintln!("AI分析失败: {}", e);
+                        error_log!("AI分析失败: {}", e);
                         // AI分析失败不影响基础分析
                     }
                 }
@@ -396,49 +568,45 @@ impl AnalysisEngine {
         repo_path: &str,
         commit_id: &str,
         commit_info: &crate::types::git_types::CommitInfo,
-        diff_info: Option<&crate::types::git_types::FileDiffResult>,
+        diff_info: Option<&CommitDiffResult>,
     ) -> Result<CommitDetailAnalysis> {
         let mut insertions = 0;
         let mut deletions = 0;
         let mut files_changed = Vec::new();
         let mut tags = Vec::new();
 
-        // 解析文件变更
+        // 解析文件变更（numstat 汇总 + 每个文件的变更类型）
         if let Some(diff) = diff_info {
-            for hunk in &diff.hunks {
-                for line in &hunk.lines {
-                    match line.line_type {
-                        crate::types::git_types::DiffLineType::Insert => insertions += 1,
-                        crate::types::git_types::DiffLineType::Delete => deletions += 1,
-                        _ => {}
-                    }
-                }
+            insertions = diff.insertions;
+            deletions = diff.deletions;
+
+            for file in &diff.files {
+                files_changed.push(CommitFileChange {
+                    file_path: file.file_path.clone(),
+                    change_type: file.change_type.clone(),
+                    insertions: file.insertions,
+                    deletions: file.deletions,
+                    is_binary: file.is_binary,
+                    language: file.file_language.clone(),
+                });
             }
-
-            // 分析文件变更类型
-            let change_type = if diff.is_new_file {
-                FileChangeType::Added
-            } else if diff.is_deleted_file {
-                FileChangeType::Deleted
-            } else {
-                FileChangeType::Modified
-            };
-
-            files_changed.push(CommitFileChange {
-                file_path: diff.file_path.clone(),
-                change_type,
-                insertions,
-                deletions,
-                is_binary: diff.is_binary,
-                language: diff.file_language.clone(),
-            });
         }
 
-        // 确定影响级别
-        let impact_level = self.determine_impact_level(insertions, deletions, &commit_info.message);
+        // 基于代码信号评估影响级别
+        let impact = diff_info.and_then(|diff| self.assess_impact(repo_path, commit_id, diff));
+        let impact_level = impact
+            .as_ref()
+            .map(|assessment| assessment.level)
+            .unwrap_or(ImpactLevel::Low);
 
         // 生成标签
-        self.generate_tags(&mut tags, insertions, deletions, &commit_info.message);
+        self.generate_tags(
+            &mut tags,
+            insertions,
+            deletions,
+            &commit_info.message,
+            impact.as_ref(),
+        );
 
         // 生成摘要
         let summary = self.generate_summary(&commit_info.message, insertions, deletions);
@@ -457,49 +625,69 @@ impl AnalysisEngine {
             summary,
             impact_level,
             tags,
+            impact,
         })
     }
 
-    /// 提取差异内容
-    fn extract_diff_content(
-        &self,
-        diff: &crate::types::git_types::FileDiffResult,
-    ) -> Result<String> {
+    /// 提取差异内容：按文件输出头部与区块，二进制文件只列出路径
+    fn extract_diff_content(&self, diff: &CommitDiffResult) -> Result<String> {
         let mut diff_content = String::new();
 
-        for hunk in &diff.hunks {
-            diff_content.push_str(&format!(
-                "@@ -{},{} +{},{} @@\n",
-                hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
-            ));
+        for file in &diff.files {
+            match &file.old_path {
+                Some(old_path) => diff_content.push_str(&format!(
+                    "diff {} -> {} ({:?})\n",
+                    old_path, file.file_path, file.change_type
+                )),
+                None => diff_content.push_str(&format!(
+                    "diff {} ({:?})\n",
+                    file.file_path, file.change_type
+                )),
+            }
+            if file.is_binary {
+                diff_content.push_str("(二进制文件)\n");
+                continue;
+            }
 
-            for line in &hunk.lines {
-                match line.line_type {
-                    crate::types::git_types::DiffLineType::Context => {
-                        diff_content.push_str("  ");
-                    }
-                    crate::types::git_types::DiffLineType::Insert => {
-                        diff_content.push_str("+ ");
-                    }
-                    crate::types::git_types::DiffLineType::Delete => {
-                        diff_content.push_str("- ");
+            for hunk in &file.hunks {
+                diff_content.push_str(&format!(
+                    "@@ -{},{} +{},{} @@\n",
+                    hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+                ));
+
+                for line in &hunk.lines {
+                    match line.line_type {
+                        crate::types::git_types::DiffLineType::Context => {
+                            diff_content.push_str("  ");
+                        }
+                        crate::types::git_types::DiffLineType::Insert => {
+                            diff_content.push_str("+ ");
+                        }
+                        crate::types::git_types::DiffLineType::Delete => {
+                            diff_content.push_str("- ");
+                        }
                     }
+                    diff_content.push_str(&line.content);
+                    diff_content.push('\n');
                 }
-                diff_content.push_str(&line.content);
-                diff_content.push('\n');
             }
         }
 
         // 限制长度以避免超出token限制
         if diff_content.len() > self.ai_config.max_code_length {
-            diff_content.truncate(self.ai_config.max_code_length);
+            let mut cut = self.ai_config.max_code_length;
+            while !diff_content.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            diff_content.truncate(cut);
             diff_content.push_str("\n... (内容被截断)");
         }
 
         Ok(diff_content)
     }
 
-    /// 执行AI分析
+    /// 执行AI分析：发送渲染后的提示词，要求 JSON 输出，解析失败时带着错误原因重新询问
+    /// 整个过程（含重试）受 AIAnalysisConfig.timeout_seconds 限制
     async fn perform_ai_analysis(
         &self,
         ai_manager: Arc<RwLock<AIManager>>,
@@ -508,10 +696,12 @@ impl AnalysisEngine {
         depth: AnalysisDepth,
         include_code_review: bool,
     ) -> Result<AIAnalysisResult> {
+        let start_time = std::time::Instant::now();
+
         // 生成AI提示
-        let _prompt = self
+        let prompt = self
             .prompt_manager
-            .get_commit_analysis_prompt(analysis, depth, include_code_review, diff_content)
+            .get_commit_analysis_prompt(analysis, depth.clone(), include_code_review, diff_content)
             .map_err(|e| anyhow::anyhow!(e))?;
 
         // 获取用户配置的模型
@@ -525,27 +715,80 @@ impl AnalysisEngine {
             self.ai_config.model.clone()
         };
 
-        let ai_result = AIAnalysisResult {
+        let mut messages = vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: commit_analysis_system_prompt(&user_config.base.language),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: prompt,
+            },
+        ];
+
+        let ask = async {
+            let mut last_error = String::new();
+            for attempt in 1..=MAX_ANALYSIS_ATTEMPTS {
+                let request = AIRequest {
+                    messages: messages.clone(),
+                    model: model_name.clone(),
+                    temperature: Some(user_config.advanced.temperature),
+                    max_tokens: Some(user_config.advanced.max_tokens),
+                    stream: Some(false),
+                };
+                let response = ai_manager_guard.generate_analysis_report(request).await?;
+
+                match parse_commit_analysis(&response.content) {
+                    Ok(parsed) => return Ok((parsed, response.model)),
+                    Err(e) => {
+                        error_log!("AI分析输出无效（第 {} 次）: {}", attempt, e);
+                        last_error = e;
+                        messages.push(ChatMessage {
+                            role: "assistant".to_string(),
+                            content: response.content,
+                        });
+                        messages.push(ChatMessage {
+                            role: "user".to_string(),
+                            content: format!("{}\n问题：{}", ANALYSIS_REPAIR_PROMPT, last_error),
+                        });
+                    }
+                }
+            }
+            Err(anyhow::anyhow!(
+                "AI分析输出连续 {} 次无法解析: {}",
+                MAX_ANALYSIS_ATTEMPTS,
+                last_error
+            ))
+        };
+
+        let timeout = std::time::Duration::from_secs(self.ai_config.timeout_seconds.max(1));
+        let (parsed, response_model) = tokio::time::timeout(timeout, ask)
+            .await
+            .map_err(|_| anyhow::anyhow!("AI分析超时（{} 秒）", timeout.as_secs()))??;
+
+        Ok(AIAnalysisResult {
             analysis_id: uuid::Uuid::new_v4().to_string(),
             commit_id: analysis.commit_id.clone(),
             analysis_type: crate::types::git_types::AIAnalysisTemplate::CommitAnalysis {
-                depth: crate::types::git_types::AnalysisDepth::Detailed,
-                include_code_review: true,
+                depth,
+                include_code_review,
             },
-            content: format!("AI分析结果: {}", analysis.message),
-            key_findings: vec!["发现重要变更".to_string()],
-            suggestions: vec!["建议添加测试".to_string()],
-            risk_assessment: Some(crate::types::git_types::RiskAssessment {
-                level: crate::types::git_types::RiskLevel::Medium,
-                description: "代码复杂度较高".to_string(),
-                mitigation: vec!["建议代码审查".to_string()],
+            content: parsed.summary,
+            key_findings: parsed.key_findings,
+            suggestions: parsed.suggestions,
+            risk_assessment: Some(RiskAssessment {
+                level: parsed.risk.level,
+                description: parsed.risk.description,
+                mitigation: parsed.risk.mitigation,
             }),
             analyzed_at: chrono::Utc::now().timestamp(),
-            ai_model: format!("{} (mock)", model_name),
-            analysis_duration_ms: 100,
-        };
-
-        Ok(ai_result)
+            ai_model: if response_model.is_empty() {
+                model_name
+            } else {
+                response_model
+            },
+            analysis_duration_ms: start_time.elapsed().as_millis() as u64,
+        })
     }
 
     /// 合并AI分析结果
@@ -555,12 +798,23 @@ impl AnalysisEngine {
             analysis.summary = ai_result.content.clone();
         }
 
-        // 使用AI确定的影响级别
-        analysis.impact_level = ai_result
-            .risk_assessment
-            .as_ref()
-            .map(|r| r.level.clone().into())
-            .unwrap_or(analysis.impact_level);
+        // 使用AI确定的影响级别，与代码信号评级不同时记录在评分依据中
+        if let Some(risk) = ai_result.risk_assessment.as_ref() {
+            let level: ImpactLevel = risk.level.clone().into();
+            if level != analysis.impact_level {
+                if let Some(impact) = analysis.impact.as_mut() {
+                    impact.factors.push(ImpactFactor {
+                        signal: "ai_risk".to_string(),
+                        points: 0,
+                        detail: format!(
+                            "AI 风险评估为 {:?}，覆盖代码信号评级 {:?}：{}",
+                            level, analysis.impact_level, risk.description
+                        ),
+                    });
+                }
+                analysis.impact_level = level;
+            }
+        }
 
         // 合并标签
         analysis.tags.extend(ai_result.key_findings);
@@ -568,83 +822,69 @@ impl AnalysisEngine {
         analysis.tags.dedup();
     }
 
-    /// 确定提交影响级别
-    fn determine_impact_level(
+    /// 按仓库规则评估影响，规则文件无效时回退到默认规则
+    fn assess_impact(
         &self,
-        insertions: u32,
-        deletions: u32,
-        message: &str,
-    ) -> ImpactLevel {
-        let total_changes = insertions + deletions;
-        let msg_lower = message.to_lowercase();
-
-        // 基于关键词判断
-        if msg_lower.contains("critical")
-            || msg_lower.contains("重要")
-            || msg_lower.contains("核心")
-        {
-            return ImpactLevel::Critical;
-        }
-
-        if msg_lower.contains("feature") || msg_lower.contains("功能") || msg_lower.contains("新增")
-        {
-            if total_changes > 100 {
-                return ImpactLevel::High;
-            }
-            return ImpactLevel::Medium;
-        }
-
-        if msg_lower.contains("fix") || msg_lower.contains("修复") || msg_lower.contains("bug") {
-            return ImpactLevel::High;
-        }
+        repo_path: &str,
+        commit_id: &str,
+        diff: &CommitDiffResult,
+    ) -> Option<ImpactAssessment> {
+        let rules = impact_model::load_rules(&self.base_dir, repo_path).unwrap_or_else(|e| {
+            error_log!("影响评分规则无效，使用默认规则: {}", e);
+            ImpactRules::default()
+        });
+
+        let paths: Vec<String> = diff.files.iter().map(|f| f.file_path.clone()).collect();
+        let history = git2::Repository::open(repo_path)
+            .and_then(|repo| {
+                impact_model::change_frequency(&repo, commit_id, &paths, rules.hot_file_window)
+                    .map_err(|e| git2::Error::from_str(&e.to_string()))
+            })
+            .unwrap_or_else(|e| {
+                error_log!("统计文件修改频率失败: {}", e);
+                Default::default()
+            });
 
-        if msg_lower.contains("refactor") || msg_lower.contains("重构") {
-            if total_changes > 200 {
-                return ImpactLevel::High;
+        match impact_model::assess(&rules, diff, &history) {
+            Ok(assessment) => Some(assessment),
+            Err(e) => {
+                error_log!("影响评估失败: {}", e);
+                None
             }
-            return ImpactLevel::Medium;
-        }
-
-        // 基于代码量判断
-        if total_changes > 500 {
-            ImpactLevel::Critical
-        } else if total_changes > 200 {
-            ImpactLevel::High
-        } else if total_changes > 50 {
-            ImpactLevel::Medium
-        } else {
-            ImpactLevel::Low
         }
     }
 
-    /// 生成标签
+    /// 生成标签：提交类型取自 Conventional Commits 标题，其余来自影响评分依据
     fn generate_tags(
         &self,
         tags: &mut Vec<String>,
         insertions: u32,
         deletions: u32,
         message: &str,
+        impact: Option<&ImpactAssessment>,
     ) {
-        let msg_lower = message.to_lowercase();
-
-        // 基于关键词的标签
-        if msg_lower.contains("feature") || msg_lower.contains("功能") {
-            tags.push("新功能".to_string());
-        }
-        if msg_lower.contains("fix") || msg_lower.contains("修复") || msg_lower.contains("bug") {
-            tags.push("缺陷修复".to_string());
-        }
-        if msg_lower.contains("refactor") || msg_lower.contains("重构") {
-            tags.push("代码重构".to_string());
-        }
-        if msg_lower.contains("test") || msg_lower.contains("测试") {
-            tags.push("测试相关".to_string());
-        }
-        if msg_lower.contains("docs") || msg_lower.contains("文档") {
-            tags.push("文档更新".to_string());
+        let header = message.lines().next().unwrap_or_default().trim();
+        let kind = header
+            .split([':', '(', '!'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let kind_tag = match kind.as_str() {
+            "feat" => Some("新功能"),
+            "fix" => Some("缺陷修复"),
+            "refactor" => Some("代码重构"),
+            "perf" => Some("性能优化"),
+            "style" => Some("代码格式"),
+            "revert" => Some("回滚"),
+            _ => None,
+        };
+        if header.contains(':') {
+            tags.extend(kind_tag.map(str::to_string));
         }
-        if msg_lower.contains("style") || msg_lower.contains("格式") {
-            tags.push("代码格式".to_string());
+
+        if let Some(impact) = impact {
+            tags.extend(impact_model::tags(impact));
         }
 
         // 基于代码量的标签
@@ -785,3 +1025,32 @@ impl AnalysisEngine {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_structured_commit_analysis() {
+        let response = "```json\n{\"summary\": \"新增登录接口\", \"keyFindings\": [\"新增 token 校验\", \" \"], \"suggestions\": [\"补充单元测试\"], \"risk\": {\"level\": \"High\", \"description\": \"影响鉴权\", \"mitigation\": [\"灰度发布\"]}}\n```";
+        let parsed = parse_commit_analysis(response).unwrap();
+        assert_eq!(parsed.summary, "新增登录接口");
+        assert_eq!(parsed.key_findings, vec!["新增 token 校验"]);
+        assert_eq!(parsed.risk.level, RiskLevel::High);
+        assert_eq!(parsed.risk.mitigation, vec!["灰度发布"]);
+
+        let error =
+            parse_commit_analysis("{\"summary\": \"x\", \"risk\": {\"level\": \"unknown\"}}")
+                .unwrap_err();
+        assert!(error.contains("risk.level"), "{}", error);
+        assert_eq!(
+            parse_commit_analysis("{\"summary\": \" \", \"risk\": {\"level\": \"low\"}}")
+                .unwrap_err(),
+            "summary 不能为空"
+        );
+        assert_eq!(
+            parse_commit_analysis("无法分析").unwrap_err(),
+            "回答中没有 JSON 对象"
+        );
+    }
+}
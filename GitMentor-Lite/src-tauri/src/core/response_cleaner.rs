@@ -1,3 +1,4 @@
+use crate::{debug_log, warn_log};
 use regex::Regex;
 
 /**
@@ -18,14 +19,14 @@ impl ResponseCleaner {
     /// # Returns
     /// * `String` - 清理后的提交消息
     pub fn clean_commit_message(content: &str) -> String {
-        println!("🔍 [ResponseCleaner] 开始清理响应内容...");
+        debug_log!("🔍 [ResponseCleaner] 开始清理响应内容...");
 
         let mut cleaned = content.to_string();
 
         // 1. 移除思考标记中的内容
         let step0 = Self::remove_thinking_content(&cleaned);
         if cleaned.len() != step0.len() {
-            println!(
+            debug_log!(
                 "🔍 [Step 0] 移除思考内容后长度: {} -> {}",
                 cleaned.len(),
                 step0.len()
@@ -35,7 +36,7 @@ impl ResponseCleaner {
 
         // 2. 移除开头的分析过程
         let step1 = Self::remove_analysis_prefix(&cleaned);
-        println!(
+        debug_log!(
             "🔍 [Step 1] 移除分析过程后长度: {} -> {}",
             cleaned.len(),
             step1.len()
@@ -44,7 +45,7 @@ impl ResponseCleaner {
 
         // 3. 移除重复的提交消息（AI可能会重复生成）
         let step2 = Self::remove_duplicates(&cleaned);
-        println!(
+        debug_log!(
             "🔍 [Step 2] 移除重复后长度: {} -> {}",
             cleaned.len(),
             step2.len()
@@ -53,16 +54,16 @@ impl ResponseCleaner {
 
         // 4. 提取最后的完整提交消息
         let step3 = Self::extract_final_message(&cleaned);
-        println!(
+        debug_log!(
             "🔍 [Step 3] 提取最终消息后长度: {} -> {}",
             cleaned.len(),
             step3.len()
         );
         if step3.is_empty() {
-            println!("🔍 [WARNING] 提取最终消息后内容为空！");
+            warn_log!("🔍 [WARNING] 提取最终消息后内容为空！");
             // 如果提取后内容为空，使用原始内容的前几行作为备选
             let fallback = content.lines().take(10).collect::<Vec<_>>().join("\n");
-            println!(
+            debug_log!(
                 "🔍 [FALLBACK] 使用原始内容前10行作为备选，长度: {}",
                 fallback.len()
             );
@@ -72,7 +73,7 @@ impl ResponseCleaner {
 
         // 5. 清理多余的空白行
         let step4 = Self::clean_whitespace(&cleaned);
-        println!(
+        debug_log!(
             "🔍 [Step 4] 清理空白后长度: {} -> {}",
             cleaned.len(),
             step4.len()
@@ -80,7 +81,7 @@ impl ResponseCleaner {
         cleaned = step4;
 
         let final_result = cleaned.trim().to_string();
-        println!(
+        debug_log!(
             "🔍 [ResponseCleaner] 清理完成，最终长度: {}",
             final_result.len()
         );
@@ -95,7 +96,7 @@ impl ResponseCleaner {
 
         // 如果找到了思考内容，打印日志
         if result.len() != content.len() {
-            println!("🔍 [remove_thinking_content] 已移除思考标记内容");
+            debug_log!("🔍 [remove_thinking_content] 已移除思考标记内容");
         }
 
         result
@@ -156,19 +157,19 @@ impl ResponseCleaner {
         let mut result = Vec::new();
         let mut in_message = false;
 
-        println!("🔍 [extract_final_message] 开始处理 {} 行内容", lines.len());
+        debug_log!("🔍 [extract_final_message] 开始处理 {} 行内容", lines.len());
 
         // 从后往前找，找到最后一个有效的提交消息
         for (i, line) in lines.iter().rev().enumerate() {
             let trimmed = line.trim();
 
-            println!("🔍 [extract_final_message] 处理第 {} 行: '{}'", i, trimmed);
+            debug_log!("🔍 [extract_final_message] 处理第 {} 行: '{}'", i, trimmed);
 
             // 空行处理
             if trimmed.is_empty() {
                 if in_message {
                     result.insert(0, line.to_string());
-                    println!("🔍 [extract_final_message] 添加空行到结果");
+                    debug_log!("🔍 [extract_final_message] 添加空行到结果");
                 }
                 continue;
             }
@@ -177,21 +178,21 @@ impl ResponseCleaner {
             if Self::is_commit_message_start(trimmed) {
                 if !in_message {
                     in_message = true;
-                    println!("🔍 [extract_final_message] 找到消息开头，开始收集");
+                    debug_log!("🔍 [extract_final_message] 找到消息开头，开始收集");
                 }
                 result.insert(0, line.to_string());
-                println!("🔍 [extract_final_message] 添加到结果");
+                debug_log!("🔍 [extract_final_message] 添加到结果");
             } else if in_message {
                 // 如果已经在消息中，继续添加
                 result.insert(0, line.to_string());
-                println!("🔍 [extract_final_message] 在消息中，继续添加");
+                debug_log!("🔍 [extract_final_message] 在消息中，继续添加");
             } else {
-                println!("🔍 [extract_final_message] 跳过此行");
+                debug_log!("🔍 [extract_final_message] 跳过此行");
             }
         }
 
         let final_result = result.join("\n");
-        println!(
+        debug_log!(
             "🔍 [extract_final_message] 最终结果包含 {} 行",
             final_result.lines().count()
         );
@@ -229,16 +230,16 @@ impl ResponseCleaner {
             "implement",
         ];
 
-        println!("🔍 [is_commit_message_start] 检查文本: '{}'", text);
+        debug_log!("🔍 [is_commit_message_start] 检查文本: '{}'", text);
 
         for verb in start_verbs {
             if text.starts_with(verb) {
-                println!("🔍 [is_commit_message_start] 匹配到动词: {}", verb);
+                debug_log!("🔍 [is_commit_message_start] 匹配到动词: {}", verb);
                 return true;
             }
         }
 
-        println!("🔍 [is_commit_message_start] 未匹配到任何动词");
+        debug_log!("🔍 [is_commit_message_start] 未匹配到任何动词");
         false
     }
 
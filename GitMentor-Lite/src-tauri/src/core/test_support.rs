@@ -0,0 +1,38 @@
+// 测试夹具：用系统 git 搭建临时仓库，仓库登记、工作区、文件监控的测试共用
+// Author: Evilek, Date: 2025-10-20
+// 只在 cfg(test) 下编译，别在这里放业务逻辑
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// 在 dir 中执行 git 命令，失败直接断言
+pub fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// 初始化 main 分支的空仓库并配置提交身份，目录不存在时创建
+pub fn init_repo(dir: &Path) {
+    fs::create_dir_all(dir).unwrap();
+    git(dir, &["init", "-q", "-b", "main"]);
+    git(dir, &["config", "user.email", "dev@example.com"]);
+    git(dir, &["config", "user.name", "Dev"]);
+}
+
+/// 写入文件（相对仓库根目录）并提交全部改动
+pub fn commit_files(dir: &Path, files: &[(&str, &str)], message: &str) {
+    for (path, content) in files {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, content).unwrap();
+    }
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", message]);
+}
@@ -403,6 +403,68 @@ impl VersionedTemplateManager {
             },
         );
 
+        // Gitflow 报告模板 - 状态播报
+        templates.insert(
+            "gitflow_status_report".to_string(),
+            SystemTemplateDefinition {
+                id: "gitflow_status_report".to_string(),
+                name: "Gitflow 状态播报".to_string(),
+                description: "根据分支提交、差异与元数据生成状态播报".to_string(),
+                template_type: "gitflow_report".to_string(),
+                current_content: include_str!("../../templates/gitflow_status_report.hbs")
+                    .to_string(),
+                current_version: "1.0.0".to_string(),
+                version_history: vec![SystemTemplateVersion {
+                    version: "1.0.0".to_string(),
+                    name: "初始版本".to_string(),
+                    description: "Gitflow 状态播报模板初始版本".to_string(),
+                    content: include_str!("../../templates/gitflow_status_report.hbs").to_string(),
+                    release_date: "2025-10-06T00:00:00Z".to_string(),
+                }],
+            },
+        );
+
+        // Gitflow 报告模板 - 事故复盘
+        templates.insert(
+            "gitflow_postmortem".to_string(),
+            SystemTemplateDefinition {
+                id: "gitflow_postmortem".to_string(),
+                name: "Gitflow 事故复盘".to_string(),
+                description: "根据热修分支的修复过程生成事故复盘草稿".to_string(),
+                template_type: "gitflow_report".to_string(),
+                current_content: include_str!("../../templates/gitflow_postmortem.hbs").to_string(),
+                current_version: "1.0.0".to_string(),
+                version_history: vec![SystemTemplateVersion {
+                    version: "1.0.0".to_string(),
+                    name: "初始版本".to_string(),
+                    description: "Gitflow 事故复盘模板初始版本".to_string(),
+                    content: include_str!("../../templates/gitflow_postmortem.hbs").to_string(),
+                    release_date: "2025-10-06T00:00:00Z".to_string(),
+                }],
+            },
+        );
+
+        // Gitflow 报告模板 - 迭代回顾
+        templates.insert(
+            "gitflow_retrospective".to_string(),
+            SystemTemplateDefinition {
+                id: "gitflow_retrospective".to_string(),
+                name: "Gitflow 迭代回顾".to_string(),
+                description: "根据分支开发过程生成迭代回顾".to_string(),
+                template_type: "gitflow_report".to_string(),
+                current_content: include_str!("../../templates/gitflow_retrospective.hbs")
+                    .to_string(),
+                current_version: "1.0.0".to_string(),
+                version_history: vec![SystemTemplateVersion {
+                    version: "1.0.0".to_string(),
+                    name: "初始版本".to_string(),
+                    description: "Gitflow 迭代回顾模板初始版本".to_string(),
+                    content: include_str!("../../templates/gitflow_retrospective.hbs").to_string(),
+                    release_date: "2025-10-06T00:00:00Z".to_string(),
+                }],
+            },
+        );
+
         templates
     }
 
@@ -1,120 +1,68 @@
 mod commands;
 // 应用后端入口：初始化组件并注册 Tauri 命令
 // Author: Evilek, Date: 2025-08-11
-// 这个憨批入口别乱加打印，日志走 write_startup_log，调试走 debug_log!
+// 这个憨批入口别乱加打印，日志走 info_log!/error_log!（core::app_logger 落盘），调试走 debug_log!
 
 mod core;
 mod types;
 mod utils;
 
 /// 调试日志宏
-/// 只有在调试开关启用时才输出日志
+/// 调试开关启用（或模块级别设为 debug）时才输出，target 为调用处模块路径
 #[macro_export]
 macro_rules! debug_log {
     ($($arg:tt)*) => {
-        if $crate::commands::debug_commands::is_debug_enabled() {
-            println!($($arg)*);
-        }
+        ::log::debug!($($arg)*)
     };
 }
 
-/// 警告日志宏（始终显示）
+/// 警告日志宏
 #[macro_export]
 macro_rules! warn_log {
     ($($arg:tt)*) => {
-        println!($($arg)*);
+        ::log::warn!($($arg)*)
     };
 }
 
-/// 错误日志宏（始终显示）
+/// 错误日志宏
 #[macro_export]
 macro_rules! error_log {
     ($($arg:tt)*) => {
-        eprintln!($($arg)*);
+        ::log::error!($($arg)*)
     };
 }
 
-/// 信息日志宏（始终显示）
+/// 信息日志宏
 #[macro_export]
 macro_rules! info_log {
     ($($arg:tt)*) => {
-        println!($($arg)*);
+        ::log::info!($($arg)*)
     };
 }
 
-use chrono::Local;
 use commands::{
     ai_analysis_commands, ai_commands, daily_report_commands, debug_commands, git_commands,
-    git_config_commands, gitflow_commands, repository_commands, system_commands, template_commands,
-    unified_template_commands, update_commands,
+    git_config_commands, gitflow_commands, log_commands, repository_commands, system_commands,
+    template_commands, unified_template_commands, update_commands, workspace_commands,
 };
 use core::{
     ai_manager::AIManager,
     git_config::GitConfigManager,
+    git_credentials::GitCredentialManager,
     git_engine::GitEngine,
+    git_process::GitOperationRegistry,
     llm_client::{LLMClient, LLMConfig},
+    repo_registry::RepoRegistry,
+    repo_workspace::RepoWorkspace,
 };
 use std::env;
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
-fn resolve_log_file_path() -> PathBuf {
-    if cfg!(debug_assertions) {
-        std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("."))
-            .join("startup.log")
-    } else {
-        std::env::current_exe()
-            .ok()
-            .and_then(|exe| exe.parent().map(|p| p.to_path_buf()))
-            .or_else(|| std::env::current_dir().ok())
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("startup.log")
-    }
-}
-
-/// 写入启动日志到文件
-/// Author: Evilek, Date: 2025-01-09
-fn write_startup_log(message: &str) {
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-    let log_message = format!("[{}] {}\n", timestamp, message);
-
-    let log_path = resolve_log_file_path();
-    if let Some(parent) = log_path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&log_path) {
-        let _ = file.write_all(log_message.as_bytes());
-        let _ = file.flush();
-    }
-
-    // 同时输出到控制台
-    println!("{}", log_message.trim());
-}
-
-/// 写入错误日志到文件
-/// Author: Evilek, Date: 2025-01-09
-fn write_error_log(error: &str) {
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-    let log_message = format!("[{}] ERROR: {}\n", timestamp, error);
-
-    let log_path = resolve_log_file_path();
-    if let Some(parent) = log_path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&log_path) {
-        let _ = file.write_all(log_message.as_bytes());
-        let _ = file.flush();
-    }
-
-    // 同时输出到控制台
-    eprintln!("{}", log_message.trim());
-}
+/// Tauri 应用标识（与 tauri.conf.json 一致），用于定位应用日志目录
+const APP_IDENTIFIER: &str = "com.gitmentor-lite.app";
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -144,7 +92,7 @@ async fn handle_pending_updates() {
         return;
     }
 
-    println!("[STARTUP] 检测到待更新文件，开始应用更新...");
+    info_log!("[STARTUP] 检测到待更新文件，开始应用更新...");
 
     // 查找 ZIP 文件
     let mut zip_files: Vec<_> = match fs::read_dir(&pending_dir) {
@@ -161,7 +109,7 @@ async fn handle_pending_updates() {
     };
 
     if zip_files.is_empty() {
-        println!("[STARTUP] 未找到 ZIP 文件");
+        info_log!("[STARTUP] 未找到 ZIP 文件");
         let _ = fs::remove_dir_all(&pending_dir);
         return;
     }
@@ -173,7 +121,7 @@ async fn handle_pending_updates() {
     });
     let zip_path = zip_files.last().unwrap().path();
 
-    println!("[STARTUP] 应用延迟更新: {:?}", zip_path);
+    info_log!("[STARTUP] 应用延迟更新: {:?}", zip_path);
 
     let update_manager = crate::core::update_manager::UpdateManager::new(
         env!("CARGO_PKG_VERSION").to_string()
@@ -182,12 +130,19 @@ async fn handle_pending_updates() {
     // 使用 install_update 而不是直接的 install_portable_zip
     match update_manager.install_update(&zip_path).await {
         Ok(_) => {
-            println!("[STARTUP] 延迟更新成功");
+            info_log!("[STARTUP] 延迟更新成功");
             // 清理待更新目录
             let _ = fs::remove_dir_all(&pending_dir);
         }
+        Err(e) if e
+            .downcast_ref::<crate::core::update_manager::VerificationError>()
+            .is_some() =>
+        {
+            error_log!("[STARTUP] 待更新文件校验未通过，已丢弃: {}", e);
+            let _ = fs::remove_dir_all(&pending_dir);
+        }
         Err(e) => {
-            println!("[STARTUP] 延迟更新失败: {}", e);
+            info_log!("[STARTUP] 延迟更新失败: {}", e);
             // 不清理待更新目录，保留以便下次重试
         }
     }
@@ -198,8 +153,8 @@ fn handle_updater_mode() -> bool {
     let args: Vec<String> = std::env::args().collect();
 
     if args.len() > 1 && args[1] == "updater" {
-        println!("[UPDATER] 进入更新器模式");
-        println!("[UPDATER] 参数: {:?}", args);
+        info_log!("[UPDATER] 进入更新器模式");
+        info_log!("[UPDATER] 参数: {:?}", args);
 
         // 解析命令行参数
         let mut installer_path = None;
@@ -227,7 +182,7 @@ fn handle_updater_mode() -> bool {
 
         // 验证参数
         if installer_path.is_none() || app_dir.is_none() {
-            eprintln!("[UPDATER] 错误：缺少必要参数");
+            error_log!("[UPDATER] 错误：缺少必要参数");
             std::process::exit(1);
         }
 
@@ -235,9 +190,9 @@ fn handle_updater_mode() -> bool {
         let app_dir = PathBuf::from(app_dir.unwrap());
         let exe_name = exe_name.unwrap_or_else(|| "GitMentorLite.exe".to_string());
 
-        println!("[UPDATER] 安装包路径: {:?}", installer_path);
-        println!("[UPDATER] 应用目录: {:?}", app_dir);
-        println!("[UPDATER] 可执行文件名: {}", exe_name);
+        info_log!("[UPDATER] 安装包路径: {:?}", installer_path);
+        info_log!("[UPDATER] 应用目录: {:?}", app_dir);
+        info_log!("[UPDATER] 可执行文件名: {}", exe_name);
 
         // 执行更新
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -247,12 +202,12 @@ fn handle_updater_mode() -> bool {
             );
 
             if let Err(e) = update_manager.run_updater_process(&installer_path, &app_dir, &exe_name).await {
-                eprintln!("[UPDATER] 更新失败: {}", e);
+                error_log!("[UPDATER] 更新失败: {}", e);
                 std::process::exit(1);
             }
         });
 
-        println!("[UPDATER] 更新完成，重新启动应用...");
+        info_log!("[UPDATER] 更新完成，重新启动应用...");
 
         // 重新启动应用
         let app_exe = app_dir.join(&exe_name);
@@ -260,7 +215,7 @@ fn handle_updater_mode() -> bool {
             .spawn()
             .expect("重新启动应用失败");
 
-        println!("[UPDATER] 应用已重启，更新器退出");
+        info_log!("[UPDATER] 应用已重启，更新器退出");
         std::process::exit(0);
     }
 
@@ -269,8 +224,12 @@ fn handle_updater_mode() -> bool {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    write_startup_log("=== GitMentor-Lite 启动开始 ===");
-    write_startup_log("Author: Evilek, Date: 2025-01-09");
+    // 日志最先初始化，更新器模式下的输出也要落盘
+    if let Err(e) = core::app_logger::init(core::app_logger::default_log_dir(APP_IDENTIFIER)) {
+        eprintln!("初始化日志失败: {}", e);
+    }
+    info_log!("=== GitMentor-Lite 启动开始 ===");
+    info_log!("Author: Evilek, Date: 2025-01-09");
 
     // 检查是否为更新器模式
     if handle_updater_mode() {
@@ -285,88 +244,130 @@ pub fn run() {
 
     // 记录当前工作目录
     match std::env::current_dir() {
-        Ok(dir) => write_startup_log(&format!("当前工作目录: {}", dir.display())),
-        Err(e) => write_error_log(&format!("无法获取当前工作目录: {}", e)),
+        Ok(dir) => info_log!("当前工作目录: {}", dir.display()),
+        Err(e) => error_log!("无法获取当前工作目录: {}", e),
     }
 
-    // 过滤PNG警告和其他不必要的日志
-    //std::env::set_var("RUST_LOG", "warn,libpng=off,image=off");
-    std::env::set_var("RUST_LOG", "error"); // 只显示错误日志
-    write_startup_log("设置日志级别为 ERROR");
+    // 日志级别由 log_settings.json 控制（默认 info，hyper/wry 等依赖压到 warn）
 
     // Initialize configuration directory
-    write_startup_log("初始化配置目录...");
+    info_log!("初始化配置目录...");
     let config_dir = match std::env::current_dir() {
         Ok(dir) => {
             let config_path = dir.join(".config");
-            write_startup_log(&format!("配置目录路径: {}", config_path.display()));
+            info_log!("配置目录路径: {}", config_path.display());
             config_path
         }
         Err(e) => {
-            write_error_log(&format!("获取当前目录失败: {}", e));
+            error_log!("获取当前目录失败: {}", e);
             panic!("无法获取当前目录");
         }
     };
 
     // Initialize Git configuration
-    write_startup_log("初始化Git配置管理器...");
+    info_log!("初始化Git配置管理器...");
     let git_config_path = config_dir.join("git_config.json");
-    write_startup_log(&format!("Git配置文件路径: {}", git_config_path.display()));
+    info_log!("Git配置文件路径: {}", git_config_path.display());
 
     let git_config_manager = match GitConfigManager::new(git_config_path) {
         Ok(manager) => {
-            write_startup_log("Git配置管理器初始化成功");
+            info_log!("Git配置管理器初始化成功");
             manager
         }
         Err(e) => {
-            write_error_log(&format!("Git配置管理器初始化失败: {}", e));
+            error_log!("Git配置管理器初始化失败: {}", e);
             panic!("Failed to initialize Git Config Manager: {}", e);
         }
     };
     let git_config = git_config_manager.get_config().clone();
 
+    // Initialize Git credentials (Git2Api network authentication)
+    let git_credentials_path = config_dir.join("git_credentials.json");
+    let git_credential_manager = match GitCredentialManager::new(git_credentials_path) {
+        Ok(manager) => {
+            info_log!("Git凭据管理器初始化成功");
+            manager
+        }
+        Err(e) => {
+            error_log!("Git凭据管理器初始化失败: {}", e);
+            panic!("Failed to initialize Git Credential Manager: {}", e);
+        }
+    };
+
+    // Initialize repository registry (recent / pinned repositories)
+    let repo_registry = match RepoRegistry::new(config_dir.join("repositories.json")) {
+        Ok(registry) => {
+            info_log!("仓库登记簿初始化成功");
+            Mutex::new(registry)
+        }
+        Err(e) => {
+            error_log!("仓库登记簿初始化失败: {}", e);
+            panic!("Failed to initialize Repository Registry: {}", e);
+        }
+    };
+
     // Initialize components
-    write_startup_log("初始化核心组件...");
-    let git_engine = Mutex::new(GitEngine::new_with_config(git_config));
-    write_startup_log("Git引擎初始化完成");
+    info_log!("初始化核心组件...");
+    let mut git_engine = GitEngine::new_with_config(git_config);
+    git_engine.update_credential_settings(git_credential_manager.get_settings().clone());
+    let workspace = RepoWorkspace::new(git_engine);
+    info_log!("Git引擎初始化完成");
 
     let git_config_manager = Mutex::new(git_config_manager);
+    let git_credential_manager = Mutex::new(git_credential_manager);
     let llm_config = LLMConfig::default();
     let llm_client = LLMClient::new(llm_config);
-    write_startup_log("LLM客户端初始化完成");
+    info_log!("LLM客户端初始化完成");
 
     // Initialize AI Manager
-    write_startup_log("初始化AI管理器...");
+    info_log!("初始化AI管理器...");
     let ai_config_path = config_dir.join("ai_config.json");
-    write_startup_log(&format!("AI配置文件路径: {}", ai_config_path.display()));
+    info_log!("AI配置文件路径: {}", ai_config_path.display());
 
     let ai_manager = match AIManager::new(ai_config_path) {
         Ok(manager) => {
-            write_startup_log("AI管理器初始化成功");
+            info_log!("AI管理器初始化成功");
             Arc::new(RwLock::new(manager))
         }
         Err(e) => {
-            write_error_log(&format!("AI管理器初始化失败: {}", e));
+            error_log!("AI管理器初始化失败: {}", e);
             panic!("Failed to initialize AI Manager: {}", e);
         }
     };
 
-    write_startup_log("构建Tauri应用...");
+    info_log!("构建Tauri应用...");
     let app_result = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_fs::init())
-        .manage(git_engine)
+        .manage(workspace)
+        .manage(repo_registry)
         .manage(git_config_manager)
+        .manage(git_credential_manager)
         .manage(llm_client)
         .manage(ai_manager)
+        .manage(GitOperationRegistry::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             git_commands::select_repository,
             git_commands::close_repository,
             git_commands::stop_repo_watcher,
+            // Workspace commands
+            workspace_commands::open_workspace_repository,
+            workspace_commands::close_workspace_repository,
+            workspace_commands::set_active_repository,
+            workspace_commands::list_workspace_repositories,
+            workspace_commands::get_workspace_status,
+            workspace_commands::get_recent_repositories,
+            workspace_commands::register_repositories,
+            workspace_commands::update_registered_repository,
+            workspace_commands::remove_registered_repository,
+            workspace_commands::get_repository_groups,
+            workspace_commands::get_repository_defaults,
+            workspace_commands::set_repository_defaults,
+            workspace_commands::scan_repositories,
             git_commands::get_git_status,
             git_commands::get_remote_configuration,
             git_commands::add_remote,
@@ -380,6 +381,7 @@ pub fn run() {
             git_commands::generate_commit_message,
             git_commands::stage_files,
             git_commands::commit_changes,
+            git_commands::lint_commit_message,
             git_commands::revert_files,
             debug_commands::get_debug_settings,
             debug_commands::set_debug_logs_enabled,
@@ -390,12 +392,21 @@ pub fn run() {
             git_config_commands::get_available_git_modes,
             git_config_commands::test_git_execution_mode,
             git_config_commands::reset_git_config,
+            git_config_commands::get_repository_git_config,
+            git_config_commands::clear_repository_git_config,
+            git_config_commands::get_git_credential_settings,
+            git_config_commands::update_git_credential_settings,
             git_commands::get_commit_history,
+            git_commands::get_commit_diff,
+            git_commands::query_commit_history,
+            git_commands::blame_file,
+            git_commands::get_file_history,
             git_commands::get_branches,
             git_commands::checkout_branch,
             git_commands::pull_current_branch,
             git_commands::push_current_branch,
             git_commands::fetch_remote,
+            git_commands::cancel_git_operation,
             git_commands::discard_all_changes,
             git_commands::stage_all_changes,
             git_commands::unstage_all_changes,
@@ -409,6 +420,15 @@ pub fn run() {
             gitflow_commands::list_gitflow_branches,
             gitflow_commands::create_gitflow_branch,
             gitflow_commands::execute_gitflow_action,
+            gitflow_commands::get_gitflow_config,
+            gitflow_commands::update_gitflow_config,
+            gitflow_commands::init_gitflow,
+            gitflow_commands::open_pull_request,
+            gitflow_commands::plan_gitflow_release,
+            gitflow_commands::get_gitflow_branch_metadata,
+            gitflow_commands::update_gitflow_branch_metadata,
+            gitflow_commands::sync_gitflow_branch_metadata,
+            gitflow_commands::generate_gitflow_report,
             // Daily report commands
             daily_report_commands::get_available_repositories,
             daily_report_commands::get_repo_contributors,
@@ -433,6 +453,7 @@ pub fn run() {
             ai_commands::refresh_provider_models,
             ai_commands::generate_commit_message_ai,
             ai_commands::generate_commit_with_template,
+            ai_commands::preview_commit_template,
             ai_commands::get_prompt_templates,
             ai_commands::add_prompt_template,
             ai_commands::create_custom_template,
@@ -454,16 +475,25 @@ pub fn run() {
             ai_commands::cancel_layered_commit,
             ai_commands::check_first_time_setup,
             ai_commands::test_ai_connection,
+            ai_commands::explain_line_history,
             // Update commands
             update_commands::test_network_connection,
             update_commands::check_for_updates,
             update_commands::download_update,
             update_commands::install_update,
+            update_commands::pick_update_package,
+            update_commands::install_update_from_file,
             update_commands::get_current_version,
             update_commands::cleanup_update_files,
             update_commands::check_update_file_exists,
             update_commands::get_update_settings,
             update_commands::save_update_settings,
+            // Log commands
+            log_commands::get_log_settings,
+            log_commands::update_log_settings,
+            log_commands::get_logs,
+            log_commands::get_log_dir,
+            log_commands::collect_diagnostics,
             // System commands
             system_commands::open_browser_url,
             system_commands::get_app_info,
@@ -479,6 +509,8 @@ pub fn run() {
             ai_analysis_commands::get_ai_templates,
             ai_analysis_commands::update_ai_template,
             ai_analysis_commands::reset_ai_template,
+            ai_analysis_commands::get_impact_rules,
+            ai_analysis_commands::save_impact_rules,
             // Template version management commands
             template_commands::get_all_templates,
             template_commands::get_template_details,
@@ -517,10 +549,10 @@ pub fn run() {
 
     match app_result {
         Ok(_) => {
-            write_startup_log("=== GitMentor-Lite 正常退出 ===");
+            info_log!("=== GitMentor-Lite 正常退出 ===");
         }
         Err(e) => {
-            write_error_log(&format!("Tauri应用运行失败: {}", e));
+            error_log!("Tauri应用运行失败: {}", e);
             panic!("error while running tauri application: {}", e);
         }
     }
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// 文件状态枚举，类似VSCode Git面板
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -45,6 +45,12 @@ pub struct CommitRequest {
     pub selected_files: Vec<String>,
     pub additional_context: Option<String>,
     pub amend: bool, // 是否修正上次提交
+    /// 跳过提交规范校验（等同于 --no-verify）
+    #[serde(default)]
+    pub skip_lint: bool,
+    /// 校验时读取该模板的 commit_types 作为允许的类型
+    #[serde(default)]
+    pub template_id: Option<String>,
 }
 
 /// 提交消息生成结果
@@ -55,6 +61,36 @@ pub struct CommitMessageResult {
     pub processing_time_ms: u64,
 }
 
+/// 提交规范问题的级别：error 阻止提交，warning 仅提示
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitLintSeverity {
+    Error,
+    Warning,
+}
+
+/// 单条提交规范问题，rule 使用 commitlint 的规则名
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitLintIssue {
+    pub rule: String,
+    pub severity: CommitLintSeverity,
+    pub message: String,
+    /// 能否通过确定性修正自动解决
+    pub fixable: bool,
+}
+
+/// 提交消息校验结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitLintResult {
+    /// 没有 error 级别的问题
+    pub valid: bool,
+    pub issues: Vec<CommitLintIssue>,
+    /// 存在可修正问题时给出的修正结果
+    pub fixed_message: Option<String>,
+}
+
 /// 提交历史记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitInfo {
@@ -239,6 +275,17 @@ impl GitError {
     }
 }
 
+impl std::fmt::Display for GitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.suggestion() {
+            Some(suggestion) => write!(f, "{}（{}）", self.user_message(), suggestion),
+            None => write!(f, "{}", self.user_message()),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
 /// Git操作结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitOperationResult {
@@ -316,6 +363,213 @@ pub struct FileDiffResult {
     pub is_deleted_file: bool,
 }
 
+/// 提交中单个文件的差异
+/// 作者：Evilek
+/// 编写日期：2025-10-08
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitFileDiff {
+    pub file_path: String,
+    /// 重命名或复制前的路径
+    pub old_path: Option<String>,
+    pub change_type: FileChangeType,
+    /// 重命名/复制的相似度（百分比）
+    pub similarity: Option<u32>,
+    pub file_language: Option<String>,
+    pub is_binary: bool,
+    pub insertions: u32,
+    pub deletions: u32,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 提交差异：相对某个父提交（默认第一父提交，根提交相对空树）的全部文件变更
+/// 作者：Evilek
+/// 编写日期：2025-10-08
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitDiffResult {
+    pub commit_id: String,
+    /// 对比的父提交，根提交为空
+    pub parent_id: Option<String>,
+    pub parent_count: usize,
+    pub files: Vec<CommitFileDiff>,
+    /// numstat 汇总
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// 文件追溯（blame）请求
+/// 作者：Evilek
+/// 编写日期：2025-09-28
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameRequest {
+    pub file_path: String,
+    pub revision: Option<String>, // 为空时使用 HEAD
+    pub start_line: Option<u32>,  // 1-based，含
+    pub end_line: Option<u32>,    // 1-based，含
+}
+
+/// 追溯结果中的连续行区间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameHunk {
+    pub start_line: u32, // 当前版本中的起始行（1-based）
+    pub line_count: u32,
+    pub commit_hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub summary: String,
+    pub original_path: String, // 跟随重命名/移动后的原始路径
+    pub original_start_line: u32,
+    pub is_boundary: bool, // 是否为边界提交（历史起点）
+}
+
+/// 文件追溯结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameResult {
+    pub file_path: String,
+    pub revision: String,
+    pub total_lines: u32,
+    pub hunks: Vec<BlameHunk>,
+}
+
+/// 文件历史中的单次提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHistoryEntry {
+    pub commit: CommitInfo,
+    pub path: String,             // 该提交中文件的路径
+    pub old_path: Option<String>, // 发生重命名时的旧路径
+    pub change_type: FileChangeType,
+    pub insertions: u32,
+    pub deletions: u32,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// 行历史解释请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineHistoryRequest {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: Option<u32>,
+    pub max_commits: Option<usize>,
+}
+
+/// 行历史解释结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineHistoryExplanation {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub explanation: String,
+    pub commits: Vec<FileHistoryEntry>,
+    pub model_used: String,
+    pub processing_time_ms: u64,
+}
+
+/// 提交历史查询条件（游标分页）
+/// 作者：Evilek
+/// 编写日期：2025-09-29
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    pub cursor: Option<String>,   // 上一页返回的 next_cursor，为空表示从头开始
+    pub page_size: Option<usize>, // 默认 100
+    pub all_branches: bool,       // true 时遍历所有本地/远程分支与标签
+    pub revision: Option<String>, // 起始版本，all_branches 为 false 时生效，默认 HEAD
+    pub author: Option<String>,   // 作者名或邮箱，忽略大小写的包含匹配
+    pub path: Option<String>,     // 只保留修改了该路径（文件或目录）的提交
+    pub since: Option<i64>,       // 提交时间下限（Unix 秒，含）
+    pub until: Option<i64>,       // 提交时间上限（Unix 秒，含）
+    pub message_pattern: Option<String>, // 提交信息正则
+    pub merge_filter: MergeFilter,
+}
+
+/// 合并提交过滤方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeFilter {
+    #[default]
+    All,
+    MergesOnly,
+    NoMerges,
+}
+
+/// 提交上的引用装饰
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommitRef {
+    pub name: String, // 短名称，如 main / origin/main / v1.0.0
+    pub ref_type: CommitRefType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitRefType {
+    Head,
+    LocalBranch,
+    RemoteBranch,
+    Tag,
+}
+
+/// 用于绘制提交图的历史节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    pub timestamp: i64,
+    pub parents: Vec<String>,
+    pub refs: Vec<CommitRef>,
+}
+
+/// 提交历史分页结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub commits: Vec<GraphCommit>,
+    pub next_cursor: Option<String>, // 为空表示没有更多数据
+    pub scanned: usize,              // 本页遍历过的提交数（含被过滤掉的）
+}
+
+/// 暂存区中单个文件的变更概况（提交模板上下文）
+/// 作者：Evilek
+/// 编写日期：2025-10-01
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StagedFileChange {
+    pub path: String,
+    pub old_path: Option<String>, // 重命名/复制前的路径
+    pub status: String,           // added / modified / deleted / renamed / copied / typechange
+    pub additions: usize,
+    pub deletions: usize,
+    pub is_binary: bool,
+}
+
+/// 仓库语言占比（按已跟踪文件数统计）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LanguageShare {
+    pub language: String,
+    pub files: usize,
+    pub percentage: f64,
+}
+
+/// 提交作者（取自 user.name / user.email）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommitAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+/// 从仓库收集的提交模板上下文信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitTemplateFacts {
+    pub files: Vec<StagedFileChange>,
+    pub branch_name: Option<String>, // 分离HEAD时为空
+    pub upstream: Option<String>,
+    pub recent_commits: Vec<String>, // 最近提交的标题行，新的在前
+    pub languages: Vec<LanguageShare>,
+    pub author: Option<CommitAuthor>,
+}
+
 /// Gitflow 分支类型
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -324,6 +578,7 @@ pub enum GitflowBranchType {
     Release,
     Bugfix,
     Hotfix,
+    Support,
 }
 
 /// Gitflow 分支状态
@@ -360,11 +615,52 @@ pub struct GitflowBranchInfo {
     pub divergence: GitflowDivergence,
     pub upstream: Option<String>,
     pub is_current: bool,
+    #[serde(default)]
+    pub metadata: GitflowBranchMetadata,
+}
+
+/// 分支 QA 状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitflowQaStatus {
+    Pending,
+    InProgress,
+    Passed,
+    Failed,
+    Blocked,
+}
+
+impl GitflowQaStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GitflowQaStatus::Pending => "待测试",
+            GitflowQaStatus::InProgress => "测试中",
+            GitflowQaStatus::Passed => "已通过",
+            GitflowQaStatus::Failed => "未通过",
+            GitflowQaStatus::Blocked => "受阻",
+        }
+    }
+}
+
+/// Gitflow 分支元数据
+/// 保存在 refs/gitmentor/branch-metadata 下，随远程推送/拉取在团队间同步
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GitflowBranchMetadata {
+    pub qa_status: Option<GitflowQaStatus>,
+    pub ticket_url: Option<String>,
+    pub owner: Option<String>,
+    pub risk_notes: Option<String>,
+    /// 创建分支时传入的其他自定义字段
+    pub extra: BTreeMap<String, String>,
+    pub updated_at: Option<String>,
+    pub updated_by: Option<String>,
 }
 
 /// Gitflow 配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// 按仓库保存在 .git/config 的 gitflow.branch.* / gitflow.prefix.* 中，与 git-flow 命令行互通
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
 pub struct GitflowConfig {
     pub develop_branch: String,
     pub main_branch: String,
@@ -372,6 +668,8 @@ pub struct GitflowConfig {
     pub release_prefix: String,
     pub bugfix_prefix: String,
     pub hotfix_prefix: String,
+    pub support_prefix: String,
+    pub version_tag_prefix: String, // 版本标签前缀，如 v；git-flow 默认为空
 }
 
 impl Default for GitflowConfig {
@@ -383,6 +681,8 @@ impl Default for GitflowConfig {
             release_prefix: "release/".to_string(),
             bugfix_prefix: "bugfix/".to_string(),
             hotfix_prefix: "hotfix/".to_string(),
+            support_prefix: "support/".to_string(),
+            version_tag_prefix: String::new(),
         }
     }
 }
@@ -394,6 +694,9 @@ pub struct GitflowSummary {
     pub config: GitflowConfig,
     pub branches: Vec<GitflowBranchInfo>,
     pub has_origin_remote: bool,
+    /// 仓库是否已写入 gitflow 配置（git flow init 或本工具初始化过）
+    #[serde(default)]
+    pub initialized: bool,
 }
 
 /// Gitflow 操作请求
@@ -402,6 +705,237 @@ pub struct GitflowSummary {
 pub struct GitflowActionRequest {
     pub branch_name: String,
     pub action: String,
+    /// create_pull_request / request_code_review 使用的 PR 参数
+    #[serde(default)]
+    pub pull_request: Option<PullRequestOptions>,
+    /// release_pipeline 使用的发布参数
+    #[serde(default)]
+    pub release: Option<ReleaseOptions>,
+    /// update_qa_status 要写入的分支元数据，只更新有值的字段
+    #[serde(default)]
+    pub metadata: Option<GitflowBranchMetadata>,
+}
+
+/// 版本升级级别，按影响从小到大排序
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// 发布流水线参数（release_pipeline 操作）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseOptions {
+    /// 只返回计划的每个步骤，不修改仓库
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 指定版本号，为空时优先取 release 分支名中的版本，其次按约定式提交推导
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 完成后推送主干、开发分支与标签
+    #[serde(default)]
+    pub push: bool,
+}
+
+/// 发布计划
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleasePlan {
+    pub branch_name: String,
+    pub previous_tag: Option<String>,
+    pub current_version: String,
+    pub next_version: String,
+    pub bump: Option<VersionBump>, // 为空表示版本由参数或分支名指定
+    pub tag_name: String,
+    pub commit_count: usize,
+    pub version_files: Vec<String>, // 相对仓库根目录
+    pub release_notes: String,
+    pub steps: Vec<String>,
+}
+
+/// Gitflow 步骤状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitflowStepStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// Gitflow 操作中单个步骤的执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitflowStepLog {
+    pub step: String,
+    pub status: GitflowStepStatus,
+    pub output: String,
+}
+
+/// Gitflow 操作结果；失败时本地分支、标签与工作区已回滚，steps 中包含回滚步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitflowActionResult {
+    pub success: bool,
+    pub message: String,
+    pub steps: Vec<GitflowStepLog>,
+    pub rolled_back: bool,
+    /// 状态播报、发布说明、PR 链接等生成内容
+    pub report: Option<String>,
+    /// AI 生成的状态播报/复盘的结构化内容
+    #[serde(default)]
+    pub gitflow_report: Option<GitflowReport>,
+}
+
+/// Gitflow AI 报告类型，对应 generate_status_report / generate_postmortem / generate_retrospective
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitflowReportKind {
+    StatusReport,
+    Postmortem,
+    Retrospective,
+}
+
+impl GitflowReportKind {
+    pub fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "generate_status_report" => Some(GitflowReportKind::StatusReport),
+            "generate_postmortem" => Some(GitflowReportKind::Postmortem),
+            "generate_retrospective" => Some(GitflowReportKind::Retrospective),
+            _ => None,
+        }
+    }
+
+    pub fn action(&self) -> &'static str {
+        match self {
+            GitflowReportKind::StatusReport => "generate_status_report",
+            GitflowReportKind::Postmortem => "generate_postmortem",
+            GitflowReportKind::Retrospective => "generate_retrospective",
+        }
+    }
+
+    /// 版本化模板管理器中的内置模板ID
+    pub fn template_id(&self) -> &'static str {
+        match self {
+            GitflowReportKind::StatusReport => "gitflow_status_report",
+            GitflowReportKind::Postmortem => "gitflow_postmortem",
+            GitflowReportKind::Retrospective => "gitflow_retrospective",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GitflowReportKind::StatusReport => "状态播报",
+            GitflowReportKind::Postmortem => "事故复盘",
+            GitflowReportKind::Retrospective => "迭代回顾",
+        }
+    }
+}
+
+/// 报告上下文中的提交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitflowReportCommit {
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    pub date: String,
+}
+
+/// 相对基线的单文件改动统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitflowFileStat {
+    pub path: String,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// 渲染 Gitflow 报告模板所用的分支上下文
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitflowReportContext {
+    pub repo_path: String,
+    pub branch_name: String,
+    pub branch_type: Option<GitflowBranchType>,
+    pub base_branch: String,
+    pub divergence: GitflowDivergence,
+    pub metadata: GitflowBranchMetadata,
+    /// 分支独有的提交，新的在前
+    pub commits: Vec<GitflowReportCommit>,
+    pub diffstat: Vec<GitflowFileStat>,
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// AI 生成的 Gitflow 报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitflowReport {
+    pub kind: GitflowReportKind,
+    pub branch_name: String,
+    pub repo_path: String,
+    pub summary: String,
+    pub risks: Vec<String>,
+    pub timeline: Vec<String>,
+    pub action_items: Vec<String>,
+    /// 按章节拼好的 Markdown，保存到报告存储时使用
+    pub content: String,
+    pub template_id: String,
+    pub template_version: String,
+    pub model: String,
+    pub generated_at: String,
+    /// 保存到报告存储后的报告ID
+    #[serde(default)]
+    pub report_id: Option<String>,
+}
+
+/// 创建 PR/MR 的参数，标题或描述为空时由 AI 根据分支摘要生成
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestOptions {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// 目标分支，为空时按 Gitflow 分支类型推导
+    #[serde(default)]
+    pub base_branch: Option<String>,
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// 创建 PR/MR 前从仓库收集的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestContext {
+    pub branch_name: String,
+    pub base_branch: String,
+    pub push_output: String,
+    pub digest: String,       // build_branch_digest 生成的分支摘要
+    pub commits: Vec<String>, // 源分支相对目标分支独有的提交标题，新的在前
+}
+
+/// PR/MR 创建结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestResult {
+    pub forge: String,
+    pub number: u64,
+    pub url: String,
+    pub title: String,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub ai_generated: bool,
+    /// 评审人、标签或 AI 摘要失败等不影响创建的提示
+    pub warnings: Vec<String>,
 }
 
 /// 创建 Gitflow 分支请求
@@ -490,6 +1024,9 @@ pub struct CommitDetailAnalysis {
     pub summary: String,
     pub impact_level: ImpactLevel,
     pub tags: Vec<String>,
+    /// 影响评分及其依据（旧缓存中没有该字段）
+    #[serde(default)]
+    pub impact: Option<ImpactAssessment>,
 }
 
 /// 文件变更详情
@@ -522,6 +1059,183 @@ pub enum ImpactLevel {
     Critical, // 核心功能或架构改动
 }
 
+/// 影响评分中的单项依据
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImpactFactor {
+    /// 信号标识，如 category:core、churn、public_api
+    pub signal: String,
+    pub points: u32,
+    pub detail: String,
+}
+
+/// 基于代码信号的影响评估
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImpactAssessment {
+    pub score: u32,
+    pub level: ImpactLevel,
+    pub factors: Vec<ImpactFactor>,
+}
+
+/// 影响模型中的路径分类
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpactCategory {
+    Core,
+    Config,
+    Tests,
+    Docs,
+    Migrations,
+}
+
+/// 路径分类规则：patterns 为匹配仓库相对路径的正则，命中该分类时加 weight 分（每个分类只计一次）
+/// 每个文件归入第一个匹配的分类，因此测试、文档规则排在前面
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImpactPathRule {
+    pub category: ImpactCategory,
+    pub patterns: Vec<String>,
+    pub weight: u32,
+}
+
+/// 改动行数达到 lines 时计 points 分，取达到的最高档
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImpactChurnStep {
+    pub lines: u32,
+    pub points: u32,
+}
+
+/// 提交影响评分规则，按仓库 id 保存在应用数据目录；也可提交 .gitmentor/impact-rules.json 共享
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ImpactRules {
+    pub categories: Vec<ImpactPathRule>,
+    pub churn_steps: Vec<ImpactChurnStep>,
+    /// 公共接口声明的正则（匹配新增或删除的行）
+    pub api_patterns: Vec<String>,
+    /// 每处公共接口变更的分数
+    pub api_weight: u32,
+    pub api_max_points: u32,
+    /// 依赖清单文件名
+    pub dependency_manifests: Vec<String>,
+    pub dependency_weight: u32,
+    /// 统计历史修改频率时回溯的提交数
+    pub hot_file_window: usize,
+    /// 窗口内修改次数达到该值视为热点文件
+    pub hot_file_min_changes: u32,
+    pub hot_file_weight: u32,
+    pub hot_file_max_points: u32,
+    /// 测试声明的正则，删除这类行或删除测试文件都计入
+    pub test_patterns: Vec<String>,
+    pub deleted_test_weight: u32,
+    /// 分数达到对应阈值时的级别
+    pub medium_threshold: u32,
+    pub high_threshold: u32,
+    pub critical_threshold: u32,
+}
+
+impl Default for ImpactRules {
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+        let rule = |category, patterns: &[&str], weight| ImpactPathRule {
+            category,
+            patterns: strings(patterns),
+            weight,
+        };
+
+        Self {
+            categories: vec![
+                rule(
+                    ImpactCategory::Tests,
+                    &[
+                        r"(^|/)(tests?|__tests__|spec)/",
+                        r"(_test|_spec|\.test|\.spec)\.[a-z]+$",
+                        r"(^|/)test_[^/]+\.py$",
+                    ],
+                    0,
+                ),
+                rule(
+                    ImpactCategory::Docs,
+                    &[r"\.(md|rst|adoc|txt)$", r"(^|/)docs?/"],
+                    0,
+                ),
+                rule(
+                    ImpactCategory::Migrations,
+                    &[r"(^|/)migrations?/", r"\.sql$"],
+                    25,
+                ),
+                rule(
+                    ImpactCategory::Core,
+                    &[
+                        r"(^|/)(core|kernel|engine|lib)/",
+                        r"(^|/)src/(main|lib)\.rs$",
+                    ],
+                    20,
+                ),
+                rule(
+                    ImpactCategory::Config,
+                    &[
+                        r"\.(toml|ya?ml|ini|env|conf|properties)$",
+                        r"(^|/)\.github/",
+                        r"(^|/)(Dockerfile|Makefile)$",
+                        r"(^|/)[^/]*config[^/]*\.(json|js|ts)$",
+                    ],
+                    10,
+                ),
+            ],
+            churn_steps: vec![
+                ImpactChurnStep {
+                    lines: 50,
+                    points: 10,
+                },
+                ImpactChurnStep {
+                    lines: 200,
+                    points: 20,
+                },
+                ImpactChurnStep {
+                    lines: 500,
+                    points: 35,
+                },
+            ],
+            api_patterns: strings(&[
+                r"^\s*pub\s+(async\s+|unsafe\s+)*(fn|struct|enum|trait|type|const|static|mod)\b",
+                r"^\s*export\s+(default\s+)?(async\s+)?(function|class|interface|type|const|let|enum)\b",
+                r"^\s*public\s+",
+                r"^func\s+(\([^)]*\)\s*)?[A-Z]",
+            ]),
+            api_weight: 5,
+            api_max_points: 25,
+            dependency_manifests: strings(&[
+                "Cargo.toml",
+                "Cargo.lock",
+                "package.json",
+                "package-lock.json",
+                "pnpm-lock.yaml",
+                "yarn.lock",
+                "go.mod",
+                "requirements.txt",
+                "pyproject.toml",
+                "pom.xml",
+                "build.gradle",
+            ]),
+            dependency_weight: 15,
+            hot_file_window: 200,
+            hot_file_min_changes: 5,
+            hot_file_weight: 5,
+            hot_file_max_points: 15,
+            test_patterns: strings(&[
+                r"#\[(tokio::)?test\]",
+                r"^\s*(it|test|describe)\(",
+                r"^\s*def test_",
+                r"@Test\b",
+                r"^func Test",
+            ]),
+            deleted_test_weight: 20,
+            medium_threshold: 20,
+            high_threshold: 45,
+            critical_threshold: 70,
+        }
+    }
+}
+
 /// 从RiskLevel转换为ImpactLevel
 impl From<crate::types::git_types::RiskLevel> for ImpactLevel {
     fn from(risk_level: crate::types::git_types::RiskLevel) -> Self {
@@ -0,0 +1,67 @@
+use crate::warn_log;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// JSON 配置文件读取
+// Author: Evilek, Date: 2025-10-19
+// 文件损坏时先改名备份再用默认值，避免下次保存覆盖掉用户数据
+
+/// 读取 JSON 配置；文件不存在时返回默认值，无法解析时备份原文件后返回默认值
+pub fn load_json_or_backup<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let content = fs::read_to_string(path)?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Ok(value),
+        Err(parse_error) => {
+            let backup = corrupt_backup_path(path);
+            fs::rename(path, &backup)?;
+            warn_log!(
+                "配置文件 {} 无法解析（{}），已备份为 {} 并使用默认配置",
+                path.display(),
+                parse_error,
+                backup.display()
+            );
+            Ok(T::default())
+        }
+    }
+}
+
+fn corrupt_backup_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+    path.with_file_name(format!("{}.corrupt-{}", file_name, stamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_corrupt_file_is_backed_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        let missing: BTreeMap<String, String> = load_json_or_backup(&path).unwrap();
+        assert!(missing.is_empty());
+
+        fs::write(&path, r#"{"name": "kept""#).unwrap();
+        let loaded: BTreeMap<String, String> = load_json_or_backup(&path).unwrap();
+        assert!(loaded.is_empty());
+        assert!(!path.exists());
+
+        let backups: Vec<_> = fs::read_dir(dir.path()).unwrap().flatten().collect();
+        assert_eq!(backups.len(), 1);
+        let backup_name = backups[0].file_name().to_string_lossy().to_string();
+        assert!(backup_name.starts_with("settings.json.corrupt-"));
+        assert_eq!(
+            fs::read_to_string(backups[0].path()).unwrap(),
+            r#"{"name": "kept""#
+        );
+    }
+}
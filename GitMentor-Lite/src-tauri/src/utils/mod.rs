@@ -3,4 +3,5 @@
  * 作者：Evilek
  * 编写日期：2025-08-04
  */
+pub mod config_file;
 pub mod token_counter;
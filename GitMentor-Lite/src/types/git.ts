@@ -61,3 +61,13 @@ export interface OperationFeedback {
   suggestion?: string | null
   duration_ms?: number
 }
+
+// 工作区中已打开的仓库，path 为规范化后的绝对路径，文件监控事件按 id 区分仓库
+export interface WorkspaceRepository {
+  id: string
+  path: string
+  name: string
+  opened_at: string
+  active: boolean
+  custom_config: boolean
+}